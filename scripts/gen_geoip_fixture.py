@@ -0,0 +1,117 @@
+#!/usr/bin/env python3
+"""Generate tests/fixtures/GeoLite2-Test.mmdb, the GeoIP fixture used by the
+network::geoip tests.
+
+The database is IPv4-only and carries both GeoLite2-ASN and
+GeoLite2-Country fields in each record, so the same file can stand in for
+either database. Run from the repository root:
+
+    python3 scripts/gen_geoip_fixture.py
+"""
+
+import struct
+
+NETWORKS = [
+    ("1.128.0.0", 11, {
+        "autonomous_system_number": 1221,
+        "autonomous_system_organization": "Telstra Pty Ltd",
+        "country": {"iso_code": "AU"},
+    }),
+    ("89.160.20.112", 28, {
+        "autonomous_system_number": 29518,
+        "autonomous_system_organization": "Bredband2 AB",
+        "country": {"iso_code": "SE"},
+    }),
+]
+
+OUTPUT = "tests/fixtures/GeoLite2-Test.mmdb"
+
+
+def control(type_id, size):
+    if size < 29:
+        size_bits, extra = size, b""
+    elif size < 285:
+        size_bits, extra = 29, bytes([size - 29])
+    else:
+        size_bits, extra = 30, struct.pack(">H", size - 285)
+
+    if type_id <= 7:
+        return bytes([(type_id << 5) | size_bits]) + extra
+    return bytes([size_bits, type_id - 7]) + extra
+
+
+def encode_uint(type_id, value):
+    payload = value.to_bytes((value.bit_length() + 7) // 8, "big") if value else b""
+    return control(type_id, len(payload)) + payload
+
+
+def encode(value):
+    if isinstance(value, str):
+        data = value.encode("utf-8")
+        return control(2, len(data)) + data
+    if isinstance(value, dict):
+        out = control(7, len(value))
+        for key, item in value.items():
+            out += encode(key) + encode(item)
+        return out
+    if isinstance(value, list):
+        return control(11, len(value)) + b"".join(encode(item) for item in value)
+    if isinstance(value, tuple):  # (type_id, int) for sized unsigned ints
+        return encode_uint(*value)
+    if isinstance(value, int):
+        return encode_uint(6, value)  # uint32
+    raise TypeError(value)
+
+
+def build():
+    data = b""
+    nodes = [[None, None]]
+
+    for address, prefix, record in NETWORKS:
+        offset = len(data)
+        data += encode(record)
+        bits = struct.unpack(">I", bytes(int(part) for part in address.split(".")))[0]
+
+        node = 0
+        for depth in range(prefix):
+            bit = (bits >> (31 - depth)) & 1
+            if depth == prefix - 1:
+                nodes[node][bit] = ("data", offset)
+            else:
+                if nodes[node][bit] is None:
+                    nodes.append([None, None])
+                    nodes[node][bit] = ("node", len(nodes) - 1)
+                node = nodes[node][bit][1]
+
+    node_count = len(nodes)
+
+    def record_value(record):
+        if record is None:
+            return node_count
+        kind, value = record
+        return value if kind == "node" else node_count + 16 + value
+
+    tree = b"".join(
+        record_value(left).to_bytes(3, "big") + record_value(right).to_bytes(3, "big")
+        for left, right in nodes
+    )
+
+    metadata = {
+        "binary_format_major_version": (5, 2),
+        "binary_format_minor_version": (5, 0),
+        "build_epoch": (9, 1700000000),
+        "database_type": "GeoLite2-Test",
+        "description": {"en": "Port-ZiLLA GeoIP test fixture"},
+        "ip_version": (5, 4),
+        "languages": ["en"],
+        "node_count": (6, node_count),
+        "record_size": (5, 24),
+    }
+
+    return tree + b"\x00" * 16 + data + b"\xab\xcd\xefMaxMind.com" + encode(metadata)
+
+
+if __name__ == "__main__":
+    with open(OUTPUT, "wb") as output:
+        output.write(build())
+    print("wrote", OUTPUT)
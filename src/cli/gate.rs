@@ -0,0 +1,110 @@
+//! CI gating for `--fail-on-open` and `--fail-on-severity`.
+//!
+//! Exit-code contract: `0` clean (or no gate requested), `1` error (the scan
+//! could not complete, including a host that didn't answer), `2` policy
+//! violation, `130` interrupted with Ctrl-C (the partial result was saved).
+//! On a violation the offending ports or findings are the only thing printed
+//! to stdout, one per line.
+
+use crate::error::{Error, Result};
+use crate::scanner::ScanResult;
+use crate::vulnerability::{VulnerabilityLevel, VulnerabilityReport};
+
+pub const EXIT_CLEAN: i32 = 0;
+pub const EXIT_ERROR: i32 = 1;
+pub const EXIT_POLICY_VIOLATION: i32 = 2;
+pub const EXIT_INTERRUPTED: i32 = 130; // 128 + SIGINT, as shells report it
+
+/// Open ports from `forbidden`, as `port/protocol`
+pub fn open_port_violations(scan: &ScanResult, forbidden: &[u16]) -> Vec<String> {
+    scan.open_ports
+        .iter()
+        .filter(|p| forbidden.contains(&p.port))
+        .map(|p| format!("{}/{}", p.port, format!("{:?}", p.protocol).to_lowercase()))
+        .collect()
+}
+
+/// Unsuppressed findings at or above `threshold`
+pub fn severity_violations(report: &VulnerabilityReport, threshold: &VulnerabilityLevel) -> Vec<String> {
+    report.vulnerabilities
+        .iter()
+        .filter(|v| !v.suppressed && v.level >= *threshold)
+        .map(|v| format!("{}/{} {:?} {}", v.port, v.protocol.to_lowercase(), v.level, v.title))
+        .collect()
+}
+
+pub fn exit_code(outcome: &Result<Vec<String>>) -> i32 {
+    match outcome {
+        Ok(violations) if violations.is_empty() => EXIT_CLEAN,
+        Ok(_) => EXIT_POLICY_VIOLATION,
+        Err(Error::Interrupted(_)) => EXIT_INTERRUPTED,
+        Err(_) => EXIT_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{PortInfo, PortStatus, Protocol, ScanType};
+    use crate::vulnerability::Vulnerability;
+
+    fn scan_with_open(ports: &[u16]) -> ScanResult {
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        for &port in ports {
+            scan.add_open_port(PortInfo {
+                port,
+                status: PortStatus::Open,
+                service: None,
+                banner: None,
+                banner_raw: None,
+                response_time: None,
+                protocol: Protocol::Tcp,
+                tls: None,
+            });
+        }
+        scan
+    }
+
+    #[test]
+    fn test_clean_scan_exits_zero() {
+        let violations = open_port_violations(&scan_with_open(&[80, 443]), &[22, 3389]);
+        assert_eq!(exit_code(&Ok(violations)), EXIT_CLEAN);
+    }
+
+    #[test]
+    fn test_forbidden_open_port_exits_two() {
+        let violations = open_port_violations(&scan_with_open(&[22, 80]), &[22, 3389]);
+        assert_eq!(violations, vec!["22/tcp".to_string()]);
+        assert_eq!(exit_code(&Ok(violations)), EXIT_POLICY_VIOLATION);
+    }
+
+    #[test]
+    fn test_scan_error_exits_one() {
+        let outcome: Result<Vec<String>> = Err(Error::Scan("target unreachable".to_string()));
+        assert_eq!(exit_code(&outcome), EXIT_ERROR);
+
+        let interrupted: Result<Vec<String>> = Err(Error::Interrupted("scan stopped".to_string()));
+        assert_eq!(exit_code(&interrupted), EXIT_INTERRUPTED);
+    }
+
+    #[test]
+    fn test_severity_gate_ignores_lower_and_suppressed_findings() {
+        let mut report = VulnerabilityReport::new(
+            "scan".to_string(), "10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(),
+        );
+        let finding = |title: &str, level| Vulnerability::new(
+            title.to_string(), String::new(), level, 443, "https".to_string(), String::new(),
+        );
+        report.add_vulnerability(finding("Weak cipher", VulnerabilityLevel::Medium));
+        let mut accepted = finding("Known issue", VulnerabilityLevel::Critical);
+        accepted.suppressed = true;
+        report.add_vulnerability(accepted);
+
+        assert_eq!(exit_code(&Ok(severity_violations(&report, &VulnerabilityLevel::High))), EXIT_CLEAN);
+
+        report.add_vulnerability(finding("TLS Certificate Expired", VulnerabilityLevel::High));
+        let violations = severity_violations(&report, &VulnerabilityLevel::High);
+        assert_eq!(violations, vec!["443/tcp High TLS Certificate Expired".to_string()]);
+        assert_eq!(exit_code(&Ok(violations)), EXIT_POLICY_VIOLATION);
+    }
+}
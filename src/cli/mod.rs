@@ -1,5 +1,6 @@
 pub mod args;
 pub mod commands;
+pub mod gate;
 
 use clap::{Parser, Subcommand};
 use std::net::IpAddr;
@@ -28,6 +29,31 @@ pub struct Cli {
     /// Configuration file path
     #[arg(short, long, global = true, default_value = "config/default.toml")]
     pub config: String,
+    
+    /// Emit logs as JSON lines (overrides logging.format)
+    #[arg(long, global = true)]
+    pub json_logs: bool,
+    
+    /// Config profile overlaid on the base file, e.g. `prod` loads
+    /// config/prod.toml (defaults to $PORTZILLA_PROFILE)
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+    
+    /// Disable colored output (also off when NO_COLOR is set or output is
+    /// not a terminal)
+    #[arg(long, global = true)]
+    pub no_color: bool,
+}
+
+impl Cli {
+    /// A CI gate was requested, so normal output is suppressed
+    pub fn is_gating(&self) -> bool {
+        match &self.command {
+            Command::Scan(args) => !args.fail_on_open.is_empty(),
+            Command::Vulnerability(args) => args.fail_on_severity.is_some(),
+            _ => false,
+        }
+    }
 }
 
 #[derive(Subcommand)]
@@ -35,15 +61,52 @@ pub enum Command {
     /// Perform port scanning
     Scan(ScanArgs),
     
+    /// Run service/banner detection on the open ports of a stored scan
+    /// (the follow-up to `scan --discovery-only`)
+    Enrich(EnrichArgs),
+    
     /// Run vulnerability assessment
     Vulnerability(VulnerabilityArgs),
     
-    /// View scan history
+    /// Compare the assessments of two scans of the same target: new,
+    /// resolved and persistent findings and the risk score change
+    VulnDiff(VulnDiffArgs),
+    
+    /// Compare the open ports of two scans of the same target and write a
+    /// side-by-side HTML page of what was added, removed and changed
+    ScanDiff(ScanDiffArgs),
+    
+    /// Manage risk-acknowledged vulnerability suppressions
+    Suppress(SuppressArgs),
+    
+    /// View scan history, or purge a target's history
     History(HistoryArgs),
     
+    /// Show open-port counts for a target over time
+    Timeline(TimelineArgs),
+    
+    /// Print the ports a scan type, preset or range would probe, without
+    /// scanning anything
+    ListPorts(ListPortsArgs),
+    
+    /// Estimate how long a scan would take, as an optimistic to
+    /// pessimistic range, without scanning anything
+    Estimate(EstimateArgs),
+    
+    /// Scan a target once per technique (connect, SYN, UDP) and print
+    /// which technique saw which port, to study its filtering
+    CompareTechniques(CompareTechniquesArgs),
+    
     /// Export scan results
     Export(ExportArgs),
     
+    /// Export every scan of a target into one file
+    ExportBatch(ExportBatchArgs),
+    
+    /// Store a scan from a JSON export (ours, or another tool's in the same
+    /// layout) after validating it
+    Import(ImportArgs),
+    
     /// Manage configuration
     Config(ConfigArgs),
     
@@ -57,35 +120,189 @@ pub enum Command {
 #[derive(clap::Args)]
 pub struct ScanArgs {
     /// Target IP address or hostname
-    pub target: String,
+    #[arg(required_unless_present = "resume")]
+    pub target: Option<String>,
+    
+    /// Continue an interrupted scan from its last checkpoint; the target
+    /// and scan type come from the checkpoint
+    #[arg(long, value_name = "SCAN_ID", conflicts_with_all = ["target", "scan_type", "port_range", "preset", "known_services"])]
+    pub resume: Option<String>,
     
     /// Scan type
     #[arg(short, long)]
     pub scan_type: Option<ScanType>,
     
+    /// Only probe the ports of well-known services (SSH, HTTP, SMB,
+    /// databases...): a fast triage scan
+    #[arg(long, conflicts_with_all = ["scan_type", "port_range"])]
+    pub known_services: bool,
+    
+    /// Named preset from the config (built in: web-audit, db-audit,
+    /// quick-triage); explicit flags override it
+    #[arg(long)]
+    pub preset: Option<String>,
+    
     /// Custom port range (e.g., 1-1000)
     #[arg(short, long)]
     pub port_range: Option<PortRange>,
     
-    /// Timeout in milliseconds
-    #[arg(long, default_value = "100")]
-    pub timeout: u64,
+    /// Timeout in milliseconds [default: 100]
+    #[arg(long)]
+    pub timeout: Option<u64>,
     
     /// Maximum concurrent threads
     #[arg(long, default_value = "200")]
     pub threads: usize,
     
+    /// Seconds over which concurrency grows from 1 to --threads, so the
+    /// scan doesn't open with a burst of connects; 0 starts at full speed
+    #[arg(long, value_name = "SECONDS")]
+    pub rampup: Option<u64>,
+    
     /// Enable stealth mode (SYN scan)
     #[arg(long)]
     pub stealth: bool,
     
-    /// Enable UDP scanning
+    /// Also scan the selected ports over UDP; results are merged with TCP
     #[arg(long)]
     pub udp: bool,
     
+    /// Also send SCTP INIT probes to the selected ports (IPv4, root or
+    /// CAP_NET_RAW; needs a build with --features sctp)
+    #[arg(long)]
+    pub sctp: bool,
+    
     /// Rate limit (scans per second)
     #[arg(long)]
     pub rate_limit: Option<u32>,
+    
+    /// Packets per second ceiling (probes and responses, estimated), e.g.
+    /// to stay under an uplink's capacity. Independent of --threads
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_pps: Option<u32>,
+    
+    /// Shuffle the port dispatch order
+    #[arg(long)]
+    pub randomize: bool,
+    
+    /// Derive per-port timeout from observed RTTs (--timeout seeds the first probes)
+    #[arg(long)]
+    pub adaptive_timeout: bool,
+    
+    /// Re-probe a port that timed out up to N more times before calling it
+    /// filtered (lossy links); refused ports are never retried
+    #[arg(long, default_value = "0")]
+    pub max_retries: u32,
+    
+    /// Skip reverse DNS lookups for the target and traceroute hops
+    #[arg(long)]
+    pub no_reverse_dns: bool,
+    
+    /// Name server(s) for hostname and reverse lookups (e.g. 10.0.0.53,1.1.1.1)
+    /// instead of scanner.dns_servers or the system resolver
+    #[arg(long, value_delimiter = ',')]
+    pub dns_server: Vec<std::net::IpAddr>,
+    
+    /// Seed for the shuffled order (implies --randomize)
+    #[arg(long)]
+    pub seed: Option<u64>,
+    
+    /// Decoy source addresses for SYN scans (e.g. 10.0.0.5,ME,10.0.0.9).
+    /// Requires raw sockets; ME marks the real source and must be included
+    #[arg(long, value_delimiter = ',')]
+    pub decoys: Vec<crate::scanner::Decoy>,
+    
+    /// Ports or ranges to skip (e.g. 25,8000-8100)
+    #[arg(long, value_delimiter = ',')]
+    pub exclude_ports: Vec<PortRange>,
+    
+    /// Stop at the first open port (quick "is anything listening" check)
+    #[arg(long)]
+    pub stop_on_first_open: bool,
+    
+    /// Probe ports on security.dangerous_ports (printers, industrial
+    /// controllers) without asking; logged as a security event
+    #[arg(long)]
+    pub i_understand_the_risk: bool,
+    
+    /// Start even outside security.scan_window (e.g. an approved emergency
+    /// change); logged as a security event
+    #[arg(long)]
+    pub outside_scan_window: bool,
+    
+    /// Route TCP connect scans through a SOCKS5 proxy
+    /// (socks5://[user:pass@]host:port). Not usable with --stealth, --decoys, --udp or --sctp
+    #[arg(long)]
+    pub proxy: Option<crate::scanner::ProxyConfig>,
+    
+    /// Fast sweep recording only port and status: no service, banner or OS
+    /// detection, and at least 1000 concurrent probes. Run `enrich` afterwards
+    #[arg(long)]
+    pub discovery_only: bool,
+    
+    /// Scan every port even if the host doesn't answer the TCP (80/443/22)
+    /// and ICMP pings of the host discovery phase
+    #[arg(long)]
+    pub skip_host_discovery: bool,
+    
+    /// Keep closed and filtered ports in the result and database, e.g. to
+    /// prove a port is closed. Memory and storage grow with the port count
+    #[arg(long)]
+    pub all_statuses: bool,
+    
+    /// Probe every open port a second time and report ports that don't
+    /// answer again as open|filtered (scanner.verify_open_ports)
+    #[arg(long)]
+    pub verify_open: bool,
+    
+    /// Only re-probe ports the latest stored scan of the target found open
+    /// or never scanned; the rest are carried over from that scan
+    #[arg(long)]
+    pub incremental: bool,
+    
+    /// How old a carried-over result may be with --incremental
+    /// (e.g. 30m, 1h, 2d)
+    #[arg(long, default_value = "1h", requires = "incremental")]
+    pub max_age: MaxAge,
+    
+    /// Local address connect probes originate from (multi-homed hosts).
+    /// Must be assigned to this host
+    #[arg(long)]
+    pub source_ip: Option<std::net::IpAddr>,
+    
+    /// Fixed local port for every connect probe
+    #[arg(long)]
+    pub source_port: Option<u16>,
+    
+    /// Service detection effort: 0 = port guess + passive banner,
+    /// 9 = every protocol probe and TLS handshake on every port [default: 7]
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=9))]
+    pub version_intensity: Option<u8>,
+    
+    /// Scan techniques tried in order until one is usable (e.g. syn,connect).
+    /// Fails instead of falling back past the end of the list
+    #[arg(long, value_delimiter = ',')]
+    pub technique_chain: Vec<crate::scanner::ScanTechnique>,
+    
+    /// CI gate: print only the listed ports found open and exit 2 if there
+    /// are any (0 = clean, 1 = error). Normal output is suppressed
+    #[arg(long, value_delimiter = ',')]
+    pub fail_on_open: Vec<PortRange>,
+    
+    /// Output mode. `ndjson` prints one JSON object per open port as it is
+    /// discovered (discovery order, not sorted by port)
+    #[arg(long, default_value = "table")]
+    pub output: ScanOutput,
+    
+    /// Order of the displayed and auto-exported open ports; the stored scan
+    /// stays in port order
+    #[arg(long, default_value = "port")]
+    pub sort_by: SortBy,
+    
+    /// Label the saved scan (e.g. --tag q3-audit --tag prod); repeatable,
+    /// find the scans again with `history --tag`
+    #[arg(long)]
+    pub tag: Vec<String>,
 }
 
 #[derive(clap::Args)]
@@ -104,10 +321,109 @@ pub struct VulnerabilityArgs {
     /// Output format for vulnerabilities
     #[arg(long, default_value = "table")]
     pub format: VulnOutputFormat,
+    
+    /// Count suppressed findings in the summary
+    #[arg(long)]
+    pub include_suppressed: bool,
+    
+    /// Only list (and auto-export) findings at or above this severity; the
+    /// summary still counts every finding, and the stored report keeps them all
+    #[arg(long)]
+    pub min_level: Option<Severity>,
+    
+    /// CI gate: print only findings at or above this severity and exit 2 if
+    /// there are any (0 = clean, 1 = error)
+    #[arg(long)]
+    pub fail_on_severity: Option<Severity>,
+}
+
+#[derive(clap::Args)]
+pub struct VulnDiffArgs {
+    /// Scan ID of the earlier assessment
+    pub previous: String,
+    
+    /// Scan ID of the later assessment
+    pub current: String,
+    
+    /// Output format
+    #[arg(long, default_value = "table")]
+    pub format: VulnOutputFormat,
+    
+    /// Compare suppressed findings too
+    #[arg(long)]
+    pub include_suppressed: bool,
+}
+
+#[derive(clap::Args)]
+pub struct ScanDiffArgs {
+    /// Scan ID of the earlier scan
+    pub previous: String,
+    
+    /// Scan ID of the later scan
+    pub current: String,
+    
+    /// Output file path of the HTML page
+    #[arg(short, long)]
+    pub output_path: Option<std::path::PathBuf>,
+}
+
+#[derive(clap::Args)]
+pub struct SuppressArgs {
+    /// Suppression action
+    #[command(subcommand)]
+    pub action: SuppressAction,
+}
+
+#[derive(Subcommand)]
+pub enum SuppressAction {
+    /// Suppress a finding for a target
+    Add {
+        /// Target the suppression applies to
+        target: String,
+        
+        /// CVE ID, or finding title for non-CVE checks
+        rule_id: String,
+        
+        /// Port to match (omit to match any port)
+        #[arg(long)]
+        port: Option<u16>,
+        
+        /// Why the finding is accepted
+        #[arg(long)]
+        reason: String,
+        
+        /// Expire the suppression after this many days
+        #[arg(long)]
+        expires_in_days: Option<i64>,
+    },
+    /// List suppressions
+    List {
+        /// Only show suppressions for this target
+        #[arg(long)]
+        target: Option<String>,
+    },
+    /// Remove a suppression by ID
+    Remove {
+        /// Suppression ID
+        id: String,
+    },
+}
+
+#[derive(clap::Args)]
+pub struct EnrichArgs {
+    /// Scan ID whose open ports should be enriched
+    pub scan_id: String,
+    
+    /// Service detection effort (see `scan --version-intensity`)
+    #[arg(long, default_value = "7", value_parser = clap::value_parser!(u8).range(0..=9))]
+    pub version_intensity: u8,
 }
 
 #[derive(clap::Args)]
 pub struct HistoryArgs {
+    #[command(subcommand)]
+    pub action: Option<HistoryAction>,
+    
     /// Number of scans to show
     #[arg(short, long, default_value = "10")]
     pub limit: usize,
@@ -115,6 +431,164 @@ pub struct HistoryArgs {
     /// Show detailed information
     #[arg(short, long)]
     pub detailed: bool,
+    
+    /// List each scan's open ports (port/service) under it
+    #[arg(long)]
+    pub show_ports: bool,
+    
+    /// Only scans carrying this tag
+    #[arg(long)]
+    pub tag: Option<String>,
+}
+
+#[derive(Subcommand)]
+pub enum HistoryAction {
+    /// Delete every stored scan of a target, with its ports, statistics
+    /// and vulnerability findings (e.g. for a decommissioned host)
+    Purge {
+        /// Target exactly as it was scanned
+        #[arg(long)]
+        target: String,
+        
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Most-scanned targets, with the most open ports any scan found
+    Top {
+        /// Number of targets to show
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+    },
+    /// Add a tag to a stored scan
+    Tag {
+        scan_id: String,
+        tag: String,
+    },
+    /// Remove a tag from a stored scan
+    Untag {
+        scan_id: String,
+        tag: String,
+    },
+}
+
+#[derive(clap::Args)]
+pub struct TimelineArgs {
+    /// Target IP address or hostname
+    pub target: String,
+    
+    /// Number of days to look back
+    #[arg(long, default_value = "30")]
+    pub days: i64,
+}
+
+#[derive(clap::Args)]
+pub struct ListPortsArgs {
+    /// Scan type [default: standard]
+    #[arg(short, long, conflicts_with_all = ["preset", "port_range"])]
+    pub scan_type: Option<ScanType>,
+    
+    /// Named preset from the config
+    #[arg(long, conflicts_with = "port_range")]
+    pub preset: Option<String>,
+    
+    /// Custom port range (e.g., 1-1000)
+    #[arg(short, long)]
+    pub port_range: Option<PortRange>,
+    
+    /// Ports or ranges to skip, on top of scanner.exclude_ports
+    #[arg(long, value_delimiter = ',')]
+    pub exclude_ports: Vec<PortRange>,
+}
+
+#[derive(clap::Args)]
+pub struct EstimateArgs {
+    /// Target IP address or hostname
+    pub target: String,
+    
+    /// Scan type [default: standard]
+    #[arg(short, long)]
+    pub scan_type: Option<ScanType>,
+    
+    /// Custom port range (e.g., 1-1000)
+    #[arg(short, long)]
+    pub port_range: Option<PortRange>,
+    
+    /// Timeout in milliseconds [default: 100]
+    #[arg(long)]
+    pub timeout: Option<u64>,
+    
+    /// Maximum concurrent threads
+    #[arg(long, default_value = "200")]
+    pub threads: usize,
+    
+    /// Seconds over which concurrency grows from 1 to --threads
+    #[arg(long, value_name = "SECONDS")]
+    pub rampup: Option<u64>,
+    
+    /// Rate limit (scans per second)
+    #[arg(long)]
+    pub rate_limit: Option<u32>,
+    
+    /// Packets per second ceiling
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_pps: Option<u32>,
+    
+    /// Re-probes of a port that timed out
+    #[arg(long, default_value = "0")]
+    pub max_retries: u32,
+    
+    /// Include a UDP pass over the same ports
+    #[arg(long)]
+    pub udp: bool,
+    
+    /// Port + status only, without service detection or banner grabbing
+    #[arg(long)]
+    pub discovery_only: bool,
+    
+    /// Ports or ranges to skip, on top of scanner.exclude_ports
+    #[arg(long, value_delimiter = ',')]
+    pub exclude_ports: Vec<PortRange>,
+}
+
+#[derive(clap::Args)]
+pub struct CompareTechniquesArgs {
+    /// Target IP address or hostname
+    pub target: String,
+    
+    /// Techniques to run, in column order
+    #[arg(long, value_delimiter = ',', default_value = "connect,syn,udp")]
+    pub techniques: Vec<crate::scanner::ComparedTechnique>,
+    
+    /// Scan type [default: standard]
+    #[arg(short, long)]
+    pub scan_type: Option<ScanType>,
+    
+    /// Custom port range (e.g., 1-1000)
+    #[arg(short, long)]
+    pub port_range: Option<PortRange>,
+    
+    /// Timeout in milliseconds [default: 100]
+    #[arg(long)]
+    pub timeout: Option<u64>,
+    
+    /// Print every probed port, not only open ones and disagreements
+    #[arg(long)]
+    pub all: bool,
+    
+    /// Probe ports on security.dangerous_ports without asking
+    #[arg(long)]
+    pub i_understand_the_risk: bool,
+    
+    /// Start even outside security.scan_window; logged as a security event
+    #[arg(long)]
+    pub outside_scan_window: bool,
+}
+
+#[derive(clap::Args)]
+pub struct ImportArgs {
+    /// JSON file written by `export --format json`
+    pub file: std::path::PathBuf,
 }
 
 #[derive(clap::Args)]
@@ -122,10 +596,40 @@ pub struct ExportArgs {
     /// Scan ID to export
     pub scan_id: String,
     
+    /// Export format; inferred from the output path's extension when
+    /// omitted, else export.default_format
+    #[arg(short, long)]
+    pub format: Option<ExportFormat>,
+    
+    /// Output file path
+    #[arg(short, long)]
+    pub output_path: Option<std::path::PathBuf>,
+    
+    /// List closed and filtered ports too (JSON and CSV), if the scan
+    /// recorded them with --all-statuses
+    #[arg(long)]
+    pub all_ports: bool,
+    
+    /// Order of the exported open ports; HTML reports sorted by service get
+    /// a header per service group
+    #[arg(long, default_value = "port")]
+    pub sort_by: SortBy,
+}
+
+#[derive(clap::Args)]
+pub struct ExportBatchArgs {
+    /// Target whose scans should be exported
+    #[arg(long)]
+    pub target: String,
+    
     /// Export format
     #[arg(short, long, default_value = "json")]
     pub format: ExportFormat,
     
+    /// Bundle individual scan files into a zip archive
+    #[arg(long)]
+    pub zip: bool,
+    
     /// Output file path
     #[arg(short, long)]
     pub output_path: Option<std::path::PathBuf>,
@@ -178,6 +682,56 @@ pub enum ExportFormat {
     Pdf,
     Html,
     Xml,
+    /// Compact binary archive (MessagePack), reloadable with PzrExporter::load_scan
+    Pzr,
+}
+
+#[derive(clap::ValueEnum, Clone, Debug, PartialEq)]
+pub enum ScanOutput {
+    Table,
+    Ndjson,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum SortBy {
+    Port,
+    /// Grouped by kind of service: web, database, remote access...
+    Service,
+    /// Fastest to answer first
+    ResponseTime,
+}
+
+impl From<SortBy> for crate::scanner::PortOrder {
+    fn from(sort_by: SortBy) -> Self {
+        use crate::scanner::PortOrder;
+        match sort_by {
+            SortBy::Port => PortOrder::Port,
+            SortBy::Service => PortOrder::Service,
+            SortBy::ResponseTime => PortOrder::ResponseTime,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Debug)]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl From<&Severity> for crate::vulnerability::VulnerabilityLevel {
+    fn from(severity: &Severity) -> Self {
+        use crate::vulnerability::VulnerabilityLevel;
+        match severity {
+            Severity::Info => VulnerabilityLevel::Info,
+            Severity::Low => VulnerabilityLevel::Low,
+            Severity::Medium => VulnerabilityLevel::Medium,
+            Severity::High => VulnerabilityLevel::High,
+            Severity::Critical => VulnerabilityLevel::Critical,
+        }
+    }
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
@@ -197,6 +751,11 @@ impl std::str::FromStr for PortRange {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // A bare port is a single-port range
+        if let Ok(port) = s.parse::<u16>() {
+            return Ok(PortRange { start: port, end: port });
+        }
+        
         let parts: Vec<&str> = s.split('-').collect();
         if parts.len() != 2 {
             return Err("Port range must be in format 'start-end'".to_string());
@@ -215,4 +774,65 @@ impl std::str::FromStr for PortRange {
     }
 }
 
+impl PortRange {
+    pub fn port_count(&self) -> usize {
+        (self.end - self.start) as usize + 1
+    }
+}
+
+/// Scan-size limit of `security.max_ports_per_scan`, shared by `scan` and
+/// the interactive picker
+pub fn validate_port_range(range: &PortRange, security: &crate::config::SecuritySettings) -> crate::error::Result<()> {
+    if range.start > range.end {
+        return Err(crate::error::Error::Validation("Invalid port range: start must be <= end".into()));
+    }
+    
+    if range.port_count() > security.max_ports_per_scan as usize {
+        return Err(crate::error::Error::Validation(format!(
+            "Port range too large: {} ports (max: {})",
+            range.port_count(), security.max_ports_per_scan
+        )));
+    }
+    Ok(())
+}
+
+/// `validate_port_range` for the ports of a preset
+pub fn validate_preset_size(
+    preset: &crate::config::ScanPreset,
+    security: &crate::config::SecuritySettings,
+) -> crate::error::Result<()> {
+    let port_count = preset.port_list()?.len();
+    if port_count > security.max_ports_per_scan as usize {
+        return Err(crate::error::Error::Validation(format!(
+            "Preset '{}' has too many ports: {} (max: {})",
+            preset.name, port_count, security.max_ports_per_scan
+        )));
+    }
+    Ok(())
+}
+
+/// Age limit written as a number with an s, m, h or d suffix
+#[derive(Clone, Debug, PartialEq)]
+pub struct MaxAge(pub std::time::Duration);
+
+impl std::str::FromStr for MaxAge {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (amount, unit) = s.split_at(split);
+        let amount: u64 = amount.parse()
+            .map_err(|_| format!("Invalid age '{}' (expected e.g. 30m, 1h or 2d)", s))?;
+        let seconds = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            _ => return Err(format!("Invalid age unit in '{}' (expected s, m, h or d)", s)),
+        };
+        Ok(MaxAge(std::time::Duration::from_secs(amount.saturating_mul(seconds))))
+    }
+}
+
 // Implementation continues...
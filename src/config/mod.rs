@@ -1,37 +1,54 @@
 pub mod settings;
+pub mod presets;
+pub mod scan_window;
 pub mod validation;
+pub mod watcher;
 
-pub use settings::{Settings, ScannerSettings, DatabaseSettings, ExportSettings, SecuritySettings, LoggingSettings};
+pub use settings::{Settings, ScannerSettings, DatabaseSettings, ExportSettings, SecuritySettings, JwtSettings, LoggingSettings, VulnerabilitySettings, CveFeedSettings, GeoIpSettings, LogFormat, UiSettings, ColorScheme};
+pub use presets::ScanPreset;
+pub use scan_window::ScanWindow;
 pub use validation::validate_settings;
+pub use watcher::{ConfigWatcher, ReloadListener, SharedSettings};
 
 use crate::error::{Error, Result};
 use std::path::PathBuf;
 
+/// Environment variable selecting the config profile when `--profile` isn't given
+pub const PROFILE_ENV_VAR: &str = "PORTZILLA_PROFILE";
+
 pub struct ConfigManager {
     settings: Settings,
     config_path: PathBuf,
+    profile: Option<String>,
 }
 
 impl ConfigManager {
     pub fn new() -> Result<Self> {
         let config_path = Self::get_config_path()?;
-        let settings = Settings::load(&config_path)?;
-        
-        Ok(Self {
-            settings,
-            config_path,
-        })
+        Self::with_profile(config_path, None)
     }
 
     pub fn with_config_path(config_path: PathBuf) -> Result<Self> {
-        let settings = Settings::load(&config_path)?;
+        Self::with_profile(config_path, None)
+    }
+
+    /// Base config overlaid with `<config dir>/<profile>.toml`. `None` falls
+    /// back to `PORTZILLA_PROFILE`; with neither set only the base is loaded.
+    pub fn with_profile(config_path: PathBuf, profile: Option<String>) -> Result<Self> {
+        let profile = Self::resolve_profile(profile);
+        let settings = Settings::load_with_profile(&config_path, profile.as_deref())?;
         
         Ok(Self {
             settings,
             config_path,
+            profile,
         })
     }
 
+    pub fn active_profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
     pub fn get_settings(&self) -> &Settings {
         &self.settings
     }
@@ -44,8 +61,12 @@ impl ConfigManager {
         self.settings.save(&self.config_path)
     }
 
+    /// Re-read the config files; on a parse or validation error the current
+    /// settings are left untouched
     pub fn reload(&mut self) -> Result<()> {
-        self.settings = Settings::load(&self.config_path)?;
+        let settings = Settings::load_with_profile(&self.config_path, self.profile.as_deref())?;
+        validate_settings(&settings)?;
+        self.settings = settings;
         Ok(())
     }
 
@@ -53,6 +74,12 @@ impl ConfigManager {
         validate_settings(&self.settings)
     }
 
+    fn resolve_profile(explicit: Option<String>) -> Option<String> {
+        explicit
+            .or_else(|| std::env::var(PROFILE_ENV_VAR).ok())
+            .filter(|profile| !profile.trim().is_empty())
+    }
+
     fn get_config_path() -> Result<PathBuf> {
         let mut path = std::env::current_dir()?;
         path.push("config");
@@ -77,7 +104,30 @@ impl Default for ConfigManager {
             Self {
                 settings,
                 config_path,
+                profile: None,
             }
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_overrides_max_threads_and_inherits_rest() {
+        let dir = tempfile::tempdir().unwrap();
+        let base_path = dir.path().join("default.toml");
+        Settings::default().save(&base_path).unwrap();
+        std::fs::write(dir.path().join("staging.toml"), "[scanner]\nmax_threads = 50\n").unwrap();
+
+        let manager = ConfigManager::with_profile(base_path, Some("staging".to_string())).unwrap();
+        let settings = manager.get_settings();
+        let defaults = Settings::default();
+
+        assert_eq!(manager.active_profile(), Some("staging"));
+        assert_eq!(settings.scanner.max_threads, 50);
+        assert_eq!(settings.scanner.default_timeout_ms, defaults.scanner.default_timeout_ms);
+        assert_eq!(settings.database.connection_string, defaults.database.connection_string);
+    }
+}
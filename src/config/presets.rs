@@ -0,0 +1,158 @@
+use crate::error::{Error, Result};
+use crate::scanner::{ScanConfig, ScanType};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Named bundle of scan options, declared as `[[presets]]` in the config and
+/// selected with `scan --preset <name>`.
+///
+/// Options left out keep the configured defaults, and explicit CLI flags win
+/// over the preset. Config presets shadow built-in ones of the same name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanPreset {
+    pub name: String,
+    pub ports: String, // Comma-separated ports and ranges, e.g. "80,443,8000-8100"
+    #[serde(default)]
+    pub service_detection: Option<bool>,
+    #[serde(default)]
+    pub banner: Option<bool>,
+    #[serde(default)]
+    pub version_intensity: Option<u8>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub udp: Option<bool>,
+}
+
+impl ScanPreset {
+    /// Sorted, de-duplicated ports of the preset
+    pub fn port_list(&self) -> Result<Vec<u16>> {
+        let mut ports = Vec::new();
+        for item in self.ports.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            let (start, end) = item.split_once('-').unwrap_or((item, item));
+            match (start.trim().parse::<u16>(), end.trim().parse::<u16>()) {
+                (Ok(start), Ok(end)) if start <= end => ports.extend(start..=end),
+                _ => {
+                    return Err(Error::Validation(format!(
+                        "Preset '{}': invalid port or range '{}'", self.name, item
+                    )));
+                }
+            }
+        }
+
+        if ports.is_empty() {
+            return Err(Error::Validation(format!("Preset '{}' has no ports", self.name)));
+        }
+        ports.sort_unstable();
+        ports.dedup();
+        Ok(ports)
+    }
+
+    pub fn scan_type(&self) -> Result<ScanType> {
+        Ok(ScanType::Targeted(self.port_list()?))
+    }
+
+    /// `config` with every option the preset sets replaced
+    pub fn apply(&self, mut config: ScanConfig) -> ScanConfig {
+        if let Some(service_detection) = self.service_detection {
+            config.enable_service_detection = service_detection;
+        }
+        if let Some(banner) = self.banner {
+            config.enable_banner_grabbing = banner;
+        }
+        if let Some(version_intensity) = self.version_intensity {
+            config.version_intensity = version_intensity.min(9);
+        }
+        if let Some(timeout_ms) = self.timeout_ms {
+            config.timeout = Duration::from_millis(timeout_ms);
+        }
+        if let Some(udp) = self.udp {
+            config.enable_udp = udp;
+        }
+        config
+    }
+}
+
+/// Presets available without any `[[presets]]` in the config
+pub fn builtin_presets() -> Vec<ScanPreset> {
+    vec![
+        ScanPreset {
+            name: "web-audit".to_string(),
+            ports: "80,443,8000,8008,8080,8443,8888".to_string(),
+            service_detection: Some(true),
+            banner: Some(true),
+            version_intensity: Some(9), // TLS handshake on every port
+            timeout_ms: None,
+            udp: None,
+        },
+        ScanPreset {
+            name: "db-audit".to_string(),
+            ports: "1433,1521,3306,5432,5984,6379,9042,9200,11211,27017".to_string(),
+            service_detection: Some(true),
+            banner: Some(true),
+            version_intensity: None,
+            timeout_ms: None,
+            udp: None,
+        },
+        ScanPreset {
+            name: "quick-triage".to_string(),
+            ports: "21,22,23,25,53,80,110,139,143,443,445,3389,8080".to_string(),
+            service_detection: Some(false),
+            banner: Some(false),
+            version_intensity: None,
+            timeout_ms: Some(300),
+            udp: None,
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Settings;
+
+    #[test]
+    fn test_builtin_preset_expands_to_config_and_ports() {
+        let preset = Settings::default().preset("web-audit").unwrap();
+
+        let config = preset.apply(ScanConfig {
+            enable_service_detection: false,
+            enable_banner_grabbing: false,
+            ..ScanConfig::default()
+        });
+        assert!(config.enable_service_detection);
+        assert!(config.enable_banner_grabbing);
+        assert_eq!(config.version_intensity, 9);
+        // Not set by the preset: left alone
+        assert_eq!(config.timeout, ScanConfig::default().timeout);
+        assert!(!config.enable_udp);
+
+        assert_eq!(preset.port_list().unwrap(), vec![80, 443, 8000, 8008, 8080, 8443, 8888]);
+    }
+
+    #[test]
+    fn test_config_preset_shadows_builtin() {
+        let mut settings = Settings::default();
+        settings.presets = vec![toml::from_str(r#"
+            name = "quick-triage"
+            ports = "8080, 22, 1-3, 22"
+            timeout_ms = 50
+        "#).unwrap()];
+
+        let preset = settings.preset("quick-triage").unwrap();
+        assert_eq!(preset.port_list().unwrap(), vec![1, 2, 3, 22, 8080]);
+        assert_eq!(preset.apply(ScanConfig::default()).timeout, Duration::from_millis(50));
+
+        assert!(settings.preset("db-audit").is_ok());
+        assert!(matches!(settings.preset("nope"), Err(Error::Validation(_))));
+    }
+
+    #[test]
+    fn test_invalid_ports_are_rejected() {
+        let mut preset = builtin_presets().remove(0);
+        for ports in ["80,http", "9000-80", "", "70000"] {
+            preset.ports = ports.to_string();
+            assert!(preset.port_list().is_err(), "{:?} should be rejected", ports);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use crate::error::{Error, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// Daily time span scans may start in, declared as `[security.scan_window]`
+/// for change-management rules such as "production only in the nightly
+/// maintenance window".
+///
+/// A window belongs to the day it opens on: `days = ["weekdays"]` with
+/// 22:00–04:00 allows Friday 22:00 to Saturday 04:00, but not Monday 02:00.
+/// Times are wall-clock times in `timezone`, so the window follows DST.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanWindow {
+    #[serde(default = "default_days")]
+    pub days: Vec<String>, // Day names ("mon", "friday"), "weekdays", "weekends" or "daily"
+    pub start: String, // "HH:MM"
+    pub end: String, // "HH:MM"; earlier than start for a window past midnight
+    #[serde(default = "default_timezone")]
+    pub timezone: String, // IANA name, e.g. "Europe/Berlin"
+}
+
+fn default_days() -> Vec<String> {
+    vec!["daily".to_string()]
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+/// One occurrence of the window, as instants
+struct Opening {
+    start: DateTime<Tz>,
+    end: DateTime<Tz>,
+}
+
+impl ScanWindow {
+    /// `Error::Validation` for a day, time or time zone that doesn't parse
+    pub fn validate(&self) -> Result<()> {
+        self.parts().map(|_| ())
+    }
+
+    /// `Error::Security` naming the next opening when `now` falls outside
+    /// the window
+    pub fn check(&self, now: DateTime<Utc>) -> Result<()> {
+        let openings = self.openings(now)?;
+        if openings.iter().any(|opening| opening.start <= now && now < opening.end) {
+            return Ok(());
+        }
+
+        let next = openings.iter()
+            .map(|opening| opening.start)
+            .find(|start| *start > now)
+            .map(|start| start.format("%a %Y-%m-%d %H:%M %Z").to_string())
+            .unwrap_or_else(|| "never".to_string());
+        Err(Error::Security(format!(
+            "Scans may only start in the scan window ({}); the next one opens {}. \
+             Pass --outside-scan-window to override",
+            self, next
+        )))
+    }
+
+    pub fn contains(&self, now: DateTime<Utc>) -> Result<bool> {
+        match self.check(now) {
+            Ok(()) => Ok(true),
+            Err(Error::Security(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Openings from the day before `now` (one still running past midnight)
+    /// to a week after it, in order
+    fn openings(&self, now: DateTime<Utc>) -> Result<Vec<Opening>> {
+        let (days, start, end, timezone) = self.parts()?;
+        let today = now.with_timezone(&timezone).date_naive();
+
+        Ok((-1..=7)
+            .map(|offset| today + Duration::days(offset))
+            .filter(|date| days.contains(&date.weekday()))
+            .map(|date| {
+                let end_date = if end <= start { date + Duration::days(1) } else { date };
+                Opening { start: local(&timezone, date, start), end: local(&timezone, end_date, end) }
+            })
+            .collect())
+    }
+
+    fn parts(&self) -> Result<(Vec<Weekday>, NaiveTime, NaiveTime, Tz)> {
+        let mut days = Vec::new();
+        for day in &self.days {
+            match day.trim().to_lowercase().as_str() {
+                "daily" => days.extend(ALL_DAYS),
+                "weekdays" => days.extend(&ALL_DAYS[..5]),
+                "weekends" => days.extend(&ALL_DAYS[5..]),
+                name => days.push(name.parse::<Weekday>().map_err(|_| Error::Validation(format!(
+                    "security.scan_window: unknown day '{}' (expected e.g. mon, weekdays, weekends or daily)", day
+                )))?),
+            }
+        }
+        if days.is_empty() {
+            return Err(Error::Validation("security.scan_window: days must not be empty".to_string()));
+        }
+
+        let time = |field: &str, value: &str| NaiveTime::parse_from_str(value.trim(), "%H:%M").map_err(|_| {
+            Error::Validation(format!("security.scan_window.{}: '{}' is not an HH:MM time", field, value))
+        });
+        let (start, end) = (time("start", &self.start)?, time("end", &self.end)?);
+        if start == end {
+            return Err(Error::Validation(
+                "security.scan_window: start and end are equal; remove the window to allow scans at any time".to_string()
+            ));
+        }
+
+        let timezone = self.timezone.trim().parse::<Tz>().map_err(|_| Error::Validation(format!(
+            "security.scan_window.timezone: unknown time zone '{}' (expected an IANA name like Europe/Berlin)",
+            self.timezone
+        )))?;
+        Ok((days, start, end, timezone))
+    }
+}
+
+const ALL_DAYS: [Weekday; 7] = [
+    Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun,
+];
+
+/// `time` on `date` in `timezone`; a time skipped by a DST jump moves an
+/// hour later, a repeated one takes its first occurrence
+fn local(timezone: &Tz, date: NaiveDate, time: NaiveTime) -> DateTime<Tz> {
+    let naive = date.and_time(time);
+    timezone.from_local_datetime(&naive).earliest()
+        .or_else(|| timezone.from_local_datetime(&(naive + Duration::hours(1))).earliest())
+        .unwrap_or_else(|| timezone.from_utc_datetime(&naive))
+}
+
+impl std::fmt::Display for ScanWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}–{} {}", self.days.join(", "), self.start, self.end, self.timezone)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nightly() -> ScanWindow {
+        ScanWindow {
+            days: vec!["weekdays".to_string()],
+            start: "22:00".to_string(),
+            end: "04:00".to_string(),
+            timezone: "Europe/Berlin".to_string(),
+        }
+    }
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_window_follows_days_and_time_zone() {
+        let window = nightly();
+
+        // Wednesday 23:30 and Saturday 03:00 in Berlin (summer time, UTC+2);
+        // the latter is still Friday's window
+        assert!(window.contains(at("2024-05-08T21:30:00Z")).unwrap());
+        assert!(window.contains(at("2024-05-11T01:00:00Z")).unwrap());
+
+        // Wednesday 21:30 in Berlin: not open yet
+        assert!(!window.contains(at("2024-05-08T19:30:00Z")).unwrap());
+        // Saturday night isn't a weekday window, nor is Monday 02:00
+        assert!(!window.contains(at("2024-05-11T21:30:00Z")).unwrap());
+        assert!(!window.contains(at("2024-05-13T00:00:00Z")).unwrap());
+    }
+
+    #[test]
+    fn test_rejection_names_the_next_window() {
+        // Saturday noon: the next window is Monday night
+        match nightly().check(at("2024-05-11T10:00:00Z")) {
+            Err(Error::Security(message)) => {
+                assert!(message.contains("next one opens Mon 2024-05-13 22:00 CEST"), "{}", message);
+                assert!(message.contains("--outside-scan-window"), "{}", message);
+            }
+            other => panic!("expected a security error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_invalid_windows_fail_validation() {
+        let mut window = nightly();
+        window.timezone = "Mars/Olympus_Mons".to_string();
+        assert!(matches!(window.validate(), Err(Error::Validation(_))));
+
+        let mut window = nightly();
+        window.days = vec!["someday".to_string()];
+        assert!(matches!(window.validate(), Err(Error::Validation(_))));
+
+        let mut window = nightly();
+        window.end = "22:00".to_string();
+        assert!(matches!(window.validate(), Err(Error::Validation(_))));
+
+        assert!(nightly().validate().is_ok());
+    }
+}
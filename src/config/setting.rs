@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use std::net::IpAddr;
 use std::path::PathBuf;
 use crate::error::{Error, Result};
+use super::presets::{builtin_presets, ScanPreset};
+use super::scan_window::ScanWindow;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
@@ -11,21 +13,73 @@ pub struct Settings {
     pub security: SecuritySettings,
     pub logging: LoggingSettings,
     pub ui: UiSettings,
+    #[serde(default)]
+    pub vulnerability: VulnerabilitySettings,
+    #[serde(default)]
+    pub geoip: GeoIpSettings,
+    #[serde(default)]
+    pub presets: Vec<ScanPreset>, // Added to (and shadowing) the built-in presets
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScannerSettings {
     pub default_timeout_ms: u64,
     pub max_threads: usize,
+    #[serde(default)]
+    pub connect_max_threads: Option<usize>, // Per-technique override of max_threads, see ScanConfig::concurrency_for
+    #[serde(default)]
+    pub syn_max_threads: Option<usize>,
+    #[serde(default)]
+    pub udp_max_threads: Option<usize>,
+    #[serde(default)]
+    pub rampup_secs: u64, // Warm-up from 1 to max_threads in-flight probes, see ScanConfig::rampup_duration; 0 = none
     pub chunk_size: usize,
     pub syn_scan_enabled: bool,
     pub udp_scan_enabled: bool,
     pub rate_limit: Option<u32>,
+    #[serde(default)]
+    pub max_pps: Option<u32>, // Packets per second ceiling on probes, see ScanConfig::max_pps
     pub stealth_mode: bool,
     pub enable_service_detection: bool,
     pub enable_banner_grabbing: bool,
     pub enable_os_detection: bool,
     pub enable_traceroute: bool,
+    #[serde(default)]
+    pub exclude_ports: Vec<u16>, // Org-wide ports that are never probed
+    #[serde(default)]
+    pub probe_allow_ports: Vec<u16>, // Only these open ports get service detection/banner grabbing; empty = all
+    #[serde(default)]
+    pub probe_deny_ports: Vec<u16>, // Open ports recorded as found but never sent service or banner probes
+    #[serde(default = "default_global_max_concurrent")]
+    pub global_max_concurrent: usize, // In-flight probes across all hosts of a multi-target scan
+    #[serde(default = "default_enrich_concurrency")]
+    pub enrich_concurrency: usize, // Open ports in service detection/banner grabbing at once
+    #[serde(default = "default_banner_buffer_size")]
+    pub banner_buffer_size: usize,
+    #[serde(default = "default_banner_max_length")]
+    pub banner_max_length: usize,
+    #[serde(default = "default_max_response_bytes")]
+    pub max_response_bytes: usize, // Hard cap on one service response; guards against endless streams
+    #[serde(default = "default_auto_tune_concurrency")]
+    pub auto_tune_concurrency: bool, // Lower max_threads/global_max_concurrent to fit the open-file limit
+    #[serde(default)]
+    pub record_all_statuses: bool, // Keep closed/filtered ports too, e.g. to prove a port is closed
+    #[serde(default)]
+    pub verify_open_ports: bool, // Recheck open ports; those not open again are reported open|filtered
+    #[serde(default = "default_host_discovery")]
+    pub host_discovery: bool, // Skip the port scan of hosts that don't answer a ping
+    #[serde(default = "default_tcp_nodelay")]
+    pub tcp_nodelay: bool,
+    #[serde(default = "default_tcp_linger_reset")]
+    pub tcp_linger_reset: bool, // Close connect probes with a RST instead of a FIN
+    #[serde(default = "default_checkpoint_interval")]
+    pub checkpoint_interval: usize, // Completed probes between saved checkpoints; 0 = never
+    #[serde(default)]
+    pub dns_servers: Vec<std::net::IpAddr>, // Name servers for target and PTR lookups; empty = the system's
+    #[serde(default = "default_probe_identity")]
+    pub probe_identity: String, // Sent as HTTP User-Agent and in the SSH client banner, so scan traffic is attributable
+    #[serde(default = "default_http_keep_alive")]
+    pub http_keep_alive: bool, // HTTP probes of one host reuse kept-alive connections
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +89,14 @@ pub struct DatabaseSettings {
     pub enable_migrations: bool,
     pub backup_enabled: bool,
     pub backup_interval_hours: u32,
+    #[serde(default = "default_connect_attempts")]
+    pub connect_attempts: u32, // Tries at startup before giving up on an unreachable database
+    #[serde(default = "default_connect_retry_interval_ms")]
+    pub connect_retry_interval_ms: u64, // Wait after the first failed try; doubles per retry, up to 30s
+    #[serde(default)]
+    pub retain_days: u32, // Server mode deletes scans older than this; 0 = keep forever
+    #[serde(default = "default_retention_interval_hours")]
+    pub retention_interval_hours: u32, // How often the retention cleanup runs
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,6 +106,10 @@ pub struct ExportSettings {
     pub output_directory: String,
     pub include_timestamps: bool,
     pub compress_exports: bool,
+    #[serde(default)]
+    pub include_all_ports: bool, // Full port table (closed/filtered too) in JSON and CSV exports
+    #[serde(default)]
+    pub min_service_confidence: u8, // Services detected with less are reported as "unknown (guessed: X)"; 0 = state all
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +119,26 @@ pub struct SecuritySettings {
     pub require_authentication: bool,
     pub rate_limiting_enabled: bool,
     pub max_scans_per_hour: u32,
+    #[serde(default)]
+    pub jwt: Option<JwtSettings>, // Accept SSO bearer tokens besides API keys
+    #[serde(default = "default_dangerous_ports")]
+    pub dangerous_ports: Vec<u16>, // Printer/OT ports only probed after --i-understand-the-risk or a confirmation
+    #[serde(default)]
+    pub allow_public_targets: bool, // Off = safe mode: only private, loopback and link-local addresses
+    #[serde(default)]
+    pub scan_window: Option<ScanWindow>, // Scans only start inside it, unless --outside-scan-window
+}
+
+/// Validation of JWT bearer tokens. Keys come from `jwks_url` when set,
+/// otherwise from the PEM at `public_key_path`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JwtSettings {
+    pub issuer: String,
+    pub audience: String,
+    pub jwks_url: Option<String>,
+    pub public_key_path: Option<String>,
+    #[serde(default = "default_jwt_algorithm")]
+    pub algorithm: String, // Of the PEM key; JWKS keys name their own
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,6 +158,110 @@ pub struct UiSettings {
     pub detailed_output: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VulnerabilitySettings {
+    pub custom_rules_path: Option<String>,
+    #[serde(default = "default_cve_feeds")]
+    pub cve_feeds: Vec<CveFeedSettings>,
+    #[serde(default = "default_feed_cache_dir")]
+    pub feed_cache_dir: String, // Last successful download of each feed, reused offline
+}
+
+/// Offline ASN / country lookups from local MaxMind GeoLite2 databases
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GeoIpSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    pub asn_database: Option<String>, // GeoLite2-ASN.mmdb
+    pub country_database: Option<String>, // GeoLite2-Country.mmdb
+}
+
+/// A CVE feed fetched by `--update-db`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CveFeedSettings {
+    pub name: String, // Also the cache file name
+    pub url: String,
+}
+
+fn default_global_max_concurrent() -> usize {
+    1000
+}
+
+fn default_enrich_concurrency() -> usize {
+    crate::scanner::DEFAULT_ENRICH_CONCURRENCY
+}
+
+fn default_banner_buffer_size() -> usize {
+    crate::network::banner_grabber::DEFAULT_BANNER_BUFFER_SIZE
+}
+
+fn default_banner_max_length() -> usize {
+    crate::network::banner_grabber::DEFAULT_BANNER_MAX_LENGTH
+}
+
+fn default_max_response_bytes() -> usize {
+    crate::network::banner_grabber::DEFAULT_MAX_RESPONSE_BYTES
+}
+
+fn default_probe_identity() -> String {
+    crate::network::banner_grabber::DEFAULT_PROBE_IDENTITY.to_string()
+}
+
+fn default_http_keep_alive() -> bool {
+    true
+}
+
+fn default_auto_tune_concurrency() -> bool {
+    true
+}
+
+fn default_jwt_algorithm() -> String {
+    "RS256".to_string()
+}
+
+fn default_host_discovery() -> bool {
+    true
+}
+
+fn default_tcp_nodelay() -> bool {
+    true
+}
+
+fn default_tcp_linger_reset() -> bool {
+    true
+}
+
+fn default_checkpoint_interval() -> usize {
+    1000
+}
+
+fn default_connect_attempts() -> u32 {
+    5
+}
+
+fn default_connect_retry_interval_ms() -> u64 {
+    1000
+}
+
+fn default_retention_interval_hours() -> u32 {
+    6
+}
+
+fn default_dangerous_ports() -> Vec<u16> {
+    crate::scanner::DEFAULT_DANGEROUS_PORTS.to_vec()
+}
+
+fn default_cve_feeds() -> Vec<CveFeedSettings> {
+    vec![CveFeedSettings {
+        name: "nvd".to_string(),
+        url: "https://services.nvd.nist.gov/rest/json/cves/2.0".to_string(),
+    }]
+}
+
+fn default_feed_cache_dir() -> String {
+    "data/feeds".to_string()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ExportFormat {
     Json,
@@ -105,6 +295,27 @@ pub enum ColorScheme {
 }
 
 impl Settings {
+    /// Config preset `name`, else the built-in one
+    pub fn preset(&self, name: &str) -> Result<ScanPreset> {
+        self.presets.iter().cloned()
+            .chain(builtin_presets())
+            .find(|preset| preset.name == name)
+            .ok_or_else(|| Error::Validation(format!(
+                "Unknown preset '{}' (available: {})", name, self.preset_names().join(", ")
+            )))
+    }
+
+    /// Names of the config and built-in presets, sorted
+    pub fn preset_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.presets.iter().cloned()
+            .chain(builtin_presets())
+            .map(|preset| preset.name)
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
     pub fn load(config_path: &PathBuf) -> Result<Self> {
         if config_path.exists() {
             let content = std::fs::read_to_string(config_path)?;
@@ -117,6 +328,39 @@ impl Settings {
         }
     }
 
+    /// Load `config_path`, then overlay `<config dir>/<profile>.toml` on top.
+    /// The profile file only needs the keys it overrides; tables merge
+    /// recursively, any other value replaces the base one.
+    pub fn load_with_profile(config_path: &PathBuf, profile: Option<&str>) -> Result<Self> {
+        let Some(profile) = profile else {
+            return Self::load(config_path);
+        };
+
+        let profile_path = Self::profile_path(config_path, profile);
+        if !profile_path.exists() {
+            return Err(Error::Validation(format!(
+                "Profile '{}' not found (expected {})", profile, profile_path.display()
+            )));
+        }
+
+        let mut base: toml::Value = if config_path.exists() {
+            toml::from_str(&std::fs::read_to_string(config_path)?)?
+        } else {
+            toml::Value::try_from(Settings::default())?
+        };
+        let overrides: toml::Value = toml::from_str(&std::fs::read_to_string(&profile_path)?)?;
+
+        merge_toml(&mut base, overrides);
+        Ok(base.try_into()?)
+    }
+
+    pub fn profile_path(config_path: &PathBuf, profile: &str) -> PathBuf {
+        config_path
+            .parent()
+            .map(|dir| dir.join(format!("{}.toml", profile)))
+            .unwrap_or_else(|| PathBuf::from(format!("{}.toml", profile)))
+    }
+
     pub fn save(&self, config_path: &PathBuf) -> Result<()> {
         let content = toml::to_string_pretty(self)?;
         
@@ -127,14 +371,29 @@ impl Settings {
         std::fs::write(config_path, content)?;
         Ok(())
     }
+}
+
+impl SecuritySettings {
+    /// `Error::Security` for a target off the allowed list or, in safe mode,
+    /// a public IP. Hostnames pass here; the engine checks the address they
+    /// resolve to (`ScanConfig::allow_public_targets`).
+    pub fn check_target(&self, target: &str) -> Result<()> {
+        if !self.is_target_allowed(target) {
+            return Err(Error::Security(format!("Target {} is not in allowed list", target)));
+        }
+        match target.parse::<IpAddr>() {
+            Ok(ip) if !self.allow_public_targets => crate::utils::check_private_target(ip),
+            _ => Ok(()),
+        }
+    }
 
     pub fn is_target_allowed(&self, target: &str) -> bool {
-        if self.security.allowed_targets.is_empty() {
+        if self.allowed_targets.is_empty() {
             return true; // No restrictions
         }
 
         if let Ok(ip_addr) = target.parse::<IpAddr>() {
-            self.security.allowed_targets.contains(&ip_addr)
+            self.allowed_targets.contains(&ip_addr)
         } else {
             // For hostnames, we might want to resolve and check
             // For now, allow all hostnames if IP restrictions are set
@@ -143,6 +402,22 @@ impl Settings {
     }
 }
 
+fn merge_toml(base: &mut toml::Value, overrides: toml::Value) {
+    match (base, overrides) {
+        (toml::Value::Table(base), toml::Value::Table(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, value) => *base = value,
+    }
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self {
@@ -152,6 +427,9 @@ impl Default for Settings {
             security: SecuritySettings::default(),
             logging: LoggingSettings::default(),
             ui: UiSettings::default(),
+            vulnerability: VulnerabilitySettings::default(),
+            geoip: GeoIpSettings::default(),
+            presets: Vec::new(),
         }
     }
 }
@@ -161,15 +439,38 @@ impl Default for ScannerSettings {
         Self {
             default_timeout_ms: 1000,
             max_threads: 200,
+            connect_max_threads: None,
+            syn_max_threads: None,
+            udp_max_threads: None,
+            rampup_secs: 0,
             chunk_size: 100,
             syn_scan_enabled: false,
             udp_scan_enabled: false,
             rate_limit: None,
+            max_pps: None,
             stealth_mode: false,
             enable_service_detection: true,
             enable_banner_grabbing: true,
             enable_os_detection: false,
             enable_traceroute: false,
+            exclude_ports: Vec::new(),
+            probe_allow_ports: Vec::new(),
+            probe_deny_ports: Vec::new(),
+            global_max_concurrent: default_global_max_concurrent(),
+            enrich_concurrency: default_enrich_concurrency(),
+            banner_buffer_size: default_banner_buffer_size(),
+            banner_max_length: default_banner_max_length(),
+            max_response_bytes: default_max_response_bytes(),
+            auto_tune_concurrency: default_auto_tune_concurrency(),
+            record_all_statuses: false,
+            verify_open_ports: false,
+            host_discovery: default_host_discovery(),
+            tcp_nodelay: default_tcp_nodelay(),
+            tcp_linger_reset: default_tcp_linger_reset(),
+            checkpoint_interval: default_checkpoint_interval(),
+            dns_servers: Vec::new(),
+            probe_identity: default_probe_identity(),
+            http_keep_alive: default_http_keep_alive(),
         }
     }
 }
@@ -182,6 +483,10 @@ impl Default for DatabaseSettings {
             enable_migrations: true,
             backup_enabled: true,
             backup_interval_hours: 24,
+            connect_attempts: default_connect_attempts(),
+            connect_retry_interval_ms: default_connect_retry_interval_ms(),
+            retain_days: 0,
+            retention_interval_hours: default_retention_interval_hours(),
         }
     }
 }
@@ -194,6 +499,8 @@ impl Default for ExportSettings {
             output_directory: "exports".to_string(),
             include_timestamps: true,
             compress_exports: false,
+            include_all_ports: false,
+            min_service_confidence: 0,
         }
     }
 }
@@ -206,6 +513,10 @@ impl Default for SecuritySettings {
             require_authentication: false,
             rate_limiting_enabled: true,
             max_scans_per_hour: 10,
+            jwt: None,
+            dangerous_ports: default_dangerous_ports(),
+            allow_public_targets: false,
+            scan_window: None,
         }
     }
 }
@@ -222,6 +533,16 @@ impl Default for LoggingSettings {
     }
 }
 
+impl Default for VulnerabilitySettings {
+    fn default() -> Self {
+        Self {
+            custom_rules_path: None, // Only the built-in rules
+            cve_feeds: default_cve_feeds(),
+            feed_cache_dir: default_feed_cache_dir(),
+        }
+    }
+}
+
 impl Default for UiSettings {
     fn default() -> Self {
         Self {
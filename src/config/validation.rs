@@ -6,6 +6,7 @@ pub fn validate_settings(settings: &Settings) -> Result<()> {
     validate_database_settings(&settings.database)?;
     validate_security_settings(&settings.security)?;
     validate_export_settings(&settings.export)?;
+    validate_presets(&settings.presets)?;
     
     Ok(())
 }
@@ -19,6 +20,16 @@ fn validate_scanner_settings(settings: &super::ScannerSettings) -> Result<()> {
         return Err(Error::Validation("Max threads must be greater than 0".to_string()));
     }
     
+    for (technique, threads) in [
+        ("connect", settings.connect_max_threads),
+        ("syn", settings.syn_max_threads),
+        ("udp", settings.udp_max_threads),
+    ] {
+        if threads == Some(0) {
+            return Err(Error::Validation(format!("{}_max_threads must be greater than 0", technique)));
+        }
+    }
+    
     if settings.chunk_size == 0 {
         return Err(Error::Validation("Chunk size must be greater than 0".to_string()));
     }
@@ -29,6 +40,10 @@ fn validate_scanner_settings(settings: &super::ScannerSettings) -> Result<()> {
         }
     }
     
+    if settings.max_pps == Some(0) {
+        return Err(Error::Validation("max_pps must be greater than 0".to_string()));
+    }
+    
     Ok(())
 }
 
@@ -45,6 +60,10 @@ fn validate_database_settings(settings: &super::DatabaseSettings) -> Result<()>
         return Err(Error::Validation("Backup interval must be greater than 0".to_string()));
     }
     
+    if settings.connect_attempts == 0 {
+        return Err(Error::Validation("Database connect attempts must be greater than 0".to_string()));
+    }
+    
     Ok(())
 }
 
@@ -57,6 +76,10 @@ fn validate_security_settings(settings: &super::SecuritySettings) -> Result<()>
         return Err(Error::Validation("Max scans per hour must be greater than 0".to_string()));
     }
     
+    if let Some(window) = &settings.scan_window {
+        window.validate()?;
+    }
+    
     Ok(())
 }
 
@@ -64,6 +87,22 @@ fn validate_export_settings(settings: &super::ExportSettings) -> Result<()> {
     if settings.output_directory.is_empty() {
         return Err(Error::Validation("Export output directory cannot be empty".to_string()));
     }
+
+    if settings.min_service_confidence > 100 {
+        return Err(Error::Validation("Minimum service confidence must be between 0 and 100".to_string()));
+    }
+
+    Ok(())
+}
+
+fn validate_presets(presets: &[super::ScanPreset]) -> Result<()> {
+    let mut names = std::collections::HashSet::new();
+    for preset in presets {
+        if !names.insert(preset.name.as_str()) {
+            return Err(Error::Validation(format!("Preset '{}' is defined more than once", preset.name)));
+        }
+        preset.port_list()?;
+    }
     
     Ok(())
 }
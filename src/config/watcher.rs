@@ -0,0 +1,162 @@
+use super::{ConfigManager, Settings};
+use crate::error::{Error, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::{error, info, warn};
+
+/// Settings handle read by long-running services; swapped in place on reload
+pub type SharedSettings = Arc<RwLock<Settings>>;
+
+/// Called with the new settings after each accepted reload, for services
+/// that keep state built from them (e.g. a scan engine)
+pub type ReloadListener = Box<dyn Fn(&Settings) + Send>;
+
+/// Reloads the config (base file and active profile) whenever it changes on
+/// disk. Invalid edits are logged and ignored so the running service keeps
+/// its last good settings. Watching stops when this is dropped.
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+    listeners: Arc<Mutex<Vec<ReloadListener>>>,
+}
+
+impl ConfigWatcher {
+    pub fn spawn(manager: ConfigManager) -> Result<(SharedSettings, Self)> {
+        let shared: SharedSettings = Arc::new(RwLock::new(manager.get_settings().clone()));
+        let watched_files = manager.watched_files();
+        let config_dir = manager.config_dir();
+
+        let handle = Arc::clone(&shared);
+        let listeners: Arc<Mutex<Vec<ReloadListener>>> = Arc::default();
+        let notify_listeners = Arc::clone(&listeners);
+        let manager = Mutex::new(manager);
+
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    warn!("Config watcher error: {}", e);
+                    return;
+                }
+            };
+
+            if !matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) {
+                return;
+            }
+            if !event.paths.iter().any(|path| watched_files.iter().any(|f| path.ends_with(f))) {
+                return;
+            }
+
+            let (Ok(mut manager), Ok(listeners)) = (manager.lock(), notify_listeners.lock()) else {
+                return;
+            };
+            apply_reload(&mut manager, &handle, &listeners);
+        })
+        .map_err(|e| Error::Validation(format!("Failed to start config watcher: {}", e)))?;
+
+        // Watch the directory: editors usually replace the file rather than write in place
+        watcher
+            .watch(&config_dir, RecursiveMode::NonRecursive)
+            .map_err(|e| Error::Validation(format!("Failed to watch {}: {}", config_dir.display(), e)))?;
+
+        info!("Watching {} for configuration changes", config_dir.display());
+        Ok((shared, Self { _watcher: watcher, listeners }))
+    }
+
+    /// Register a listener for accepted reloads; rejected ones never reach it
+    pub fn on_reload(&self, listener: impl Fn(&Settings) + Send + 'static) {
+        if let Ok(mut listeners) = self.listeners.lock() {
+            listeners.push(Box::new(listener));
+        }
+    }
+}
+
+/// Reload `manager` and publish the result to `shared` and the listeners,
+/// or log the error and leave both on the previous settings
+fn apply_reload(manager: &mut ConfigManager, shared: &SharedSettings, listeners: &[ReloadListener]) {
+    if let Err(e) = manager.reload() {
+        error!("Rejected config reload, keeping previous settings: {}", e);
+        return;
+    }
+
+    let settings = manager.get_settings();
+    if let Ok(mut current) = shared.write() {
+        *current = settings.clone();
+    }
+    for listener in listeners {
+        listener(settings);
+    }
+    info!(
+        profile = manager.active_profile().unwrap_or("default"),
+        "Configuration reloaded"
+    );
+}
+
+impl ConfigManager {
+    /// Start hot-reloading this config; see [`ConfigWatcher`]
+    pub fn watch(self) -> Result<(SharedSettings, ConfigWatcher)> {
+        ConfigWatcher::spawn(self)
+    }
+
+    fn config_dir(&self) -> PathBuf {
+        self.config_path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    fn watched_files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        if let Some(name) = self.config_path.file_name() {
+            files.push(PathBuf::from(name));
+        }
+        if let Some(profile) = &self.profile {
+            files.push(PathBuf::from(format!("{}.toml", profile)));
+        }
+        files
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_invalid_reload_keeps_previous_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("default.toml");
+        let mut settings = Settings::default();
+        settings.scanner.max_threads = 50;
+        settings.save(&path).unwrap();
+
+        let mut manager = ConfigManager::with_config_path(path.clone()).unwrap();
+        let shared: SharedSettings = Arc::new(RwLock::new(manager.get_settings().clone()));
+        let reloads = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&reloads);
+        let listeners: Vec<ReloadListener> = vec![Box::new(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        })];
+
+        // Fails validation
+        settings.scanner.max_threads = 0;
+        settings.save(&path).unwrap();
+        apply_reload(&mut manager, &shared, &listeners);
+        assert_eq!(shared.read().unwrap().scanner.max_threads, 50);
+        assert_eq!(manager.get_settings().scanner.max_threads, 50);
+        assert_eq!(reloads.load(Ordering::SeqCst), 0);
+
+        // Doesn't parse
+        std::fs::write(&path, "[scanner\nmax_threads = 75\n").unwrap();
+        apply_reload(&mut manager, &shared, &listeners);
+        assert_eq!(shared.read().unwrap().scanner.max_threads, 50);
+        assert_eq!(reloads.load(Ordering::SeqCst), 0);
+
+        settings.scanner.max_threads = 75;
+        settings.save(&path).unwrap();
+        apply_reload(&mut manager, &shared, &listeners);
+        assert_eq!(shared.read().unwrap().scanner.max_threads, 75);
+        assert_eq!(reloads.load(Ordering::SeqCst), 1);
+    }
+}
@@ -1,5 +1,6 @@
 use thiserror::Error;
 use std::net::AddrParseError;
+use std::time::Duration;
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -27,6 +28,9 @@ pub enum Error {
     #[error("Scan error: {0}")]
     Scan(String),
     
+    #[error("Interrupted: {0}")]
+    Interrupted(String), // Stopped by the user (Ctrl-C); what was done so far is kept
+    
     #[error("Vulnerability database error: {0}")]
     VulnerabilityDb(String),
     
@@ -36,9 +40,18 @@ pub enum Error {
     #[error("Target resolution error: {0}")]
     TargetResolution(String),
     
+    #[error("Host unreachable: {0}")]
+    HostUnreachable(String), // The network reported the host (not the port) unreachable
+    
+    #[error("Timed out after {duration:?}: {operation}")]
+    Timeout { operation: String, duration: Duration }, // No answer in time, as opposed to a refusal
+    
     #[error("Authentication error: {0}")]
     Auth(String),
     
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String), // Missing or unknown credentials, as opposed to insufficient permissions
+    
     #[error("Rate limit exceeded: {0}")]
     RateLimit(String),
     
@@ -55,4 +68,129 @@ impl From<AddrParseError> for Error {
     }
 }
 
+impl From<tokio::sync::AcquireError> for Error {
+    fn from(err: tokio::sync::AcquireError) -> Self {
+        Error::Scan(format!("Scan concurrency limiter closed: {}", err))
+    }
+}
+
+impl Error {
+    /// Stable machine-readable code for API clients
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Error::Config(_) => "CONFIG_ERROR",
+            Error::Database(_) => "DATABASE_ERROR",
+            Error::Io(_) => "IO_ERROR",
+            Error::Network(_) => "NETWORK_ERROR",
+            Error::Serialization(_) => "SERIALIZATION_ERROR",
+            Error::Validation(_) => "VALIDATION_ERROR",
+            Error::Security(_) => "SECURITY_VIOLATION",
+            Error::Scan(_) => "SCAN_ERROR",
+            Error::Interrupted(_) => "INTERRUPTED",
+            Error::VulnerabilityDb(_) => "VULNERABILITY_DB_ERROR",
+            Error::Export(_) => "EXPORT_ERROR",
+            Error::TargetResolution(_) => "TARGET_RESOLUTION_ERROR",
+            Error::HostUnreachable(_) => "HOST_UNREACHABLE",
+            Error::Timeout { .. } => "TIMEOUT",
+            Error::Auth(_) => "FORBIDDEN",
+            Error::Unauthorized(_) => "UNAUTHORIZED",
+            Error::RateLimit(_) => "RATE_LIMITED",
+            Error::NotImplemented(_) => "NOT_IMPLEMENTED",
+            Error::Unknown(_) => "UNKNOWN_ERROR",
+        }
+    }
+
+    /// HTTP status the API responds with for this error
+    pub fn http_status(&self) -> u16 {
+        match self {
+            Error::Validation(_) | Error::TargetResolution(_) => 400,
+            Error::Unauthorized(_) => 401,
+            Error::Security(_) | Error::Auth(_) => 403,
+            Error::RateLimit(_) => 429,
+            Error::NotImplemented(_) => 501,
+            Error::Network(_) | Error::HostUnreachable(_) => 502,
+            Error::Timeout { .. } => 504,
+            Error::Config(_)
+            | Error::Database(_)
+            | Error::Io(_)
+            | Error::Serialization(_)
+            | Error::Scan(_)
+            | Error::Interrupted(_)
+            | Error::VulnerabilityDb(_)
+            | Error::Export(_)
+            | Error::Unknown(_) => 500,
+        }
+    }
+
+    /// Whether the same attempt may succeed later: timeouts, rate limits
+    /// and dropped connections. A refused connection or a validation error
+    /// is a definite answer and won't change on retry.
+    pub fn is_retriable(&self) -> bool {
+        match self {
+            Error::Timeout { .. } | Error::RateLimit(_) => true,
+            Error::Io(e) => matches!(
+                e.kind(),
+                std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::Interrupted
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+            ),
+            _ => false,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_mapping(error: Error, code: &str, status: u16) {
+        assert_eq!(error.error_code(), code, "{:?}", error);
+        assert_eq!(error.http_status(), status, "{:?}", error);
+    }
+
+    #[test]
+    fn test_error_code_and_status_mapping() {
+        assert_mapping(Error::Config(config::ConfigError::Message("bad".into())), "CONFIG_ERROR", 500);
+        assert_mapping(Error::Database(sqlx::Error::PoolClosed), "DATABASE_ERROR", 500);
+        assert_mapping(Error::Io(std::io::Error::new(std::io::ErrorKind::Other, "io")), "IO_ERROR", 500);
+        assert_mapping(
+            Error::Serialization(serde_json::from_str::<u8>("x").unwrap_err()),
+            "SERIALIZATION_ERROR",
+            500,
+        );
+        assert_mapping(Error::Validation("v".into()), "VALIDATION_ERROR", 400);
+        assert_mapping(Error::Security("s".into()), "SECURITY_VIOLATION", 403);
+        assert_mapping(Error::Scan("s".into()), "SCAN_ERROR", 500);
+        assert_mapping(Error::VulnerabilityDb("v".into()), "VULNERABILITY_DB_ERROR", 500);
+        assert_mapping(Error::Export("e".into()), "EXPORT_ERROR", 500);
+        assert_mapping(Error::TargetResolution("t".into()), "TARGET_RESOLUTION_ERROR", 400);
+        assert_mapping(Error::HostUnreachable("h".into()), "HOST_UNREACHABLE", 502);
+        assert_mapping(
+            Error::Timeout { operation: "t".into(), duration: Duration::from_secs(1) },
+            "TIMEOUT",
+            504,
+        );
+        assert_mapping(Error::Auth("a".into()), "FORBIDDEN", 403);
+        assert_mapping(Error::Unauthorized("u".into()), "UNAUTHORIZED", 401);
+        assert_mapping(Error::RateLimit("r".into()), "RATE_LIMITED", 429);
+        assert_mapping(Error::NotImplemented("n".into()), "NOT_IMPLEMENTED", 501);
+        assert_mapping(Error::Unknown("u".into()), "UNKNOWN_ERROR", 500);
+        // Error::Network wraps reqwest::Error, which has no public constructor;
+        // it maps to NETWORK_ERROR / 502
+    }
+
+    #[test]
+    fn test_timeout_retriable_refusal_terminal() {
+        let timeout = Error::Timeout { operation: "connect to 10.0.0.1:22".into(), duration: Duration::from_millis(500) };
+        assert!(timeout.is_retriable());
+        assert_eq!(timeout.to_string(), "Timed out after 500ms: connect to 10.0.0.1:22");
+
+        let refused = Error::Io(std::io::Error::from(std::io::ErrorKind::ConnectionRefused));
+        assert!(!refused.is_retriable());
+        assert!(Error::Io(std::io::Error::from(std::io::ErrorKind::ConnectionReset)).is_retriable());
+        assert!(!Error::Validation("v".into()).is_retriable());
+    }
+}
@@ -1,17 +1,34 @@
 use super::Exporter;
 use crate::error::Result;
-use crate::scanner::ScanResult;
+use crate::scanner::{PortInfo, ScanResult};
 use crate::vulnerability::VulnerabilityReport;
 use csv::Writer;
 use std::fs::File;
 use std::path::PathBuf;
 use async_trait::async_trait;
 
-pub struct CsvExporter;
+pub struct CsvExporter {
+    include_all_ports: bool,
+    min_service_confidence: u8,
+}
 
 impl CsvExporter {
     pub fn new() -> Self {
-        Self
+        Self { include_all_ports: false, min_service_confidence: 0 }
+    }
+
+    /// List closed and filtered ports in the ports file too, when the scan
+    /// recorded them (see `ScanConfig::record_all_statuses`)
+    pub fn with_all_ports(mut self, include_all_ports: bool) -> Self {
+        self.include_all_ports = include_all_ports;
+        self
+    }
+
+    /// Name services detected with less confidence "unknown (guessed: X)",
+    /// see `ServiceInfo::reported_name`
+    pub fn with_min_service_confidence(mut self, min_service_confidence: u8) -> Self {
+        self.min_service_confidence = min_service_confidence;
+        self
     }
 }
 
@@ -74,6 +91,37 @@ impl Exporter for CsvExporter {
 }
 
 impl CsvExporter {
+    pub(crate) const BATCH_HEADER: [&'static str; 10] = [
+        "Scan ID",
+        "Port",
+        "Status",
+        "Protocol",
+        "Service Name",
+        "Service Certainty",
+        "Service Version",
+        "Service Product",
+        "Banner",
+        "Response Time (ms)"
+    ];
+
+    /// Append one port to a combined CSV, prefixed with the scan ID
+    pub(crate) fn write_batch_row<W: std::io::Write>(&self, writer: &mut Writer<W>, scan_id: &str, port_info: &PortInfo) -> Result<()> {
+        writer.write_record(&[
+            scan_id,
+            &port_info.port.to_string(),
+            &format!("{:?}", port_info.status),
+            &format!("{:?}", port_info.protocol),
+            &port_info.service.as_ref().map(|s| s.reported_name(self.min_service_confidence)).unwrap_or_default(),
+            port_info.service.as_ref().map(|s| s.certainty(self.min_service_confidence)).unwrap_or(""),
+            port_info.service.as_ref().and_then(|s| s.version.as_deref()).unwrap_or(""),
+            port_info.service.as_ref().and_then(|s| s.product.as_deref()).unwrap_or(""),
+            port_info.banner.as_deref().unwrap_or(""),
+            &port_info.response_time.map(|d| d.as_millis().to_string()).unwrap_or_else(|| "".to_string())
+        ])?;
+
+        Ok(())
+    }
+
     async fn export_ports_csv(&self, scan: &ScanResult, output_path: &PathBuf) -> Result<()> {
         let mut writer = Writer::from_path(output_path)?;
         
@@ -82,18 +130,25 @@ impl CsvExporter {
             "Status",
             "Protocol",
             "Service Name",
+            "Service Certainty",
             "Service Version",
             "Service Product",
             "Banner",
             "Response Time (ms)"
         ])?;
         
-        for port_info in &scan.open_ports {
+        let ports = if self.include_all_ports {
+            scan.port_table()
+        } else {
+            scan.open_ports.iter().collect()
+        };
+        for port_info in ports {
             writer.write_record(&[
                 &port_info.port.to_string(),
                 &format!("{:?}", port_info.status),
                 &format!("{:?}", port_info.protocol),
-                port_info.service.as_ref().map(|s| s.name.as_str()).unwrap_or(""),
+                &port_info.service.as_ref().map(|s| s.reported_name(self.min_service_confidence)).unwrap_or_default(),
+                port_info.service.as_ref().map(|s| s.certainty(self.min_service_confidence)).unwrap_or(""),
                 port_info.service.as_ref().and_then(|s| s.version.as_deref()).unwrap_or(""),
                 port_info.service.as_ref().and_then(|s| s.product.as_deref()).unwrap_or(""),
                 port_info.banner.as_deref().unwrap_or(""),
@@ -1,17 +1,45 @@
 use super::Exporter;
-use crate::error::Result;
-use crate::scanner::ScanResult;
-use crate::vulnerability::VulnerabilityReport;
+use crate::config::ColorScheme;
+use crate::error::{Error, Result};
+use crate::scanner::{PortChange, PortChangeKind, PortInfo, PortOrder, ScanDiff, ScanResult, ServiceGroup, ServiceInfo};
+use crate::vulnerability::{Vulnerability, VulnerabilityLevel, VulnerabilityReport};
+use askama::Template;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
 use async_trait::async_trait;
 
-pub struct HtmlExporter;
+/// Renders the askama templates under `templates/report`; everything taken
+/// from a scan (banners, evidence...) is HTML-escaped by the templates
+pub struct HtmlExporter {
+    color_scheme: ColorScheme,
+    port_order: PortOrder,
+    min_service_confidence: u8,
+}
 
 impl HtmlExporter {
     pub fn new() -> Self {
-        Self
+        Self { color_scheme: ColorScheme::Dark, port_order: PortOrder::Port, min_service_confidence: 0 }
+    }
+
+    /// Theme the stylesheet after `ui.color_scheme`
+    pub fn with_color_scheme(mut self, color_scheme: ColorScheme) -> Self {
+        self.color_scheme = color_scheme;
+        self
+    }
+
+    /// Order the open port tables; `PortOrder::Service` adds a header row
+    /// above each service group
+    pub fn with_port_order(mut self, port_order: PortOrder) -> Self {
+        self.port_order = port_order;
+        self
+    }
+
+    /// Show services detected with less confidence as "unknown (guessed: X)",
+    /// see `ServiceInfo::reported_name`
+    pub fn with_min_service_confidence(mut self, min_service_confidence: u8) -> Self {
+        self.min_service_confidence = min_service_confidence;
+        self
     }
 }
 
@@ -44,282 +72,395 @@ impl Exporter for HtmlExporter {
 
 impl HtmlExporter {
     fn generate_scan_html(&self, scan: &ScanResult) -> Result<String> {
-        let open_ports_rows: String = scan.open_ports.iter().map(|port| {
-            let service_info = port.service.as_ref().map(|s| {
-                format!("{} {} {}", s.name, s.version.as_deref().unwrap_or(""), s.product.as_deref().unwrap_or(""))
-            }).unwrap_or_else(|| "Unknown".to_string());
-            
-            format!(
-                r#"<tr>
-                    <td>{}</td>
-                    <td><span class="status-open">OPEN</span></td>
-                    <td>{}</td>
-                    <td>{}</td>
-                    <td>{}</td>
-                    <td>{}</td>
-                </tr>"#,
-                port.port,
-                format!("{:?}", port.protocol),
-                service_info,
-                port.banner.as_deref().unwrap_or(""),
-                port.response_time.map(|d| format!("{}ms", d.as_millis())).unwrap_or_else(|| "N/A".to_string())
-            )
+        let scan = &scan.sorted_by(self.port_order);
+        let grouped = self.port_order == PortOrder::Service;
+
+        // TCP always gets a section; UDP only when a UDP pass ran or found something
+        let min_confidence = self.min_service_confidence;
+        let mut sections = vec![PortSection::new("🔍 Open TCP Ports", "tcp-ports", scan.tcp_ports(), grouped, min_confidence)];
+        if scan.statistics.udp_ports_scanned > 0 || scan.udp_ports().next().is_some() {
+            sections.push(PortSection::new("📡 Open UDP Ports", "udp-ports", scan.udp_ports(), grouped, min_confidence));
+        }
+
+        render(&ScanPage {
+            theme: self.theme(),
+            footer: Footer::now(),
+            scan,
+            duration_secs: scan.duration().as_secs_f64(),
+            network: network_label(scan),
+            sections,
+        })
+    }
+
+    pub(crate) fn generate_batch_header(&self, title: &str) -> Result<String> {
+        render(&BatchHeader { theme: self.theme(), title })
+    }
+
+    pub(crate) fn generate_batch_section(&self, scan: &ScanResult) -> Result<String> {
+        let rows = scan.open_ports.iter().map(|port| BatchRow {
+            port: port.port,
+            protocol: format!("{:?}", port.protocol),
+            service: port.service.as_ref()
+                .map(|s| s.reported_name(self.min_service_confidence))
+                .unwrap_or_else(|| "Unknown".to_string()),
+            banner: port.banner.as_deref().unwrap_or(""),
         }).collect();
 
-        let html = format!(
-            r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>Port-ZiLLA Scan Report - {}</title>
-    <style>
-        body {{ font-family: 'Segoe UI', Tahoma, Geneva, Verdana, sans-serif; margin: 0; padding: 20px; background: #1a1a1a; color: #e0e0e0; }}
-        .container {{ max-width: 1200px; margin: 0 auto; }}
-        .header {{ background: linear-gradient(135deg, #ffd700, #ffed4e); color: #1a1a1a; padding: 30px; border-radius: 10px; margin-bottom: 30px; text-align: center; }}
-        .header h1 {{ margin: 0; font-size: 2.5em; }}
-        .header .subtitle {{ font-size: 1.2em; opacity: 0.9; }}
-        .card {{ background: #2d2d2d; padding: 20px; border-radius: 8px; margin-bottom: 20px; border-left: 4px solid #ffd700; }}
-        .stats {{ display: grid; grid-template-columns: repeat(auto-fit, minmax(200px, 1fr)); gap: 15px; margin-bottom: 30px; }}
-        .stat-card {{ background: #3d3d3d; padding: 15px; border-radius: 6px; text-align: center; }}
-        .stat-number {{ font-size: 2em; font-weight: bold; color: #ffd700; }}
-        .ports-table {{ width: 100%; border-collapse: collapse; }}
-        .ports-table th, .ports-table td {{ padding: 12px; text-align: left; border-bottom: 1px solid #444; }}
-        .ports-table th {{ background: #3d3d3d; color: #ffd700; }}
-        .status-open {{ color: #4CAF50; font-weight: bold; }}
-        .footer {{ text-align: center; margin-top: 40px; opacity: 0.7; font-size: 0.9em; }}
-    </style>
-</head>
-<body>
-    <div class="container">
-        <div class="header">
-            <h1>🦖 Port-ZiLLA Enterprise</h1>
-            <div class="subtitle">Professional Port Scanning & Security Assessment</div>
-        </div>
-        
-        <div class="card">
-            <h2>📊 Scan Summary</h2>
-            <div class="stats">
-                <div class="stat-card">
-                    <div class="stat-number">{}</div>
-                    <div>Open Ports</div>
-                </div>
-                <div class="stat-card">
-                    <div class="stat-number">{}</div>
-                    <div>Total Ports Scanned</div>
-                </div>
-                <div class="stat-card">
-                    <div class="stat-number">{:.2}s</div>
-                    <div>Scan Duration</div>
-                </div>
-                <div class="stat-card">
-                    <div class="stat-number">{:.1}%</div>
-                    <div>Success Rate</div>
-                </div>
-            </div>
-        </div>
-
-        <div class="card">
-            <h2>🎯 Scan Details</h2>
-            <table style="width: 100%; border-collapse: collapse;">
-                <tr><td style="padding: 8px; border-bottom: 1px solid #444;"><strong>Target:</strong></td><td style="padding: 8px; border-bottom: 1px solid #444;">{} ({})</td></tr>
-                <tr><td style="padding: 8px; border-bottom: 1px solid #444;"><strong>Scan Type:</strong></td><td style="padding: 8px; border-bottom: 1px solid #444;">{:?}</td></tr>
-                <tr><td style="padding: 8px; border-bottom: 1px solid #444;"><strong>Start Time:</strong></td><td style="padding: 8px; border-bottom: 1px solid #444;">{}</td></tr>
-                <tr><td style="padding: 8px;"><strong>End Time:</strong></td><td style="padding: 8px;">{}</td></tr>
-            </table>
-        </div>
-
-        <div class="card">
-            <h2>🔍 Open Ports</h2>
-            <table class="ports-table">
-                <thead>
-                    <tr>
-                        <th>Port</th>
-                        <th>Status</th>
-                        <th>Protocol</th>
-                        <th>Service</th>
-                        <th>Banner</th>
-                        <th>Response Time</th>
-                    </tr>
-                </thead>
-                <tbody>
-                    {}
-                </tbody>
-            </table>
-        </div>
-
-        <div class="footer">
-            Generated by Port-ZiLLA Enterprise v{} | {} | Contact: cyberzilla.systems@gmail.com
-        </div>
-    </div>
-</body>
-</html>"#,
-            scan.target,
-            scan.open_ports.len(),
-            scan.statistics.total_ports,
-            scan.duration().as_secs_f64(),
-            scan.statistics.success_rate,
-            scan.target,
-            scan.target_ip,
-            scan.scan_type,
-            scan.start_time.to_rfc3339(),
-            scan.end_time.to_rfc3339(),
-            open_ports_rows,
-            env!("CARGO_PKG_VERSION"),
-            chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
-        );
+        render(&BatchSection { scan, rows })
+    }
 
-        Ok(html)
+    pub(crate) fn generate_batch_footer(&self) -> Result<String> {
+        render(&BatchFooter { footer: Footer::now() })
     }
 
     fn generate_vulnerability_html(&self, report: &VulnerabilityReport) -> Result<String> {
-        let vulnerabilities_rows: String = report.vulnerabilities.iter().map(|vuln| {
-            let level_class = match vuln.level {
-                crate::vulnerability::VulnerabilityLevel::Critical => "level-critical",
-                crate::vulnerability::VulnerabilityLevel::High => "level-high",
-                crate::vulnerability::VulnerabilityLevel::Medium => "level-medium",
-                crate::vulnerability::VulnerabilityLevel::Low => "level-low",
-                crate::vulnerability::VulnerabilityLevel::Info => "level-info",
-            };
-            
-            format!(
-                r#"<tr>
-                    <td>{}</td>
-                    <td><span class="{}">{:?}</span></td>
-                    <td>{}</td>
-                    <td>{}</td>
-                    <td>{}</td>
-                    <td>{}</td>
-                </tr>"#,
-                vuln.port,
-                level_class,
-                vuln.level,
-                vuln.service,
-                vuln.title,
-                vuln.evidence.chars().take(100).collect::<String>(),
-                vuln.mitigation.chars().take(100).collect::<String>()
-            )
+        let rows = report.vulnerabilities.iter().map(|vuln| VulnerabilityRow {
+            vuln,
+            level_class: level_class(&vuln.level),
+            severity_rank: severity_rank(&vuln.level),
+            evidence: vuln.evidence.chars().take(100).collect(),
+            mitigation: vuln.mitigation.chars().take(100).collect(),
         }).collect();
 
-        let html = format!(
-            r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>Port-ZiLLA Vulnerability Report - {}</title>
-    <style>
-        body {{ font-family: 'Segoe UI', Tahoma, Geneva, Verdana, sans-serif; margin: 0; padding: 20px; background: #1a1a1a; color: #e0e0e0; }}
-        .container {{ max-width: 1400px; margin: 0 auto; }}
-        .header {{ background: linear-gradient(135deg, #ff6b6b, #ff8e8e); color: white; padding: 30px; border-radius: 10px; margin-bottom: 30px; text-align: center; }}
-        .header h1 {{ margin: 0; font-size: 2.5em; }}
-        .card {{ background: #2d2d2d; padding: 20px; border-radius: 8px; margin-bottom: 20px; }}
-        .stats {{ display: grid; grid-template-columns: repeat(auto-fit, minmax(150px, 1fr)); gap: 15px; }}
-        .stat-card {{ background: #3d3d3d; padding: 15px; border-radius: 6px; text-align: center; }}
-        .stat-critical {{ border-left: 4px solid #dc3545; }}
-        .stat-high {{ border-left: 4px solid #fd7e14; }}
-        .stat-medium {{ border-left: 4px solid #ffc107; }}
-        .stat-low {{ border-left: 4px solid #20c997; }}
-        .stat-info {{ border-left: 4px solid #6c757d; }}
-        .stat-number {{ font-size: 1.8em; font-weight: bold; }}
-        .level-critical {{ color: #dc3545; font-weight: bold; }}
-        .level-high {{ color: #fd7e14; font-weight: bold; }}
-        .level-medium {{ color: #ffc107; font-weight: bold; }}
-        .level-low {{ color: #20c997; }}
-        .level-info {{ color: #6c757d; }}
-        .vuln-table {{ width: 100%; border-collapse: collapse; }}
-        .vuln-table th, .vuln-table td {{ padding: 12px; text-align: left; border-bottom: 1px solid #444; }}
-        .vuln-table th {{ background: #3d3d3d; color: #ffd700; }}
-        .footer {{ text-align: center; margin-top: 40px; opacity: 0.7; font-size: 0.9em; }}
-    </style>
-</head>
-<body>
-    <div class="container">
-        <div class="header">
-            <h1>🦖 Port-ZiLLA Vulnerability Report</h1>
-            <div class="subtitle">Security Assessment Findings</div>
-        </div>
-        
-        <div class="card">
-            <h2>📈 Risk Summary</h2>
-            <div class="stats">
-                <div class="stat-card stat-critical">
-                    <div class="stat-number">{}</div>
-                    <div>Critical</div>
-                </div>
-                <div class="stat-card stat-high">
-                    <div class="stat-number">{}</div>
-                    <div>High</div>
-                </div>
-                <div class="stat-card stat-medium">
-                    <div class="stat-number">{}</div>
-                    <div>Medium</div>
-                </div>
-                <div class="stat-card stat-low">
-                    <div class="stat-number">{}</div>
-                    <div>Low</div>
-                </div>
-                <div class="stat-card stat-info">
-                    <div class="stat-number">{}</div>
-                    <div>Info</div>
-                </div>
-            </div>
-        </div>
-
-        <div class="card">
-            <h2>🎯 Assessment Details</h2>
-            <table style="width: 100%; border-collapse: collapse;">
-                <tr><td style="padding: 8px; border-bottom: 1px solid #444;"><strong>Target:</strong></td><td style="padding: 8px; border-bottom: 1px solid #444;">{} ({})</td></tr>
-                <tr><td style="padding: 8px; border-bottom: 1px solid #444;"><strong>Overall Risk:</strong></td><td style="padding: 8px; border-bottom: 1px solid #444;"><span class="level-{}">{:?}</span></td></tr>
-                <tr><td style="padding: 8px; border-bottom: 1px solid #444;"><strong>Risk Score:</strong></td><td style="padding: 8px; border-bottom: 1px solid #444;">{:.2}/10</td></tr>
-                <tr><td style="padding: 8px;"><strong>Generated:</strong></td><td style="padding: 8px;">{}</td></tr>
-            </table>
-        </div>
-
-        <div class="card">
-            <h2>🔍 Vulnerabilities Found</h2>
-            <table class="vuln-table">
-                <thead>
-                    <tr>
-                        <th>Port</th>
-                        <th>Level</th>
-                        <th>Service</th>
-                        <th>Title</th>
-                        <th>Evidence</th>
-                        <th>Mitigation</th>
-                    </tr>
-                </thead>
-                <tbody>
-                    {}
-                </tbody>
-            </table>
-        </div>
-
-        <div class="footer">
-            Generated by Port-ZiLLA Enterprise v{} | {} | Contact: cyberzilla.systems@gmail.com
-        </div>
-    </div>
-</body>
-</html>"#,
-            report.target,
-            report.summary.critical_count,
-            report.summary.high_count,
-            report.summary.medium_count,
-            report.summary.low_count,
-            report.summary.info_count,
-            report.target,
-            report.target_ip,
-            report.risk_assessment.overall_risk.to_string().to_lowercase(),
-            report.risk_assessment.overall_risk,
-            report.summary.risk_score,
-            report.generated_at.to_rfc3339(),
-            vulnerabilities_rows,
-            env!("CARGO_PKG_VERSION"),
-            chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
-        );
+        render(&VulnerabilityPage {
+            theme: self.theme(),
+            footer: Footer::now(),
+            report,
+            overall_class: level_class(&report.risk_assessment.overall_risk),
+            rows,
+        })
+    }
+
+    /// Self-contained page comparing two scans of a target side by side:
+    /// added ports highlighted green, removed ones red, and changed service
+    /// versions annotated with what changed
+    pub async fn export_scan_diff(&self, diff: &ScanDiff, output_path: &PathBuf) -> Result<PathBuf> {
+        let html_content = self.generate_scan_diff_html(diff)?;
+
+        let mut file = File::create(output_path)?;
+        file.write_all(html_content.as_bytes())?;
+        file.flush()?;
+
+        Ok(output_path.clone())
+    }
+
+    fn generate_scan_diff_html(&self, diff: &ScanDiff) -> Result<String> {
+        let min_confidence = self.min_service_confidence;
+        let sections = [
+            ("➕ Added Ports", "added-ports", PortChangeKind::Added),
+            ("➖ Removed Ports", "removed-ports", PortChangeKind::Removed),
+            ("✏️ Changed Services", "changed-ports", PortChangeKind::Changed),
+            ("✔️ Unchanged Ports", "unchanged-ports", PortChangeKind::Unchanged),
+        ].into_iter().map(|(title, id, kind)| DiffSection {
+            title,
+            id,
+            class: diff_class(kind),
+            rows: diff.of_kind(kind).map(|change| DiffRow::new(change, min_confidence)).collect(),
+        }).collect();
+
+        render(&ScanDiffPage {
+            theme: self.theme(),
+            footer: Footer::now(),
+            diff,
+            previous_time: chrono::DateTime::<chrono::Utc>::from(diff.previous_time).to_rfc3339(),
+            current_time: chrono::DateTime::<chrono::Utc>::from(diff.current_time).to_rfc3339(),
+            sections,
+        })
+    }
+
+    fn theme(&self) -> Theme {
+        Theme::for_scheme(&self.color_scheme)
+    }
+}
 
-        Ok(html)
+fn render(template: &impl Template) -> Result<String> {
+    template.render()
+        .map_err(|e| Error::Export(format!("Failed to render HTML report: {}", e)))
+}
+
+/// ASN / country label; `None` unless GeoIP enrichment found the target
+fn network_label(scan: &ScanResult) -> Option<String> {
+    let metadata = &scan.metadata;
+    let mut parts = Vec::new();
+    if let Some(asn) = metadata.asn {
+        parts.push(format!("AS{}", asn));
+    }
+    if let Some(as_org) = &metadata.as_org {
+        parts.push(as_org.clone());
     }
+    if let Some(country_code) = &metadata.country_code {
+        parts.push(format!("({})", country_code));
+    }
+
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// "name version product" of a confirmed service, else its reported name.
+/// A guessed service shows no version or product, which would only lend it
+/// credibility.
+fn service_label(service: Option<&ServiceInfo>, min_confidence: u8) -> String {
+    service.map(|s| if s.is_confirmed(min_confidence) {
+        format!("{} {} {}", s.name, s.version.as_deref().unwrap_or(""), s.product.as_deref().unwrap_or(""))
+    } else {
+        s.reported_name(min_confidence)
+    }).unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// Row class of a diff section; the stylesheet colors added rows green,
+/// removed ones red and changed ones amber
+fn diff_class(kind: PortChangeKind) -> &'static str {
+    match kind {
+        PortChangeKind::Added => "diff-added",
+        PortChangeKind::Removed => "diff-removed",
+        PortChangeKind::Changed => "diff-changed",
+        PortChangeKind::Unchanged => "diff-unchanged",
+    }
+}
+
+fn level_class(level: &VulnerabilityLevel) -> &'static str {
+    match level {
+        VulnerabilityLevel::Critical => "level-critical",
+        VulnerabilityLevel::High => "level-high",
+        VulnerabilityLevel::Medium => "level-medium",
+        VulnerabilityLevel::Low => "level-low",
+        VulnerabilityLevel::Info => "level-info",
+    }
+}
+
+/// Sort key of the level column, so sorting orders by severity rather than name
+fn severity_rank(level: &VulnerabilityLevel) -> u8 {
+    match level {
+        VulnerabilityLevel::Critical => 4,
+        VulnerabilityLevel::High => 3,
+        VulnerabilityLevel::Medium => 2,
+        VulnerabilityLevel::Low => 1,
+        VulnerabilityLevel::Info => 0,
+    }
+}
+
+/// Colors behind the stylesheet's CSS custom properties
+#[derive(Clone, Copy)]
+struct Palette {
+    background: &'static str,
+    surface: &'static str,
+    raised: &'static str,
+    text: &'static str,
+    border: &'static str,
+    accent: &'static str,
+}
+
+const DARK: Palette = Palette {
+    background: "#1a1a1a",
+    surface: "#2d2d2d",
+    raised: "#3d3d3d",
+    text: "#e0e0e0",
+    border: "#444444",
+    accent: "#ffd700",
+};
+
+const LIGHT: Palette = Palette {
+    background: "#f5f5f5",
+    surface: "#ffffff",
+    raised: "#ececec",
+    text: "#1a1a1a",
+    border: "#dddddd",
+    accent: "#b8860b",
+};
+
+/// Report theme for `ui.color_scheme`; `Auto` follows the viewer's
+/// `prefers-color-scheme` and falls back to dark
+struct Theme {
+    palette: Palette,
+    light_override: Option<Palette>,
+}
+
+impl Theme {
+    fn for_scheme(scheme: &ColorScheme) -> Self {
+        match scheme {
+            ColorScheme::Dark => Self { palette: DARK, light_override: None },
+            ColorScheme::Light => Self { palette: LIGHT, light_override: None },
+            ColorScheme::Auto => Self { palette: DARK, light_override: Some(LIGHT) },
+        }
+    }
+}
+
+struct Footer {
+    version: &'static str,
+    generated_at: String,
+}
+
+impl Footer {
+    fn now() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            generated_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "report/scan.html")]
+struct ScanPage<'a> {
+    theme: Theme,
+    footer: Footer,
+    scan: &'a ScanResult,
+    duration_secs: f64,
+    network: Option<String>,
+    sections: Vec<PortSection<'a>>,
+}
+
+struct PortSection<'a> {
+    title: &'static str,
+    id: &'static str, // Table id; templates/report/table.js enhances it by this id
+    rows: Vec<PortRow<'a>>,
+}
+
+impl<'a> PortSection<'a> {
+    /// With `grouped`, the first row of each service group carries the
+    /// group's name for a header row
+    fn new(
+        title: &'static str,
+        id: &'static str,
+        ports: impl Iterator<Item = &'a PortInfo>,
+        grouped: bool,
+        min_confidence: u8,
+    ) -> Self {
+        let mut previous_group = None;
+        let rows = ports.map(|port| PortRow {
+            group: grouped.then(|| ServiceGroup::of(port))
+                .filter(|group| previous_group.replace(*group) != Some(*group)),
+            port: port.port,
+            service: service_label(port.service.as_ref(), min_confidence),
+            banner: port.banner.as_deref().unwrap_or(""),
+            response_time: port.response_time
+                .map(|d| format!("{}ms", d.as_millis()))
+                .unwrap_or_else(|| "N/A".to_string()),
+        }).collect();
+
+        Self { title, id, rows }
+    }
+}
+
+struct PortRow<'a> {
+    group: Option<ServiceGroup>, // Service group starting at this row, when grouping
+    port: u16,
+    service: String,
+    banner: &'a str,
+    response_time: String,
+}
+
+#[derive(Template)]
+#[template(path = "report/vulnerability.html")]
+struct VulnerabilityPage<'a> {
+    theme: Theme,
+    footer: Footer,
+    report: &'a VulnerabilityReport,
+    overall_class: &'static str,
+    rows: Vec<VulnerabilityRow<'a>>,
+}
+
+struct VulnerabilityRow<'a> {
+    vuln: &'a Vulnerability,
+    level_class: &'static str,
+    severity_rank: u8,
+    evidence: String,
+    mitigation: String,
+}
+
+#[derive(Template)]
+#[template(path = "report/scan_diff.html")]
+struct ScanDiffPage<'a> {
+    theme: Theme,
+    footer: Footer,
+    diff: &'a ScanDiff,
+    previous_time: String,
+    current_time: String,
+    sections: Vec<DiffSection>,
+}
+
+struct DiffSection {
+    title: &'static str,
+    id: &'static str,
+    class: &'static str,
+    rows: Vec<DiffRow>,
+}
+
+struct DiffRow {
+    port: u16,
+    protocol: String,
+    previous: String, // Service in the earlier scan; empty when the port wasn't open
+    current: String,
+    note: String,
+}
+
+impl DiffRow {
+    fn new(change: &PortChange, min_confidence: u8) -> Self {
+        let label = |port: &Option<PortInfo>| port.as_ref()
+            .map(|p| service_label(p.service.as_ref(), min_confidence))
+            .unwrap_or_default();
+
+        Self {
+            port: change.port,
+            protocol: format!("{:?}", change.protocol),
+            previous: label(&change.previous),
+            current: label(&change.current),
+            note: change_note(change),
+        }
+    }
+}
+
+/// What changed on the port, e.g. "version 8.9 → 9.6"
+fn change_note(change: &PortChange) -> String {
+    match change.kind {
+        PortChangeKind::Added => "Newly open".to_string(),
+        PortChangeKind::Removed => "No longer open".to_string(),
+        PortChangeKind::Unchanged => String::new(),
+        PortChangeKind::Changed => {
+            let fields = |port: &Option<PortInfo>| {
+                let service = port.as_ref().and_then(|p| p.service.as_ref());
+                [
+                    service.map(|s| s.name.to_lowercase()),
+                    service.and_then(|s| s.version.clone()),
+                    service.and_then(|s| s.product.clone()),
+                ]
+            };
+            let none = || "none".to_string();
+
+            ["service", "version", "product"].into_iter()
+                .zip(fields(&change.previous).into_iter().zip(fields(&change.current)))
+                .filter(|(_, (was, now))| was != now)
+                .map(|(field, (was, now))| format!("{} {} → {}", field, was.unwrap_or_else(none), now.unwrap_or_else(none)))
+                .collect::<Vec<_>>()
+                .join("; ")
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "report/batch_header.html")]
+struct BatchHeader<'a> {
+    theme: Theme,
+    title: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "report/batch_section.html")]
+struct BatchSection<'a> {
+    scan: &'a ScanResult,
+    rows: Vec<BatchRow<'a>>,
+}
+
+struct BatchRow<'a> {
+    port: u16,
+    protocol: String,
+    service: String,
+    banner: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "report/batch_footer.html")]
+struct BatchFooter {
+    footer: Footer,
 }
 
 impl Default for HtmlExporter {
@@ -327,3 +468,168 @@ impl Default for HtmlExporter {
         Self::new()
     }
               }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{PortStatus, Protocol, ScanType};
+
+    fn scan_with_banner(banner: &str) -> ScanResult {
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        scan.add_open_port(PortInfo {
+            port: 80,
+            status: PortStatus::Open,
+            service: None,
+            banner: Some(banner.to_string()),
+            banner_raw: None,
+            response_time: None,
+            protocol: Protocol::Tcp,
+            tls: None,
+        });
+        scan
+    }
+
+    #[test]
+    fn test_banner_is_html_escaped() {
+        let scan = scan_with_banner("</td><script>alert('x')</script>");
+        let exporter = HtmlExporter::new();
+
+        let escaped = "&lt;/td&gt;&lt;script&gt;alert(&#x27;x&#x27;)&lt;/script&gt;";
+        for html in [
+            exporter.generate_scan_html(&scan).unwrap(),
+            exporter.generate_batch_section(&scan).unwrap(),
+        ] {
+            assert!(html.contains(escaped));
+            assert!(!html.contains("<script>alert"));
+        }
+    }
+
+    #[test]
+    fn test_added_port_renders_green_in_the_added_section() {
+        let previous = scan_with_banner("HTTP/1.1 200 OK");
+        let mut current = previous.clone();
+        current.id = "scan-2".to_string();
+        current.add_open_port(PortInfo {
+            port: 443,
+            status: PortStatus::Open,
+            service: None,
+            banner: None,
+            banner_raw: None,
+            response_time: None,
+            protocol: Protocol::Tcp,
+            tls: None,
+        });
+        let diff = ScanDiff::between(&previous, &current).unwrap();
+
+        let html = HtmlExporter::new().generate_scan_diff_html(&diff).unwrap();
+        let section = |id: &str| {
+            let start = html.find(&format!(r#"id="{}""#, id)).expect("section rendered");
+            &html[start..start + html[start..].find("</table>").unwrap()]
+        };
+
+        let added = section("added-ports");
+        assert!(added.contains(r#"<tr class="diff-added">"#));
+        assert!(added.contains("<td>443</td>"));
+        assert!(!section("unchanged-ports").contains("<td>443</td>"));
+        assert!(section("unchanged-ports").contains("<td>80</td>"));
+        assert!(html.contains(".diff-added td"), "stylesheet colors added rows");
+        assert!(!html.contains("src=\"http"), "no external resources");
+    }
+
+    #[test]
+    fn test_stylesheet_follows_color_scheme() {
+        let scan = scan_with_banner("SSH-2.0-OpenSSH_9.6");
+        let page = |scheme| HtmlExporter::new().with_color_scheme(scheme).generate_scan_html(&scan).unwrap();
+
+        let dark = page(ColorScheme::Dark);
+        assert!(dark.contains(&format!("--bg: {};", DARK.background)));
+        assert!(!dark.contains("prefers-color-scheme"));
+
+        let light = page(ColorScheme::Light);
+        assert!(light.contains(&format!("--bg: {};", LIGHT.background)));
+        assert!(!light.contains(&format!("--bg: {};", DARK.background)));
+
+        // Auto ships both palettes and lets the browser pick
+        let auto = page(ColorScheme::Auto);
+        assert!(auto.contains(&format!("--bg: {};", DARK.background)));
+        assert!(auto.contains("@media (prefers-color-scheme: light)"));
+    }
+
+    #[test]
+    fn test_service_order_adds_a_header_per_group() {
+        let mut scan = scan_with_banner("HTTP/1.1 200 OK");
+        for (port, name) in [(22, "ssh"), (443, "https"), (3306, "mysql")] {
+            scan.add_open_port(PortInfo {
+                port,
+                status: PortStatus::Open,
+                service: Some(crate::scanner::ServiceInfo {
+                    name: name.to_string(),
+                    version: None,
+                    product: None,
+                    extra_info: None,
+                    confidence: 90,
+                    tunnel: None,
+                    alpn: None,
+                }),
+                banner: None,
+                banner_raw: None,
+                response_time: None,
+                protocol: Protocol::Tcp,
+                tls: None,
+            });
+        }
+
+        let headers = |html: &str| -> Vec<String> {
+            html.match_indices(r#"<tr class="group-row"><th colspan="5">"#)
+                .map(|(start, tag)| {
+                    let rest = &html[start + tag.len()..];
+                    rest[..rest.find('<').unwrap()].to_string()
+                })
+                .collect()
+        };
+
+        let by_port = HtmlExporter::new().generate_scan_html(&scan).unwrap();
+        assert!(headers(&by_port).is_empty());
+
+        // Port 80 has no detected service, so it lands under Other
+        let by_service = HtmlExporter::new().with_port_order(PortOrder::Service).generate_scan_html(&scan).unwrap();
+        assert_eq!(headers(&by_service), vec!["Web", "Database", "Remote access", "Other"]);
+        assert!(by_service.find("<td>443</td>").unwrap() < by_service.find("<td>3306</td>").unwrap());
+    }
+
+    /// Ids listed in the embedded script's `SORTABLE_TABLES`
+    fn sortable_table_ids(html: &str) -> Vec<String> {
+        let start = html.find("SORTABLE_TABLES = [").expect("table script embedded") + "SORTABLE_TABLES = [".len();
+        let end = start + html[start..].find(']').unwrap();
+        html[start..end].split(',').map(|id| id.trim().trim_matches('"').to_string()).collect()
+    }
+
+    #[test]
+    fn test_report_tables_carry_ids_the_script_targets() {
+        let exporter = HtmlExporter::new();
+
+        let scan_page = exporter.generate_scan_html(&scan_with_banner("SSH-2.0-OpenSSH_9.6")).unwrap();
+        let ids = sortable_table_ids(&scan_page);
+        assert!(ids.contains(&"tcp-ports".to_string()));
+        assert!(scan_page.contains(r#"<table class="data-table" id="tcp-ports">"#));
+        assert!(!scan_page.contains("src=\"http"), "no external scripts");
+
+        let mut report = VulnerabilityReport::new(
+            "scan-1".to_string(),
+            "10.0.0.1".to_string(),
+            "10.0.0.1".parse().unwrap(),
+        );
+        report.add_vulnerability(Vulnerability::new(
+            "Telnet Service Exposed".to_string(),
+            "Telnet in use".to_string(),
+            VulnerabilityLevel::High,
+            23,
+            "Telnet".to_string(),
+            "Telnet service detected".to_string(),
+        ));
+        let vuln_page = exporter.generate_vulnerability_html(&report).unwrap();
+        assert!(sortable_table_ids(&vuln_page).contains(&"vulnerabilities".to_string()));
+        assert!(vuln_page.contains(r#"<table class="data-table" id="vulnerabilities">"#));
+        assert!(vuln_page.contains(r#"<td data-sort="3">"#));
+    }
+}
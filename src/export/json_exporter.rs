@@ -1,6 +1,6 @@
 use super::Exporter;
 use crate::error::Result;
-use crate::scanner::ScanResult;
+use crate::scanner::{PortInfo, ScanResult};
 use crate::vulnerability::VulnerabilityReport;
 use serde_json::{json, Value};
 use std::fs::File;
@@ -8,11 +8,28 @@ use std::io::Write;
 use std::path::PathBuf;
 use async_trait::async_trait;
 
-pub struct JsonExporter;
+pub struct JsonExporter {
+    include_all_ports: bool,
+    min_service_confidence: u8,
+}
 
 impl JsonExporter {
     pub fn new() -> Self {
-        Self
+        Self { include_all_ports: false, min_service_confidence: 0 }
+    }
+
+    /// Add `results.all_ports`: every recorded port, closed and filtered ones
+    /// included (see `ScanConfig::record_all_statuses`)
+    pub fn with_all_ports(mut self, include_all_ports: bool) -> Self {
+        self.include_all_ports = include_all_ports;
+        self
+    }
+
+    /// Label services detected with less confidence as guessed, see
+    /// `ServiceInfo::reported_name`
+    pub fn with_min_service_confidence(mut self, min_service_confidence: u8) -> Self {
+        self.min_service_confidence = min_service_confidence;
+        self
     }
 }
 
@@ -44,24 +61,10 @@ impl Exporter for JsonExporter {
 }
 
 impl JsonExporter {
-    fn serialize_scan(&self, scan: &ScanResult) -> Result<Value> {
-        let open_ports: Vec<Value> = scan.open_ports.iter().map(|port| {
-            json!({
-                "port": port.port,
-                "status": format!("{:?}", port.status),
-                "protocol": format!("{:?}", port.protocol),
-                "service": port.service.as_ref().map(|s| {
-                    json!({
-                        "name": s.name,
-                        "version": s.version,
-                        "product": s.product,
-                        "confidence": s.confidence
-                    })
-                }),
-                "banner": port.banner,
-                "response_time_ms": port.response_time.map(|d| d.as_millis() as u64)
-            })
-        }).collect();
+    pub(crate) fn serialize_scan(&self, scan: &ScanResult) -> Result<Value> {
+        let open_ports: Vec<Value> = scan.open_ports.iter().map(|port| self.port_json(port)).collect();
+        let all_ports: Option<Vec<Value>> = self.include_all_ports
+            .then(|| scan.port_table().into_iter().map(|port| self.port_json(port)).collect());
 
         let json_data = json!({
             "metadata": {
@@ -83,14 +86,34 @@ impl JsonExporter {
                 "scan_duration_ms": scan.statistics.scan_duration.as_millis(),
                 "packets_sent": scan.statistics.packets_sent,
                 "packets_received": scan.statistics.packets_received,
-                "success_rate": scan.statistics.success_rate
+                "success_rate": scan.statistics.success_rate,
+                "average_pps": scan.statistics.average_pps,
+                "latency_ms": scan.statistics.latency.as_ref().map(|l| serde_json::json!({
+                    "min": l.min.as_secs_f64() * 1000.0,
+                    "median": l.median.as_secs_f64() * 1000.0,
+                    "p90": l.p90.as_secs_f64() * 1000.0,
+                    "p99": l.p99.as_secs_f64() * 1000.0,
+                    "max": l.max.as_secs_f64() * 1000.0,
+                }))
             },
             "results": {
-                "open_ports": open_ports
+                "open_ports": open_ports,
+                // Only with include_all_ports; open ports appear in both lists
+                "all_ports": all_ports,
+                // Ports not probed by this scan; every other planned port was scanned fresh
+                "incremental": scan.metadata.incremental.as_ref().map(|incremental| json!({
+                    "baseline_scan_id": incremental.baseline_scan_id,
+                    "carried_over": incremental.carried_over,
+                    "carried_over_count": incremental.carried_over_count(),
+                    "carried_over_since": chrono::DateTime::<chrono::Utc>::from(incremental.carried_over_since).to_rfc3339(),
+                }))
             },
             "scan_metadata": {
                 "scanner_version": scan.metadata.scanner_version,
                 "hostname": scan.metadata.hostname,
+                "asn": scan.metadata.asn,
+                "as_org": scan.metadata.as_org,
+                "country_code": scan.metadata.country_code,
                 "os_detection": scan.metadata.os_detection.as_ref().map(|os| {
                     json!({
                         "name": os.name,
@@ -105,6 +128,31 @@ impl JsonExporter {
         Ok(json_data)
     }
 
+    /// `service.name` is the detected name even for a guess, so the file
+    /// imports back unchanged; `reported_as` is what reports state
+    fn port_json(&self, port: &PortInfo) -> Value {
+        json!({
+            "port": port.port,
+            "status": format!("{:?}", port.status),
+            "protocol": format!("{:?}", port.protocol),
+            "service": port.service.as_ref().map(|s| {
+                json!({
+                    "name": s.name,
+                    "version": s.version,
+                    "product": s.product,
+                    "confidence": s.confidence,
+                    "certainty": s.certainty(self.min_service_confidence),
+                    "reported_as": s.reported_name(self.min_service_confidence),
+                    "tunnel": s.tunnel,
+                    "alpn": s.alpn
+                })
+            }),
+            "banner": port.banner,
+            "banner_raw": port.banner_raw,
+            "response_time_ms": port.response_time.map(|d| d.as_millis() as u64)
+        })
+    }
+
     fn serialize_vulnerability_report(&self, report: &VulnerabilityReport) -> Result<Value> {
         let vulnerabilities: Vec<Value> = report.vulnerabilities.iter().map(|vuln| {
             json!({
@@ -125,7 +173,8 @@ impl JsonExporter {
                 "exploit_available": vuln.exploit_available,
                 "impact": vuln.impact,
                 "certainty": vuln.certainty,
-                "tags": vuln.tags
+                "tags": vuln.tags,
+                "suppressed": vuln.suppressed
             })
         }).collect();
 
@@ -3,19 +3,33 @@ pub mod csv_exporter;
 pub mod pdf_exporter;
 pub mod html_exporter;
 pub mod xml_exporter;
+pub mod pzr_exporter;
 
 pub use json_exporter::JsonExporter;
 pub use csv_exporter::CsvExporter;
 pub use pdf_exporter::PdfExporter;
 pub use html_exporter::HtmlExporter;
 pub use xml_exporter::XmlExporter;
+pub use pzr_exporter::PzrExporter;
 
+use crate::config::{ColorScheme, ExportSettings, Settings};
 use crate::error::{Error, Result};
-use crate::scanner::ScanResult;
+use crate::scanner::{PortOrder, ScanResult};
+use crate::storage::ScanRepository;
+use crate::storage::repository::port_record_to_info;
 use crate::vulnerability::VulnerabilityReport;
-use std::path::PathBuf;
+use futures::TryStreamExt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
 use async_trait::async_trait;
 
+/// Output format plugged into [`ExportManager`].
+///
+/// This is the extension point for library users: implement it for a custom
+/// format (a SIEM feed, an internal report layout...) and add it with
+/// [`ExportManager::register`]; it is then available by key everywhere a
+/// built-in format is, e.g. `export_scan(&scan, "siem", None)`.
 #[async_trait]
 pub trait Exporter: Send + Sync {
     async fn export_scan(&self, scan: &ScanResult, output_path: &PathBuf) -> Result<PathBuf>;
@@ -23,22 +37,170 @@ pub trait Exporter: Send + Sync {
     fn get_file_extension(&self) -> &'static str;
 }
 
+/// Export format key implied by an output file extension. `md` and `sarif`
+/// need an exporter registered under that key.
+const EXTENSION_FORMATS: &[(&str, &str)] = &[
+    ("json", "json"),
+    ("csv", "csv"),
+    ("html", "html"),
+    ("htm", "html"),
+    ("xml", "xml"),
+    ("pdf", "pdf"),
+    ("md", "markdown"),
+    ("sarif", "sarif"),
+    ("pzr", "pzr"),
+];
+
 pub struct ExportManager {
     exporters: std::collections::HashMap<String, Box<dyn Exporter>>,
+    output_directory: Option<PathBuf>,
+    include_timestamps: bool,
+    color_scheme: ColorScheme,
+    port_order: PortOrder,
+    include_all_ports: bool,
+    min_service_confidence: u8,
 }
 
 impl ExportManager {
     pub fn new() -> Self {
-        let mut exporters = std::collections::HashMap::new();
+        let mut manager = Self {
+            exporters: std::collections::HashMap::new(),
+            output_directory: None,
+            include_timestamps: true,
+            color_scheme: ColorScheme::Dark,
+            port_order: PortOrder::Port,
+            include_all_ports: false,
+            min_service_confidence: 0,
+        };
         
-        // Register all exporters
-        exporters.insert("json".to_string(), Box::new(JsonExporter::new()));
-        exporters.insert("csv".to_string(), Box::new(CsvExporter::new()));
-        exporters.insert("pdf".to_string(), Box::new(PdfExporter::new()));
-        exporters.insert("html".to_string(), Box::new(HtmlExporter::new()));
-        exporters.insert("xml".to_string(), Box::new(XmlExporter::new()));
+        // Register built-in exporters
+        manager.register("json".to_string(), Box::new(JsonExporter::new()));
+        manager.register("csv".to_string(), Box::new(CsvExporter::new()));
+        manager.register("pdf".to_string(), Box::new(PdfExporter::new()));
+        manager.register("html".to_string(), Box::new(HtmlExporter::new()));
+        manager.register("xml".to_string(), Box::new(XmlExporter::new()));
+        manager.register("pzr".to_string(), Box::new(PzrExporter::new()));
         
-        Self { exporters }
+        manager
+    }
+
+    /// Make `exporter` available under `format_key`, replacing any exporter
+    /// (built-in included) already registered for that key
+    pub fn register(&mut self, format_key: String, exporter: Box<dyn Exporter>) {
+        self.exporters.insert(format_key, exporter);
+    }
+
+    pub fn get_exporter(&self, format_key: &str) -> Option<&dyn Exporter> {
+        self.exporters.get(format_key).map(|exporter| exporter.as_ref())
+    }
+
+    /// Format key for `path`'s extension, or None if it names no format. An
+    /// extension matching a custom exporter's key counts too.
+    pub fn format_for_path(&self, path: &Path) -> Option<String> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        EXTENSION_FORMATS.iter()
+            .find(|(ext, _)| *ext == extension)
+            .map(|(_, format)| format.to_string())
+            .or_else(|| self.exporters.contains_key(&extension).then_some(extension))
+    }
+
+    /// The format to export with: `explicit` if given, else the one implied
+    /// by `output_path`'s extension, else `default`. An explicit format the
+    /// extension contradicts is an error, not a file whose name lies about
+    /// its contents.
+    pub fn resolve_format(&self, explicit: Option<&str>, output_path: Option<&Path>, default: &str) -> Result<String> {
+        let inferred = output_path.and_then(|path| self.format_for_path(path));
+        let format = match (explicit, inferred) {
+            (Some(explicit), Some(inferred)) if explicit != inferred => {
+                return Err(Error::Validation(format!(
+                    "Format {} conflicts with output file {}, which names {}; drop one of them",
+                    explicit,
+                    output_path.map(|path| path.display().to_string()).unwrap_or_default(),
+                    inferred,
+                )));
+            }
+            (Some(explicit), _) => explicit.to_string(),
+            (None, Some(inferred)) => inferred,
+            (None, None) => default.to_string(),
+        };
+
+        if self.get_exporter(&format).is_none() {
+            return Err(Error::Export(format!("Unsupported export format: {}", format)));
+        }
+        Ok(format)
+    }
+
+    /// Place auto-named files under `output_directory` and honor
+    /// `include_timestamps`, `include_all_ports` and `min_service_confidence`
+    pub fn with_settings(mut self, settings: &ExportSettings) -> Self {
+        self.output_directory = Some(PathBuf::from(&settings.output_directory));
+        self.include_timestamps = settings.include_timestamps;
+        if settings.include_all_ports {
+            self = self.with_all_ports(true);
+        }
+        if settings.min_service_confidence > 0 {
+            self = self.with_min_service_confidence(settings.min_service_confidence);
+        }
+        self
+    }
+
+    /// Include closed and filtered ports in JSON and CSV exports
+    pub fn with_all_ports(mut self, include_all_ports: bool) -> Self {
+        self.include_all_ports = include_all_ports;
+        self.register("json".to_string(), Box::new(self.json_exporter()));
+        self.register("csv".to_string(), Box::new(self.csv_exporter()));
+        self
+    }
+
+    /// Report services detected with less confidence as "unknown (guessed:
+    /// X)" and label each service guessed or confirmed in the built-in formats
+    pub fn with_min_service_confidence(mut self, min_service_confidence: u8) -> Self {
+        self.min_service_confidence = min_service_confidence;
+        self.register("json".to_string(), Box::new(self.json_exporter()));
+        self.register("csv".to_string(), Box::new(self.csv_exporter()));
+        self.register("pdf".to_string(), Box::new(PdfExporter::new().with_min_service_confidence(min_service_confidence)));
+        self.register("xml".to_string(), Box::new(XmlExporter::new().with_min_service_confidence(min_service_confidence)));
+        self.register_html();
+        self
+    }
+
+    /// Theme HTML reports after `ui.color_scheme`
+    pub fn with_color_scheme(mut self, color_scheme: ColorScheme) -> Self {
+        self.color_scheme = color_scheme;
+        self.register_html();
+        self
+    }
+
+    /// Order open ports in single-scan exports; the scan passed in is left
+    /// as it is. HTML reports get a header row per service group with
+    /// `PortOrder::Service`.
+    pub fn with_port_order(mut self, port_order: PortOrder) -> Self {
+        self.port_order = port_order;
+        self.register_html();
+        self
+    }
+
+    fn register_html(&mut self) {
+        let exporter = self.html_exporter().with_port_order(self.port_order);
+        self.register("html".to_string(), Box::new(exporter));
+    }
+
+    fn json_exporter(&self) -> JsonExporter {
+        JsonExporter::new()
+            .with_all_ports(self.include_all_ports)
+            .with_min_service_confidence(self.min_service_confidence)
+    }
+
+    fn csv_exporter(&self) -> CsvExporter {
+        CsvExporter::new()
+            .with_all_ports(self.include_all_ports)
+            .with_min_service_confidence(self.min_service_confidence)
+    }
+
+    fn html_exporter(&self) -> HtmlExporter {
+        HtmlExporter::new()
+            .with_color_scheme(self.color_scheme.clone())
+            .with_min_service_confidence(self.min_service_confidence)
     }
 
     pub async fn export_scan(
@@ -47,14 +209,15 @@ impl ExportManager {
         format: &str, 
         output_path: Option<PathBuf>
     ) -> Result<PathBuf> {
-        let exporter = self.exporters.get(format)
+        let exporter = self.get_exporter(format)
             .ok_or_else(|| Error::Export(format!("Unsupported export format: {}", format)))?;
 
-        let output_path = output_path.unwrap_or_else(|| {
-            Self::generate_default_filename(scan, exporter.get_file_extension())
-        });
+        let output_path = match output_path {
+            Some(path) => path,
+            None => self.generate_default_filename(scan, exporter.get_file_extension())?,
+        };
 
-        exporter.export_scan(scan, &output_path).await?;
+        exporter.export_scan(&scan.sorted_by(self.port_order), &output_path).await?;
         
         Ok(output_path)
     }
@@ -65,33 +228,218 @@ impl ExportManager {
         format: &str,
         output_path: Option<PathBuf>
     ) -> Result<PathBuf> {
-        let exporter = self.exporters.get(format)
+        let exporter = self.get_exporter(format)
             .ok_or_else(|| Error::Export(format!("Unsupported export format: {}", format)))?;
 
-        let output_path = output_path.unwrap_or_else(|| {
-            Self::generate_vulnerability_filename(report, exporter.get_file_extension())
-        });
+        let output_path = match output_path {
+            Some(path) => path,
+            None => self.generate_vulnerability_filename(report, exporter.get_file_extension())?,
+        };
 
         exporter.export_vulnerability_report(report, &output_path).await?;
         
         Ok(output_path)
     }
 
+    /// Export several stored scans into a single file.
+    ///
+    /// JSON produces an array, CSV one table with a scan-id column and HTML one
+    /// page with a section per scan. With `archive` set, each scan is exported
+    /// individually in `format` and bundled into a `.zip`. Scans are loaded and
+    /// written one at a time so memory stays bounded.
+    pub async fn export_scans(
+        &self,
+        repository: &ScanRepository,
+        scan_ids: &[String],
+        format: &str,
+        output_path: &PathBuf,
+        archive: bool,
+    ) -> Result<PathBuf> {
+        if archive {
+            return self.export_scans_zip(repository, scan_ids, format, output_path).await;
+        }
+
+        let mut file = BufWriter::new(File::create(output_path)?);
+
+        match format {
+            "json" => {
+                let json_exporter = JsonExporter::new().with_min_service_confidence(self.min_service_confidence);
+                file.write_all(b"[\n")?;
+                for (index, scan) in Self::load_scans(repository, scan_ids).enumerate() {
+                    let scan = scan.await?;
+                    if index > 0 {
+                        file.write_all(b",\n")?;
+                    }
+                    serde_json::to_writer_pretty(&mut file, &json_exporter.serialize_scan(&scan)?)?;
+                }
+                file.write_all(b"\n]\n")?;
+            }
+            "csv" => {
+                let csv_exporter = CsvExporter::new().with_min_service_confidence(self.min_service_confidence);
+                let mut writer = csv::Writer::from_writer(file);
+                writer.write_record(&CsvExporter::BATCH_HEADER)?;
+                // Rows go straight from the database to the file, so a large
+                // batch never holds more than one port in memory
+                for scan_id in scan_ids {
+                    if repository.get_scan(scan_id).await?.is_none() {
+                        return Err(Error::Export(format!("Scan not found: {}", scan_id)));
+                    }
+                    let mut ports = repository.stream_scan_ports(scan_id)
+                        .try_filter(|record| futures::future::ready(record.status == "open"));
+                    while let Some(record) = ports.try_next().await? {
+                        csv_exporter.write_batch_row(&mut writer, scan_id, &port_record_to_info(record))?;
+                    }
+                }
+                writer.flush()?;
+                return Ok(output_path.clone());
+            }
+            "html" => {
+                let html_exporter = self.html_exporter();
+                let title = format!("{} scans", scan_ids.len());
+                file.write_all(html_exporter.generate_batch_header(&title)?.as_bytes())?;
+                for scan in Self::load_scans(repository, scan_ids) {
+                    file.write_all(html_exporter.generate_batch_section(&scan.await?)?.as_bytes())?;
+                }
+                file.write_all(html_exporter.generate_batch_footer()?.as_bytes())?;
+            }
+            other => {
+                return Err(Error::Export(format!(
+                    "Combined {} export is not supported; use a zip archive instead", other
+                )));
+            }
+        }
+
+        file.flush()?;
+        Ok(output_path.clone())
+    }
+
+    async fn export_scans_zip(
+        &self,
+        repository: &ScanRepository,
+        scan_ids: &[String],
+        format: &str,
+        output_path: &PathBuf,
+    ) -> Result<PathBuf> {
+        let exporter = self.get_exporter(format)
+            .ok_or_else(|| Error::Export(format!("Unsupported export format: {}", format)))?;
+
+        let output_path = output_path.with_extension("zip");
+        let mut zip = zip::ZipWriter::new(File::create(&output_path)?);
+        let options = zip::write::FileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+
+        // Removed when dropped, so an error on the way doesn't leak it
+        let staging_dir = tempfile::Builder::new().prefix("portzilla-export-").tempdir()?;
+
+        for scan in Self::load_scans(repository, scan_ids) {
+            let scan = scan.await?;
+            let entry_name = format!("{}.{}", scan.id, exporter.get_file_extension());
+            let staged_path = staging_dir.path().join(&entry_name);
+
+            exporter.export_scan(&scan, &staged_path).await?;
+
+            zip.start_file(entry_name, options)
+                .map_err(|e| Error::Export(e.to_string()))?;
+            std::io::copy(&mut File::open(&staged_path)?, &mut zip)?;
+            std::fs::remove_file(&staged_path)?;
+        }
+
+        zip.finish().map_err(|e| Error::Export(e.to_string()))?;
+        staging_dir.close()?;
+
+        Ok(output_path)
+    }
+
+    fn load_scans<'a>(
+        repository: &'a ScanRepository,
+        scan_ids: &'a [String],
+    ) -> impl Iterator<Item = impl std::future::Future<Output = Result<ScanResult>> + 'a> + 'a {
+        scan_ids.iter().map(move |scan_id| async move {
+            repository.get_scan_result(scan_id).await?
+                .ok_or_else(|| Error::Export(format!("Scan not found: {}", scan_id)))
+        })
+    }
+
     pub fn get_supported_formats(&self) -> Vec<&str> {
         self.exporters.keys().map(|s| s.as_str()).collect()
     }
 
-    fn generate_default_filename(scan: &ScanResult, extension: &str) -> PathBuf {
-        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-        let target_clean = scan.target.replace(['.', ':'], "_");
-        PathBuf::from(format!("portzilla_scan_{}_{}.{}", target_clean, timestamp, extension))
+    fn generate_default_filename(&self, scan: &ScanResult, extension: &str) -> Result<PathBuf> {
+        self.resolve_filename("scan", &scan.target, extension)
+    }
+
+    fn generate_vulnerability_filename(&self, report: &VulnerabilityReport, extension: &str) -> Result<PathBuf> {
+        self.resolve_filename("vuln", &report.target, extension)
     }
 
-    fn generate_vulnerability_filename(report: &VulnerabilityReport, extension: &str) -> PathBuf {
-        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-        let target_clean = report.target.replace(['.', ':'], "_");
-        PathBuf::from(format!("portzilla_vuln_{}_{}.{}", target_clean, timestamp, extension))
+    /// `<output_directory>/portzilla_<kind>_<target>[_<timestamp>].<ext>`,
+    /// creating the directory if it doesn't exist yet
+    fn resolve_filename(&self, kind: &str, target: &str, extension: &str) -> Result<PathBuf> {
+        let target_clean = sanitize_target(target);
+        let filename = if self.include_timestamps {
+            let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+            format!("portzilla_{}_{}_{}.{}", kind, target_clean, timestamp, extension)
+        } else {
+            format!("portzilla_{}_{}.{}", kind, target_clean, extension)
+        };
+
+        match &self.output_directory {
+            Some(directory) => {
+                std::fs::create_dir_all(directory)?;
+                Ok(directory.join(filename))
+            }
+            None => Ok(PathBuf::from(filename)),
+        }
+    }
+}
+
+/// Export a finished scan in the configured default format and location
+pub async fn auto_export(scan: &ScanResult, settings: &Settings, port_order: PortOrder) -> Result<PathBuf> {
+    let format = format!("{:?}", settings.export.default_format).to_lowercase();
+    let path = ExportManager::new()
+        .with_settings(&settings.export)
+        .with_color_scheme(settings.ui.color_scheme.clone())
+        .with_port_order(port_order)
+        .export_scan(scan, &format, None)
+        .await?;
+
+    tracing::info!("Auto-exported scan {} to {}", scan.id, path.display());
+    Ok(path)
+}
+
+/// `auto_export` for a vulnerability assessment. With `min_level` the file
+/// lists the same findings as the terminal (`vulnerability --min-level`); the
+/// summary keeps the true totals, as `retain_min_level` does.
+pub async fn auto_export_vulnerability_report(
+    report: &VulnerabilityReport,
+    settings: &Settings,
+    min_level: Option<&crate::vulnerability::VulnerabilityLevel>,
+) -> Result<PathBuf> {
+    let mut report = report.clone();
+    if let Some(min_level) = min_level {
+        report.retain_min_level(min_level);
     }
+
+    let format = format!("{:?}", settings.export.default_format).to_lowercase();
+    let path = ExportManager::new()
+        .with_settings(&settings.export)
+        .with_color_scheme(settings.ui.color_scheme.clone())
+        .export_vulnerability_report(&report, &format, None)
+        .await?;
+
+    tracing::info!("Auto-exported vulnerability report {} to {}", report.id, path.display());
+    Ok(path)
+}
+
+/// Make a target usable as a file name component on every platform
+/// (IPv6 colons and brackets, path separators, ...)
+fn sanitize_target(target: &str) -> String {
+    target
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect()
 }
 
 impl Default for ExportManager {
@@ -99,3 +447,236 @@ impl Default for ExportManager {
         Self::new()
     }
                                  }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ColorScheme, ExportSettings, Settings};
+    use crate::scanner::ScanType;
+
+    #[test]
+    fn test_ipv6_target_filename_is_safe_and_under_output_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_directory = temp_dir.path().join("exports");
+        let settings = ExportSettings {
+            output_directory: output_directory.to_string_lossy().into_owned(),
+            include_timestamps: false,
+            ..ExportSettings::default()
+        };
+        let manager = ExportManager::new().with_settings(&settings);
+        let scan = ScanResult::new(
+            "2001:db8::1".to_string(),
+            "2001:db8::1".parse().unwrap(),
+            ScanType::Quick,
+        );
+
+        let path = manager.generate_default_filename(&scan, "json").unwrap();
+
+        assert_eq!(path, output_directory.join("portzilla_scan_2001_db8__1.json"));
+        assert!(output_directory.is_dir());
+        assert_eq!(sanitize_target("[2001:db8::1]"), "2001_db8__1");
+    }
+
+    #[tokio::test]
+    async fn test_bad_input_is_an_error_not_a_panic() {
+        let manager = ExportManager::new();
+        let scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+
+        assert!(matches!(manager.export_scan(&scan, "docx", None).await, Err(Error::Export(_))));
+        assert!(manager.resolve_format(Some("csv"), Some(Path::new("scan.json")), "json").is_err());
+        assert!(manager.resolve_format(None, Some(Path::new("scan.docx")), "docx").is_err());
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let missing_directory = temp_dir.path().join("missing").join("scan.json");
+        assert!(manager.export_scan(&scan, "json", Some(missing_directory)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_zip_export_removes_its_staging_directory() {
+        let staged = || std::fs::read_dir(std::env::temp_dir()).unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("portzilla-export-"))
+            .count();
+        let before = staged();
+
+        let repository = ScanRepository::new(crate::storage::database::Database::new("sqlite::memory:").await.unwrap());
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        scan.finalize();
+        repository.save_scan(&scan).await.unwrap();
+
+        // The second scan doesn't exist, so the export fails half way
+        let temp_dir = tempfile::tempdir().unwrap();
+        let scan_ids = vec![scan.id.clone(), "no-such-scan".to_string()];
+        let result = ExportManager::new()
+            .export_scans(&repository, &scan_ids, "json", &temp_dir.path().join("scans"), true)
+            .await;
+
+        assert!(matches!(result, Err(Error::Export(_))));
+        assert_eq!(staged(), before);
+    }
+
+    #[tokio::test]
+    async fn test_combined_csv_lists_each_scans_open_ports() {
+        let repository = ScanRepository::new(crate::storage::database::Database::new("sqlite::memory:").await.unwrap());
+        let mut scans = Vec::new();
+        for open in [vec![80, 22], vec![]] {
+            let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+            for port in open {
+                scan.add_open_port(crate::scanner::PortInfo {
+                    port,
+                    status: crate::scanner::PortStatus::Open,
+                    service: None,
+                    banner: None,
+                    banner_raw: None,
+                    response_time: None,
+                    protocol: crate::scanner::Protocol::Tcp,
+                    tls: None,
+                });
+            }
+            scan.finalize();
+            repository.save_scan(&scan).await.unwrap();
+            scans.push(scan.id);
+        }
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("scans.csv");
+        ExportManager::new()
+            .export_scans(&repository, &scans, "csv", &output_path, false)
+            .await
+            .unwrap();
+
+        let mut reader = csv::Reader::from_path(&output_path).unwrap();
+        let rows: Vec<(String, String)> = reader.records()
+            .map(|record| {
+                let record = record.unwrap();
+                (record[0].to_string(), record[1].to_string())
+            })
+            .collect();
+        assert_eq!(rows, vec![
+            (scans[0].clone(), "22".to_string()),
+            (scans[0].clone(), "80".to_string()),
+        ]);
+
+        let missing = vec!["no-such-scan".to_string()];
+        let result = ExportManager::new()
+            .export_scans(&repository, &missing, "csv", &output_path, false)
+            .await;
+        assert!(matches!(result, Err(Error::Export(_))));
+    }
+
+    /// One `target port` line per open port
+    struct SiemExporter;
+
+    #[async_trait]
+    impl Exporter for SiemExporter {
+        async fn export_scan(&self, scan: &ScanResult, output_path: &PathBuf) -> Result<PathBuf> {
+            let lines: String = scan.open_ports.iter()
+                .map(|p| format!("{} {}\n", scan.target, p.port))
+                .collect();
+            std::fs::write(output_path, lines)?;
+            Ok(output_path.clone())
+        }
+
+        async fn export_vulnerability_report(&self, _report: &VulnerabilityReport, output_path: &PathBuf) -> Result<PathBuf> {
+            Ok(output_path.clone())
+        }
+
+        fn get_file_extension(&self) -> &'static str {
+            "siem"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_exporter_by_key() {
+        let mut manager = ExportManager::new();
+        assert!(manager.get_exporter("siem").is_none());
+        manager.register("siem".to_string(), Box::new(SiemExporter));
+        assert_eq!(manager.get_exporter("siem").unwrap().get_file_extension(), "siem");
+        assert!(manager.get_exporter("json").is_some());
+
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        scan.add_open_port(crate::scanner::PortInfo {
+            port: 22,
+            status: crate::scanner::PortStatus::Open,
+            service: None,
+            banner: None,
+            banner_raw: None,
+            response_time: None,
+            protocol: crate::scanner::Protocol::Tcp,
+            tls: None,
+        });
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output = temp_dir.path().join("scan.siem");
+        let path = manager.export_scan(&scan, "siem", Some(output.clone())).await.unwrap();
+
+        assert_eq!(path, output);
+        assert_eq!(std::fs::read_to_string(output).unwrap(), "10.0.0.1 22\n");
+    }
+
+    #[tokio::test]
+    async fn test_vulnerability_auto_export_honors_min_level() {
+        use crate::vulnerability::{Vulnerability, VulnerabilityLevel};
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut settings = Settings::default();
+        settings.export.output_directory = temp_dir.path().to_string_lossy().into_owned();
+        settings.export.include_timestamps = false;
+
+        let mut report = VulnerabilityReport::new("scan-1".to_string(), "10.0.0.1".to_string(), "10.0.0.1".parse().unwrap());
+        for (title, level) in [("Telnet exposed", VulnerabilityLevel::High), ("Banner leaks version", VulnerabilityLevel::Low)] {
+            report.add_vulnerability(Vulnerability::new(
+                title.to_string(),
+                String::new(),
+                level,
+                23,
+                "telnet".to_string(),
+                String::new(),
+            ));
+        }
+        let titles = |path: &PathBuf| -> Vec<String> {
+            let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+            json["vulnerabilities"].as_array().unwrap().iter()
+                .map(|v| v["title"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        let path = auto_export_vulnerability_report(&report, &settings, Some(&VulnerabilityLevel::High)).await.unwrap();
+        assert_eq!(titles(&path), vec!["Telnet exposed".to_string()]);
+        assert_eq!(report.vulnerabilities.len(), 2);
+
+        let path = auto_export_vulnerability_report(&report, &settings, None).await.unwrap();
+        assert_eq!(titles(&path).len(), 2);
+    }
+
+    #[test]
+    fn test_format_inferred_from_output_extension() {
+        let mut manager = ExportManager::new();
+        let resolve = |manager: &ExportManager, path: &str| manager.resolve_format(None, Some(Path::new(path)), "json");
+
+        assert_eq!(resolve(&manager, "report.csv").unwrap(), "csv");
+        assert_eq!(resolve(&manager, "out/Report.HTML").unwrap(), "html");
+        assert_eq!(resolve(&manager, "report").unwrap(), "json");
+        assert_eq!(manager.resolve_format(None, None, "xml").unwrap(), "xml");
+
+        // Known extension, but nothing registered for it yet
+        assert!(matches!(resolve(&manager, "report.sarif"), Err(Error::Export(_))));
+        manager.register("siem".to_string(), Box::new(SiemExporter));
+        assert_eq!(resolve(&manager, "feed.siem").unwrap(), "siem");
+    }
+
+    #[test]
+    fn test_explicit_format_wins_unless_extension_conflicts() {
+        let manager = ExportManager::new();
+
+        assert_eq!(manager.resolve_format(Some("pdf"), Some(Path::new("report.out")), "json").unwrap(), "pdf");
+        assert_eq!(manager.resolve_format(Some("csv"), Some(Path::new("report.csv")), "json").unwrap(), "csv");
+        assert_eq!(manager.resolve_format(Some("xml"), None, "json").unwrap(), "xml");
+
+        let conflict = manager.resolve_format(Some("json"), Some(Path::new("report.csv")), "json");
+        match conflict {
+            Err(Error::Validation(message)) => assert!(message.contains("report.csv"), "{}", message),
+            other => panic!("expected a conflict error, got {:?}", other),
+        }
+    }
+}
@@ -5,11 +5,20 @@ use crate::vulnerability::VulnerabilityReport;
 use std::path::PathBuf;
 use async_trait::async_trait;
 
-pub struct PdfExporter;
+pub struct PdfExporter {
+    min_service_confidence: u8,
+}
 
 impl PdfExporter {
     pub fn new() -> Self {
-        Self
+        Self { min_service_confidence: 0 }
+    }
+
+    /// Name services detected with less confidence "unknown (guessed: X)",
+    /// see `ServiceInfo::reported_name`
+    pub fn with_min_service_confidence(mut self, min_service_confidence: u8) -> Self {
+        self.min_service_confidence = min_service_confidence;
+        self
     }
 }
 
@@ -58,7 +67,9 @@ impl PdfExporter {
             scan.statistics.success_rate,
             scan.open_ports.iter().map(|p| {
                 format!("  - Port {}: {} ({})", p.port, 
-                    p.service.as_ref().map(|s| &s.name).unwrap_or("unknown"),
+                    p.service.as_ref()
+                        .map(|s| s.reported_name(self.min_service_confidence))
+                        .unwrap_or_else(|| "unknown".to_string()),
                     p.banner.as_deref().unwrap_or("no banner")
                 )
             }).collect::<Vec<String>>().join("\n")
@@ -0,0 +1,111 @@
+use super::Exporter;
+use crate::error::{Error, Result};
+use crate::scanner::ScanResult;
+use crate::vulnerability::VulnerabilityReport;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use async_trait::async_trait;
+
+/// File signature and format version, ahead of the MessagePack body
+const MAGIC: &[u8; 4] = b"PZR\x01";
+
+/// Compact binary archive of a scan (`.pzr`), for long-term storage and
+/// reloading; JSON stays the interchange format. Structs are written as
+/// MessagePack arrays rather than maps, half the size of a keyed encoding;
+/// older archives still load as long as new fields are appended last with
+/// `#[serde(default)]`.
+pub struct PzrExporter;
+
+impl PzrExporter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn to_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+        let mut bytes = MAGIC.to_vec();
+        rmp_serde::encode::write(&mut bytes, value)
+            .map_err(|e| Error::Export(format!("Failed to encode .pzr archive: {}", e)))?;
+        Ok(bytes)
+    }
+
+    pub fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+        let body = bytes.strip_prefix(MAGIC.as_slice())
+            .ok_or_else(|| Error::Validation("Not a .pzr archive, or one from a newer version".to_string()))?;
+        rmp_serde::from_slice(body)
+            .map_err(|e| Error::Export(format!("Corrupt .pzr archive: {}", e)))
+    }
+
+    /// Reload a scan archived with this exporter, e.g. to diff or re-export it
+    pub fn load_scan(path: &Path) -> Result<ScanResult> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    pub fn load_vulnerability_report(path: &Path) -> Result<VulnerabilityReport> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    fn write(value: &impl Serialize, output_path: &PathBuf) -> Result<PathBuf> {
+        let mut file = BufWriter::new(File::create(output_path)?);
+        file.write_all(&Self::to_bytes(value)?)?;
+        file.flush()?;
+        Ok(output_path.clone())
+    }
+}
+
+#[async_trait]
+impl Exporter for PzrExporter {
+    async fn export_scan(&self, scan: &ScanResult, output_path: &PathBuf) -> Result<PathBuf> {
+        Self::write(scan, output_path)
+    }
+
+    async fn export_vulnerability_report(&self, report: &VulnerabilityReport, output_path: &PathBuf) -> Result<PathBuf> {
+        Self::write(report, output_path)
+    }
+
+    fn get_file_extension(&self) -> &'static str {
+        "pzr"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{PortInfo, PortStatus, Protocol, ScanType};
+    use std::time::Duration;
+
+    #[test]
+    fn test_round_trip_keeps_every_field_and_beats_json() {
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Full);
+        for port in 1..=2000 {
+            scan.add_open_port(PortInfo {
+                port,
+                status: PortStatus::Open,
+                service: None,
+                banner: Some(format!("SSH-2.0-OpenSSH_9.{}", port % 10)),
+                banner_raw: (port % 7 == 0).then(|| format!("00ff{:04x}", port)),
+                response_time: Some(Duration::from_micros(1500 + u64::from(port))),
+                protocol: if port % 2 == 0 { Protocol::Tcp } else { Protocol::Udp },
+                tls: None,
+            });
+        }
+
+        let bytes = PzrExporter::to_bytes(&scan).unwrap();
+        let loaded: ScanResult = PzrExporter::from_bytes(&bytes).unwrap();
+
+        // Same logical content: every field serializes identically
+        assert_eq!(serde_json::to_value(&loaded).unwrap(), serde_json::to_value(&scan).unwrap());
+
+        let json = serde_json::to_vec(&scan).unwrap();
+        assert!(
+            bytes.len() * 2 < json.len(),
+            ".pzr is {} bytes against {} of JSON",
+            bytes.len(),
+            json.len()
+        );
+
+        assert!(matches!(PzrExporter::from_bytes::<ScanResult>(&json), Err(Error::Validation(_))));
+    }
+}
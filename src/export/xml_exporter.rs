@@ -1,19 +1,29 @@
 use super::Exporter;
 use crate::error::Result;
-use crate::scanner::ScanResult;
+use crate::scanner::{PortInfo, ScanResult};
 use crate::vulnerability::VulnerabilityReport;
 use quick_xml::events::{BytesDecl, Event};
 use quick_xml::Writer;
+use std::borrow::Cow;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
 use async_trait::async_trait;
 
-pub struct XmlExporter;
+pub struct XmlExporter {
+    min_service_confidence: u8,
+}
 
 impl XmlExporter {
     pub fn new() -> Self {
-        Self
+        Self { min_service_confidence: 0 }
+    }
+
+    /// Label services detected with less confidence as guessed, see
+    /// `ServiceInfo::reported_name`
+    pub fn with_min_service_confidence(mut self, min_service_confidence: u8) -> Self {
+        self.min_service_confidence = min_service_confidence;
+        self
     }
 }
 
@@ -71,38 +81,35 @@ impl XmlExporter {
         self.write_xml_element(&mut writer, "open_ports_found", &scan.statistics.open_ports.to_string())?;
         self.write_xml_element(&mut writer, "closed_ports", &scan.statistics.closed_ports.to_string())?;
         self.write_xml_element(&mut writer, "success_rate", &scan.statistics.success_rate.to_string())?;
+        if let Some(latency) = &scan.statistics.latency {
+            writer.write_event(Event::Start(quick_xml::events::BytesStart::new("latency_ms")))?;
+            for (name, value) in [
+                ("min", latency.min),
+                ("median", latency.median),
+                ("p90", latency.p90),
+                ("p99", latency.p99),
+                ("max", latency.max),
+            ] {
+                self.write_xml_element(&mut writer, name, &format!("{:.3}", value.as_secs_f64() * 1000.0))?;
+            }
+            writer.write_event(Event::End(quick_xml::events::BytesEnd::new("latency_ms")))?;
+        }
+        writer.write_event(Event::Start(quick_xml::events::BytesStart::new("per_protocol")))?;
+        for stats in &scan.statistics.per_protocol {
+            let name = format!("{:?}", stats.protocol).to_lowercase();
+            writer.write_event(Event::Start(quick_xml::events::BytesStart::new(name.as_str())))?;
+            self.write_xml_element(&mut writer, "total_ports", &stats.total_ports.to_string())?;
+            self.write_xml_element(&mut writer, "open_ports", &stats.open_ports.to_string())?;
+            writer.write_event(Event::End(quick_xml::events::BytesEnd::new(name.as_str())))?;
+        }
+        writer.write_event(Event::End(quick_xml::events::BytesEnd::new("per_protocol")))?;
         writer.write_event(Event::End(quick_xml::events::BytesEnd::new("statistics")))?;
         
-        // Open ports
+        // Open ports, grouped by protocol
         writer.write_event(Event::Start(quick_xml::events::BytesStart::new("open_ports")))?;
-        for port in &scan.open_ports {
-            writer.write_event(Event::Start(quick_xml::events::BytesStart::new("port")))?;
-            self.write_xml_element(&mut writer, "number", &port.port.to_string())?;
-            self.write_xml_element(&mut writer, "status", &format!("{:?}", port.status))?;
-            self.write_xml_element(&mut writer, "protocol", &format!("{:?}", port.protocol))?;
-            
-            if let Some(service) = &port.service {
-                writer.write_event(Event::Start(quick_xml::events::BytesStart::new("service")))?;
-                self.write_xml_element(&mut writer, "name", &service.name)?;
-                if let Some(version) = &service.version {
-                    self.write_xml_element(&mut writer, "version", version)?;
-                }
-                if let Some(product) = &service.product {
-                    self.write_xml_element(&mut writer, "product", product)?;
-                }
-                self.write_xml_element(&mut writer, "confidence", &service.confidence.to_string())?;
-                writer.write_event(Event::End(quick_xml::events::BytesEnd::new("service")))?;
-            }
-            
-            if let Some(banner) = &port.banner {
-                self.write_xml_element(&mut writer, "banner", banner)?;
-            }
-            
-            if let Some(response_time) = port.response_time {
-                self.write_xml_element(&mut writer, "response_time_ms", &response_time.as_millis().to_string())?;
-            }
-            
-            writer.write_event(Event::End(quick_xml::events::BytesEnd::new("port")))?;
+        self.write_port_group(&mut writer, "tcp", scan.tcp_ports())?;
+        if scan.statistics.udp_ports_scanned > 0 || scan.udp_ports().next().is_some() {
+            self.write_port_group(&mut writer, "udp", scan.udp_ports())?;
         }
         writer.write_event(Event::End(quick_xml::events::BytesEnd::new("open_ports")))?;
         
@@ -168,6 +175,54 @@ impl XmlExporter {
         Ok(String::from_utf8(writer.into_inner())?)
     }
 
+    fn write_port_group<'a>(
+        &self,
+        writer: &mut Writer<Vec<u8>>,
+        protocol: &str,
+        ports: impl Iterator<Item = &'a PortInfo>,
+    ) -> Result<()> {
+        writer.write_event(Event::Start(quick_xml::events::BytesStart::new(protocol)))?;
+        for port in ports {
+            writer.write_event(Event::Start(quick_xml::events::BytesStart::new("port")))?;
+            self.write_xml_element(writer, "number", &port.port.to_string())?;
+            self.write_xml_element(writer, "status", &format!("{:?}", port.status))?;
+            self.write_xml_element(writer, "protocol", &format!("{:?}", port.protocol))?;
+            
+            if let Some(service) = &port.service {
+                writer.write_event(Event::Start(quick_xml::events::BytesStart::new("service")))?;
+                self.write_xml_element(writer, "name", &service.name)?;
+                if let Some(version) = &service.version {
+                    self.write_xml_element(writer, "version", version)?;
+                }
+                if let Some(product) = &service.product {
+                    self.write_xml_element(writer, "product", product)?;
+                }
+                self.write_xml_element(writer, "confidence", &service.confidence.to_string())?;
+                self.write_xml_element(writer, "certainty", service.certainty(self.min_service_confidence))?;
+                self.write_xml_element(writer, "reported_as", &service.reported_name(self.min_service_confidence))?;
+                if let Some(tunnel) = &service.tunnel {
+                    self.write_xml_element(writer, "tunnel", tunnel)?;
+                }
+                if let Some(alpn) = &service.alpn {
+                    self.write_xml_element(writer, "alpn", alpn)?;
+                }
+                writer.write_event(Event::End(quick_xml::events::BytesEnd::new("service")))?;
+            }
+            
+            if let Some(banner) = &port.banner {
+                self.write_xml_element(writer, "banner", banner)?;
+            }
+            
+            if let Some(response_time) = port.response_time {
+                self.write_xml_element(writer, "response_time_ms", &response_time.as_millis().to_string())?;
+            }
+            
+            writer.write_event(Event::End(quick_xml::events::BytesEnd::new("port")))?;
+        }
+        writer.write_event(Event::End(quick_xml::events::BytesEnd::new(protocol)))?;
+        Ok(())
+    }
+
     fn write_xml_element<W: std::io::Write>(
         &self,
         writer: &mut Writer<W>,
@@ -175,14 +230,118 @@ impl XmlExporter {
         value: &str,
     ) -> Result<()> {
         writer.write_event(Event::Start(quick_xml::events::BytesStart::new(name)))?;
-        writer.write_event(Event::Text(quick_xml::events::BytesText::new(value)))?;
+        // BytesText escapes the markup characters; control bytes are ours to handle
+        writer.write_event(Event::Text(quick_xml::events::BytesText::new(&xml_safe(value))))?;
         writer.write_event(Event::End(quick_xml::events::BytesEnd::new(name)))?;
         Ok(())
     }
 }
 
+/// Banners and evidence come straight off the wire and may hold characters
+/// XML 1.0 can't carry at all, not even as character references (NUL and most
+/// C0 controls); those are written as `\xNN` so the document still parses
+fn xml_safe(value: &str) -> Cow<'_, str> {
+    if value.chars().all(is_xml_char) {
+        return Cow::Borrowed(value);
+    }
+
+    Cow::Owned(value.chars().map(|c| {
+        if is_xml_char(c) {
+            c.to_string()
+        } else {
+            format!("\\x{:02X}", c as u32)
+        }
+    }).collect())
+}
+
+/// The XML 1.0 `Char` production
+fn is_xml_char(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r' | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..='\u{10FFFF}')
+}
+
 impl Default for XmlExporter {
     fn default() -> Self {
         Self::new()
     }
       }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{PortStatus, Protocol, ScanType};
+
+    #[test]
+    fn test_banner_with_control_chars_parses_back() {
+        let banner = "SSH-2.0\0<script>&\u{7}</banner>";
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        scan.add_open_port(PortInfo {
+            port: 22,
+            status: PortStatus::Open,
+            service: None,
+            banner: Some(banner.to_string()),
+            banner_raw: None,
+            response_time: None,
+            protocol: Protocol::Tcp,
+            tls: None,
+        });
+
+        let xml = XmlExporter::new().generate_scan_xml(&scan).unwrap();
+        let document = roxmltree::Document::parse(&xml).expect("export must be well-formed XML 1.0");
+
+        let parsed = document.descendants()
+            .find(|node| node.has_tag_name("banner"))
+            .and_then(|node| node.text());
+        assert_eq!(parsed, Some("SSH-2.0\\x00<script>&\\x07</banner>"));
+    }
+
+    #[test]
+    fn test_services_are_labelled_against_the_threshold() {
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        for (port, name, confidence) in [(22, "ssh", 90), (8080, "http-proxy", 60)] {
+            scan.add_open_port(PortInfo {
+                port,
+                status: PortStatus::Open,
+                service: Some(crate::scanner::ServiceInfo {
+                    name: name.to_string(),
+                    version: None,
+                    product: None,
+                    extra_info: None,
+                    confidence,
+                    tunnel: None,
+                    alpn: None,
+                }),
+                banner: None,
+                banner_raw: None,
+                response_time: None,
+                protocol: Protocol::Tcp,
+                tls: None,
+            });
+        }
+
+        let labels = |min_service_confidence: u8| {
+            let xml = XmlExporter::new().with_min_service_confidence(min_service_confidence).generate_scan_xml(&scan).unwrap();
+            let document = roxmltree::Document::parse(&xml).unwrap();
+            let texts = |tag: &str| -> Vec<String> {
+                document.descendants()
+                    .filter(|node| node.has_tag_name(tag))
+                    .filter_map(|node| node.text().map(str::to_string))
+                    .collect()
+            };
+            (texts("certainty"), texts("reported_as"))
+        };
+
+        // Exactly at the port-number guess's confidence it still counts
+        assert_eq!(labels(60).0, vec!["confirmed", "confirmed"]);
+        assert_eq!(labels(61), (
+            vec!["confirmed".to_string(), "guessed".to_string()],
+            vec!["ssh".to_string(), "unknown (guessed: http-proxy)".to_string()],
+        ));
+        assert_eq!(labels(91).0, vec!["guessed", "guessed"]);
+    }
+
+    #[test]
+    fn test_xml_safe_leaves_valid_text_alone() {
+        assert!(matches!(xml_safe("nginx/1.25 \"<ok>\"\t\r\n"), Cow::Borrowed(_)));
+        assert_eq!(xml_safe("a\u{1B}[0m\u{FFFF}"), "a\\x1B[0m\\xFFFF");
+    }
+}
@@ -1,117 +1,400 @@
 use portscanner_enterprise::{
-    cli::{Cli, Command},
-    config::Settings,
+    cli::{gate, Cli, Command},
+    config::{ConfigManager, LogFormat, Settings},
     error::{Error, Result},
-    storage::ScanRepository,
+    storage::{database::Database, repository::ScanStatusRecorder, ScanRepository},
     utils::setup_logging,
 };
-use tracing::{error, info, Level};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn, Level};
 use std::process;
 
+/// Connect timeout when neither --timeout nor a preset sets one
+const DEFAULT_TIMEOUT_MS: u64 = 100;
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Parse command line arguments
+    let cli = Cli::parse();
+    
+    // Load configuration (logging settings come from it)
+    let config = ConfigManager::with_profile(std::path::PathBuf::from(&cli.config), cli.profile.clone())?;
+    
+    // Colors follow ui.color_scheme, unless disabled or piped
+    portscanner_enterprise::ui::theme::init(&config.get_settings().ui.color_scheme, cli.no_color);
+    let log_colors = portscanner_enterprise::ui::theme::should_color(cli.no_color, std::io::IsTerminal::is_terminal(&std::io::stderr()));
+    
     // Setup logging and error handling
-    setup_logging(Level::INFO)?;
+    let mut logging = config.get_settings().logging.clone();
+    if cli.json_logs {
+        logging.format = LogFormat::Json;
+    }
+    // CI gates print nothing but their violations
+    let log_level = if cli.is_gating() { Level::ERROR } else { Level::INFO };
+    let _log_guard = setup_logging(log_level, &logging, log_colors)?;
     
     // Initialize panic hook for better error reporting
     initialize_panic_hook();
     
     info!("🚀 Starting PortScanner Enterprise v1.0.0");
     
-    if let Err(e) = run().await {
-        error!("❌ Application error: {}", e);
-        eprintln!("Error: {}", e);
-        process::exit(1);
+    let outcome = run(cli, config).await;
+    match &outcome {
+        Ok(violations) => violations.iter().for_each(|violation| println!("{}", violation)),
+        Err(e) => {
+            error!("❌ Application error: {}", e);
+            eprintln!("Error: {}", e);
+        }
+    }
+    
+    let code = gate::exit_code(&outcome);
+    if code != gate::EXIT_CLEAN {
+        process::exit(code);
     }
     
     info!("👋 PortScanner Enterprise shutdown complete");
     Ok(())
 }
 
-async fn run() -> Result<()> {
-    // Parse command line arguments
-    let cli = Cli::parse();
+/// Returns CI gate violations; empty unless a gate was requested and tripped
+async fn run(cli: Cli, config: ConfigManager) -> Result<Vec<String>> {
+    let settings = config.get_settings().clone();
+    match config.active_profile() {
+        Some(profile) => info!("📋 Configuration loaded successfully (profile: {})", profile),
+        None => info!("📋 Configuration loaded successfully"),
+    }
     
-    // Load configuration
-    let settings = Settings::new()?;
-    info!("📋 Configuration loaded successfully");
+    // Listing ports touches neither the network nor the database
+    if let Command::ListPorts(list_args) = &cli.command {
+        list_ports(list_args, &settings)?;
+        return Ok(Vec::new());
+    }
+    if let Command::Estimate(estimate_args) = &cli.command {
+        estimate_scan(estimate_args, &settings)?;
+        return Ok(Vec::new());
+    }
     
     // Initialize database connection
-    let repository = ScanRepository::new(&settings.database.connection_string).await?;
+    let db = Database::connect_with_retry(
+        &settings.database.connection_string,
+        settings.database.enable_migrations,
+        settings.database.connect_attempts,
+        std::time::Duration::from_millis(settings.database.connect_retry_interval_ms),
+    ).await?;
+    let repository = ScanRepository::new(db);
     info!("💾 Database connection established");
     
     // Execute the requested command
     match cli.command {
         Command::Scan(scan_args) => {
-            execute_scan(scan_args, &settings, &repository).await?;
+            return execute_scan(scan_args, &settings, &repository).await;
+        }
+        Command::Enrich(enrich_args) => {
+            enrich_scan(enrich_args, &settings, &repository).await?;
         }
         Command::Vulnerability(vuln_args) => {
-            execute_vulnerability_scan(vuln_args, &settings, &repository).await?;
+            return execute_vulnerability_scan(vuln_args, &settings, &repository).await;
+        }
+        Command::VulnDiff(diff_args) => {
+            show_vulnerability_diff(diff_args, &repository).await?;
+        }
+        Command::ScanDiff(diff_args) => {
+            export_scan_diff(diff_args, &settings, &repository).await?;
+        }
+        Command::Suppress(suppress_args) => {
+            manage_suppressions(suppress_args, &repository).await?;
         }
         Command::History(history_args) => {
             show_scan_history(history_args, &repository).await?;
         }
+        Command::Timeline(timeline_args) => {
+            show_target_timeline(timeline_args, &repository).await?;
+        }
+        Command::ListPorts(_) | Command::Estimate(_) => unreachable!("handled before connecting to the database"),
+        Command::CompareTechniques(compare_args) => {
+            compare_techniques(compare_args, &settings, &repository).await?;
+        }
         Command::Export(export_args) => {
-            export_scan_results(export_args, &repository).await?;
+            export_scan_results(export_args, &settings, &repository).await?;
+        }
+        Command::ExportBatch(batch_args) => {
+            export_scan_batch(batch_args, &settings, &repository).await?;
+        }
+        Command::Import(import_args) => {
+            import_scan(import_args, &repository).await?;
         }
         Command::Config(config_args) => {
             manage_configuration(config_args, &settings).await?;
         }
         Command::Server(server_args) => {
-            start_web_server(server_args, &settings, repository).await?;
+            start_web_server(server_args, config, repository).await?;
         }
         Command::Interactive => {
             start_interactive_mode(&settings, repository).await?;
         }
     }
     
-    Ok(())
+    Ok(Vec::new())
 }
 
 async fn execute_scan(
     scan_args: crate::cli::ScanArgs,
     settings: &Settings,
     repository: &ScanRepository,
-) -> Result<()> {
+) -> Result<Vec<String>> {
     use portscanner_enterprise::scanner::{ScanEngine, ScanType};
+    use tokio::sync::mpsc;
+    
+    // A resumed scan takes its target and scan type from the checkpoint
+    let checkpoint = match &scan_args.resume {
+        Some(scan_id) => Some(
+            repository.get_checkpoint(scan_id).await?
+                .ok_or_else(|| Error::Validation(format!("No checkpoint for scan {}", scan_id)))?
+        ),
+        None => None,
+    };
+    let target = match (&checkpoint, &scan_args.target) {
+        (Some(checkpoint), _) => checkpoint.target.clone(),
+        (None, Some(target)) => target.clone(),
+        (None, None) => return Err(Error::Validation("A target or --resume is required".to_string())),
+    };
     
-    info!("🎯 Starting scan for target: {}", scan_args.target);
+    info!("🎯 Starting scan for target: {}", target);
+    
+    let preset = scan_args.preset.as_deref().map(|name| settings.preset(name)).transpose()?;
     
     // Validate target and parameters
-    validate_scan_parameters(&scan_args, settings)?;
+    validate_scan_parameters(&target, &scan_args, preset.as_ref(), settings)?;
     
-    // Create scan engine
-    let engine = ScanEngine::new(settings)?;
+    // Create scan engine; checkpoints are persisted as the scan goes
+    let (checkpoint_tx, mut checkpoint_rx) = mpsc::unbounded_channel();
+    let status_recorder = std::sync::Arc::new(ScanStatusRecorder::new(repository.clone()));
+    let mut engine = ScanEngine::new(build_scan_config(&scan_args, settings, preset.as_ref()))?
+        .with_checkpoints(checkpoint_tx)
+        .with_observer(status_recorder.clone());
+    let checkpoint_writer = {
+        let repository = repository.clone();
+        tokio::spawn(async move {
+            let mut announced = false;
+            while let Some(checkpoint) = checkpoint_rx.recv().await {
+                match repository.save_checkpoint(&checkpoint).await {
+                    Ok(()) if !announced => {
+                        info!("📍 Checkpointing scan {}; if interrupted, continue with --resume {}", checkpoint.scan_id, checkpoint.scan_id);
+                        announced = true;
+                    }
+                    Ok(()) => {}
+                    Err(e) => warn!("Could not save checkpoint of scan {}: {}", checkpoint.scan_id, e),
+                }
+            }
+        })
+    };
+    if scan_args.incremental {
+        let max_age = chrono::Duration::from_std(scan_args.max_age.0)
+            .map_err(|e| Error::Validation(format!("--max-age out of range: {}", e)))?;
+        match repository.get_latest_scan_result(&target, chrono::Utc::now() - max_age).await? {
+            Some(baseline) => {
+                info!("♻️  Incremental scan based on scan {}", baseline.id);
+                engine = engine.with_baselines([baseline]);
+            }
+            None => info!("♻️  No scan of {} within --max-age; scanning every port", target),
+        }
+    }
     
-    // Determine scan type
-    let scan_type = match (scan_args.scan_type, scan_args.port_range) {
-        (Some(scan_type), _) => scan_type,
-        (None, Some(range)) => ScanType::CustomRange(range.start, range.end),
-        (None, None) => ScanType::Standard, // Default to standard scan
+    // Determine scan type; explicit flags beat the preset's ports
+    let scan_type = match (&checkpoint, scan_args.scan_type, scan_args.port_range, &preset) {
+        (Some(checkpoint), _, _, _) => checkpoint.scan_type.clone(),
+        (None, _, _, _) if scan_args.known_services => ScanType::KnownServices,
+        (None, Some(scan_type), _, _) => scan_type,
+        (None, None, Some(range), _) => ScanType::CustomRange(range.start, range.end),
+        (None, None, None, Some(preset)) => preset.scan_type()?,
+        (None, None, None, None) => ScanType::Standard, // Default to standard scan
     };
     
+    let dangerous = engine.dangerous_ports(&scan_type);
+    if !dangerous.is_empty() && !scan_args.i_understand_the_risk && std::io::IsTerminal::is_terminal(&std::io::stdin())
+        && portscanner_enterprise::ui::interactive::confirm_dangerous_ports(&dangerous)?
+    {
+        engine = engine.with_dangerous_ports_allowed();
+    }
+    
+    let gating = !scan_args.fail_on_open.is_empty();
+    
     // Execute scan
-    let scan_result = engine
-        .scan(&scan_args.target, scan_type)
-        .await?;
+    if let Some(checkpoint) = checkpoint {
+        engine = engine.with_resume(checkpoint);
+    }
+    // Ctrl-C once stops the scan with what it found; twice quits at once
+    let cancellation = CancellationToken::new();
+    engine = engine.with_cancellation(cancellation.clone());
+    tokio::spawn(watch_interrupts(cancellation.clone()));
+    let scan = async {
+        if gating {
+            engine.scan(&target, scan_type).await
+        } else if scan_args.output == crate::cli::ScanOutput::Ndjson {
+            stream_scan_ndjson(&engine, &target, scan_type).await
+        } else if settings.ui.progress_bars_enabled && std::io::IsTerminal::is_terminal(&std::io::stdout()) {
+            scan_with_progress_bar(&engine, &target, scan_type).await
+        } else {
+            engine.scan(&target, scan_type).await
+        }
+    };
+    let scan_result = scan.await;
+    // Dropping the engine closes the channel, so every checkpoint is written
+    // before the scan's own is deleted below
+    drop(engine);
+    let _ = checkpoint_writer.await;
+    // The scan is over either way; from here a Ctrl-C quits at once
+    let interrupted_by_user = cancellation.is_cancelled();
+    cancellation.cancel();
+    let scan_result = match scan_result {
+        Ok(scan_result) => scan_result,
+        Err(e) => {
+            // A scan that errored out after Ctrl-C was cancelled, not failed
+            if let Some(scan_id) = status_recorder.running_scan(&target) {
+                if interrupted_by_user {
+                    status_recorder.cancel(&scan_id).await?;
+                    warn!("⏹️  Scan {} cancelled", scan_id);
+                } else {
+                    status_recorder.fail(&scan_id, &e.to_string()).await?;
+                    warn!("❌ Scan {} failed", scan_id);
+                }
+            }
+            return Err(e);
+        }
+    };
+    let interrupted = scan_result.statistics.cancelled;
     
-    info!(
-        "✅ Scan completed: {} open ports found", 
-        scan_result.open_ports.len()
-    );
+    if interrupted {
+        warn!("⏹️  Scan interrupted: {} open ports found before Ctrl-C", scan_result.open_ports.len());
+    } else {
+        info!(
+            "✅ Scan completed: {} open ports found", 
+            scan_result.open_ports.len()
+        );
+    }
+    if scan_result.statistics.stopped_early {
+        info!("⏹️  Stopped at first open port; remaining ports were not scanned");
+    }
+    if let Some(incremental) = &scan_result.metadata.incremental {
+        info!(
+            "♻️  {} ports not open in scan {} were carried over; the rest were scanned fresh",
+            incremental.carried_over_count(),
+            incremental.baseline_scan_id
+        );
+    }
+    if scan_result.is_host_down() {
+        warn!("💤 {} did not answer host discovery; no ports were scanned (use --skip-host-discovery to force)", target);
+    }
+    if let Some(partial) = &scan_result.partial {
+        warn!(
+            "⚠️  Partial scan: target became unreachable after port {}; {}",
+            partial.last_reached_port.map_or("(none)".to_string(), |port| port.to_string()),
+            partial.reason
+        );
+    }
+    for note in &scan_result.metadata.notes {
+        warn!("⚠️  {}", note);
+    }
     
-    // Save to database
-    let scan_id = repository.save_scan(&scan_result).await?;
+    // Save to database; an interrupted scan keeps its checkpoint for --resume
+    let scan_id = status_recorder.save(&scan_result).await?;
+    if !interrupted {
+        repository.delete_checkpoint(&scan_id).await?;
+    }
     info!("💾 Scan saved with ID: {}", scan_id);
+    for tag in &scan_args.tag {
+        repository.add_tag(&scan_id, tag).await?;
+    }
     
-    // Display results
-    crate::ui::display_scan_results(&scan_result)?;
+    // Display results (NDJSON records were already written to stdout)
+    let port_order = scan_args.sort_by.into();
+    if !gating && scan_args.output != crate::cli::ScanOutput::Ndjson {
+        crate::ui::display_scan_results(&scan_result.sorted_by(port_order))?;
+        crate::ui::display_latency_summary(&scan_result.statistics);
+    }
+    
+    if interrupted {
+        return Err(Error::Interrupted(format!(
+            "Scan {} stopped with Ctrl-C; its partial result was saved. Continue with --resume {}",
+            scan_id, scan_id
+        )));
+    }
     
     // Auto-export if configured
     if settings.export.auto_export {
-        crate::export::auto_export(&scan_result, &settings.export).await?;
+        crate::export::auto_export(&scan_result, settings, port_order).await?;
+    }
+    
+    // A gate must not pass a host it couldn't fully scan
+    if gating && scan_result.is_host_down() {
+        return Err(Error::HostUnreachable(format!("{} did not answer host discovery; no ports were scanned", target)));
+    }
+    if let (true, Some(partial)) = (gating, &scan_result.partial) {
+        return Err(Error::HostUnreachable(format!("{} became unreachable mid-scan: {}", target, partial.reason)));
     }
     
+    let forbidden: Vec<u16> = scan_args.fail_on_open.iter()
+        .flat_map(|range| range.start..=range.end)
+        .collect();
+    Ok(gate::open_port_violations(&scan_result, &forbidden))
+}
+
+/// Standard scan of `target` with service detection, saved like any other,
+/// for `vulnerability <target>` to assess
+async fn scan_for_vulnerabilities(
+    target: &str,
+    settings: &Settings,
+    repository: &ScanRepository,
+) -> Result<portscanner_enterprise::scanner::ScanResult> {
+    use portscanner_enterprise::scanner::{ScanConfig, ScanEngine, ScanType};
+    
+    portscanner_enterprise::utils::validate_target(target)?;
+    settings.security.check_target(target)?;
+    
+    let engine = ScanEngine::new(ScanConfig {
+        enable_service_detection: true,
+        ..ScanConfig::from_settings(settings)
+    })?;
+    
+    let scan_result = engine.scan(target, ScanType::Standard).await?;
+    let scan_id = repository.save_scan(&scan_result).await?;
+    info!("💾 Scan saved with ID: {}", scan_id);
+    Ok(scan_result)
+}
+
+async fn enrich_scan(
+    enrich_args: crate::cli::EnrichArgs,
+    settings: &Settings,
+    repository: &ScanRepository,
+) -> Result<()> {
+    use portscanner_enterprise::scanner::{ScanConfig, ScanEngine};
+    
+    let mut scan_result = repository.get_scan_result(&enrich_args.scan_id).await?
+        .ok_or_else(|| Error::Validation(format!("Scan not found: {}", enrich_args.scan_id)))?;
+    
+    settings.security.check_target(&scan_result.target)?;
+    
+    let engine = ScanEngine::new(ScanConfig {
+        enable_service_detection: true,
+        enable_banner_grabbing: settings.scanner.enable_banner_grabbing,
+        version_intensity: enrich_args.version_intensity,
+        probe_allow: settings.scanner.probe_allow_ports.clone(),
+        probe_deny: settings.scanner.probe_deny_ports.clone(),
+        banner_buffer_size: settings.scanner.banner_buffer_size,
+        banner_max_length: settings.scanner.banner_max_length,
+        max_response_bytes: settings.scanner.max_response_bytes,
+        enrich_concurrency: settings.scanner.enrich_concurrency,
+        probe_identity: settings.scanner.probe_identity.clone(),
+        http_keep_alive: settings.scanner.http_keep_alive,
+        auto_tune_concurrency: settings.scanner.auto_tune_concurrency,
+        ..ScanConfig::default()
+    })?;
+    
+    info!("🔬 Enriching {} open ports of scan {}", scan_result.open_ports.len(), scan_result.id);
+    engine.enrich(&mut scan_result).await?;
+    repository.replace_scan_ports(&scan_result).await?;
+    
+    crate::ui::display_scan_results(&scan_result)?;
     Ok(())
 }
 
@@ -119,28 +402,234 @@ async fn execute_vulnerability_scan(
     vuln_args: crate::cli::VulnerabilityArgs,
     settings: &Settings,
     repository: &ScanRepository,
-) -> Result<()> {
-    use portscanner_enterprise::vulnerability::VulnerabilityScanner;
+) -> Result<Vec<String>> {
+    use portscanner_enterprise::vulnerability::{SuppressionList, VulnerabilityAnalyzer};
+    
+    if vuln_args.update_db {
+        update_cve_feeds(settings, repository).await?;
+        if vuln_args.scan_id.is_none() && vuln_args.target.is_none() {
+            return Ok(Vec::new());
+        }
+    }
     
     info!("🔍 Starting vulnerability assessment");
     
-    let scanner = VulnerabilityScanner::new(settings)?;
+    // Built-in rules plus vulnerability.custom_rules_path
+    let analyzer = VulnerabilityAnalyzer::from_settings(&settings.vulnerability)?;
     
-    let vulnerability_report = if let Some(scan_id) = vuln_args.scan_id {
+    let scan_result = if let Some(scan_id) = vuln_args.scan_id {
         // Run vulnerability scan on existing scan results
-        scanner.analyze_existing_scan(scan_id).await?
+        repository.get_scan_result(&scan_id).await?
+            .ok_or_else(|| Error::Validation(format!("Scan not found: {}", scan_id)))?
     } else if let Some(target) = vuln_args.target {
         // Run new scan with vulnerability assessment
-        scanner.scan_and_analyze(&target).await?
+        scan_for_vulnerabilities(&target, settings, repository).await?
     } else {
         return Err(Error::Validation("Either scan_id or target must be provided".into()));
     };
+    let mut vulnerability_report = analyzer.analyze_scan(&scan_result).await?;
+    
+    // Flag risk-acknowledged findings
+    let suppressions = repository.get_active_suppressions(&vulnerability_report.target).await?;
+    SuppressionList::new(suppressions).apply(&mut vulnerability_report, vuln_args.include_suppressed);
     
     // Save vulnerability report
     repository.save_vulnerability_report(&vulnerability_report).await?;
     
-    // Display results
-    crate::ui::display_vulnerability_report(&vulnerability_report)?;
+    // Stored in full above; only the output and the auto-export are trimmed
+    let min_level = vuln_args.min_level.as_ref().map(portscanner_enterprise::vulnerability::VulnerabilityLevel::from);
+    if settings.export.auto_export {
+        crate::export::auto_export_vulnerability_report(&vulnerability_report, settings, min_level.as_ref()).await?;
+    }
+    if let Some(min_level) = &min_level {
+        vulnerability_report.retain_min_level(min_level);
+    }
+    
+    // Display results, or only the gate's violations
+    match &vuln_args.fail_on_severity {
+        Some(severity) => Ok(gate::severity_violations(&vulnerability_report, &severity.into())),
+        None => {
+            crate::ui::display_vulnerability_report(&vulnerability_report)?;
+            Ok(Vec::new())
+        }
+    }
+}
+
+/// Fetch every configured CVE feed, reporting each one, then import the
+/// fetched (or cached) copies; fails only after all feeds were attempted
+async fn update_cve_feeds(settings: &Settings, repository: &ScanRepository) -> Result<()> {
+    use portscanner_enterprise::vulnerability::{CveFeedDownloader, CveImporter, FeedStatus};
+    
+    info!("📥 Updating {} CVE feed(s)", settings.vulnerability.cve_feeds.len());
+    let downloader = CveFeedDownloader::from_settings(&settings.vulnerability);
+    let report = downloader.update_all(&settings.vulnerability.cve_feeds).await;
+    
+    for outcome in &report.outcomes {
+        match &outcome.status {
+            FeedStatus::Downloaded(path) => println!("✅ {}: updated ({})", outcome.name, path.display()),
+            FeedStatus::Cached(path, error) => {
+                println!("⚠️  {}: update failed ({}); using cached {}", outcome.name, error, path.display())
+            }
+            FeedStatus::Failed(error) => println!("❌ {}: {}", outcome.name, error),
+        }
+    }
+    
+    let downloaded: Vec<(String, std::path::PathBuf)> = report.outcomes.iter()
+        .filter_map(|outcome| match &outcome.status {
+            FeedStatus::Downloaded(path) | FeedStatus::Cached(path, _) => Some((outcome.name.clone(), path.clone())),
+            FeedStatus::Failed(_) => None,
+        })
+        .collect();
+    let importer = CveImporter::new(repository.database().get_pool().clone());
+    for import in import_with_progress_bar(importer, &downloaded).await? {
+        match import.resumed_from {
+            0 => println!("📚 {}: {} records imported", import.feed, import.total),
+            resumed => println!("📚 {}: {} records imported (resumed at {})", import.feed, import.total, resumed),
+        }
+    }
+    
+    let failed = report.failures().count();
+    if failed > 0 {
+        return Err(Error::VulnerabilityDb(format!(
+            "{} of {} CVE feeds failed with no cached copy", failed, report.outcomes.len()
+        )));
+    }
+    
+    Ok(())
+}
+
+/// Import the feeds while rendering a bar per feed from the importer's
+/// progress channel
+async fn import_with_progress_bar(
+    importer: portscanner_enterprise::vulnerability::CveImporter,
+    feeds: &[(String, std::path::PathBuf)],
+) -> Result<Vec<portscanner_enterprise::vulnerability::FeedImport>> {
+    use portscanner_enterprise::ui::progress::import_bar;
+    use tokio::sync::mpsc;
+    
+    let (progress_tx, mut progress_rx) = mpsc::channel(100);
+    
+    let renderer = tokio::spawn(async move {
+        let mut current: Option<(String, indicatif::ProgressBar)> = None;
+        while let Some(progress) = progress_rx.recv().await {
+            match &current {
+                Some((feed, bar)) if *feed == progress.feed => bar.set_position(progress.processed as u64),
+                _ => {
+                    if let Some((_, bar)) = current.take() {
+                        bar.finish();
+                    }
+                    let bar = import_bar(&progress.feed, progress.processed as u64, progress.total as u64);
+                    current = Some((progress.feed, bar));
+                }
+            }
+        }
+        if let Some((_, bar)) = current {
+            bar.finish();
+        }
+    });
+    
+    let importer = importer.with_progress(progress_tx);
+    let imports = importer.update_database(feeds).await;
+    // Dropping the importer closes the channel, so the renderer finishes up
+    drop(importer);
+    let _ = renderer.await;
+    
+    imports
+}
+
+async fn manage_suppressions(
+    suppress_args: crate::cli::SuppressArgs,
+    repository: &ScanRepository,
+) -> Result<()> {
+    use portscanner_enterprise::vulnerability::Suppression;
+    
+    match suppress_args.action {
+        crate::cli::SuppressAction::Add { target, rule_id, port, reason, expires_in_days } => {
+            let mut suppression = Suppression::new(target, port, rule_id, reason);
+            if let Some(days) = expires_in_days {
+                suppression = suppression.with_expiry(chrono::Utc::now() + chrono::Duration::days(days));
+            }
+            let id = repository.add_suppression(&suppression).await?;
+            info(&format!("✅ Suppression added: {}", id));
+        }
+        crate::cli::SuppressAction::List { target } => {
+            for suppression in repository.list_suppressions(target.as_deref()).await? {
+                println!(
+                    "{}  {}:{}  {}  ({}{})",
+                    suppression.id,
+                    suppression.target,
+                    suppression.port.map(|p| p.to_string()).unwrap_or_else(|| "*".to_string()),
+                    suppression.rule_id,
+                    suppression.reason,
+                    suppression.expires_at.map(|e| format!(", expires {}", e.to_rfc3339())).unwrap_or_default()
+                );
+            }
+        }
+        crate::cli::SuppressAction::Remove { id } => {
+            if !repository.delete_suppression(&id).await? {
+                return Err(Error::Validation(format!("Suppression not found: {}", id)));
+            }
+            info(&format!("🗑️ Suppression removed: {}", id));
+        }
+    }
+    
+    Ok(())
+}
+
+async fn show_vulnerability_diff(
+    diff_args: crate::cli::VulnDiffArgs,
+    repository: &ScanRepository,
+) -> Result<()> {
+    let diff = repository
+        .diff_vulnerability_reports(&diff_args.previous, &diff_args.current, diff_args.include_suppressed)
+        .await?;
+    match diff_args.format {
+        crate::cli::VulnOutputFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+        crate::cli::VulnOutputFormat::Csv => {
+            println!("change,finding,port,level");
+            let rows = diff.new.iter().map(|v| ("new", v))
+                .chain(diff.resolved.iter().map(|v| ("resolved", v)))
+                .chain(diff.persistent.iter().map(|v| ("persistent", v)));
+            for (change, vulnerability) in rows {
+                println!("{},\"{}\",{},{:?}", change, vulnerability.rule_key().replace('"', "\"\""), vulnerability.port, vulnerability.level);
+            }
+        }
+        crate::cli::VulnOutputFormat::Table => crate::ui::display_vulnerability_diff(&diff)?,
+    }
+    
+    Ok(())
+}
+
+async fn export_scan_diff(
+    diff_args: crate::cli::ScanDiffArgs,
+    settings: &Settings,
+    repository: &ScanRepository,
+) -> Result<()> {
+    use portscanner_enterprise::export::HtmlExporter;
+    use portscanner_enterprise::scanner::{PortChangeKind, ScanDiff};
+    
+    let mut scans = Vec::with_capacity(2);
+    for scan_id in [&diff_args.previous, &diff_args.current] {
+        scans.push(repository.get_scan_result(scan_id).await?
+            .ok_or_else(|| Error::Validation(format!("Scan not found: {}", scan_id)))?);
+    }
+    let diff = ScanDiff::between(&scans[0], &scans[1])?;
+    
+    let output_path = diff_args.output_path.unwrap_or_else(|| {
+        let target_clean = diff.target.replace(['.', ':'], "_");
+        std::path::PathBuf::from(format!("portzilla_diff_{}.html", target_clean))
+    });
+    let exporter = HtmlExporter::new()
+        .with_color_scheme(settings.ui.color_scheme.clone())
+        .with_min_service_confidence(settings.export.min_service_confidence);
+    let output_path = exporter.export_scan_diff(&diff, &output_path).await?;
+    info!(
+        "📤 Scan comparison exported to: {} ({} added, {} removed, {} changed)",
+        output_path.display(),
+        diff.of_kind(PortChangeKind::Added).count(),
+        diff.of_kind(PortChangeKind::Removed).count(),
+        diff.of_kind(PortChangeKind::Changed).count(),
+    );
     
     Ok(())
 }
@@ -149,26 +638,294 @@ async fn show_scan_history(
     history_args: crate::cli::HistoryArgs,
     repository: &ScanRepository,
 ) -> Result<()> {
-    let scans = repository.get_scan_history(history_args.limit).await?;
-    crate::ui::display_scan_history(&scans, history_args.detailed)?;
+    match history_args.action {
+        Some(crate::cli::HistoryAction::Purge { target, yes }) => {
+            return purge_target_history(&target, yes, repository).await;
+        }
+        Some(crate::cli::HistoryAction::Top { limit }) => {
+            let targets = repository.top_targets(limit).await?;
+            return crate::ui::display_top_targets(&targets);
+        }
+        Some(crate::cli::HistoryAction::Tag { scan_id, tag }) => {
+            repository.add_tag(&scan_id, &tag).await?;
+            info(&format!("🏷️ Tagged scan {} with {}", scan_id, tag.trim()));
+            return Ok(());
+        }
+        Some(crate::cli::HistoryAction::Untag { scan_id, tag }) => {
+            if repository.remove_tag(&scan_id, &tag).await? {
+                info(&format!("🏷️ Removed tag {} from scan {}", tag.trim(), scan_id));
+            } else {
+                info(&format!("Scan {} has no tag {}", scan_id, tag.trim()));
+            }
+            return Ok(());
+        }
+        None => {}
+    }
+    
+    let scans = match &history_args.tag {
+        Some(tag) => repository.list_by_tag(tag, Some(history_args.limit)).await?,
+        None => repository.get_scan_summaries(Some(history_args.limit)).await?,
+    };
+    let open_ports = if history_args.show_ports {
+        let scan_ids: Vec<String> = scans.iter().map(|scan| scan.id.clone()).collect();
+        Some(repository.get_open_ports_for_scans(&scan_ids).await?)
+    } else {
+        None
+    };
+    crate::ui::display_scan_history(&scans, history_args.detailed, open_ports.as_ref())?;
+    Ok(())
+}
+
+async fn purge_target_history(target: &str, yes: bool, repository: &ScanRepository) -> Result<()> {
+    let scan_count = repository.get_scan_ids_for_target(target).await?.len();
+    if scan_count == 0 {
+        info(&format!("No stored scans for {}", target));
+        return Ok(());
+    }
+    
+    if !yes {
+        let confirmed = dialoguer::Confirm::new()
+            .with_prompt(format!(
+                "Permanently delete {} scan(s) of {} with their ports and findings?",
+                scan_count, target
+            ))
+            .default(false)
+            .interact()?;
+        if !confirmed {
+            info("Purge cancelled");
+            return Ok(());
+        }
+    }
+    
+    let removed = repository.delete_by_target(target).await?;
+    info(&format!("🗑️ Purged {} scan(s) of {}", removed, target));
+    Ok(())
+}
+
+async fn show_target_timeline(
+    timeline_args: crate::cli::TimelineArgs,
+    repository: &ScanRepository,
+) -> Result<()> {
+    let since = chrono::Utc::now() - chrono::Duration::days(timeline_args.days);
+    let timeline = repository.get_target_timeline(&timeline_args.target, since).await?;
+    crate::ui::display_target_timeline(&timeline_args.target, &timeline)?;
     Ok(())
 }
 
+/// Print the ports a scan would probe, resolved the way `scan` resolves
+/// them: the scan type, else the range, else the preset, else standard
+fn list_ports(list_args: &crate::cli::ListPortsArgs, settings: &Settings) -> Result<()> {
+    use portscanner_enterprise::scanner::{ScanConfig, ScanEngine, ScanType};
+    
+    let (scan_type, scope) = match (scan_scope(list_args.scan_type.as_ref(), list_args.port_range.as_ref())?, &list_args.preset) {
+        (Some(scope), _) => scope,
+        (None, Some(name)) => (settings.preset(name)?.scan_type()?, format!("preset '{}'", name)),
+        (None, None) => (ScanType::Standard, "standard scan".to_string()),
+    };
+    
+    let config = ScanConfig {
+        exclude: list_args.exclude_ports.iter()
+            .flat_map(|range| range.start..=range.end)
+            .chain(settings.scanner.exclude_ports.iter().copied())
+            .collect(),
+        ..ScanConfig::default()
+    };
+    let ports = ScanEngine::new(config)?.planned_ports(&scan_type);
+    crate::ui::display_port_list(&scope, &ports)
+}
+
+/// Print how long scanning the target would take with these flags and the
+/// scanner settings, from the ports `scan` would pick. Nothing is sent.
+fn estimate_scan(estimate_args: &crate::cli::EstimateArgs, settings: &Settings) -> Result<()> {
+    use portscanner_enterprise::scanner::{ScanConfig, ScanEngine, ScanType};
+    use std::time::Duration;
+    
+    portscanner_enterprise::utils::validate_target(&estimate_args.target)?;
+    let (scan_type, scope) = scan_scope(estimate_args.scan_type.as_ref(), estimate_args.port_range.as_ref())?
+        .unwrap_or((ScanType::Standard, "standard scan".to_string()));
+    
+    let config = ScanConfig {
+        timeout: Duration::from_millis(estimate_args.timeout.unwrap_or(DEFAULT_TIMEOUT_MS)),
+        max_concurrent_tasks: estimate_args.threads,
+        rampup_duration: Duration::from_secs(estimate_args.rampup.unwrap_or(settings.scanner.rampup_secs)),
+        rate_limit: estimate_args.rate_limit.or(settings.scanner.rate_limit),
+        max_pps: estimate_args.max_pps.or(settings.scanner.max_pps),
+        max_retries: estimate_args.max_retries,
+        enable_service_detection: settings.scanner.enable_service_detection,
+        enable_banner_grabbing: settings.scanner.enable_banner_grabbing,
+        enable_udp: estimate_args.udp,
+        exclude: estimate_args.exclude_ports.iter()
+            .flat_map(|range| range.start..=range.end)
+            .chain(settings.scanner.exclude_ports.iter().copied())
+            .collect(),
+        global_max_concurrent: settings.scanner.global_max_concurrent,
+        enrich_concurrency: settings.scanner.enrich_concurrency,
+        auto_tune_concurrency: settings.scanner.auto_tune_concurrency,
+        ..ScanConfig::default()
+    };
+    let config = if estimate_args.discovery_only { config.with_discovery_only() } else { config };
+    
+    let engine = ScanEngine::new(config)?;
+    let port_count = engine.planned_ports(&scan_type).len();
+    crate::ui::display_scan_estimate(&estimate_args.target, &scope, port_count, &engine.estimate(&scan_type))
+}
+
+/// Scan the target once per technique, save each scan and print the
+/// port × technique matrix. A technique that can't run here (SYN without
+/// raw sockets) is skipped with a warning.
+async fn compare_techniques(
+    compare_args: crate::cli::CompareTechniquesArgs,
+    settings: &Settings,
+    repository: &ScanRepository,
+) -> Result<()> {
+    use portscanner_enterprise::scanner::{ScanConfig, ScanEngine, ScanType, TechniqueMatrix};
+    use std::time::Duration;
+    
+    let target = &compare_args.target;
+    portscanner_enterprise::utils::validate_target(target)?;
+    settings.security.check_target(target)?;
+    if let Some(range) = &compare_args.port_range {
+        crate::cli::validate_port_range(range, &settings.security)?;
+    }
+    let (scan_type, _) = scan_scope(compare_args.scan_type.as_ref(), compare_args.port_range.as_ref())?
+        .unwrap_or((ScanType::Standard, String::new()));
+    
+    // Probing only: detection would skew the timings
+    let base = ScanConfig {
+        timeout: Duration::from_millis(compare_args.timeout.unwrap_or(DEFAULT_TIMEOUT_MS)),
+        max_concurrent_tasks: settings.scanner.max_threads,
+        rate_limit: settings.scanner.rate_limit,
+        max_pps: settings.scanner.max_pps,
+        enable_service_detection: false,
+        enable_banner_grabbing: false,
+        enable_os_detection: false,
+        enable_traceroute: false,
+        exclude: settings.scanner.exclude_ports.clone(),
+        dangerous_ports: settings.security.dangerous_ports.clone(),
+        allow_dangerous_ports: compare_args.i_understand_the_risk,
+        allow_public_targets: settings.security.allow_public_targets,
+        scan_window: settings.security.scan_window.clone(),
+        allow_outside_window: compare_args.outside_scan_window,
+        dns_servers: settings.scanner.dns_servers.clone(),
+        auto_tune_concurrency: settings.scanner.auto_tune_concurrency,
+        ..ScanConfig::default()
+    };
+    
+    let mut runs = Vec::new();
+    for technique in &compare_args.techniques {
+        let engine = match ScanEngine::new(technique.config(&base)) {
+            Ok(engine) => engine,
+            Err(e) => {
+                warn!("⏭️  Skipping {} technique: {}", technique, e);
+                continue;
+            }
+        };
+        info!("🔬 Scanning {} with the {} technique", target, technique);
+        let scan_result = engine.scan(target, scan_type.clone()).await?;
+        let scan_id = repository.save_scan(&scan_result).await?;
+        info!("💾 {} scan saved with ID: {}", technique, scan_id);
+        runs.push((*technique, scan_result));
+    }
+    
+    if runs.is_empty() {
+        return Err(Error::Scan("None of the requested techniques could run".to_string()));
+    }
+    crate::ui::display_technique_matrix(target, &TechniqueMatrix::build(&runs), compare_args.all)
+}
+
+/// `--scan-type`, else `--port-range`, with a label for output; `None`
+/// when neither is given
+fn scan_scope(
+    scan_type: Option<&crate::cli::ScanType>,
+    port_range: Option<&crate::cli::PortRange>,
+) -> Result<Option<(portscanner_enterprise::scanner::ScanType, String)>> {
+    use portscanner_enterprise::scanner::ScanType;
+    
+    Ok(match (scan_type, port_range) {
+        (Some(crate::cli::ScanType::Custom), None) => {
+            return Err(Error::Validation("--scan-type custom needs --port-range".to_string()));
+        }
+        (Some(crate::cli::ScanType::Quick), _) => Some((ScanType::Quick, "quick scan".to_string())),
+        (Some(crate::cli::ScanType::Standard), _) => Some((ScanType::Standard, "standard scan".to_string())),
+        (Some(crate::cli::ScanType::Full), _) => Some((ScanType::Full, "full scan".to_string())),
+        (_, Some(range)) => Some((
+            ScanType::CustomRange(range.start, range.end),
+            format!("ports {}-{}", range.start, range.end),
+        )),
+        (None, None) => None,
+    })
+}
+
 async fn export_scan_results(
     export_args: crate::cli::ExportArgs,
+    settings: &Settings,
     repository: &ScanRepository,
 ) -> Result<()> {
-    use portscanner_enterprise::export::{ExportFormat, Exporter};
+    use portscanner_enterprise::export::ExportManager;
     
-    let scan = repository.get_scan(export_args.scan_id).await?;
-    let exporter = Exporter::new(export_args.format);
+    let scan = repository.get_scan_result(&export_args.scan_id).await?
+        .ok_or_else(|| Error::Validation(format!("Scan not found: {}", export_args.scan_id)))?;
     
-    let output_path = exporter.export(&scan, &export_args.output_path).await?;
+    let mut export_manager = ExportManager::new()
+        .with_settings(&settings.export)
+        .with_color_scheme(settings.ui.color_scheme.clone())
+        .with_port_order(export_args.sort_by.into());
+    if export_args.all_ports {
+        export_manager = export_manager.with_all_ports(true);
+    }
+    let explicit = export_args.format.map(|format| format!("{:?}", format).to_lowercase());
+    let default = format!("{:?}", settings.export.default_format).to_lowercase();
+    let format = export_manager.resolve_format(explicit.as_deref(), export_args.output_path.as_deref(), &default)?;
+    let output_path = export_manager.export_scan(&scan, &format, export_args.output_path).await?;
     info!("📤 Scan exported to: {}", output_path.display());
     
     Ok(())
 }
 
+async fn export_scan_batch(
+    batch_args: crate::cli::ExportBatchArgs,
+    settings: &Settings,
+    repository: &ScanRepository,
+) -> Result<()> {
+    use portscanner_enterprise::export::ExportManager;
+    
+    let scan_ids = repository.get_scan_ids_for_target(&batch_args.target).await?;
+    if scan_ids.is_empty() {
+        return Err(Error::Validation(format!("No scans found for target: {}", batch_args.target)));
+    }
+    
+    let format = format!("{:?}", batch_args.format).to_lowercase();
+    let output_path = batch_args.output_path.unwrap_or_else(|| {
+        let target_clean = batch_args.target.replace(['.', ':'], "_");
+        std::path::PathBuf::from(format!("portzilla_batch_{}.{}", target_clean, format))
+    });
+    
+    let export_manager = ExportManager::new()
+        .with_color_scheme(settings.ui.color_scheme.clone())
+        .with_min_service_confidence(settings.export.min_service_confidence);
+    let output_path = export_manager
+        .export_scans(repository, &scan_ids, &format, &output_path, batch_args.zip)
+        .await?;
+    info!("📤 {} scans exported to: {}", scan_ids.len(), output_path.display());
+    
+    Ok(())
+}
+
+async fn import_scan(import_args: crate::cli::ImportArgs, repository: &ScanRepository) -> Result<()> {
+    use portscanner_enterprise::scanner::ScanResult;
+    
+    let json = tokio::fs::read_to_string(&import_args.file).await?;
+    let scan = ScanResult::from_json(&json).map_err(|e| match e {
+        Error::Validation(message) => Error::Validation(format!("{}: {}", import_args.file.display(), message)),
+        other => other,
+    })?;
+    
+    let scan_id = repository.save_scan(&scan).await?;
+    info!("📥 Imported scan of {} ({} open ports) as {}", scan.target, scan.open_ports.len(), scan_id);
+    
+    Ok(())
+}
+
 async fn manage_configuration(
     config_args: crate::cli::ConfigArgs,
     settings: &Settings,
@@ -191,13 +948,23 @@ async fn manage_configuration(
 
 async fn start_web_server(
     server_args: crate::cli::ServerArgs,
-    settings: &Settings,
+    config: ConfigManager,
     repository: ScanRepository,
 ) -> Result<()> {
+    use portscanner_enterprise::storage::repository::RetentionTask;
     use portscanner_enterprise::web::Server;
     
     info!("🌐 Starting web server on {}:{}", server_args.host, server_args.port);
     
+    // Keep the watcher alive for the server's lifetime
+    let (settings, _config_watcher) = config.watch()?;
+    
+    let database_settings = settings.read()
+        .map_err(|_| Error::Unknown("Settings lock poisoned".to_string()))?
+        .database
+        .clone();
+    let _retention = RetentionTask::from_settings(repository.clone(), &database_settings)
+        .map(RetentionTask::spawn);
     let server = Server::new(settings, repository);
     server.run(server_args.host, server_args.port).await?;
     
@@ -213,32 +980,210 @@ async fn start_interactive_mode(
     Ok(())
 }
 
-fn validate_scan_parameters(scan_args: &crate::cli::ScanArgs, settings: &Settings) -> Result<()> {
-    use std::net::IpAddr;
+/// On the first Ctrl-C, cancel the scan so it returns (and the caller saves)
+/// what it found; on the next, or once the scan is over, exit with
+/// `EXIT_INTERRUPTED` immediately
+async fn watch_interrupts(cancellation: CancellationToken) {
+    if tokio::signal::ctrl_c().await.is_err() {
+        return;
+    }
+    if !cancellation.is_cancelled() {
+        warn!("⏹️  Stopping the scan and saving what it found; press Ctrl-C again to quit immediately");
+        cancellation.cancel();
+        let _ = tokio::signal::ctrl_c().await;
+    }
+    process::exit(gate::EXIT_INTERRUPTED);
+}
+
+/// Run the scan while rendering a live progress bar from the engine's progress channel
+async fn scan_with_progress_bar(
+    engine: &portscanner_enterprise::scanner::ScanEngine,
+    target: &str,
+    scan_type: portscanner_enterprise::scanner::ScanType,
+) -> Result<portscanner_enterprise::scanner::ScanResult> {
+    use portscanner_enterprise::ui::progress::AnimatedProgress;
+    use tokio::sync::mpsc;
+    
+    let (progress_tx, mut progress_rx) = mpsc::channel(100);
+    
+    let renderer = tokio::spawn(async move {
+        // The port total is only known once the engine reports in
+        let mut bar: Option<AnimatedProgress> = None;
+        while let Some(progress) = progress_rx.recv().await {
+            bar.get_or_insert_with(|| AnimatedProgress::new(progress.total_ports as u64))
+                .update_from_scan(&progress);
+        }
+        if let Some(bar) = bar {
+            bar.finish_all();
+        }
+    });
     
-    // Validate target format
-    if scan_args.target.parse::<IpAddr>().is_err() && scan_args.target.parse::<std::net::Ipv4Addr>().is_err() {
-        return Err(Error::Validation(format!("Invalid target format: {}", scan_args.target)));
+    let scan_result = engine.scan_with_progress(target, scan_type, progress_tx).await;
+    // The sender was dropped with the scan, so the renderer finishes the bar and exits
+    let _ = renderer.await;
+    
+    scan_result
+}
+
+/// Print each open port as an NDJSON record the moment it is discovered.
+/// Records follow discovery order and are not sorted by port.
+async fn stream_scan_ndjson(
+    engine: &portscanner_enterprise::scanner::ScanEngine,
+    target: &str,
+    scan_type: portscanner_enterprise::scanner::ScanType,
+) -> Result<portscanner_enterprise::scanner::ScanResult> {
+    use std::io::Write;
+    use tokio::sync::mpsc;
+    
+    // Nobody reads progress here; dropping the receiver makes sends no-ops
+    let (progress_tx, _) = mpsc::channel(1);
+    let (result_tx, mut result_rx) = mpsc::channel(1000);
+    
+    let printer = tokio::spawn(async move {
+        let stdout = std::io::stdout();
+        while let Some(port_info) = result_rx.recv().await {
+            let port_info: portscanner_enterprise::scanner::PortInfo = port_info;
+            let record = serde_json::json!({
+                "port": port_info.port,
+                "protocol": format!("{:?}", port_info.protocol).to_lowercase(),
+                "service": port_info.service.as_ref().map(|s| s.name.clone()),
+                "ts": chrono::Utc::now().to_rfc3339(),
+            });
+            let mut handle = stdout.lock();
+            let _ = writeln!(handle, "{}", record);
+            let _ = handle.flush();
+        }
+    });
+    
+    let scan_result = engine.scan_streaming(target, scan_type, progress_tx, result_tx).await;
+    // The engine's sender is gone once the scan returns, so the printer drains and exits
+    let _ = printer.await;
+    
+    scan_result
+}
+
+/// Settings, then the preset, then explicit flags
+fn build_scan_config(
+    scan_args: &crate::cli::ScanArgs,
+    settings: &Settings,
+    preset: Option<&portscanner_enterprise::config::ScanPreset>,
+) -> portscanner_enterprise::scanner::ScanConfig {
+    use portscanner_enterprise::scanner::ScanConfig;
+    use std::time::Duration;
+    
+    let mut config = ScanConfig {
+        timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
+        max_concurrent_tasks: scan_args.threads,
+        connect_max_concurrent: settings.scanner.connect_max_threads,
+        syn_max_concurrent: settings.scanner.syn_max_threads,
+        udp_max_concurrent: settings.scanner.udp_max_threads,
+        rampup_duration: Duration::from_secs(scan_args.rampup.unwrap_or(settings.scanner.rampup_secs)),
+        rate_limit: scan_args.rate_limit.or(settings.scanner.rate_limit),
+        max_pps: scan_args.max_pps.or(settings.scanner.max_pps),
+        enable_service_detection: settings.scanner.enable_service_detection,
+        enable_banner_grabbing: settings.scanner.enable_banner_grabbing,
+        enable_os_detection: settings.scanner.enable_os_detection,
+        enable_traceroute: settings.scanner.enable_traceroute,
+        // Decoys only apply to raw SYN probes
+        stealth_mode: scan_args.stealth || settings.scanner.stealth_mode || !scan_args.decoys.is_empty(),
+        randomize_order: scan_args.randomize || scan_args.seed.is_some(),
+        seed: scan_args.seed,
+        decoys: scan_args.decoys.clone(),
+        adaptive_timeout: scan_args.adaptive_timeout,
+        max_retries: scan_args.max_retries,
+        enable_reverse_dns: !scan_args.no_reverse_dns,
+        dns_servers: if scan_args.dns_server.is_empty() {
+            settings.scanner.dns_servers.clone()
+        } else {
+            scan_args.dns_server.clone()
+        },
+        exclude: scan_args.exclude_ports.iter()
+            .flat_map(|range| range.start..=range.end)
+            .chain(settings.scanner.exclude_ports.iter().copied())
+            .collect(),
+        probe_allow: settings.scanner.probe_allow_ports.clone(),
+        probe_deny: settings.scanner.probe_deny_ports.clone(),
+        stop_on_first_open: scan_args.stop_on_first_open,
+        verify_open: scan_args.verify_open || settings.scanner.verify_open_ports,
+        dangerous_ports: settings.security.dangerous_ports.clone(),
+        allow_dangerous_ports: scan_args.i_understand_the_risk,
+        allow_public_targets: settings.security.allow_public_targets,
+        scan_window: settings.security.scan_window.clone(),
+        allow_outside_window: scan_args.outside_scan_window,
+        proxy: scan_args.proxy.clone(),
+        technique_chain: scan_args.technique_chain.clone(),
+        global_max_concurrent: settings.scanner.global_max_concurrent,
+        banner_buffer_size: settings.scanner.banner_buffer_size,
+        banner_max_length: settings.scanner.banner_max_length,
+        max_response_bytes: settings.scanner.max_response_bytes,
+        enrich_concurrency: settings.scanner.enrich_concurrency,
+        probe_identity: settings.scanner.probe_identity.clone(),
+        http_keep_alive: settings.scanner.http_keep_alive,
+        auto_tune_concurrency: settings.scanner.auto_tune_concurrency,
+        geoip: settings.geoip.clone(),
+        source_addr: scan_args.source_ip,
+        source_port: scan_args.source_port,
+        incremental: scan_args.incremental,
+        incremental_max_age: scan_args.max_age.0,
+        record_all_statuses: scan_args.all_statuses || settings.scanner.record_all_statuses,
+        host_discovery: settings.scanner.host_discovery && !scan_args.skip_host_discovery,
+        tcp_nodelay: settings.scanner.tcp_nodelay,
+        tcp_linger_reset: settings.scanner.tcp_linger_reset,
+        checkpoint_interval: settings.scanner.checkpoint_interval,
+        ..ScanConfig::default()
+    };
+    
+    if let Some(preset) = preset {
+        config = preset.apply(config);
+    }
+    if let Some(timeout) = scan_args.timeout {
+        config.timeout = Duration::from_millis(timeout);
+    }
+    if let Some(version_intensity) = scan_args.version_intensity {
+        config.version_intensity = version_intensity;
     }
+    config.enable_udp |= scan_args.udp;
+    config.enable_sctp = scan_args.sctp;
     
-    // Check if target is allowed
-    if !settings.security.is_target_allowed(&scan_args.target) {
-        return Err(Error::Security(format!("Target {} is not in allowed list", scan_args.target)));
+    if scan_args.discovery_only {
+        config.with_discovery_only()
+    } else {
+        config
+    }
+}
+
+fn validate_scan_parameters(
+    target: &str,
+    scan_args: &crate::cli::ScanArgs,
+    preset: Option<&portscanner_enterprise::config::ScanPreset>,
+    settings: &Settings,
+) -> Result<()> {
+    // Validate target format; hostnames are resolved by the engine
+    portscanner_enterprise::utils::validate_target(target)?;
+    
+    // Raw-socket modes bypass the proxy and would leak the real source
+    let udp = scan_args.udp || preset.and_then(|p| p.udp).unwrap_or(false);
+    if scan_args.proxy.is_some() && (scan_args.stealth || udp || scan_args.sctp || !scan_args.decoys.is_empty()) {
+        return Err(Error::Validation(
+            "--proxy only supports TCP connect scans; drop --stealth, --udp (or a UDP preset), --sctp and --decoys".to_string()
+        ));
     }
     
+    // Check if target is allowed, and not public in safe mode
+    settings.security.check_target(target)?;
+    
     // Validate port range if provided
     if let Some(range) = &scan_args.port_range {
-        if range.start > range.end {
-            return Err(Error::Validation("Invalid port range: start must be <= end".into()));
-        }
-        
-        let port_count = (range.end - range.start) + 1;
-        if port_count > settings.security.max_ports_per_scan {
-            return Err(Error::Validation(format!(
-                "Port range too large: {} ports (max: {})", 
-                port_count, settings.security.max_ports_per_scan
-            )));
-        }
+        crate::cli::validate_port_range(range, &settings.security)?;
+    }
+    
+    if let (Some(preset), None, None, false) = (preset, &scan_args.scan_type, &scan_args.port_range, scan_args.known_services) {
+        crate::cli::validate_preset_size(preset, &settings.security)?;
+    }
+    
+    // Tags are added after the scan is saved; reject a bad one before it runs
+    if scan_args.tag.iter().any(|tag| tag.trim().is_empty()) {
+        return Err(Error::Validation("--tag cannot be empty".to_string()));
     }
     
     Ok(())
@@ -1,20 +1,83 @@
 use crate::error::{Error, Result};
+use std::borrow::Cow;
 use std::net::{IpAddr, SocketAddr};
 use std::time::Duration;
 use tokio::net::TcpStream;
 use tokio::time::timeout;
 use tracing::{debug, info, warn};
 
+/// Bytes read from a service per banner unless configured otherwise
+pub const DEFAULT_BANNER_BUFFER_SIZE: usize = 1024;
+/// Characters kept in a banner's display text unless configured otherwise
+pub const DEFAULT_BANNER_MAX_LENGTH: usize = 500;
+/// Hard ceiling on bytes read from one service response, whatever the
+/// buffer size: a hostile service can stream without end
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 64 * 1024;
+/// Bytes requested per read while a response is collected
+const READ_CHUNK: usize = 4096;
+/// Who is probing, sent as the HTTP User-Agent and in the SSH client banner
+/// unless configured otherwise, so target owners can tell where traffic comes from
+pub const DEFAULT_PROBE_IDENTITY: &str =
+    concat!("Port-ZiLLA/", env!("CARGO_PKG_VERSION"), " (+https://github.com/FJ-cyberzilla/Port-ZiLLA)");
+
+/// How long a silent service gets before it is probed / given up on
+const FIRST_BYTE_WAIT: Duration = Duration::from_secs(2);
+/// A pause this long after data arrived ends the banner
+const IDLE_GAP: Duration = Duration::from_millis(200);
+
+/// What a service sent: display text, plus the raw bytes whenever the text
+/// can't faithfully carry them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Banner {
+    pub text: String,
+    pub raw: Option<Vec<u8>>, // Set for binary or truncated responses
+    pub lossy: bool, // Invalid UTF-8 in `text` was replaced with U+FFFD; `raw` has the original bytes
+    pub truncated: bool, // The service was still sending when the byte cap was reached
+}
+
+impl Banner {
+    /// Invalid UTF-8 doesn't discard the banner: the readable rest is kept
+    /// (an HTTP response with one stray byte still identifies the server)
+    pub fn from_bytes(bytes: &[u8], max_length: usize) -> Self {
+        let decoded = String::from_utf8_lossy(bytes);
+        let lossy = matches!(decoded, Cow::Owned(_));
+        let binary = lossy
+            || decoded.chars().any(|c| c.is_control() && !matches!(c, '\r' | '\n' | '\t'));
+
+        let cleaned = clean_banner(&decoded);
+        let truncated = cleaned.chars().count() > max_length;
+
+        Self {
+            text: cleaned.chars().take(max_length).collect(),
+            raw: (binary || truncated).then(|| bytes.to_vec()),
+            lossy,
+            truncated: false,
+        }
+    }
+
+    /// Lowercase hex of the raw bytes, as stored in `PortInfo::banner_raw`
+    pub fn raw_hex(&self) -> Option<String> {
+        self.raw.as_ref().map(|bytes| bytes.iter().map(|b| format!("{:02x}", b)).collect())
+    }
+}
+
+#[derive(Clone)]
 pub struct BannerGrabber {
     timeout: Duration,
     buffer_size: usize,
+    max_length: usize,
+    max_response_bytes: usize,
+    identity: String,
 }
 
 impl BannerGrabber {
     pub fn new() -> Self {
         Self {
             timeout: Duration::from_secs(5),
-            buffer_size: 1024,
+            buffer_size: DEFAULT_BANNER_BUFFER_SIZE,
+            max_length: DEFAULT_BANNER_MAX_LENGTH,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            identity: DEFAULT_PROBE_IDENTITY.to_string(),
         }
     }
 
@@ -23,7 +86,34 @@ impl BannerGrabber {
         self
     }
 
-    pub async fn grab_banner(&self, target: IpAddr, port: u16) -> Result<String> {
+    /// Most bytes read from a service for one banner, up to the response cap
+    pub fn with_buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size.max(1);
+        self
+    }
+
+    /// Hard cap on the bytes of one response, above any buffer size; the
+    /// read stops there and the banner is marked truncated
+    pub fn with_max_response_bytes(mut self, max_response_bytes: usize) -> Self {
+        self.max_response_bytes = max_response_bytes.max(1);
+        self
+    }
+
+    /// Most characters kept in `Banner::text`; the full capture stays in `Banner::raw`
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Identity the probes carry: the HTTP User-Agent and, with its first
+    /// word as software version, the SSH client banner
+    pub fn with_identity(mut self, identity: impl Into<String>) -> Self {
+        self.identity = identity.into();
+        self
+    }
+
+    /// `None` when the service stayed silent, even after being probed
+    pub async fn grab_banner(&self, target: IpAddr, port: u16) -> Result<Option<Banner>> {
         let addr = SocketAddr::new(target, port);
         
         debug!("Grabbing banner from {}:{}", target, port);
@@ -39,38 +129,85 @@ impl BannerGrabber {
             }
             Err(_) => {
                 warn!("Timeout grabbing banner from {}:{}", target, port);
-                Err(Error::Network("Banner grab timeout".to_string()))
+                Err(self.timed_out("banner grab", addr))
             }
         }
     }
 
-    async fn connect_and_read(&self, addr: &SocketAddr) -> Result<String> {
+    /// Read whatever the service sends unprompted; never writes to the socket
+    pub async fn read_banner(&self, target: IpAddr, port: u16) -> Result<Option<Banner>> {
+        let addr = SocketAddr::new(target, port);
+        let mut stream = timeout(self.timeout, TcpStream::connect(addr))
+            .await
+            .map_err(|_| self.timed_out("connect", addr))??;
+
+        Ok(self.to_banner(self.read_response(&mut stream).await))
+    }
+
+    /// Send the protocol probe registered for `port` (generic probe otherwise)
+    pub async fn probe_banner(&self, target: IpAddr, port: u16) -> Result<Option<Banner>> {
+        self.send_probes(&SocketAddr::new(target, port)).await
+    }
+
+    /// Send an HTTP request regardless of port
+    pub async fn probe_http_banner(&self, target: IpAddr, port: u16) -> Result<Option<Banner>> {
+        self.probe_http(&SocketAddr::new(target, port)).await
+    }
+
+    async fn connect_and_read(&self, addr: &SocketAddr) -> Result<Option<Banner>> {
         let mut stream = TcpStream::connect(addr).await?;
-        
-        // Set read timeout
-        let _ = stream.try_readable().await?;
-        
-        let mut buffer = vec![0u8; self.buffer_size];
-        let mut banner = String::new();
-        
-        // Try to read initial data
-        match tokio::time::timeout(Duration::from_secs(2), stream.try_read(&mut buffer)).await {
-            Ok(Ok(n)) if n > 0 => {
-                let data = &buffer[..n];
-                if let Ok(text) = String::from_utf8(data.to_vec()) {
-                    banner = self.clean_banner(&text);
+
+        match self.to_banner(self.read_response(&mut stream).await) {
+            Some(banner) => Ok(Some(banner)),
+            // Silent until spoken to: send the protocol-specific probe
+            None => self.send_probes(addr).await,
+        }
+    }
+
+    /// Read until `buffer_size` bytes (at most `max_response_bytes`), EOF,
+    /// or the service goes quiet. The buffer grows with the data, so a
+    /// large limit costs nothing for a short banner. True if the limit
+    /// stopped the read.
+    async fn read_response(&self, stream: &mut TcpStream) -> (Vec<u8>, bool) {
+        let limit = self.buffer_size.min(self.max_response_bytes);
+        let mut response = Vec::new();
+        let mut chunk = [0u8; READ_CHUNK];
+        let mut wait = self.timeout.min(FIRST_BYTE_WAIT);
+
+        while response.len() < limit {
+            let want = (limit - response.len()).min(READ_CHUNK);
+            match timeout(wait, stream.read(&mut chunk[..want])).await {
+                Ok(Ok(n)) if n > 0 => {
+                    response.extend_from_slice(&chunk[..n]);
+                    wait = IDLE_GAP;
                 }
-            }
-            _ => {
-                // Send protocol-specific probes for common services
-                banner = self.send_probes(addr).await?;
+                _ => return (response, false),
             }
         }
 
-        Ok(banner)
+        if limit == self.max_response_bytes {
+            warn!("Stopped reading a response at the {}-byte cap", limit);
+        }
+        (response, true)
+    }
+
+    fn timed_out(&self, operation: &str, addr: SocketAddr) -> Error {
+        Error::Timeout { operation: format!("{} to {}", operation, addr), duration: self.timeout }
+    }
+
+    fn to_banner(&self, (data, capped): (Vec<u8>, bool)) -> Option<Banner> {
+        let mut banner = (!data.is_empty()).then(|| Banner::from_bytes(&data, self.max_length))?;
+        if banner.lossy {
+            debug!("Banner of {} bytes has invalid UTF-8; kept with replacement characters", data.len());
+        }
+        if capped {
+            banner.truncated = true;
+            banner.raw = Some(data);
+        }
+        Some(banner)
     }
 
-    async fn send_probes(&self, addr: &SocketAddr) -> Result<String> {
+    async fn send_probes(&self, addr: &SocketAddr) -> Result<Option<Banner>> {
         let port = addr.port();
         
         match port {
@@ -97,25 +234,25 @@ impl BannerGrabber {
         }
     }
 
-    async fn probe_http(&self, addr: &SocketAddr) -> Result<String> {
-        let probe = "GET / HTTP/1.0\r\n\r\n";
+    async fn probe_http(&self, addr: &SocketAddr) -> Result<Option<Banner>> {
+        let probe = format!("GET / HTTP/1.0\r\nUser-Agent: {}\r\n\r\n", header_value(&self.identity));
         self.send_probe_and_read(addr, probe.as_bytes()).await
     }
 
-    async fn probe_ssh(&self, addr: &SocketAddr) -> Result<String> {
+    async fn probe_ssh(&self, addr: &SocketAddr) -> Result<Option<Banner>> {
         // SSH servers typically send their banner immediately
-        self.send_probe_and_read(addr, b"SSH-2.0-PortZiLLA\r\n").await
+        self.send_probe_and_read(addr, ssh_client_banner(&self.identity).as_bytes()).await
     }
 
-    async fn probe_ftp(&self, addr: &SocketAddr) -> Result<String> {
+    async fn probe_ftp(&self, addr: &SocketAddr) -> Result<Option<Banner>> {
         self.send_probe_and_read(addr, b"USER anonymous\r\n").await
     }
 
-    async fn probe_smtp(&self, addr: &SocketAddr) -> Result<String> {
+    async fn probe_smtp(&self, addr: &SocketAddr) -> Result<Option<Banner>> {
         self.send_probe_and_read(addr, b"EHLO example.com\r\n").await
     }
 
-    async fn probe_dns(&self, addr: &SocketAddr) -> Result<String> {
+    async fn probe_dns(&self, addr: &SocketAddr) -> Result<Option<Banner>> {
         // Simple DNS query for google.com
         let probe = vec![
             0x00, 0x00, // Transaction ID
@@ -132,13 +269,13 @@ impl BannerGrabber {
         self.send_probe_and_read(addr, &probe).await
     }
 
-    async fn probe_mysql(&self, addr: &SocketAddr) -> Result<String> {
+    async fn probe_mysql(&self, addr: &SocketAddr) -> Result<Option<Banner>> {
         // MySQL handshake initiation
         let probe = vec![0x0a, 0x00, 0x00, 0x00, 0x0a, 0x35, 0x2e, 0x37, 0x2e, 0x32, 0x38, 0x00];
         self.send_probe_and_read(addr, &probe).await
     }
 
-    async fn probe_postgresql(&self, addr: &SocketAddr) -> Result<String> {
+    async fn probe_postgresql(&self, addr: &SocketAddr) -> Result<Option<Banner>> {
         // PostgreSQL startup message
         let probe = vec![
             0x00, 0x00, 0x00, 0x08, // Length
@@ -147,11 +284,11 @@ impl BannerGrabber {
         self.send_probe_and_read(addr, &probe).await
     }
 
-    async fn probe_redis(&self, addr: &SocketAddr) -> Result<String> {
+    async fn probe_redis(&self, addr: &SocketAddr) -> Result<Option<Banner>> {
         self.send_probe_and_read(addr, b"PING\r\n").await
     }
 
-    async fn probe_mongodb(&self, addr: &SocketAddr) -> Result<String> {
+    async fn probe_mongodb(&self, addr: &SocketAddr) -> Result<Option<Banner>> {
         // MongoDB OP_QUERY
         let probe = vec![
             0x3a, 0x00, 0x00, 0x00, // Message length
@@ -168,48 +305,49 @@ impl BannerGrabber {
         self.send_probe_and_read(addr, &probe).await
     }
 
-    async fn probe_generic(&self, addr: &SocketAddr) -> Result<String> {
+    async fn probe_generic(&self, addr: &SocketAddr) -> Result<Option<Banner>> {
         // Generic probe - just try to read whatever the service sends
         self.send_probe_and_read(addr, b"\r\n\r\n").await
     }
 
-    async fn send_probe_and_read(&self, addr: &SocketAddr, probe: &[u8]) -> Result<String> {
+    async fn send_probe_and_read(&self, addr: &SocketAddr, probe: &[u8]) -> Result<Option<Banner>> {
         let mut stream = TcpStream::connect(addr).await?;
-        
-        // Send probe
         stream.write_all(probe).await?;
-        
-        // Read response
-        let mut buffer = vec![0u8; self.buffer_size];
-        let n = match timeout(Duration::from_secs(2), stream.try_read(&mut buffer)).await {
-            Ok(Ok(n)) => n,
-            _ => 0,
-        };
 
-        if n > 0 {
-            let data = &buffer[..n];
-            if let Ok(text) = String::from_utf8(data.to_vec()) {
-                Ok(self.clean_banner(&text))
-            } else {
-                Ok(format!("[Binary data: {} bytes]", n))
-            }
-        } else {
-            Ok("[No response]".to_string())
-        }
+        Ok(self.to_banner(self.read_response(&mut stream).await))
     }
+}
+
+/// `identity` without the control characters that would end the header early
+fn header_value(identity: &str) -> String {
+    identity.chars().filter(|c| !c.is_control()).collect()
+}
 
-    fn clean_banner(&self, banner: &str) -> String {
-        banner
-            .trim()
-            .replace("\r\n", " | ")
-            .replace('\n', " | ")
-            .replace('\r', " | ")
-            .chars()
-            .take(500) // Limit banner length
-            .collect()
+/// RFC 4253 identification line: the first word of `identity` is the
+/// software version, which may contain neither whitespace nor `-`; the
+/// rest goes in the comments
+fn ssh_client_banner(identity: &str) -> String {
+    let identity = header_value(identity);
+    let (software, comments) = identity.trim().split_once(' ').unwrap_or((identity.trim(), ""));
+    let software = if software.is_empty() { "PortZiLLA".to_string() } else { software.replace('-', "_") };
+    match comments.trim() {
+        "" => format!("SSH-2.0-{}\r\n", software),
+        comments => format!("SSH-2.0-{} {}\r\n", software, comments),
     }
 }
 
+/// One line of printable text: line breaks become ` | `, other control
+/// characters `.`
+fn clean_banner(banner: &str) -> String {
+    banner
+        .trim()
+        .replace("\r\n", " | ")
+        .replace(['\n', '\r'], " | ")
+        .chars()
+        .map(|c| if c.is_control() && c != '\t' { '.' } else { c })
+        .collect()
+}
+
 impl Default for BannerGrabber {
     fn default() -> Self {
         Self::new()
@@ -217,3 +355,163 @@ impl Default for BannerGrabber {
 }
 
 use tokio::io::{AsyncWriteExt, AsyncReadExt};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Port of a one-shot server that sends `payload` on connect, then closes
+    async fn serve_once(payload: Vec<u8>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream.write_all(&payload).await.unwrap();
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_long_text_banner_keeps_full_capture() {
+        let payload = format!("220 {} ESMTP ready\r\n", "mail.example.com ".repeat(150)).into_bytes();
+        assert!(payload.len() > DEFAULT_BANNER_BUFFER_SIZE);
+        let port = serve_once(payload.clone()).await;
+
+        let grabber = BannerGrabber::new().with_buffer_size(4096).with_max_length(100);
+        let banner = grabber.read_banner("127.0.0.1".parse().unwrap(), port).await.unwrap().unwrap();
+
+        assert_eq!(banner.text.chars().count(), 100);
+        assert!(banner.text.starts_with("220 mail.example.com"));
+        assert_eq!(banner.raw.as_deref(), Some(payload.as_slice()));
+    }
+
+    #[tokio::test]
+    async fn test_binary_banner_is_captured_raw() {
+        // MySQL-style greeting: length header, version string, binary tail
+        let mut payload = vec![0x4a, 0x00, 0x00, 0x00, 0x0a];
+        payload.extend_from_slice(b"5.7.28\0");
+        payload.extend_from_slice(&[0xff, 0xfe, 0x01]);
+        let port = serve_once(payload.clone()).await;
+
+        let banner = BannerGrabber::new()
+            .read_banner("127.0.0.1".parse().unwrap(), port)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(banner.text.contains("5.7.28"));
+        assert!(!banner.text.chars().any(|c| c.is_control()));
+        assert_eq!(banner.raw_hex().as_deref(), Some("4a0000000a352e372e323800fffe01"));
+    }
+
+    #[test]
+    fn test_short_text_banner_has_no_raw_copy() {
+        let banner = Banner::from_bytes(b"SSH-2.0-OpenSSH_9.6\r\n", DEFAULT_BANNER_MAX_LENGTH);
+        assert_eq!(banner.text, "SSH-2.0-OpenSSH_9.6");
+        assert_eq!(banner.raw_hex(), None);
+        assert!(!banner.lossy);
+    }
+
+    #[tokio::test]
+    async fn test_one_invalid_byte_keeps_readable_banner() {
+        let mut payload = b"HTTP/1.1 200 OK\r\nServer: nginx/1.24.0\r\nX-Note: caf".to_vec();
+        payload.push(0xe9); // Latin-1 'é'
+        payload.extend_from_slice(b"\r\n\r\n");
+        let port = serve_once(payload.clone()).await;
+
+        let banner = BannerGrabber::new()
+            .read_banner("127.0.0.1".parse().unwrap(), port)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(banner.lossy);
+        assert_eq!(banner.text, "HTTP/1.1 200 OK | Server: nginx/1.24.0 | X-Note: caf\u{fffd}");
+        assert_eq!(banner.raw.as_deref(), Some(payload.as_slice()));
+    }
+
+    #[tokio::test]
+    async fn test_endless_response_stops_at_byte_cap() {
+        // Streams 8 MiB of filler; the write fails once we hang up
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let chunk = vec![b'A'; 64 * 1024];
+            for _ in 0..128 {
+                if stream.write_all(&chunk).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let grabber = BannerGrabber::new()
+            .with_buffer_size(16 * 1024 * 1024)
+            .with_max_response_bytes(DEFAULT_MAX_RESPONSE_BYTES);
+        let banner = grabber.read_banner("127.0.0.1".parse().unwrap(), port).await.unwrap().unwrap();
+
+        assert!(banner.truncated);
+        assert_eq!(banner.raw.map(|raw| raw.len()), Some(DEFAULT_MAX_RESPONSE_BYTES));
+        assert_eq!(banner.text.len(), DEFAULT_BANNER_MAX_LENGTH);
+    }
+
+    #[tokio::test]
+    async fn test_silent_service_times_out_and_closed_port_is_refused() {
+        // Connects complete from the backlog, but nothing is ever sent
+        let silent = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let grabber = BannerGrabber::new().with_timeout(Duration::from_millis(200));
+
+        let error = grabber.grab_banner("127.0.0.1".parse().unwrap(), silent.local_addr().unwrap().port())
+            .await
+            .unwrap_err();
+        assert!(matches!(&error, Error::Timeout { duration, .. } if *duration == Duration::from_millis(200)), "{:?}", error);
+        assert!(error.is_retriable());
+
+        let closed = {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let error = grabber.grab_banner("127.0.0.1".parse().unwrap(), closed).await.unwrap_err();
+        assert!(matches!(&error, Error::Io(e) if e.kind() == std::io::ErrorKind::ConnectionRefused), "{:?}", error);
+        assert!(!error.is_retriable());
+    }
+
+    #[tokio::test]
+    async fn test_configured_identity_is_sent_in_probes() {
+        // Records the probe, then answers like a web server
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let received = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut chunk = [0u8; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut chunk).await.unwrap();
+                assert!(n > 0, "connection closed mid-request");
+                request.extend_from_slice(&chunk[..n]);
+            }
+            stream.write_all(b"HTTP/1.0 200 OK\r\nServer: test\r\n\r\n").await.unwrap();
+            request
+        });
+
+        let identity = "AcmeRedTeam/2.1 (+https://acme.example/scans; soc@acme.example)";
+        let banner = BannerGrabber::new()
+            .with_identity(identity)
+            .probe_http_banner("127.0.0.1".parse().unwrap(), port)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(banner.text.starts_with("HTTP/1.0 200 OK"));
+
+        let request = String::from_utf8(received.await.unwrap()).unwrap();
+        assert!(request.starts_with("GET / HTTP/1.0\r\n"), "{:?}", request);
+        assert!(request.contains(&format!("\r\nUser-Agent: {}\r\n", identity)), "{:?}", request);
+
+        assert_eq!(
+            ssh_client_banner(identity),
+            "SSH-2.0-AcmeRedTeam/2.1 (+https://acme.example/scans; soc@acme.example)\r\n"
+        );
+        assert!(ssh_client_banner(DEFAULT_PROBE_IDENTITY).starts_with("SSH-2.0-Port_ZiLLA/"));
+    }
+}
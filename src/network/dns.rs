@@ -0,0 +1,200 @@
+use crate::error::{Error, Result};
+use async_trait::async_trait;
+use lru::LruCache;
+use std::net::IpAddr;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::timeout;
+use trust_dns_resolver::config::{NameServerConfigGroup, ResolverConfig, ResolverOpts};
+use trust_dns_resolver::TokioAsyncResolver;
+use tracing::{debug, warn};
+
+/// Names and addresses remembered per resolver
+pub const DEFAULT_DNS_CACHE_SIZE: usize = 1024;
+
+/// The queries `DnsResolver` sends; a trait so tests can count them
+#[async_trait]
+pub trait DnsLookup: Send + Sync {
+    async fn lookup_ip(&self, host: &str) -> Result<Vec<IpAddr>>;
+
+    /// `Ok(None)` when the address has no PTR record
+    async fn reverse_lookup(&self, ip: IpAddr) -> Result<Option<String>>;
+}
+
+/// Queries the system's name servers, or the given ones over UDP/TCP port 53
+pub struct TrustDnsLookup {
+    resolver: TokioAsyncResolver,
+}
+
+impl TrustDnsLookup {
+    pub fn new(servers: &[IpAddr], query_timeout: Duration) -> Result<Self> {
+        let resolver = if servers.is_empty() {
+            TokioAsyncResolver::tokio_from_system_conf()
+                .map_err(|e| Error::TargetResolution(format!("Cannot read the system DNS config: {}", e)))?
+        } else {
+            let name_servers = NameServerConfigGroup::from_ips_clear(servers, 53, true);
+            let mut options = ResolverOpts::default();
+            options.timeout = query_timeout;
+            options.attempts = 1;
+            TokioAsyncResolver::tokio(ResolverConfig::from_parts(None, Vec::new(), name_servers), options)
+        };
+        Ok(Self { resolver })
+    }
+}
+
+#[async_trait]
+impl DnsLookup for TrustDnsLookup {
+    async fn lookup_ip(&self, host: &str) -> Result<Vec<IpAddr>> {
+        let lookup = self.resolver.lookup_ip(host).await
+            .map_err(|e| Error::TargetResolution(format!("{}: {}", host, e)))?;
+        Ok(lookup.iter().collect())
+    }
+
+    async fn reverse_lookup(&self, ip: IpAddr) -> Result<Option<String>> {
+        use trust_dns_resolver::error::ResolveErrorKind;
+
+        match self.resolver.reverse_lookup(ip).await {
+            Ok(names) => Ok(names.iter().next().map(|name| name.to_utf8().trim_end_matches('.').to_string())),
+            Err(e) if matches!(e.kind(), ResolveErrorKind::NoRecordsFound { .. }) => Ok(None),
+            Err(e) => Err(Error::TargetResolution(format!("PTR lookup for {}: {}", ip, e))),
+        }
+    }
+}
+
+/// Forward and reverse lookups behind an LRU cache, so a batch scan that
+/// keeps hitting the same names and gateways only pays for one query each.
+///
+/// Reverse lookups are best effort: failures and timeouts resolve to `None`
+/// and are cached as such. Failed forward lookups are not cached.
+pub struct DnsResolver {
+    lookup: Box<dyn DnsLookup>,
+    timeout: Duration,
+    forward: Mutex<LruCache<String, Vec<IpAddr>>>,
+    reverse: Mutex<LruCache<IpAddr, Option<String>>>,
+}
+
+impl DnsResolver {
+    /// Resolver for `servers`, or the system's when empty
+    pub fn new(servers: &[IpAddr]) -> Result<Self> {
+        let timeout = Duration::from_secs(2);
+        Ok(Self::with_lookup(Box::new(TrustDnsLookup::new(servers, timeout)?)).with_timeout(timeout))
+    }
+
+    pub fn with_lookup(lookup: Box<dyn DnsLookup>) -> Self {
+        let capacity = NonZeroUsize::new(DEFAULT_DNS_CACHE_SIZE).unwrap();
+        Self {
+            lookup,
+            timeout: Duration::from_secs(2),
+            forward: Mutex::new(LruCache::new(capacity)),
+            reverse: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The first address of `host`, or `host` itself when it is an IP literal
+    pub async fn resolve_host(&self, host: &str) -> Result<IpAddr> {
+        if let Ok(ip) = host.parse() {
+            return Ok(ip);
+        }
+
+        let name = crate::utils::to_ascii_hostname(host)?.to_lowercase();
+        if let Some(addresses) = self.forward.lock().unwrap().get(&name) {
+            return addresses.first().copied()
+                .ok_or_else(|| Error::TargetResolution(format!("{} has no addresses", host)));
+        }
+
+        debug!("DNS lookup for {}", name);
+        let addresses = match timeout(self.timeout, self.lookup.lookup_ip(&name)).await {
+            Ok(addresses) => addresses?,
+            Err(_) => return Err(Error::Timeout { operation: format!("DNS lookup for {}", name), duration: self.timeout }),
+        };
+        let first = addresses.first().copied()
+            .ok_or_else(|| Error::TargetResolution(format!("{} has no addresses", host)))?;
+        self.forward.lock().unwrap().put(name, addresses);
+        Ok(first)
+    }
+
+    /// Best-effort PTR name of `ip`
+    pub async fn resolve(&self, ip: IpAddr) -> Option<String> {
+        if ip.is_unspecified() {
+            return None;
+        }
+
+        if let Some(cached) = self.reverse.lock().unwrap().get(&ip) {
+            return cached.clone();
+        }
+
+        let hostname = self.reverse_lookup(ip).await;
+        self.reverse.lock().unwrap().put(ip, hostname.clone());
+        hostname
+    }
+
+    async fn reverse_lookup(&self, ip: IpAddr) -> Option<String> {
+        debug!("Reverse DNS lookup for {}", ip);
+
+        match timeout(self.timeout, self.lookup.reverse_lookup(ip)).await {
+            Ok(Ok(hostname)) => hostname,
+            Ok(Err(e)) => {
+                debug!("No PTR record for {}: {}", ip, e);
+                None
+            }
+            Err(_) => {
+                warn!("Reverse DNS lookup for {} timed out", ip);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Default)]
+    struct CountingLookup {
+        forward: Arc<AtomicUsize>,
+        reverse: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl DnsLookup for CountingLookup {
+        async fn lookup_ip(&self, _host: &str) -> Result<Vec<IpAddr>> {
+            self.forward.fetch_add(1, Ordering::SeqCst);
+            Ok(vec!["192.0.2.10".parse().unwrap()])
+        }
+
+        async fn reverse_lookup(&self, _ip: IpAddr) -> Result<Option<String>> {
+            self.reverse.fetch_add(1, Ordering::SeqCst);
+            Err(Error::TargetResolution("SERVFAIL".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeated_lookups_hit_the_cache() {
+        let lookup = CountingLookup::default();
+        let (forward, reverse) = (Arc::clone(&lookup.forward), Arc::clone(&lookup.reverse));
+        let resolver = DnsResolver::with_lookup(Box::new(lookup));
+
+        for host in ["web.example.test", "WEB.example.test", "web.example.test"] {
+            assert_eq!(resolver.resolve_host(host).await.unwrap(), "192.0.2.10".parse::<IpAddr>().unwrap());
+        }
+        assert_eq!(forward.load(Ordering::SeqCst), 1);
+
+        // IP literals never reach the server
+        resolver.resolve_host("198.51.100.7").await.unwrap();
+        assert_eq!(forward.load(Ordering::SeqCst), 1);
+
+        // A failed PTR lookup is cached as "no name" too
+        let gateway: IpAddr = "192.0.2.1".parse().unwrap();
+        assert_eq!(resolver.resolve(gateway).await, None);
+        assert_eq!(resolver.resolve(gateway).await, None);
+        assert_eq!(reverse.load(Ordering::SeqCst), 1);
+    }
+}
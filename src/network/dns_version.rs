@@ -0,0 +1,291 @@
+use crate::error::{Error, Result};
+use crate::scanner::Protocol;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::time::timeout;
+use tracing::debug;
+
+pub const DNS_PORT: u16 = 53;
+
+const TYPE_TXT: u16 = 16;
+const CLASS_CHAOS: u16 = 3;
+
+/// Largest reply accepted; version strings are far shorter
+const MAX_RESPONSE_SIZE: usize = 4096;
+
+/// Asks a DNS server for its software version with the `version.bind`
+/// CHAOS TXT query most implementations answer (unless configured to hide it)
+pub struct DnsVersionProbe {
+    timeout: Duration,
+}
+
+/// Software identified from a `version.bind` answer
+#[derive(Debug, Clone, PartialEq)]
+pub struct DnsSoftware {
+    pub product: Option<String>,
+    pub version: Option<String>,
+}
+
+impl DnsVersionProbe {
+    pub fn new() -> Self {
+        Self {
+            timeout: Duration::from_secs(3),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The TXT answer, or None when the server refused or sent no TXT record
+    pub async fn query(&self, target: IpAddr, port: u16, protocol: Protocol) -> Result<Option<String>> {
+        let addr = SocketAddr::new(target, port);
+        let id: u16 = rand::random();
+        let query = version_bind_query(id);
+        debug!("version.bind query to {} over {:?}", addr, protocol);
+
+        let exchange = async {
+            match protocol {
+                Protocol::Udp => {
+                    let bind_addr: SocketAddr = if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" }.parse().unwrap();
+                    let socket = UdpSocket::bind(bind_addr).await?;
+                    socket.connect(addr).await?;
+                    socket.send(&query).await?;
+
+                    let mut response = vec![0u8; MAX_RESPONSE_SIZE];
+                    let n = socket.recv(&mut response).await?;
+                    response.truncate(n);
+                    Ok::<_, std::io::Error>(response)
+                }
+                _ => {
+                    // DNS over TCP prefixes every message with its length
+                    let mut stream = TcpStream::connect(addr).await?;
+                    let mut framed = (query.len() as u16).to_be_bytes().to_vec();
+                    framed.extend_from_slice(&query);
+                    stream.write_all(&framed).await?;
+
+                    let mut length = [0u8; 2];
+                    stream.read_exact(&mut length).await?;
+                    let mut response = vec![0u8; (u16::from_be_bytes(length) as usize).min(MAX_RESPONSE_SIZE)];
+                    stream.read_exact(&mut response).await?;
+                    Ok(response)
+                }
+            }
+        };
+
+        let response = timeout(self.timeout, exchange)
+            .await
+            .map_err(|_| Error::Scan(format!("version.bind query to {} timed out", addr)))?
+            .map_err(Error::Io)?;
+
+        Ok(parse_txt_answer(&response, id))
+    }
+}
+
+impl Default for DnsVersionProbe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `version.bind. CH TXT`, without recursion desired
+fn version_bind_query(id: u16) -> Vec<u8> {
+    let mut query = Vec::with_capacity(30);
+    query.extend_from_slice(&id.to_be_bytes());
+    query.extend_from_slice(&[
+        0x00, 0x00, // Flags: standard query
+        0x00, 0x01, // Questions
+        0x00, 0x00, // Answer RRs
+        0x00, 0x00, // Authority RRs
+        0x00, 0x00, // Additional RRs
+    ]);
+    for label in ["version", "bind"] {
+        query.push(label.len() as u8);
+        query.extend_from_slice(label.as_bytes());
+    }
+    query.push(0);
+    query.extend_from_slice(&TYPE_TXT.to_be_bytes());
+    query.extend_from_slice(&CLASS_CHAOS.to_be_bytes());
+    query
+}
+
+/// First TXT answer of a successful response to query `id`
+fn parse_txt_answer(response: &[u8], id: u16) -> Option<String> {
+    let read_u16 = |pos: usize| response.get(pos..pos + 2).map(|b| u16::from_be_bytes([b[0], b[1]]));
+
+    let flags = read_u16(2)?;
+    let is_response = flags & 0x8000 != 0;
+    let rcode = flags & 0x000f;
+    if read_u16(0)? != id || !is_response || rcode != 0 {
+        return None;
+    }
+
+    let questions = read_u16(4)?;
+    let answers = read_u16(6)?;
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = skip_name(response, pos)? + 4; // QTYPE + QCLASS
+    }
+
+    for _ in 0..answers {
+        pos = skip_name(response, pos)?;
+        let record_type = read_u16(pos)?;
+        let rdlength = read_u16(pos + 8)? as usize; // After TYPE, CLASS and TTL
+        let rdata = response.get(pos + 10..pos + 10 + rdlength)?;
+        pos += 10 + rdlength;
+
+        if record_type == TYPE_TXT {
+            // One or more length-prefixed character strings
+            let mut text = Vec::new();
+            let mut offset = 0;
+            while offset < rdata.len() {
+                let len = rdata[offset] as usize;
+                text.extend_from_slice(rdata.get(offset + 1..offset + 1 + len)?);
+                offset += 1 + len;
+            }
+            let text = String::from_utf8_lossy(&text).trim().to_string();
+            return (!text.is_empty()).then_some(text);
+        }
+    }
+
+    None
+}
+
+/// Position just past the (possibly compressed) name starting at `pos`
+fn skip_name(message: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *message.get(pos)? as usize;
+        match len {
+            0 => return Some(pos + 1),
+            len if len & 0xc0 == 0xc0 => return Some(pos + 2), // Pointer ends the name
+            len => pos += 1 + len,
+        }
+    }
+}
+
+/// Product and version from a `version.bind` string. BIND answers with a bare
+/// version number; most others name themselves. Deliberately vague answers
+/// ("none", "go away") identify nothing.
+pub fn identify(version_bind: &str) -> DnsSoftware {
+    use regex::Regex;
+
+    let lower = version_bind.to_lowercase();
+    let product = [
+        ("powerdns", "PowerDNS"),
+        ("dnsmasq", "dnsmasq"),
+        ("unbound", "Unbound"),
+        ("knot", "Knot DNS"),
+        ("nsd", "NSD"),
+        ("microsoft", "Microsoft DNS"),
+        ("bind", "BIND"),
+    ]
+    .iter()
+    .find(|(needle, _)| lower.contains(needle))
+    .map(|(_, product)| product.to_string());
+
+    let version = Regex::new(r"(\d+\.\d+(?:\.\d+)*)")
+        .ok()
+        .and_then(|re| re.captures(version_bind))
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str().to_string());
+
+    match (product, version) {
+        // e.g. "9.18.24-1ubuntu1.2-Ubuntu"
+        (None, Some(version)) if version_bind.starts_with("9.") => DnsSoftware {
+            product: Some("BIND".to_string()),
+            version: Some(version),
+        },
+        (None, _) => DnsSoftware { product: None, version: None },
+        (product, version) => DnsSoftware { product, version },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERSION: &str = "9.18.24-1ubuntu1.2-Ubuntu";
+
+    /// Answers a query with `text` the way BIND does
+    fn txt_response(query: &[u8], text: &str) -> Vec<u8> {
+        let mut response = query[..2].to_vec();
+        response.extend_from_slice(&[0x84, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00]);
+        response.extend_from_slice(&query[12..]);
+        response.extend_from_slice(&[0xc0, 0x0c]); // Name: pointer to the question
+        response.extend_from_slice(&TYPE_TXT.to_be_bytes());
+        response.extend_from_slice(&CLASS_CHAOS.to_be_bytes());
+        response.extend_from_slice(&[0, 0, 0, 0]); // TTL
+        response.extend_from_slice(&(text.len() as u16 + 1).to_be_bytes());
+        response.push(text.len() as u8);
+        response.extend_from_slice(text.as_bytes());
+        response
+    }
+
+    #[tokio::test]
+    async fn test_version_bind_over_udp_and_tcp() {
+        let udp = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = udp.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut query = [0u8; 512];
+            while let Ok((n, peer)) = udp.recv_from(&mut query).await {
+                let _ = udp.send_to(&txt_response(&query[..n], VERSION), peer).await;
+            }
+        });
+
+        let tcp = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let tcp_port = tcp.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = tcp.accept().await {
+                let mut length = [0u8; 2];
+                stream.read_exact(&mut length).await.unwrap();
+                let mut query = vec![0u8; u16::from_be_bytes(length) as usize];
+                stream.read_exact(&mut query).await.unwrap();
+
+                let response = txt_response(&query, VERSION);
+                stream.write_all(&(response.len() as u16).to_be_bytes()).await.unwrap();
+                stream.write_all(&response).await.unwrap();
+            }
+        });
+
+        let probe = DnsVersionProbe::new().with_timeout(Duration::from_secs(2));
+        let target: IpAddr = "127.0.0.1".parse().unwrap();
+        for (port, protocol) in [(port, Protocol::Udp), (tcp_port, Protocol::Tcp)] {
+            let answer = probe.query(target, port, protocol).await.unwrap();
+            assert_eq!(answer.as_deref(), Some(VERSION), "{:?}", protocol);
+        }
+
+        assert_eq!(identify(VERSION), DnsSoftware {
+            product: Some("BIND".to_string()),
+            version: Some("9.18.24".to_string()),
+        });
+    }
+
+    #[test]
+    fn test_identify_named_servers() {
+        let powerdns = identify("PowerDNS Authoritative Server 4.8.3 (built Jan  1 2024)");
+        assert_eq!(powerdns.product.as_deref(), Some("PowerDNS"));
+        assert_eq!(powerdns.version.as_deref(), Some("4.8.3"));
+
+        let dnsmasq = identify("dnsmasq-2.90");
+        assert_eq!(dnsmasq.product.as_deref(), Some("dnsmasq"));
+        assert_eq!(dnsmasq.version.as_deref(), Some("2.90"));
+
+        assert_eq!(identify("go away"), DnsSoftware { product: None, version: None });
+    }
+
+    #[test]
+    fn test_mismatched_or_failed_answers_are_ignored() {
+        let query = version_bind_query(7);
+        let response = txt_response(&query, VERSION);
+        assert_eq!(parse_txt_answer(&response, 7).as_deref(), Some(VERSION));
+        assert_eq!(parse_txt_answer(&response, 8), None);
+
+        let mut refused = response.clone();
+        refused[3] |= 0x05; // RCODE REFUSED
+        assert_eq!(parse_txt_answer(&refused, 7), None);
+        assert_eq!(parse_txt_answer(&response[..20], 7), None);
+    }
+}
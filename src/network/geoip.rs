@@ -0,0 +1,128 @@
+use crate::config::GeoIpSettings;
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use std::net::IpAddr;
+use std::path::Path;
+use tracing::{debug, info, warn};
+
+/// Network owner and location of an address
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GeoInfo {
+    pub asn: Option<u32>,
+    pub as_org: Option<String>,
+    pub country_code: Option<String>,
+}
+
+/// Offline ASN / country lookups against local MaxMind GeoLite2 databases.
+///
+/// Either database may be missing; lookups then only fill in what the loaded
+/// one knows. Nothing is ever fetched over the network.
+pub struct GeoIpResolver {
+    asn: Option<Reader<Vec<u8>>>,
+    country: Option<Reader<Vec<u8>>>,
+}
+
+impl GeoIpResolver {
+    /// `None` when GeoIP is disabled or no database could be loaded; a
+    /// database that fails to open is logged and skipped
+    pub fn from_settings(settings: &GeoIpSettings) -> Option<Self> {
+        if !settings.enabled {
+            return None;
+        }
+
+        let resolver = Self {
+            asn: settings.asn_database.as_deref().and_then(open_database),
+            country: settings.country_database.as_deref().and_then(open_database),
+        };
+
+        if resolver.asn.is_none() && resolver.country.is_none() {
+            warn!("GeoIP enrichment enabled but no database is available; skipping");
+            return None;
+        }
+        Some(resolver)
+    }
+
+    pub fn lookup(&self, ip: IpAddr) -> GeoInfo {
+        let mut info = GeoInfo::default();
+
+        if let Some(reader) = &self.asn {
+            if let Some(asn) = found(reader.lookup::<geoip2::Asn>(ip), ip) {
+                info.asn = asn.autonomous_system_number;
+                info.as_org = asn.autonomous_system_organization.map(str::to_string);
+            }
+        }
+
+        if let Some(reader) = &self.country {
+            if let Some(country) = found(reader.lookup::<geoip2::Country>(ip), ip) {
+                info.country_code = country.country
+                    .or(country.registered_country)
+                    .and_then(|c| c.iso_code)
+                    .map(str::to_string);
+            }
+        }
+
+        info
+    }
+}
+
+fn open_database(path: &str) -> Option<Reader<Vec<u8>>> {
+    match Reader::open_readfile(Path::new(path)) {
+        Ok(reader) => {
+            info!("Loaded GeoIP database {} ({})", path, reader.metadata.database_type);
+            Some(reader)
+        }
+        Err(e) => {
+            warn!("Could not open GeoIP database {}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Private and unallocated addresses are simply absent from the databases
+fn found<T>(result: Result<T, MaxMindDBError>, ip: IpAddr) -> Option<T> {
+    match result {
+        Ok(record) => Some(record),
+        Err(MaxMindDBError::AddressNotFoundError(_)) => None,
+        Err(e) => {
+            debug!("GeoIP lookup for {} failed: {}", ip, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Built by scripts/gen_geoip_fixture.py; holds ASN and country fields
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/GeoLite2-Test.mmdb");
+
+    fn settings(enabled: bool, path: Option<&str>) -> GeoIpSettings {
+        GeoIpSettings {
+            enabled,
+            asn_database: path.map(str::to_string),
+            country_database: path.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_lookup_from_fixture() {
+        let resolver = GeoIpResolver::from_settings(&settings(true, Some(FIXTURE))).unwrap();
+
+        let info = resolver.lookup("89.160.20.115".parse().unwrap());
+        assert_eq!(info, GeoInfo {
+            asn: Some(29518),
+            as_org: Some("Bredband2 AB".to_string()),
+            country_code: Some("SE".to_string()),
+        });
+
+        // Not in the database: nothing to report, not an error
+        assert_eq!(resolver.lookup("10.0.0.1".parse().unwrap()), GeoInfo::default());
+    }
+
+    #[test]
+    fn test_unconfigured_is_skipped() {
+        assert!(GeoIpResolver::from_settings(&settings(false, Some(FIXTURE))).is_none());
+        assert!(GeoIpResolver::from_settings(&settings(true, None)).is_none());
+        assert!(GeoIpResolver::from_settings(&settings(true, Some("/nonexistent.mmdb"))).is_none());
+    }
+}
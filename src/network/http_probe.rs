@@ -0,0 +1,272 @@
+use crate::error::Result;
+use reqwest::header::{HeaderMap, ALLOW, SERVER};
+use reqwest::{Client, Method};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tracing::debug;
+
+/// Response headers a web server is expected to send. HSTS only counts over
+/// HTTPS: browsers ignore it on plain HTTP.
+const SECURITY_HEADERS: &[(&str, bool)] = &[
+    ("Strict-Transport-Security", true),
+    ("X-Frame-Options", false),
+    ("Content-Security-Policy", false),
+];
+
+/// Response bodies up to this size are read to the end, so their connection
+/// can go back to the pool; a longer one is dropped with its connection
+const MAX_DRAINED_BODY: usize = 64 * 1024;
+
+/// Requests `/` and `OPTIONS /` from a web server to record what a banner
+/// doesn't show: the status, the allowed methods and absent security headers
+pub struct HttpProbe {
+    timeout: Duration,
+    user_agent: String,
+    client: Option<Client>, // Shared keep-alive pool; None = a fresh client per probe
+}
+
+/// What the server answered
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponseInfo {
+    pub status: u16,
+    pub server: Option<String>,
+    pub allowed_methods: Vec<String>,
+    pub missing_headers: Vec<String>,
+}
+
+impl HttpProbe {
+    pub fn new() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            user_agent: crate::network::banner_grabber::DEFAULT_PROBE_IDENTITY.to_string(),
+            client: None,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Send every probe through `client`, so probes of the same host reuse
+    /// its kept-alive connections; its timeout and User-Agent apply instead
+    /// of this probe's. Build it with `HttpProbe::client`.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// A client set up for probing. Scanned hosts rarely have a certificate
+    /// for their IP, and a redirect's target is a different server
+    pub fn client(&self) -> Result<Client> {
+        Ok(Client::builder()
+            .timeout(self.timeout)
+            .user_agent(self.user_agent.as_str())
+            .danger_accept_invalid_certs(true)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?)
+    }
+
+    pub async fn probe(&self, target: IpAddr, port: u16, https: bool) -> Result<HttpResponseInfo> {
+        let scheme = if https { "https" } else { "http" };
+        let url = format!("{}://{}/", scheme, SocketAddr::new(target, port));
+        debug!("HTTP probe of {}", url);
+
+        let client = match &self.client {
+            Some(client) => client.clone(),
+            None => self.client()?,
+        };
+
+        let response = client.get(&url).send().await?;
+        let status = response.status().as_u16();
+        let headers = response.headers().clone();
+        drain(response).await;
+
+        // Not every server implements OPTIONS; that only costs the method list
+        let allowed_methods = match client.request(Method::OPTIONS, &url).send().await {
+            Ok(options) => {
+                let methods = allowed_methods(options.headers());
+                drain(options).await;
+                methods
+            }
+            Err(e) => {
+                debug!("OPTIONS {} failed: {}", url, e);
+                Vec::new()
+            }
+        };
+
+        Ok(HttpResponseInfo {
+            status,
+            server: headers.get(SERVER).and_then(|v| v.to_str().ok()).map(|v| v.trim().to_string()),
+            allowed_methods,
+            missing_headers: missing_security_headers(&headers, https),
+        })
+    }
+}
+
+impl Default for HttpProbe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpResponseInfo {
+    /// One-line form for `ServiceInfo.extra_info`, e.g. "HTTP 200; Server:
+    /// nginx; Allow: GET, HEAD; Missing headers: X-Frame-Options". The
+    /// "Missing headers:" list is what the shipped vulnerability rules match.
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!("HTTP {}", self.status)];
+        if let Some(server) = &self.server {
+            parts.push(format!("Server: {}", server));
+        }
+        if !self.allowed_methods.is_empty() {
+            parts.push(format!("Allow: {}", self.allowed_methods.join(", ")));
+        }
+        if !self.missing_headers.is_empty() {
+            parts.push(format!("Missing headers: {}", self.missing_headers.join(", ")));
+        }
+        parts.join("; ")
+    }
+}
+
+/// Read a short body to its end: only then is the connection reusable
+async fn drain(mut response: reqwest::Response) {
+    let mut read = 0;
+    while let Ok(Some(chunk)) = response.chunk().await {
+        read += chunk.len();
+        if read > MAX_DRAINED_BODY {
+            break;
+        }
+    }
+}
+
+fn missing_security_headers(headers: &HeaderMap, https: bool) -> Vec<String> {
+    SECURITY_HEADERS
+        .iter()
+        .filter(|(_, https_only)| https || !https_only)
+        .filter(|(name, _)| !headers.contains_key(*name))
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+fn allowed_methods(headers: &HeaderMap) -> Vec<String> {
+    headers
+        .get_all(ALLOW)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|method| method.trim().to_uppercase())
+        .filter(|method| !method.is_empty())
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Answers OPTIONS with an Allow list and anything else with `/`'s page,
+    /// which sends X-Frame-Options but neither CSP nor HSTS
+    async fn mock_server() -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = [0u8; 1024];
+                let n = stream.read(&mut request).await.unwrap_or(0);
+                let response = if request[..n].starts_with(b"OPTIONS") {
+                    "HTTP/1.1 204 No Content\r\nAllow: GET, HEAD,options\r\nConnection: close\r\n\r\n"
+                } else {
+                    "HTTP/1.1 200 OK\r\nServer: nginx/1.24.0\r\nX-Frame-Options: DENY\r\n\
+                     Content-Length: 2\r\nConnection: close\r\n\r\nok"
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_probe_records_status_server_methods_and_missing_headers() {
+        let port = mock_server().await;
+        let probe = HttpProbe::new().with_timeout(Duration::from_secs(2));
+        let info = probe.probe("127.0.0.1".parse().unwrap(), port, false).await.unwrap();
+
+        assert_eq!(info.status, 200);
+        assert_eq!(info.server.as_deref(), Some("nginx/1.24.0"));
+        assert_eq!(info.allowed_methods, vec!["GET", "HEAD", "OPTIONS"]);
+        // HSTS isn't expected over plain HTTP
+        assert_eq!(info.missing_headers, vec!["Content-Security-Policy"]);
+        assert_eq!(
+            info.summary(),
+            "HTTP 200; Server: nginx/1.24.0; Allow: GET, HEAD, OPTIONS; Missing headers: Content-Security-Policy"
+        );
+    }
+
+    /// Keep-alive server answering every request with an empty 200; returns
+    /// its port and the number of connections it has accepted
+    async fn keep_alive_server() -> (u16, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let accepted = Arc::clone(&connections);
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                accepted.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut request = [0u8; 1024];
+                    while let Ok(n) = stream.read(&mut request).await {
+                        if n == 0 {
+                            break;
+                        }
+                        let response = "HTTP/1.1 200 OK\r\nServer: test\r\nContent-Length: 2\r\n\r\nok";
+                        if stream.write_all(response.as_bytes()).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        (port, connections)
+    }
+
+    #[tokio::test]
+    async fn test_shared_client_reuses_one_connection_per_host() {
+        let target = "127.0.0.1".parse().unwrap();
+
+        let (port, connections) = keep_alive_server().await;
+        let probe = HttpProbe::new().with_timeout(Duration::from_secs(2));
+        let probe = HttpProbe::new().with_client(probe.client().unwrap());
+        for _ in 0..3 {
+            assert_eq!(probe.probe(target, port, false).await.unwrap().status, 200);
+        }
+        // Six requests, one connection
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+        // Without a shared client every probe opens its own
+        let (port, connections) = keep_alive_server().await;
+        let probe = HttpProbe::new().with_timeout(Duration::from_secs(2));
+        for _ in 0..3 {
+            probe.probe(target, port, false).await.unwrap();
+        }
+        assert_eq!(connections.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_hsts_only_expected_over_https() {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-security-policy", "default-src 'self'".parse().unwrap());
+        headers.insert("x-frame-options", "DENY".parse().unwrap());
+
+        assert!(missing_security_headers(&headers, false).is_empty());
+        assert_eq!(missing_security_headers(&headers, true), vec!["Strict-Transport-Security"]);
+    }
+}
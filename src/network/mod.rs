@@ -3,8 +3,18 @@ pub mod service_detector;
 pub mod os_detection;
 pub mod protocols;
 pub mod traceroute;
+pub mod dns;
+pub mod tls;
+pub mod dns_version;
+pub mod http_probe;
+pub mod geoip;
 
-pub use banner_grabber::BannerGrabber;
+pub use banner_grabber::{Banner, BannerGrabber};
 pub use service_detector::ServiceDetector;
 pub use os_detection::OsDetector;
 pub use traceroute::Traceroute;
+pub use dns::{DnsLookup, DnsResolver};
+pub use tls::TlsProbe;
+pub use dns_version::DnsVersionProbe;
+pub use http_probe::{HttpProbe, HttpResponseInfo};
+pub use geoip::{GeoIpResolver, GeoInfo};
@@ -55,6 +55,14 @@ impl ProtocolUtils {
         Self::get_common_ports().get(&port).copied()
     }
 
+    /// Every port with a known service, ascending: what a `KnownServices`
+    /// scan probes
+    pub fn known_service_ports() -> Vec<u16> {
+        let mut ports: Vec<u16> = Self::get_common_ports().into_keys().collect();
+        ports.sort_unstable();
+        ports
+    }
+
     pub fn is_common_port(port: u16) -> bool {
         Self::get_common_ports().contains_key(&port)
     }
@@ -1,22 +1,33 @@
+use super::dns_version::{self, DnsVersionProbe, DNS_PORT};
+use super::http_probe::HttpProbe;
+use super::tls::{TlsHandshakeInfo, TlsProbe, TLS_PORTS};
 use crate::error::Result;
+use crate::scanner::{Protocol, ServiceInfo, TlsInfo};
 use std::collections::HashMap;
 use std::net::IpAddr;
 use std::time::Duration;
 use tokio::time::timeout;
 use tracing::{debug, info};
 
-#[derive(Debug, Clone)]
-pub struct ServiceInfo {
-    pub name: String,
-    pub version: Option<String>,
-    pub product: Option<String>,
-    pub extra_info: Option<String>,
-    pub confidence: u8,
+/// Default `--version-intensity`, as in nmap
+pub const DEFAULT_VERSION_INTENSITY: u8 = 7;
+pub const MAX_VERSION_INTENSITY: u8 = 9;
+
+/// Active probes beyond the passive banner read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceProbe {
+    PortSpecific, // The BannerGrabber probe registered for the port; version.bind on DNS
+    Http,         // HTTP request on a port not normally serving HTTP
+    Tls,          // TLS handshake
 }
 
 pub struct ServiceDetector {
     banner_grabber: super::BannerGrabber,
+    tls_probe: TlsProbe,
+    dns_probe: DnsVersionProbe,
+    http_probe: HttpProbe,
     service_patterns: HashMap<&'static str, Vec<&'static str>>,
+    intensity: u8,
 }
 
 impl ServiceDetector {
@@ -48,25 +59,195 @@ impl ServiceDetector {
 
         Self {
             banner_grabber: super::BannerGrabber::new(),
+            tls_probe: TlsProbe::new(),
+            dns_probe: DnsVersionProbe::new(),
+            http_probe: HttpProbe::new(),
             service_patterns,
+            intensity: DEFAULT_VERSION_INTENSITY,
         }
     }
 
+    /// 0-2: port guess + passive banner only; 3-6: + the port's protocol probe
+    /// (on web servers, `/` and OPTIONS requests for status and headers);
+    /// 7-8: + TLS handshake on TLS ports; 9: every probe on every port
+    pub fn with_intensity(mut self, intensity: u8) -> Self {
+        self.intensity = intensity.min(MAX_VERSION_INTENSITY);
+        self
+    }
+
+    pub fn with_banner_grabber(mut self, banner_grabber: super::BannerGrabber) -> Self {
+        self.banner_grabber = banner_grabber;
+        self
+    }
+
+    pub fn with_http_probe(mut self, http_probe: HttpProbe) -> Self {
+        self.http_probe = http_probe;
+        self
+    }
+
+    pub fn intensity(&self) -> u8 {
+        self.intensity
+    }
+
+    /// Active probes tried for `port` at the current intensity, in order
+    pub fn probe_plan(&self, port: u16) -> Vec<ServiceProbe> {
+        let mut plan = Vec::new();
+
+        if self.intensity >= 3 {
+            plan.push(ServiceProbe::PortSpecific);
+        }
+        if self.intensity >= MAX_VERSION_INTENSITY && ![80, 443, 8080, 8443].contains(&port) {
+            plan.push(ServiceProbe::Http);
+        }
+        if self.intensity >= MAX_VERSION_INTENSITY || (self.intensity >= 7 && TLS_PORTS.contains(&port)) {
+            plan.push(ServiceProbe::Tls);
+        }
+
+        plan
+    }
+
     pub async fn detect_service(&self, target: IpAddr, port: u16) -> Result<ServiceInfo> {
-        debug!("Detecting service on {}:{}", target, port);
+        Ok(self.detect_service_and_tls(target, port).await?.0)
+    }
+
+    /// `detect_service`, plus certificate and protocol details from the same
+    /// handshake on ports where the probe plan includes one
+    pub async fn detect_service_and_tls(&self, target: IpAddr, port: u16) -> Result<(ServiceInfo, Option<TlsInfo>)> {
+        debug!("Detecting service on {}:{} (intensity {})", target, port, self.intensity);
         
-        // First, try to get a banner
-        let banner = match timeout(Duration::from_secs(3), self.banner_grabber.grab_banner(target, port)).await {
-            Ok(Ok(banner)) if !banner.is_empty() && banner != "[No response]" => Some(banner),
-            _ => None,
+        let port_guess = self.detect_by_port(port).await?;
+
+        // Passive read first: many services announce themselves
+        let mut banner = Self::useful_banner(
+            timeout(Duration::from_secs(3), self.banner_grabber.read_banner(target, port)).await
+        );
+        let mut tls: Option<TlsHandshakeInfo> = None;
+        let mut dns_version: Option<String> = None;
+
+        for probe in self.probe_plan(port) {
+            match probe {
+                ServiceProbe::PortSpecific if port == DNS_PORT => {
+                    dns_version = self.dns_probe.query(target, port, Protocol::Tcp).await.ok().flatten();
+                }
+                ServiceProbe::PortSpecific if banner.is_none() => {
+                    banner = Self::useful_banner(
+                        timeout(Duration::from_secs(3), self.banner_grabber.probe_banner(target, port)).await
+                    );
+                }
+                ServiceProbe::Http if banner.is_none() => {
+                    banner = Self::useful_banner(
+                        timeout(Duration::from_secs(3), self.banner_grabber.probe_http_banner(target, port)).await
+                    );
+                }
+                ServiceProbe::Tls => {
+                    tls = self.tls_probe.handshake(target, port).await.ok();
+                }
+                _ => {}
+            }
+        }
+
+        let mut service = match &banner {
+            Some(banner) => self.analyze_banner(banner, port).await?,
+            None => port_guess.clone(),
         };
 
-        // If we have a banner, analyze it
-        if let Some(banner) = banner {
-            self.analyze_banner(&banner, port).await
-        } else {
-            // Fall back to port-based detection
-            self.detect_by_port(port).await
+        // Banner and port agree: the harder we looked, the more we trust it
+        if banner.is_some() && service.confidence >= 90 && service.name == port_guess.name {
+            service.confidence = (90 + self.intensity).min(100);
+        }
+
+        if let Some(version_bind) = &dns_version {
+            service = Self::dns_service(version_bind);
+        }
+
+        let tls_info = match &tls {
+            Some(handshake) => Some(self.tls_probe.inspect_handshake(target, port, handshake).await),
+            None => None,
+        };
+
+        if let Some(tls) = tls {
+            // ALPN names what runs inside, even on a port nothing else identified
+            let alpn_web = matches!(tls.alpn.as_deref(), Some("h2" | "http/1.1"));
+            if service.name == "http" || (alpn_web && service.name == "unknown") {
+                service.name = "https".to_string();
+            }
+            service.tunnel = Some("ssl".to_string());
+            service.alpn = tls.alpn;
+
+            let mut tls_note = format!("{}; TLS: {}", service.description(), tls.protocol_version);
+            if let Some(alpn) = &service.alpn {
+                tls_note.push_str(&format!("; ALPN: {}", alpn));
+            }
+            service.extra_info = Some(match service.extra_info.take() {
+                Some(extra) => format!("{}; {}", tls_note, extra),
+                None => tls_note,
+            });
+        }
+
+        // Headers and methods, for the vulnerability rules on web servers
+        let is_web = matches!(service.name.as_str(), "http" | "https");
+        if is_web && self.probe_plan(port).contains(&ServiceProbe::PortSpecific) {
+            match self.http_probe.probe(target, port, service.name == "https").await {
+                Ok(http) => {
+                    let http_note = http.summary();
+                    service.extra_info = Some(match service.extra_info.take() {
+                        Some(extra) => format!("{}; {}", extra, http_note),
+                        None => http_note,
+                    });
+                }
+                Err(e) => debug!("HTTP probe of {}:{} failed: {}", target, port, e),
+            }
+        }
+
+        Ok((service, tls_info))
+    }
+
+    /// Service of an open UDP port. Only DNS is identified, through its
+    /// `version.bind` answer; other UDP services get no active probe.
+    pub async fn detect_udp_service(&self, target: IpAddr, port: u16) -> Option<ServiceInfo> {
+        if port != DNS_PORT || !self.probe_plan(port).contains(&ServiceProbe::PortSpecific) {
+            return None;
+        }
+
+        match self.dns_probe.query(target, port, Protocol::Udp).await {
+            Ok(Some(version_bind)) => Some(Self::dns_service(&version_bind)),
+            Ok(None) => Some(ServiceInfo {
+                name: "dns".to_string(),
+                version: None,
+                product: None,
+                extra_info: Some("version.bind refused".to_string()),
+                confidence: 90, // It answered a DNS query
+                tunnel: None,
+                alpn: None,
+            }),
+            Err(e) => {
+                debug!("version.bind over UDP to {}:{} failed: {}", target, port, e);
+                None
+            }
+        }
+    }
+
+    fn dns_service(version_bind: &str) -> ServiceInfo {
+        let software = dns_version::identify(version_bind);
+        info!("Detected DNS server {:?} {:?} from version.bind", software.product, software.version);
+
+        ServiceInfo {
+            name: "dns".to_string(),
+            version: software.version,
+            product: software.product,
+            extra_info: Some(format!("version.bind: {}", version_bind.chars().take(100).collect::<String>())),
+            confidence: 95,
+            tunnel: None,
+            alpn: None,
+        }
+    }
+
+    fn useful_banner(
+        result: std::result::Result<Result<Option<super::Banner>>, tokio::time::error::Elapsed>,
+    ) -> Option<String> {
+        match result {
+            Ok(Ok(Some(banner))) if !banner.text.is_empty() => Some(banner.text),
+            _ => None,
         }
     }
 
@@ -86,6 +267,8 @@ impl ServiceDetector {
                         product,
                         extra_info: Some(banner.chars().take(100).collect()),
                         confidence: 90,
+                        tunnel: None,
+                        alpn: None,
                     });
                 }
             }
@@ -131,6 +314,8 @@ impl ServiceDetector {
             product: product.map(|p| p.to_string()),
             extra_info: None,
             confidence: 80, // High confidence for well-known ports
+            tunnel: None,
+            alpn: None,
         })
     }
 
@@ -188,3 +373,101 @@ impl Default for ServiceDetector {
         Self::new()
     }
                       }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::rustls::{self, Certificate, PrivateKey};
+
+    #[test]
+    fn test_probe_plan_by_intensity() {
+        let detector = ServiceDetector::new();
+        assert!(detector.with_intensity(0).probe_plan(443).is_empty());
+
+        let detector = ServiceDetector::new().with_intensity(DEFAULT_VERSION_INTENSITY);
+        assert_eq!(detector.probe_plan(443), vec![ServiceProbe::PortSpecific, ServiceProbe::Tls]);
+        assert_eq!(detector.probe_plan(22), vec![ServiceProbe::PortSpecific]);
+
+        let detector = ServiceDetector::new().with_intensity(42);
+        assert_eq!(detector.intensity(), MAX_VERSION_INTENSITY);
+        assert_eq!(
+            detector.probe_plan(22),
+            vec![ServiceProbe::PortSpecific, ServiceProbe::Http, ServiceProbe::Tls]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_intensity_zero_sends_nothing() {
+        // A silent service: anything it receives came from an active probe
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let received = tokio::spawn(async move {
+            let mut total = 0;
+            while let Ok(Ok((mut socket, _))) =
+                timeout(Duration::from_secs(2), listener.accept()).await
+            {
+                let mut buffer = [0u8; 256];
+                if let Ok(Ok(n)) = timeout(Duration::from_millis(500), socket.read(&mut buffer)).await {
+                    total += n;
+                }
+            }
+            total
+        });
+
+        let detector = ServiceDetector::new()
+            .with_intensity(0)
+            .with_banner_grabber(super::super::BannerGrabber::new().with_timeout(Duration::from_millis(300)));
+        let service = detector.detect_service("127.0.0.1".parse().unwrap(), port).await.unwrap();
+
+        assert_eq!(service.name, "unknown");
+        assert_eq!(received.await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_tls_wrapped_http_is_reported_as_tunnelled() {
+        // Web server on an unregistered port, reachable only over TLS and
+        // advertising http/1.1 through ALPN
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let mut config = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![Certificate(cert.serialize_der().unwrap())],
+                PrivateKey(cert.serialize_private_key_der()),
+            )
+            .unwrap();
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    // Plaintext probes fail the handshake and are dropped
+                    let Ok(mut tls) = acceptor.accept(stream).await else { return };
+                    let mut request = [0u8; 1024];
+                    let _ = tls.read(&mut request).await;
+                    let _ = tls
+                        .write_all(b"HTTP/1.1 200 OK\r\nServer: test\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+                        .await;
+                    let _ = tls.shutdown().await;
+                });
+            }
+        });
+
+        let detector = ServiceDetector::new()
+            .with_intensity(MAX_VERSION_INTENSITY)
+            .with_banner_grabber(super::super::BannerGrabber::new().with_timeout(Duration::from_millis(300)));
+        let service = detector.detect_service("127.0.0.1".parse().unwrap(), port).await.unwrap();
+
+        assert_eq!(service.name, "https");
+        assert_eq!(service.tunnel.as_deref(), Some("ssl"));
+        assert_eq!(service.alpn.as_deref(), Some("http/1.1"));
+        assert_eq!(service.description(), "http over ssl/tls");
+        assert!(service.extra_info.unwrap().starts_with("http over ssl/tls; TLS: "));
+    }
+}
@@ -0,0 +1,218 @@
+use crate::error::{Error, Result};
+use crate::scanner::TlsInfo;
+use chrono::{DateTime, TimeZone, Utc};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_rustls::rustls::{self, client::ServerCertVerified, Certificate, ServerName};
+use tokio_rustls::TlsConnector;
+use tracing::debug;
+
+/// Ports where a TLS handshake is worth attempting at normal intensity
+pub const TLS_PORTS: &[u16] = &[443, 465, 636, 853, 993, 995, 8443];
+
+/// Application protocols offered in the handshake; the server's pick shows
+/// what speaks inside the tunnel
+const ALPN_PROTOCOLS: &[&[u8]] = &[b"h2", b"http/1.1"];
+
+/// Deprecated protocol versions probed with a hand-built ClientHello, since
+/// rustls refuses to negotiate them
+const LEGACY_PROTOCOLS: &[(&str, [u8; 2])] = &[("SSLv3", [0x03, 0x00]), ("TLSv1.0", [0x03, 0x01])];
+
+/// Outcome of a successful TLS handshake
+#[derive(Debug, Clone)]
+pub struct TlsHandshakeInfo {
+    pub protocol_version: String,
+    pub peer_certificate: Option<Vec<u8>>, // Leaf certificate, DER encoded
+    pub alpn: Option<String>, // Protocol the server selected from ALPN_PROTOCOLS
+}
+
+pub struct TlsProbe {
+    timeout: Duration,
+    connector: TlsConnector,
+}
+
+impl TlsProbe {
+    pub fn new() -> Self {
+        // We're fingerprinting, not trusting: accept whatever certificate is presented
+        let mut config = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate))
+            .with_no_client_auth();
+        config.alpn_protocols = ALPN_PROTOCOLS.iter().map(|protocol| protocol.to_vec()).collect();
+
+        Self {
+            timeout: Duration::from_secs(3),
+            connector: TlsConnector::from(Arc::new(config)),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub async fn handshake(&self, target: IpAddr, port: u16) -> Result<TlsHandshakeInfo> {
+        let addr = SocketAddr::new(target, port);
+        debug!("TLS handshake with {}", addr);
+
+        let handshake = async {
+            let stream = TcpStream::connect(addr).await?;
+            self.connector
+                .connect(ServerName::IpAddress(target), stream)
+                .await
+                .map_err(Error::Io)
+        };
+
+        let stream = timeout(self.timeout, handshake)
+            .await
+            .map_err(|_| Error::Scan(format!("TLS handshake with {} timed out", addr)))??;
+
+        let (_, connection) = stream.get_ref();
+        Ok(TlsHandshakeInfo {
+            protocol_version: connection
+                .protocol_version()
+                .map(|version| format!("{:?}", version))
+                .unwrap_or_else(|| "unknown".to_string()),
+            peer_certificate: connection
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .map(|cert| cert.0.clone()),
+            alpn: connection
+                .alpn_protocol()
+                .map(|protocol| String::from_utf8_lossy(protocol).into_owned()),
+        })
+    }
+
+    /// Handshake, parse the leaf certificate and check which deprecated
+    /// protocol versions the server still accepts
+    pub async fn inspect(&self, target: IpAddr, port: u16) -> Result<TlsInfo> {
+        let handshake = self.handshake(target, port).await?;
+        Ok(self.inspect_handshake(target, port, &handshake).await)
+    }
+
+    /// `inspect` for a handshake already made, e.g. by service detection
+    pub async fn inspect_handshake(&self, target: IpAddr, port: u16, handshake: &TlsHandshakeInfo) -> TlsInfo {
+        let mut info = TlsInfo {
+            protocol_version: handshake.protocol_version.clone(),
+            subject: None,
+            issuer: None,
+            not_before: None,
+            not_after: None,
+            legacy_protocols: Vec::new(),
+        };
+
+        if let Some(der) = &handshake.peer_certificate {
+            match x509_parser::parse_x509_certificate(der) {
+                Ok((_, cert)) => {
+                    info.subject = Some(cert.subject().to_string());
+                    info.issuer = Some(cert.issuer().to_string());
+                    info.not_before = to_datetime(cert.validity().not_before.timestamp());
+                    info.not_after = to_datetime(cert.validity().not_after.timestamp());
+                }
+                Err(e) => debug!("Unparseable certificate from {}:{}: {}", target, port, e),
+            }
+        }
+
+        for (name, version) in LEGACY_PROTOCOLS {
+            if self.accepts_legacy(target, port, *version).await {
+                info.legacy_protocols.push(name.to_string());
+            }
+        }
+
+        info
+    }
+
+    async fn accepts_legacy(&self, target: IpAddr, port: u16, version: [u8; 2]) -> bool {
+        let probe = async {
+            let mut stream = TcpStream::connect(SocketAddr::new(target, port)).await?;
+            stream.write_all(&legacy_client_hello(version)).await?;
+
+            // Record header (5) + handshake header (4) + server_version (2)
+            let mut reply = [0u8; 11];
+            stream.read_exact(&mut reply).await?;
+            Ok::<_, std::io::Error>(reply)
+        };
+
+        match timeout(self.timeout, probe).await {
+            // A ServerHello echoing the offered version; alerts (0x15) mean refused
+            Ok(Ok(reply)) => reply[0] == 0x16 && reply[5] == 0x02 && reply[9..11] == version,
+            _ => false,
+        }
+    }
+}
+
+fn to_datetime(timestamp: i64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_opt(timestamp, 0).single()
+}
+
+/// Minimal ClientHello offering only `version` and widely supported RSA suites
+fn legacy_client_hello(version: [u8; 2]) -> Vec<u8> {
+    const CIPHER_SUITES: &[u8] = &[
+        0x00, 0x2f, // TLS_RSA_WITH_AES_128_CBC_SHA
+        0x00, 0x35, // TLS_RSA_WITH_AES_256_CBC_SHA
+        0x00, 0x0a, // TLS_RSA_WITH_3DES_EDE_CBC_SHA
+        0x00, 0x05, // TLS_RSA_WITH_RC4_128_SHA
+    ];
+
+    let mut body = Vec::new();
+    body.extend_from_slice(&version);
+    body.extend((0..32).map(|_| rand::random::<u8>())); // client random
+    body.push(0); // no session id
+    body.extend_from_slice(&(CIPHER_SUITES.len() as u16).to_be_bytes());
+    body.extend_from_slice(CIPHER_SUITES);
+    body.extend_from_slice(&[0x01, 0x00]); // null compression only
+
+    let mut handshake = vec![0x01]; // ClientHello
+    handshake.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+    handshake.extend(body);
+
+    let mut record = vec![0x16, version[0], version[1]];
+    record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+    record.extend(handshake);
+    record
+}
+
+impl Default for TlsProbe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct AcceptAnyCertificate;
+
+impl rustls::client::ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_client_hello_framing() {
+        let hello = legacy_client_hello([0x03, 0x00]);
+
+        assert_eq!(&hello[..3], &[0x16, 0x03, 0x00]);
+        let record_len = u16::from_be_bytes([hello[3], hello[4]]) as usize;
+        assert_eq!(record_len, hello.len() - 5);
+
+        assert_eq!(hello[5], 0x01);
+        let handshake_len = u32::from_be_bytes([0, hello[6], hello[7], hello[8]]) as usize;
+        assert_eq!(handshake_len, record_len - 4);
+        assert_eq!(&hello[9..11], &[0x03, 0x00]);
+    }
+}
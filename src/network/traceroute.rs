@@ -1,5 +1,7 @@
+use super::DnsResolver;
 use crate::error::{Error, Result};
 use std::net::IpAddr;
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, info};
 
@@ -15,6 +17,7 @@ pub struct Traceroute {
     max_hops: u8,
     timeout: Duration,
     port: u16,
+    resolver: Option<Arc<DnsResolver>>,
 }
 
 impl Traceroute {
@@ -23,6 +26,7 @@ impl Traceroute {
             max_hops: 30,
             timeout: Duration::from_secs(1),
             port: 33434, // Standard traceroute port
+            resolver: None,
         }
     }
 
@@ -31,12 +35,24 @@ impl Traceroute {
         self
     }
 
+    /// Fill `Hop::hostname` via reverse DNS; share the resolver to reuse its cache
+    pub fn with_resolver(mut self, resolver: Arc<DnsResolver>) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
     pub async fn trace(&self, target: IpAddr) -> Result<Vec<Hop>> {
         info!("Starting traceroute to {}", target);
         let mut hops = Vec::new();
 
         for ttl in 1..=self.max_hops {
-            if let Some(hop) = self.probe_hop(target, ttl).await? {
+            let hop = match self.probe_hop(target, ttl).await {
+                Ok(hop) => hop,
+                // A silent hop doesn't end the trace; the next TTL may answer
+                Err(Error::Timeout { .. }) => None,
+                Err(e) => return Err(e),
+            };
+            if let Some(hop) = hop {
                 hops.push(hop);
                 
                 // If we reached the target, stop
@@ -54,6 +70,12 @@ impl Traceroute {
             }
         }
 
+        if let Some(resolver) = &self.resolver {
+            for hop in hops.iter_mut() {
+                hop.hostname = resolver.resolve(hop.ip).await;
+            }
+        }
+
         info!("Traceroute completed with {} hops", hops.len());
         Ok(hops)
     }
@@ -72,7 +94,10 @@ impl Traceroute {
         ).await;
 
         if send_result.is_err() {
-            return Ok(None);
+            return Err(Error::Timeout {
+                operation: format!("traceroute probe to {} (TTL {})", target, ttl),
+                duration: self.timeout,
+            });
         }
 
         // For UDP traceroute, we expect ICMP time exceeded messages
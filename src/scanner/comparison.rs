@@ -0,0 +1,208 @@
+//! Side-by-side scans of one target with several probing techniques, to see
+//! how its filtering treats each: a port open to connect but filtered to
+//! SYN, or answering only over UDP, says something about the firewall.
+
+use super::{PortStatus, Protocol, ScanConfig, ScanResult, ScanTechnique};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+/// A probing method that `compare-techniques` can run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComparedTechnique {
+    Connect,
+    Syn,
+    Udp,
+}
+
+impl ComparedTechnique {
+    /// `base` narrowed to this technique: a single-entry technique chain, so
+    /// an unusable SYN fails instead of silently falling back to connect.
+    /// Closed and filtered ports are recorded, as the matrix needs them.
+    pub fn config(&self, base: &ScanConfig) -> ScanConfig {
+        let mut config = base.clone();
+        config.record_all_statuses = true;
+        config.enable_udp = *self == ComparedTechnique::Udp;
+        config.technique_chain = vec![match self {
+            ComparedTechnique::Syn => ScanTechnique::Syn,
+            // The engine always runs a TCP pass; a UDP run keeps it cheap
+            ComparedTechnique::Connect | ComparedTechnique::Udp => ScanTechnique::Connect,
+        }];
+        config
+    }
+
+    fn protocol(&self) -> Protocol {
+        match self {
+            ComparedTechnique::Connect | ComparedTechnique::Syn => Protocol::Tcp,
+            ComparedTechnique::Udp => Protocol::Udp,
+        }
+    }
+}
+
+impl std::fmt::Display for ComparedTechnique {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComparedTechnique::Connect => write!(f, "connect"),
+            ComparedTechnique::Syn => write!(f, "syn"),
+            ComparedTechnique::Udp => write!(f, "udp"),
+        }
+    }
+}
+
+impl std::str::FromStr for ComparedTechnique {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "connect" => Ok(ComparedTechnique::Connect),
+            "syn" => Ok(ComparedTechnique::Syn),
+            "udp" => Ok(ComparedTechnique::Udp),
+            other => Err(format!("Unknown technique '{}' (expected connect, syn or udp)", other)),
+        }
+    }
+}
+
+/// One port's status under each technique, in `TechniqueMatrix::techniques`
+/// order; `None` where the technique recorded nothing for the port (e.g. a
+/// UDP port that never answered)
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatrixRow {
+    pub port: u16,
+    pub statuses: Vec<Option<PortStatus>>,
+}
+
+impl MatrixRow {
+    /// Open under some technique, or the techniques that did answer disagree
+    pub fn is_notable(&self) -> bool {
+        let answered: Vec<&PortStatus> = self.statuses.iter().flatten().collect();
+        answered.contains(&&PortStatus::Open) || answered.windows(2).any(|pair| pair[0] != pair[1])
+    }
+}
+
+/// How long a technique's run took and what it found
+#[derive(Debug, Clone, PartialEq)]
+pub struct TechniqueTiming {
+    pub technique: ComparedTechnique,
+    pub duration: Duration,
+    pub open_ports: usize,
+    pub median_response: Option<Duration>,
+}
+
+/// Port × technique → status across the runs of one target
+#[derive(Debug, Clone, PartialEq)]
+pub struct TechniqueMatrix {
+    pub techniques: Vec<ComparedTechnique>,
+    pub rows: Vec<MatrixRow>,
+    pub timings: Vec<TechniqueTiming>,
+}
+
+impl TechniqueMatrix {
+    /// Each run contributes only its technique's protocol: the TCP pass of a
+    /// UDP run is left out of the UDP column
+    pub fn build(runs: &[(ComparedTechnique, ScanResult)]) -> Self {
+        let mut rows: BTreeMap<u16, Vec<Option<PortStatus>>> = BTreeMap::new();
+        let mut timings = Vec::with_capacity(runs.len());
+
+        for (column, (technique, result)) in runs.iter().enumerate() {
+            let protocol = technique.protocol();
+            let statuses: HashMap<u16, &PortStatus> = result.port_table().into_iter()
+                .filter(|port| port.protocol == protocol)
+                .map(|port| (port.port, &port.status))
+                .collect();
+
+            for (&port, &status) in &statuses {
+                rows.entry(port).or_insert_with(|| vec![None; runs.len()])[column] = Some(status.clone());
+            }
+
+            timings.push(TechniqueTiming {
+                technique: *technique,
+                duration: result.statistics.scan_duration,
+                open_ports: statuses.values().filter(|status| ***status == PortStatus::Open).count(),
+                median_response: result.statistics.latency.as_ref().map(|latency| latency.median),
+            });
+        }
+
+        Self {
+            techniques: runs.iter().map(|(technique, _)| *technique).collect(),
+            rows: rows.into_iter().map(|(port, statuses)| MatrixRow { port, statuses }).collect(),
+            timings,
+        }
+    }
+
+    pub fn notable_rows(&self) -> impl Iterator<Item = &MatrixRow> {
+        self.rows.iter().filter(|row| row.is_notable())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{PortInfo, ScanType};
+
+    fn port(port: u16, status: PortStatus, protocol: Protocol) -> PortInfo {
+        PortInfo {
+            port,
+            status,
+            service: None,
+            banner: None,
+            banner_raw: None,
+            response_time: None,
+            protocol,
+            tls: None,
+        }
+    }
+
+    fn run(technique: ComparedTechnique, ports: Vec<PortInfo>, duration_ms: u64) -> (ComparedTechnique, ScanResult) {
+        let mut result = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        for info in ports {
+            if info.status == PortStatus::Open {
+                result.open_ports.push(info);
+            } else {
+                result.all_ports.push(info);
+            }
+        }
+        result.statistics.scan_duration = Duration::from_millis(duration_ms);
+        (technique, result)
+    }
+
+    #[test]
+    fn test_matrix_lines_up_statuses_per_technique() {
+        use PortStatus::*;
+
+        let runs = vec![
+            run(ComparedTechnique::Connect, vec![
+                port(22, Open, Protocol::Tcp),
+                port(23, Closed, Protocol::Tcp),
+                port(80, Closed, Protocol::Tcp),
+            ], 900),
+            // The firewall drops bare SYNs to 23 but lets handshakes through
+            run(ComparedTechnique::Syn, vec![
+                port(22, Open, Protocol::Tcp),
+                port(23, Filtered, Protocol::Tcp),
+                port(80, Closed, Protocol::Tcp),
+            ], 300),
+            // The UDP run's own TCP pass must not leak into its column
+            run(ComparedTechnique::Udp, vec![
+                port(53, Open, Protocol::Udp),
+                port(22, Open, Protocol::Tcp),
+            ], 4000),
+        ];
+
+        let matrix = TechniqueMatrix::build(&runs);
+
+        assert_eq!(matrix.techniques, vec![ComparedTechnique::Connect, ComparedTechnique::Syn, ComparedTechnique::Udp]);
+        assert_eq!(matrix.rows, vec![
+            MatrixRow { port: 22, statuses: vec![Some(Open), Some(Open), None] },
+            MatrixRow { port: 23, statuses: vec![Some(Closed), Some(Filtered), None] },
+            MatrixRow { port: 53, statuses: vec![None, None, Some(Open)] },
+            MatrixRow { port: 80, statuses: vec![Some(Closed), Some(Closed), None] },
+        ]);
+
+        // Closed everywhere it was probed isn't worth a line
+        let notable: Vec<u16> = matrix.notable_rows().map(|row| row.port).collect();
+        assert_eq!(notable, vec![22, 23, 53]);
+
+        let open: Vec<usize> = matrix.timings.iter().map(|timing| timing.open_ports).collect();
+        assert_eq!(open, vec![1, 1, 1]);
+        assert_eq!(matrix.timings[1].duration, Duration::from_millis(300));
+    }
+}
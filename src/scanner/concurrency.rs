@@ -0,0 +1,120 @@
+//! Keeps probe concurrency within the process's open-file limit. Every
+//! in-flight connect probe holds a socket; past the limit `connect` fails
+//! with EMFILE and the port would be reported closed.
+
+use super::ScanConfig;
+use tracing::{info, warn};
+
+/// Share of the soft RLIMIT_NOFILE that probes may hold; the rest is left for
+/// the database, log files, DNS lookups and the like
+pub const FD_BUDGET_PERCENT: u64 = 75;
+
+/// Soft open-file limit of this process, or None where it can't be read
+pub fn open_file_limit() -> Option<u64> {
+    #[cfg(unix)]
+    {
+        match rlimit::getrlimit(rlimit::Resource::NOFILE) {
+            Ok((soft, _)) if soft != rlimit::INFINITY => Some(soft),
+            Ok(_) => None,
+            Err(e) => {
+                warn!("Could not read the open-file limit: {}", e);
+                None
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Probes that may be in flight at once under `fd_limit`
+pub fn fd_budget(fd_limit: u64) -> usize {
+    let budget = fd_limit.saturating_mul(FD_BUDGET_PERCENT) / 100;
+    usize::try_from(budget).unwrap_or(usize::MAX).max(1)
+}
+
+/// `config` with the per-host and global concurrency capped at the budget of
+/// `fd_limit`, warning when a requested value had to be lowered. Unchanged
+/// when the limit is unknown.
+pub fn tune(mut config: ScanConfig, fd_limit: Option<u64>) -> ScanConfig {
+    let Some(fd_limit) = fd_limit else {
+        return config;
+    };
+    let budget = fd_budget(fd_limit);
+
+    if config.max_concurrent_tasks > budget {
+        warn!(
+            "Requested {} concurrent probes, but the open-file limit of {} only allows {}; \
+             using {} (raise it with `ulimit -n`)",
+            config.max_concurrent_tasks, fd_limit, budget, budget
+        );
+        config.max_concurrent_tasks = budget;
+    }
+    // SYN probes all share one raw socket pair, so syn_max_concurrent is
+    // bounded by nothing but the global cap
+    for (technique, limit) in [
+        ("connect", &mut config.connect_max_concurrent),
+        ("udp", &mut config.udp_max_concurrent),
+    ] {
+        if let Some(requested) = limit.filter(|&requested| requested > budget) {
+            warn!(
+                "Requested {} concurrent {} probes, but the open-file limit of {} only allows {}; using {}",
+                requested, technique, fd_limit, budget, budget
+            );
+            *limit = Some(budget);
+        }
+    }
+    if config.global_max_concurrent > budget {
+        info!(
+            "Capping probes across hosts at {} (was {}) for the open-file limit of {}",
+            budget, config.global_max_concurrent, fd_limit
+        );
+        config.global_max_concurrent = budget;
+    }
+
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requested_threads_clamped_to_fd_limit() {
+        let config = ScanConfig {
+            max_concurrent_tasks: 10000,
+            global_max_concurrent: 10000,
+            ..ScanConfig::default()
+        };
+
+        let tuned = tune(config.clone(), Some(1024));
+        assert_eq!(tuned.max_concurrent_tasks, 768);
+        assert_eq!(tuned.global_max_concurrent, 768);
+
+        let overridden = tune(ScanConfig {
+            connect_max_concurrent: Some(5000),
+            syn_max_concurrent: Some(5000),
+            ..config.clone()
+        }, Some(1024));
+        assert_eq!(overridden.connect_max_concurrent, Some(768));
+        assert_eq!(overridden.syn_max_concurrent, Some(5000));
+
+        // Nothing known about the limit: leave the request alone
+        let untouched = tune(config, None);
+        assert_eq!(untouched.max_concurrent_tasks, 10000);
+    }
+
+    #[test]
+    fn test_requests_within_budget_are_kept() {
+        let config = ScanConfig {
+            max_concurrent_tasks: 200,
+            global_max_concurrent: 500,
+            ..ScanConfig::default()
+        };
+        let tuned = tune(config, Some(1024));
+        assert_eq!((tuned.max_concurrent_tasks, tuned.global_max_concurrent), (200, 500));
+
+        assert_eq!(fd_budget(1), 1);
+    }
+}
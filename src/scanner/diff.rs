@@ -0,0 +1,152 @@
+//! What changed on a target between two scans: ports that opened, ports
+//! that closed, and open ports whose detected service changed. The basis of
+//! "what changed since the last assessment" reviews.
+
+use super::{PortInfo, Protocol, ScanResult};
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::time::SystemTime;
+
+/// How an open port differs between the two scans
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PortChangeKind {
+    Added,     // Open now, not before
+    Removed,   // Open before, not now
+    Changed,   // Open in both, with another service, version or product
+    Unchanged,
+}
+
+/// One port open in either scan; `previous` is `None` for an added port,
+/// `current` for a removed one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortChange {
+    pub port: u16,
+    pub protocol: Protocol,
+    pub kind: PortChangeKind,
+    pub previous: Option<PortInfo>,
+    pub current: Option<PortInfo>,
+}
+
+/// Open ports of two scans of one host, matched on `(port, protocol)`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanDiff {
+    pub target: String,
+    pub target_ip: IpAddr,
+    pub previous_scan_id: String,
+    pub current_scan_id: String,
+    pub previous_time: SystemTime,
+    pub current_time: SystemTime,
+    pub changes: Vec<PortChange>, // Ordered by port
+}
+
+impl ScanDiff {
+    pub fn between(previous: &ScanResult, current: &ScanResult) -> Result<Self> {
+        if previous.target_ip != current.target_ip {
+            return Err(Error::Validation(format!(
+                "Cannot compare scans of different hosts: {} and {}",
+                previous.target_ip, current.target_ip
+            )));
+        }
+
+        let find = |scan: &ScanResult, port: &PortInfo| scan.open_ports.iter()
+            .find(|p| p.port == port.port && p.protocol == port.protocol)
+            .cloned();
+
+        let mut changes: Vec<PortChange> = current.open_ports.iter()
+            .map(|port| {
+                let before = find(previous, port);
+                let kind = match &before {
+                    None => PortChangeKind::Added,
+                    Some(before) if service_changed(before, port) => PortChangeKind::Changed,
+                    Some(_) => PortChangeKind::Unchanged,
+                };
+                PortChange { port: port.port, protocol: port.protocol.clone(), kind, previous: before, current: Some(port.clone()) }
+            })
+            .collect();
+        changes.extend(previous.open_ports.iter()
+            .filter(|port| find(current, port).is_none())
+            .map(|port| PortChange {
+                port: port.port,
+                protocol: port.protocol.clone(),
+                kind: PortChangeKind::Removed,
+                previous: Some(port.clone()),
+                current: None,
+            }));
+        changes.sort_by_key(|change| change.port);
+
+        Ok(Self {
+            target: current.target.clone(),
+            target_ip: current.target_ip,
+            previous_scan_id: previous.id.clone(),
+            current_scan_id: current.id.clone(),
+            previous_time: previous.start_time,
+            current_time: current.start_time,
+            changes,
+        })
+    }
+
+    pub fn of_kind(&self, kind: PortChangeKind) -> impl Iterator<Item = &PortChange> {
+        self.changes.iter().filter(move |change| change.kind == kind)
+    }
+}
+
+/// A service detected in only one of the scans counts as a change, so does
+/// a new version or product of the same service
+fn service_changed(before: &PortInfo, after: &PortInfo) -> bool {
+    let signature = |port: &PortInfo| port.service.as_ref()
+        .map(|s| (s.name.to_lowercase(), s.version.clone(), s.product.clone()));
+    signature(before) != signature(after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{PortStatus, ScanType, ServiceInfo};
+
+    fn open_port(port: u16, service: &str, version: &str) -> PortInfo {
+        PortInfo {
+            port,
+            status: PortStatus::Open,
+            service: Some(ServiceInfo {
+                name: service.to_string(),
+                version: Some(version.to_string()),
+                product: None,
+                extra_info: None,
+                confidence: 90,
+                tunnel: None,
+                alpn: None,
+            }),
+            banner: None,
+            banner_raw: None,
+            response_time: None,
+            protocol: Protocol::Tcp,
+            tls: None,
+        }
+    }
+
+    #[test]
+    fn test_ports_are_classified_by_change() {
+        let ip = "10.0.0.1".parse().unwrap();
+        let mut previous = ScanResult::new("10.0.0.1".to_string(), ip, ScanType::Quick);
+        previous.add_open_port(open_port(21, "ftp", "3.0.3"));
+        previous.add_open_port(open_port(22, "ssh", "8.9"));
+        previous.add_open_port(open_port(80, "http", "2.4.58"));
+        let mut current = ScanResult::new("10.0.0.1".to_string(), ip, ScanType::Quick);
+        current.add_open_port(open_port(22, "ssh", "9.6"));
+        current.add_open_port(open_port(80, "http", "2.4.58"));
+        current.add_open_port(open_port(443, "https", "2.4.58"));
+
+        let diff = ScanDiff::between(&previous, &current).unwrap();
+        let kinds: Vec<(u16, PortChangeKind)> = diff.changes.iter().map(|c| (c.port, c.kind)).collect();
+        assert_eq!(kinds, vec![
+            (21, PortChangeKind::Removed),
+            (22, PortChangeKind::Changed),
+            (80, PortChangeKind::Unchanged),
+            (443, PortChangeKind::Added),
+        ]);
+
+        let other = ScanResult::new("10.0.0.2".to_string(), "10.0.0.2".parse().unwrap(), ScanType::Quick);
+        assert!(ScanDiff::between(&previous, &other).is_err());
+    }
+}
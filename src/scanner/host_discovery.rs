@@ -0,0 +1,181 @@
+//! "Is the host alive" check run before the port scan, so a dead host costs
+//! a few probes instead of a full sweep.
+
+use socket2::{Domain, Protocol as SockProtocol, Socket, Type};
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tracing::debug;
+
+/// Ports TCP-pinged during host discovery
+pub const DEFAULT_DISCOVERY_PORTS: [u16; 3] = [80, 443, 22];
+
+/// Shortest wait for a discovery answer, however low the scan timeout is
+pub const MIN_DISCOVERY_TIMEOUT: Duration = Duration::from_millis(500);
+
+const ICMP_ECHO_REQUEST: u8 = 8;
+const ICMP_ECHO_REPLY: u8 = 0;
+const ICMPV6_ECHO_REQUEST: u8 = 128;
+const ICMPV6_ECHO_REPLY: u8 = 129;
+
+/// A host is up if any discovery port answers, with a handshake or a reset,
+/// or it replies to an ICMP echo. ICMP needs raw sockets (root / CAP_NET_RAW)
+/// and is skipped without them.
+pub struct HostDiscovery {
+    ports: Vec<u16>,
+    timeout: Duration,
+    icmp: bool,
+}
+
+impl HostDiscovery {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            ports: DEFAULT_DISCOVERY_PORTS.to_vec(),
+            timeout: timeout.max(MIN_DISCOVERY_TIMEOUT),
+            icmp: true,
+        }
+    }
+
+    pub fn with_ports(mut self, ports: Vec<u16>) -> Self {
+        self.ports = ports;
+        self
+    }
+
+    pub fn with_icmp(mut self, icmp: bool) -> Self {
+        self.icmp = icmp;
+        self
+    }
+
+    pub async fn is_alive(&self, target: IpAddr) -> bool {
+        let pings = self.ports.iter().map(|&port| self.tcp_ping(SocketAddr::new(target, port)));
+        if futures::future::join_all(pings).await.into_iter().any(|answered| answered) {
+            return true;
+        }
+
+        if self.icmp {
+            let deadline = self.timeout;
+            let answered = tokio::task::spawn_blocking(move || icmp_echo(target, deadline)).await;
+            match answered {
+                Ok(Ok(answered)) => return answered,
+                Ok(Err(e)) => debug!("ICMP echo to {} unavailable, relying on TCP pings: {}", target, e),
+                Err(e) => debug!("ICMP echo task failed: {}", e),
+            }
+        }
+
+        false
+    }
+
+    /// A refused connection is still an answer: something sent the RST
+    async fn tcp_ping(&self, addr: SocketAddr) -> bool {
+        match timeout(self.timeout, TcpStream::connect(addr)).await {
+            Ok(Ok(_)) => true,
+            Ok(Err(e)) => matches!(
+                e.kind(),
+                std::io::ErrorKind::ConnectionRefused | std::io::ErrorKind::ConnectionReset
+            ),
+            Err(_) => false,
+        }
+    }
+}
+
+/// One echo request; Err when no raw socket could be opened
+fn icmp_echo(target: IpAddr, wait: Duration) -> std::io::Result<bool> {
+    let (domain, protocol, request_type, reply_type) = match target {
+        IpAddr::V4(_) => (Domain::IPV4, SockProtocol::ICMPV4, ICMP_ECHO_REQUEST, ICMP_ECHO_REPLY),
+        IpAddr::V6(_) => (Domain::IPV6, SockProtocol::ICMPV6, ICMPV6_ECHO_REQUEST, ICMPV6_ECHO_REPLY),
+    };
+    let socket: std::net::UdpSocket = Socket::new(domain, Type::RAW, Some(protocol))?.into();
+
+    let identifier: u16 = rand::random();
+    let mut request = vec![request_type, 0, 0, 0];
+    request.extend_from_slice(&identifier.to_be_bytes());
+    request.extend_from_slice(&1u16.to_be_bytes()); // Sequence number
+    request.extend_from_slice(b"portzilla");
+    if target.is_ipv4() {
+        // The kernel fills in the ICMPv6 checksum itself
+        let checksum = internet_checksum(&request);
+        request[2..4].copy_from_slice(&checksum.to_be_bytes());
+    }
+    socket.send_to(&request, SocketAddr::new(target, 0))?;
+
+    let started = Instant::now();
+    let mut buffer = [0u8; 1500];
+    while let Some(remaining) = wait.checked_sub(started.elapsed()).filter(|d| !d.is_zero()) {
+        socket.set_read_timeout(Some(remaining))?;
+        let (n, from) = match socket.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => break,
+            Err(e) => return Err(e),
+        };
+        if from.ip() != target {
+            continue;
+        }
+
+        // Raw IPv4 sockets deliver the IP header too
+        let offset = if target.is_ipv4() { ((buffer[0] & 0x0f) as usize) * 4 } else { 0 };
+        let Some(reply) = buffer[..n].get(offset..offset + 8) else {
+            continue;
+        };
+        if reply[0] == reply_type && reply[4..6] == identifier.to_be_bytes() {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// RFC 1071 ones'-complement checksum
+fn internet_checksum(data: &[u8]) -> u16 {
+    let mut sum: u32 = data
+        .chunks(2)
+        .map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)]) as u32)
+        .sum();
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_host_answering_on_discovery_port_is_up() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = listener.local_addr().unwrap().port();
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let localhost: IpAddr = "127.0.0.1".parse().unwrap();
+
+        let discovery = HostDiscovery::new(Duration::from_millis(200)).with_icmp(false);
+        assert!(discovery.with_ports(vec![open]).is_alive(localhost).await);
+
+        // A reset proves the host is there just as well
+        let discovery = HostDiscovery::new(Duration::from_millis(200)).with_icmp(false);
+        assert!(discovery.with_ports(vec![closed]).is_alive(localhost).await);
+    }
+
+    #[tokio::test]
+    async fn test_silent_host_is_down() {
+        // Once the accept queue is full the kernel drops further SYNs, so
+        // the port neither accepts nor resets: it looks like a dead host
+        let listener = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+        listener.bind(&SocketAddr::from(([127, 0, 0, 1], 0)).into()).unwrap();
+        listener.listen(0).unwrap();
+        let silent = listener.local_addr().unwrap().as_socket().unwrap();
+        let _queued = TcpStream::connect(silent).await.unwrap();
+
+        let discovery = HostDiscovery::new(Duration::from_millis(200))
+            .with_icmp(false)
+            .with_ports(vec![silent.port()]);
+        assert!(!discovery.is_alive(silent.ip()).await);
+    }
+
+    #[test]
+    fn test_internet_checksum() {
+        // Echo request, id 1, seq 1, no payload
+        let request = [8, 0, 0, 0, 0, 1, 0, 1];
+        assert_eq!(internet_checksum(&request), 0xf7fd);
+    }
+}
@@ -0,0 +1,444 @@
+//! Loads scans from the JSON the exporter writes (`JsonExporter`), ours or
+//! another tool's converted to the same layout, for `portscanner import`.
+//!
+//! Validation is strict: a missing required field, a value of the wrong
+//! type, an unknown enum value or an unparseable IP rejects the whole file
+//! with an `Error::Validation` naming the offending field, e.g.
+//! `results.open_ports[3].status`. So does a port listed twice. Unknown
+//! fields are ignored.
+
+use super::{PortInfo, PortStatus, Protocol, ScanResult, ScanStatistics, ScanType, ServiceInfo};
+use crate::error::{Error, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::time::{Duration, SystemTime};
+
+impl ScanResult {
+    /// Parse and validate an exported scan. The scan keeps its `scan_id`
+    /// when the file has one, so re-importing the same export is refused
+    /// by the database rather than stored twice.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let root: Value = serde_json::from_str(json)
+            .map_err(|e| Error::Validation(format!("Invalid scan JSON: {}", e)))?;
+        let root = object(&root, "(root)")?;
+
+        let metadata = object(required(root, "metadata", "")?, "metadata")?;
+        let target = string(metadata, "target", "metadata")?;
+        if target.trim().is_empty() {
+            return Err(invalid("metadata.target", "must not be empty"));
+        }
+        let target_ip_text = string(metadata, "target_ip", "metadata")?;
+        let target_ip: IpAddr = target_ip_text.parse()
+            .map_err(|_| invalid("metadata.target_ip", &format!("'{}' is not an IP address", target_ip_text)))?;
+        let scan_type = parse_scan_type(&string(metadata, "scan_type", "metadata")?)
+            .map_err(|problem| invalid("metadata.scan_type", &problem))?;
+        let start_time = timestamp(metadata, "start_time", "metadata")?;
+        let end_time = timestamp(metadata, "end_time", "metadata")?;
+        if end_time < start_time {
+            return Err(invalid("metadata.end_time", "is before start_time"));
+        }
+
+        let mut scan = ScanResult::new(target, target_ip, scan_type);
+        if let Some(id) = optional_string(metadata, "scan_id", "metadata")? {
+            scan.id = id;
+        }
+        scan.start_time = start_time;
+        scan.end_time = end_time;
+
+        let results = object(required(root, "results", "")?, "results")?;
+        scan.open_ports = ports(results, "open_ports")?.unwrap_or_default();
+        if let Some(port) = scan.open_ports.iter().position(|port| port.status != PortStatus::Open) {
+            return Err(invalid(&format!("results.open_ports[{}].status", port), "must be open"));
+        }
+        // scan_ports holds one row per port number, whatever the protocol
+        let mut seen = HashSet::new();
+        for (index, port) in scan.open_ports.iter().enumerate() {
+            if !seen.insert(port.port) {
+                return Err(listed_twice(&format!("results.open_ports[{}]", index), port.port));
+            }
+        }
+        // all_ports repeats the open ports; only the others are kept apart
+        for (index, port) in ports(results, "all_ports")?.unwrap_or_default().into_iter().enumerate() {
+            if port.status == PortStatus::Open {
+                continue;
+            }
+            if !seen.insert(port.port) {
+                return Err(listed_twice(&format!("results.all_ports[{}]", index), port.port));
+            }
+            scan.all_ports.push(port);
+        }
+
+        if let Some(scan_metadata) = optional(root, "scan_metadata") {
+            let scan_metadata = object(scan_metadata, "scan_metadata")?;
+            if let Some(version) = optional_string(scan_metadata, "scanner_version", "scan_metadata")? {
+                scan.metadata.scanner_version = version;
+            }
+            scan.metadata.hostname = optional_string(scan_metadata, "hostname", "scan_metadata")?;
+            scan.metadata.as_org = optional_string(scan_metadata, "as_org", "scan_metadata")?;
+            scan.metadata.country_code = optional_string(scan_metadata, "country_code", "scan_metadata")?;
+            scan.metadata.asn = optional_number(scan_metadata, "asn", "scan_metadata")?;
+        }
+
+        scan.statistics = statistics(root, &scan)?;
+        Ok(scan)
+    }
+}
+
+/// The exporter's `Debug` form ("CustomRange(1, 1024)", "Targeted([22, 80])")
+/// or the stored one ("custom_1_1024", "quick")
+fn parse_scan_type(value: &str) -> std::result::Result<ScanType, String> {
+    let lower = value.trim().to_lowercase();
+    let numbers = |list: &str| -> std::result::Result<Vec<u16>, String> {
+        list.split([',', '_'])
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .map(|n| n.parse::<u16>().map_err(|_| format!("'{}' is not a port number", n)))
+            .collect()
+    };
+
+    match lower.as_str() {
+        "quick" => Ok(ScanType::Quick),
+        "standard" => Ok(ScanType::Standard),
+        "full" => Ok(ScanType::Full),
+        "knownservices" | "known_services" => Ok(ScanType::KnownServices),
+        other => {
+            let range = other.strip_prefix("customrange(").and_then(|rest| rest.strip_suffix(')'))
+                .or_else(|| other.strip_prefix("custom_"));
+            if let Some(range) = range {
+                return match numbers(range)?.as_slice() {
+                    [start, end] if start <= end && *start > 0 => Ok(ScanType::CustomRange(*start, *end)),
+                    _ => Err(format!("'{}' is not a valid port range", value)),
+                };
+            }
+            if let Some(list) = other.strip_prefix("targeted([").and_then(|rest| rest.strip_suffix("])")) {
+                return Ok(ScanType::Targeted(numbers(list)?));
+            }
+            Err(format!("unknown scan type '{}' (expected Quick, Standard, Full, KnownServices, CustomRange(a, b) or Targeted([...]))", value))
+        }
+    }
+}
+
+fn parse_port_status(value: &str) -> Option<PortStatus> {
+    match value.to_lowercase().as_str() {
+        "open" => Some(PortStatus::Open),
+        "closed" => Some(PortStatus::Closed),
+        "filtered" => Some(PortStatus::Filtered),
+        "openfiltered" | "open|filtered" => Some(PortStatus::OpenFiltered),
+        "unknown" => Some(PortStatus::Unknown),
+        _ => None,
+    }
+}
+
+fn parse_protocol(value: &str) -> Option<Protocol> {
+    match value.to_lowercase().as_str() {
+        "tcp" => Some(Protocol::Tcp),
+        "udp" => Some(Protocol::Udp),
+        "sctp" => Some(Protocol::Sctp),
+        _ => None,
+    }
+}
+
+/// `results.<key>`, or `None` when absent or null
+fn ports(results: &Map<String, Value>, key: &str) -> Result<Option<Vec<PortInfo>>> {
+    let path = format!("results.{}", key);
+    let Some(list) = optional(results, key) else {
+        return Ok(None);
+    };
+    let list = list.as_array().ok_or_else(|| invalid(&path, "must be an array"))?;
+    list.iter()
+        .enumerate()
+        .map(|(index, port)| port_info(port, &format!("{}[{}]", path, index)))
+        .collect::<Result<Vec<_>>>()
+        .map(Some)
+}
+
+fn port_info(value: &Value, path: &str) -> Result<PortInfo> {
+    let port = object(value, path)?;
+
+    let number: u16 = number(port, "port", path)?;
+    if number == 0 {
+        return Err(invalid(&format!("{}.port", path), "must be between 1 and 65535"));
+    }
+    let status_text = string(port, "status", path)?;
+    let status = parse_port_status(&status_text).ok_or_else(|| invalid(
+        &format!("{}.status", path),
+        &format!("unknown status '{}' (expected open, closed, filtered, openfiltered or unknown)", status_text),
+    ))?;
+    let protocol = match optional_string(port, "protocol", path)? {
+        Some(text) => parse_protocol(&text).ok_or_else(|| invalid(
+            &format!("{}.protocol", path),
+            &format!("unknown protocol '{}' (expected tcp, udp or sctp)", text),
+        ))?,
+        None => Protocol::Tcp,
+    };
+
+    let service = match optional(port, "service") {
+        Some(service) => {
+            let service_path = format!("{}.service", path);
+            let service = object(service, &service_path)?;
+            let confidence: Option<u8> = optional_number(service, "confidence", &service_path)?;
+            if confidence.is_some_and(|confidence| confidence > 100) {
+                return Err(invalid(&format!("{}.confidence", service_path), "must be at most 100"));
+            }
+            Some(ServiceInfo {
+                name: string(service, "name", &service_path)?,
+                version: optional_string(service, "version", &service_path)?,
+                product: optional_string(service, "product", &service_path)?,
+                extra_info: optional_string(service, "extra_info", &service_path)?,
+                confidence: confidence.unwrap_or(0),
+                tunnel: optional_string(service, "tunnel", &service_path)?,
+                alpn: optional_string(service, "alpn", &service_path)?,
+            })
+        }
+        None => None,
+    };
+
+    let banner_raw = optional_string(port, "banner_raw", path)?;
+    if banner_raw.as_ref().is_some_and(|raw| raw.len() % 2 != 0 || !raw.chars().all(|c| c.is_ascii_hexdigit())) {
+        return Err(invalid(&format!("{}.banner_raw", path), "must be hex"));
+    }
+
+    Ok(PortInfo {
+        port: number,
+        status,
+        service,
+        banner: optional_string(port, "banner", path)?,
+        banner_raw,
+        response_time: optional_number::<u64>(port, "response_time_ms", path)?.map(Duration::from_millis),
+        protocol,
+        tls: None,
+    })
+}
+
+/// Counts from the file where it has them, else from the ports themselves
+fn statistics(root: &Map<String, Value>, scan: &ScanResult) -> Result<ScanStatistics> {
+    let count = |ports: usize| u16::try_from(ports).unwrap_or(u16::MAX);
+    let open_ports = count(scan.open_ports.len());
+    let mut statistics = ScanStatistics {
+        total_ports: open_ports.saturating_add(count(scan.all_ports.len())),
+        open_ports,
+        scan_duration: scan.duration(),
+        ..ScanStatistics::default()
+    };
+
+    if let Some(section) = optional(root, "statistics") {
+        let section = object(section, "statistics")?;
+        if let Some(total) = optional_number(section, "total_ports_scanned", "statistics")? {
+            statistics.total_ports = total;
+        }
+        if statistics.total_ports < open_ports {
+            return Err(invalid("statistics.total_ports_scanned", "is less than the number of open ports"));
+        }
+        statistics.filtered_ports = optional_number(section, "filtered_ports", "statistics")?.unwrap_or(0);
+        statistics.closed_ports = optional_number(section, "closed_ports", "statistics")?
+            .unwrap_or_else(|| statistics.total_ports.saturating_sub(open_ports.saturating_add(statistics.filtered_ports)));
+        statistics.packets_sent = optional_number(section, "packets_sent", "statistics")?.unwrap_or(0);
+        statistics.packets_received = optional_number(section, "packets_received", "statistics")?.unwrap_or(0);
+    } else {
+        let with_status = |status: PortStatus| count(scan.all_ports.iter().filter(|port| port.status == status).count());
+        statistics.closed_ports = with_status(PortStatus::Closed);
+        statistics.filtered_ports = with_status(PortStatus::Filtered);
+    }
+    Ok(statistics)
+}
+
+fn invalid(path: &str, problem: &str) -> Error {
+    Error::Validation(format!("Invalid scan JSON: {} {}", path, problem))
+}
+
+fn listed_twice(path: &str, port: u16) -> Error {
+    invalid(&format!("{}.port", path), &format!("{} is listed more than once", port))
+}
+
+fn field_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() { key.to_string() } else { format!("{}.{}", parent, key) }
+}
+
+fn object<'a>(value: &'a Value, path: &str) -> Result<&'a Map<String, Value>> {
+    value.as_object().ok_or_else(|| invalid(path, "must be an object"))
+}
+
+/// Absent and `null` are the same: the exporter writes `null` for unset options
+fn optional<'a>(parent: &'a Map<String, Value>, key: &str) -> Option<&'a Value> {
+    parent.get(key).filter(|value| !value.is_null())
+}
+
+fn required<'a>(parent: &'a Map<String, Value>, key: &str, path: &str) -> Result<&'a Value> {
+    optional(parent, key).ok_or_else(|| invalid(&field_path(path, key), "is required"))
+}
+
+fn string(parent: &Map<String, Value>, key: &str, path: &str) -> Result<String> {
+    required(parent, key, path)?
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| invalid(&field_path(path, key), "must be a string"))
+}
+
+fn optional_string(parent: &Map<String, Value>, key: &str, path: &str) -> Result<Option<String>> {
+    match optional(parent, key) {
+        Some(value) => value.as_str()
+            .map(|text| Some(text.to_string()))
+            .ok_or_else(|| invalid(&field_path(path, key), "must be a string")),
+        None => Ok(None),
+    }
+}
+
+fn number<T: TryFrom<u64>>(parent: &Map<String, Value>, key: &str, path: &str) -> Result<T> {
+    let value = required(parent, key, path)?;
+    value.as_u64()
+        .and_then(|n| T::try_from(n).ok())
+        .ok_or_else(|| invalid(&field_path(path, key), &format!("{} is not a valid number here", value)))
+}
+
+fn optional_number<T: TryFrom<u64>>(parent: &Map<String, Value>, key: &str, path: &str) -> Result<Option<T>> {
+    match optional(parent, key) {
+        Some(_) => number(parent, key, path).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn timestamp(parent: &Map<String, Value>, key: &str, path: &str) -> Result<SystemTime> {
+    let text = string(parent, key, path)?;
+    DateTime::parse_from_rfc3339(&text)
+        .map(|time| SystemTime::from(time.with_timezone(&Utc)))
+        .map_err(|_| invalid(&field_path(path, key), &format!("'{}' is not an RFC 3339 timestamp", text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn export() -> Value {
+        json!({
+            "metadata": {
+                "scanner": "Port-ZiLLA Enterprise",
+                "version": "1.0.0",
+                "scan_id": "6f1c2a4e-archived",
+                "target": "web.example.test",
+                "target_ip": "10.0.0.8",
+                "scan_type": "CustomRange(1, 1024)",
+                "start_time": "2024-05-01T10:00:00+00:00",
+                "end_time": "2024-05-01T10:00:42+00:00",
+                "duration_seconds": 42.0
+            },
+            "statistics": { "total_ports_scanned": 1024, "open_ports_found": 2, "filtered_ports": 10 },
+            "results": {
+                "open_ports": [
+                    {
+                        "port": 22, "status": "Open", "protocol": "Tcp",
+                        "service": { "name": "ssh", "version": "9.6", "product": "OpenSSH", "confidence": 95, "tunnel": null, "alpn": null },
+                        "banner": "SSH-2.0-OpenSSH_9.6", "banner_raw": null, "response_time_ms": 3
+                    },
+                    { "port": 443, "status": "open", "protocol": "tcp", "service": null, "banner": null }
+                ],
+                "all_ports": null,
+                "incremental": null
+            },
+            "scan_metadata": { "scanner_version": "1.0.0", "hostname": "web", "asn": 64500, "os_detection": null }
+        })
+    }
+
+    fn import(json: &Value) -> Result<ScanResult> {
+        ScanResult::from_json(&json.to_string())
+    }
+
+    fn rejection(json: &Value) -> String {
+        match import(json) {
+            Err(Error::Validation(message)) => message,
+            other => panic!("expected a validation error, got {:?}", other.map(|scan| scan.id)),
+        }
+    }
+
+    #[test]
+    fn test_exported_scan_imports() {
+        let scan = import(&export()).unwrap();
+
+        assert_eq!(scan.id, "6f1c2a4e-archived");
+        assert_eq!(scan.target, "web.example.test");
+        assert_eq!(scan.target_ip, "10.0.0.8".parse::<IpAddr>().unwrap());
+        assert!(matches!(scan.scan_type, ScanType::CustomRange(1, 1024)));
+        assert_eq!(scan.duration(), Duration::from_secs(42));
+        assert_eq!(scan.open_ports.iter().map(|port| port.port).collect::<Vec<_>>(), vec![22, 443]);
+        let ssh = &scan.open_ports[0];
+        assert_eq!(ssh.service.as_ref().map(|service| service.name.as_str()), Some("ssh"));
+        assert_eq!(ssh.response_time, Some(Duration::from_millis(3)));
+        assert_eq!(scan.statistics.total_ports, 1024);
+        assert_eq!(scan.statistics.closed_ports, 1024 - 2 - 10);
+        assert_eq!(scan.metadata.asn, Some(64500));
+
+        assert!(matches!(parse_scan_type("Targeted([22, 80])"), Ok(ScanType::Targeted(ports)) if ports == vec![22, 80]));
+        assert!(matches!(parse_scan_type("custom_1_100"), Ok(ScanType::CustomRange(1, 100))));
+    }
+
+    #[test]
+    fn test_malformed_scans_are_rejected_naming_the_field() {
+        let mut bad_ip = export();
+        bad_ip["metadata"]["target_ip"] = json!("10.0.0.300");
+        assert!(rejection(&bad_ip).contains("metadata.target_ip '10.0.0.300' is not an IP address"));
+
+        let mut unknown_status = export();
+        unknown_status["results"]["open_ports"][1]["status"] = json!("listening");
+        assert!(rejection(&unknown_status).contains("results.open_ports[1].status unknown status 'listening'"));
+
+        let mut missing_target = export();
+        missing_target["metadata"].as_object_mut().unwrap().remove("target");
+        assert!(rejection(&missing_target).contains("metadata.target is required"));
+
+        let mut bad_port = export();
+        bad_port["results"]["open_ports"][0]["port"] = json!(70000);
+        assert!(rejection(&bad_port).contains("results.open_ports[0].port"));
+
+        let mut bad_scan_type = export();
+        bad_scan_type["metadata"]["scan_type"] = json!("Stealthy");
+        assert!(rejection(&bad_scan_type).contains("unknown scan type 'Stealthy'"));
+
+        let mut bad_time = export();
+        bad_time["metadata"]["end_time"] = json!("yesterday");
+        assert!(rejection(&bad_time).contains("metadata.end_time"));
+
+        assert!(rejection(&json!([1, 2, 3])).contains("(root) must be an object"));
+        assert!(matches!(ScanResult::from_json("{ not json"), Err(Error::Validation(_))));
+    }
+
+    #[test]
+    fn test_ports_listed_twice_are_rejected() {
+        let mut twice_open = export();
+        twice_open["results"]["open_ports"][1]["port"] = json!(22);
+        assert!(rejection(&twice_open).contains("results.open_ports[1].port 22 is listed more than once"));
+
+        // Open entries of all_ports are the open_ports repeated, the others must be new
+        let mut conflicting = export();
+        conflicting["results"]["all_ports"] = json!([
+            { "port": 22, "status": "open" },
+            { "port": 25, "status": "closed" },
+            { "port": 443, "status": "filtered" }
+        ]);
+        assert!(rejection(&conflicting).contains("results.all_ports[2].port 443 is listed more than once"));
+
+        let mut repeated = export();
+        repeated["results"]["all_ports"] = json!([
+            { "port": 22, "status": "open" },
+            { "port": 25, "status": "closed" },
+            { "port": 80, "status": "filtered" }
+        ]);
+        let scan = import(&repeated).unwrap();
+        assert_eq!(scan.all_ports.iter().map(|port| port.port).collect::<Vec<_>>(), vec![25, 80]);
+
+        // What the open port recheck leaves behind is stored like the rest
+        let mut rechecked = export();
+        rechecked["results"]["all_ports"] = json!([{ "port": 8080, "status": "openfiltered" }]);
+        assert_eq!(import(&rechecked).unwrap().all_ports[0].status, PortStatus::OpenFiltered);
+    }
+
+    #[test]
+    fn test_statistics_saturate_instead_of_overflowing() {
+        let mut huge = export();
+        huge["statistics"] = json!({ "total_ports_scanned": 65535, "filtered_ports": 65535 });
+        let scan = import(&huge).unwrap();
+        assert_eq!(scan.statistics.total_ports, 65535);
+        assert_eq!(scan.statistics.closed_ports, 0);
+    }
+}
@@ -1,11 +1,34 @@
 pub mod port_scanner;
 pub mod syn_scanner;
 pub mod udp_scanner;
+#[cfg(feature = "sctp")]
+pub mod sctp_scanner;
 pub mod models;
 pub mod engine;
+pub mod concurrency;
+pub mod host_discovery;
+pub mod pacer;
+pub mod observer;
+pub mod rampup;
+pub mod comparison;
+pub mod diff;
+pub mod json_import;
 
 pub use port_scanner::PortScanner;
 pub use syn_scanner::SynScanner;
 pub use udp_scanner::UdpScanner;
+#[cfg(feature = "sctp")]
+pub use sctp_scanner::SctpScanner;
 pub use engine::ScanEngine;
-pub use models::{ScanResult, PortStatus, ServiceInfo, ScanType, ScanProgress};
+pub use host_discovery::HostDiscovery;
+pub use pacer::PacketPacer;
+pub use observer::ScanObserver;
+pub use rampup::RampUp;
+pub use comparison::{ComparedTechnique, TechniqueMatrix};
+pub use diff::{PortChange, PortChangeKind, ScanDiff};
+pub use models::{
+    ScanResult, PortInfo, PortStatus, Protocol, ServiceInfo, ScanType, ScanProgress, ScanConfig,
+    ScanStatistics, ProtocolStatistics, LatencyStats, ProxyConfig, TlsInfo, ScanMetadata, ScanTechnique, Decoy, PartialScan,
+    IncrementalScan, ScanCheckpoint, ScanArguments, DISCOVERY_MAX_CONCURRENT, DEFAULT_INCREMENTAL_MAX_AGE,
+    DEFAULT_DANGEROUS_PORTS, DEFAULT_ENRICH_CONCURRENCY, PortOrder, ServiceGroup,
+};
@@ -1,3 +1,4 @@
+use crate::error::Error;
 use serde::{Deserialize, Serialize};
 use std::net::IpAddr;
 use std::time::{Duration, SystemTime};
@@ -14,6 +15,17 @@ pub struct ScanResult {
     pub open_ports: Vec<PortInfo>,
     pub statistics: ScanStatistics,
     pub metadata: ScanMetadata,
+    #[serde(default)]
+    pub partial: Option<PartialScan>, // Set when the host went down before every port was probed
+    #[serde(default)]
+    pub all_ports: Vec<PortInfo>, // Closed/filtered ports, kept only with ScanConfig::record_all_statuses
+}
+
+/// Why a scan ended before covering every planned port
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartialScan {
+    pub reason: String,
+    pub last_reached_port: Option<u16>, // Last port that answered before the host went down
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,8 +34,23 @@ pub struct PortInfo {
     pub status: PortStatus,
     pub service: Option<ServiceInfo>,
     pub banner: Option<String>,
+    #[serde(default)]
+    pub banner_raw: Option<String>, // Hex of the captured bytes when `banner` can't carry them (binary or truncated)
     pub response_time: Option<Duration>,
     pub protocol: Protocol,
+    #[serde(default)]
+    pub tls: Option<TlsInfo>, // Set when a TLS handshake succeeded on the port
+}
+
+/// Negotiated TLS parameters and leaf certificate details
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsInfo {
+    pub protocol_version: String,
+    pub subject: Option<String>,
+    pub issuer: Option<String>,
+    pub not_before: Option<chrono::DateTime<chrono::Utc>>,
+    pub not_after: Option<chrono::DateTime<chrono::Utc>>,
+    pub legacy_protocols: Vec<String>, // Deprecated versions the server still accepts (SSLv3, TLSv1.0)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -49,6 +76,119 @@ pub struct ServiceInfo {
     pub product: Option<String>,
     pub extra_info: Option<String>,
     pub confidence: u8, // 0-100
+    #[serde(default)]
+    pub tunnel: Option<String>, // "ssl" when the service speaks through TLS, as in nmap
+    #[serde(default)]
+    pub alpn: Option<String>, // Protocol negotiated in the TLS handshake (h2, http/1.1)
+}
+
+impl ServiceInfo {
+    /// nmap-style label: "http over ssl/tls" for a TLS-wrapped web server,
+    /// else the name
+    pub fn description(&self) -> String {
+        match &self.tunnel {
+            Some(tunnel) => {
+                let plain = match self.name.as_str() {
+                    "https" => "http",
+                    "imaps" => "imap",
+                    "pop3s" => "pop3",
+                    "ldaps" => "ldap",
+                    name => name,
+                };
+                format!("{} over {}/tls", plain, tunnel)
+            }
+            None => self.name.clone(),
+        }
+    }
+
+    /// Detected with at least `min_confidence` (`export.min_service_confidence`),
+    /// so reports may state it as fact
+    pub fn is_confirmed(&self, min_confidence: u8) -> bool {
+        self.confidence >= min_confidence
+    }
+
+    /// "confirmed" or "guessed", the label exports give the detection
+    pub fn certainty(&self, min_confidence: u8) -> &'static str {
+        if self.is_confirmed(min_confidence) { "confirmed" } else { "guessed" }
+    }
+
+    /// The name when confirmed, else "unknown (guessed: ssh)"
+    pub fn reported_name(&self, min_confidence: u8) -> String {
+        if self.is_confirmed(min_confidence) {
+            self.name.clone()
+        } else {
+            format!("unknown (guessed: {})", self.name)
+        }
+    }
+}
+
+/// Broad kind of service, for grouping results the way an audit reads them.
+/// Declaration order is display order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ServiceGroup {
+    Web,
+    Database,
+    RemoteAccess,
+    Mail,
+    FileSharing,
+    Directory,
+    Infrastructure, // DNS, SNMP, NTP, RPC
+    Other,          // Unidentified or uncommon services
+}
+
+impl ServiceGroup {
+    pub fn of(port: &PortInfo) -> Self {
+        let Some(service) = &port.service else { return ServiceGroup::Other };
+        match service.name.to_lowercase().as_str() {
+            name if name.starts_with("http") => ServiceGroup::Web,
+            "mysql" | "postgresql" | "mssql" | "oracle" | "mongodb" | "redis" | "memcached"
+            | "elasticsearch" | "cassandra" | "couchdb" => ServiceGroup::Database,
+            "ssh" | "telnet" | "rdp" | "vnc" | "winrm" | "rlogin" | "rsh" => ServiceGroup::RemoteAccess,
+            "smtp" | "smtps" | "submission" | "pop3" | "pop3s" | "imap" | "imaps" => ServiceGroup::Mail,
+            "ftp" | "ftps" | "tftp" | "smb" | "netbios" | "netbios-ssn" | "nfs" | "rsync" => ServiceGroup::FileSharing,
+            "ldap" | "ldaps" | "kerberos" => ServiceGroup::Directory,
+            "dns" | "snmp" | "ntp" | "rpc" | "msrpc" | "dhcp" => ServiceGroup::Infrastructure,
+            _ => ServiceGroup::Other,
+        }
+    }
+}
+
+impl std::fmt::Display for ServiceGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServiceGroup::Web => write!(f, "Web"),
+            ServiceGroup::Database => write!(f, "Database"),
+            ServiceGroup::RemoteAccess => write!(f, "Remote access"),
+            ServiceGroup::Mail => write!(f, "Mail"),
+            ServiceGroup::FileSharing => write!(f, "File sharing"),
+            ServiceGroup::Directory => write!(f, "Directory"),
+            ServiceGroup::Infrastructure => write!(f, "Infrastructure"),
+            ServiceGroup::Other => write!(f, "Other"),
+        }
+    }
+}
+
+/// Order of open ports in displays and exports. Scans are always stored in
+/// port order; see `ScanResult::sorted_by`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PortOrder {
+    #[default]
+    Port,
+    Service,      // By ServiceGroup, then service name, then port
+    ResponseTime, // Fastest first; ports without a timing last
+}
+
+impl PortOrder {
+    pub fn sort(&self, ports: &mut [PortInfo]) {
+        match self {
+            PortOrder::Port => ports.sort_by_key(|p| p.port),
+            PortOrder::Service => ports.sort_by_cached_key(|p| {
+                let name = p.service.as_ref().map(|s| s.name.to_lowercase());
+                (ServiceGroup::of(p), name, p.port)
+            }),
+            PortOrder::ResponseTime => ports.sort_by_key(|p| (p.response_time.is_none(), p.response_time, p.port)),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,15 +201,192 @@ pub struct ScanStatistics {
     pub packets_sent: u64,
     pub packets_received: u64,
     pub success_rate: f64,
+    pub effective_timeout: Option<Duration>, // Final per-port timeout in adaptive mode
+    pub excluded_ports: u16,
+    pub latency: Option<LatencyStats>, // None when no port reported a response time
+    pub stopped_early: bool, // Partial result: stop_on_first_open cut the scan short
+    #[serde(default)]
+    pub cancelled: bool, // Partial result: stopped through ScanEngine::with_cancellation
+    pub udp_ports_scanned: u16, // Set by the engine when a UDP pass ran alongside TCP
+    #[serde(default)]
+    pub sctp_ports_scanned: u16, // Likewise for an SCTP INIT pass
+    pub per_protocol: Vec<ProtocolStatistics>,
+    #[serde(default)]
+    pub effective_concurrency: usize, // Probes per host after capping to the open-file limit
+    #[serde(default)]
+    pub probe_packets: u64, // Estimated packets of every probe dispatched, both directions
+    #[serde(default)]
+    pub average_pps: f64, // probe_packets over the scan duration
+}
+
+/// Port counts for one protocol of a mixed TCP/UDP scan
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProtocolStatistics {
+    pub protocol: Protocol,
+    pub total_ports: u16,
+    pub open_ports: u16,
+}
+
+/// Connect-time distribution over the ports that answered, open or closed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LatencyStats {
+    pub min: Duration,
+    pub median: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl LatencyStats {
+    /// Nearest-rank percentiles over a single sort; even a full port range
+    /// sorts quickly enough that a streaming estimator isn't worth the error
+    pub fn from_samples(mut samples: Vec<Duration>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        samples.sort_unstable();
+        let percentile = |p: f64| {
+            let rank = ((p / 100.0) * samples.len() as f64).ceil() as usize;
+            samples[rank.clamp(1, samples.len()) - 1]
+        };
+
+        Some(Self {
+            min: samples[0],
+            median: percentile(50.0),
+            p90: percentile(90.0),
+            p99: percentile(99.0),
+            max: samples[samples.len() - 1],
+        })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScanMetadata {
     pub scanner_version: String,
-    pub arguments: Vec<String>,
+    #[serde(default)]
+    pub arguments: Option<ScanArguments>, // Set by ScanEngine once the ports are planned
     pub hostname: Option<String>,
     pub os_detection: Option<OsInfo>,
     pub traceroute: Option<Vec<Hop>>,
+    #[serde(default)]
+    pub technique: Option<ScanTechnique>, // What actually ran, after the fallback chain
+    #[serde(default)]
+    pub asn: Option<u32>, // From the local GeoIP databases, when configured
+    #[serde(default)]
+    pub as_org: Option<String>,
+    #[serde(default)]
+    pub country_code: Option<String>, // ISO 3166-1 alpha-2
+    #[serde(default)]
+    pub coverage: Vec<(u16, u16)>, // TCP ports this scan probed itself, as inclusive ranges; empty if it ended early
+    #[serde(default)]
+    pub incremental: Option<IncrementalScan>, // Set when ports were carried over from an earlier scan
+    #[serde(default)]
+    pub host_up: Option<bool>, // Host discovery verdict; None when discovery didn't run
+    #[serde(default)]
+    pub notes: Vec<String>, // Caveats for the report, e.g. ports the open-port recheck downgraded
+}
+
+/// The parameters a scan ran with, taken from its `ScanConfig`. Not the
+/// process argv: command lines can carry secrets, and library callers have
+/// none. Proxy credentials are left out by recording only that one was used.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanArguments {
+    pub scan_type: String,
+    pub port_count: usize, // Planned, after exclusions
+    pub timeout_ms: u64,
+    pub max_concurrent: usize,
+    pub rate_limit: Option<u32>,
+    pub version_intensity: u8,
+    pub flags: Vec<String>, // Enabled options, e.g. "udp", "stealth"
+}
+
+impl ScanArguments {
+    pub fn new(config: &ScanConfig, scan_type: &ScanType, port_count: usize) -> Self {
+        let flags = [
+            ("service_detection", config.enable_service_detection),
+            ("banner_grabbing", config.enable_banner_grabbing),
+            ("os_detection", config.enable_os_detection),
+            ("traceroute", config.enable_traceroute),
+            ("stealth", config.stealth_mode),
+            ("udp", config.enable_udp),
+            ("sctp", config.enable_sctp),
+            ("randomize", config.randomize_order),
+            ("adaptive_timeout", config.adaptive_timeout),
+            ("stop_on_first_open", config.stop_on_first_open),
+            ("verify_open", config.verify_open),
+            ("discovery_only", config.discovery_only),
+            ("incremental", config.incremental),
+            ("all_statuses", config.record_all_statuses),
+            ("host_discovery", config.host_discovery),
+            ("proxy", config.proxy.is_some()),
+        ];
+
+        Self {
+            scan_type: scan_type.to_string(),
+            port_count,
+            timeout_ms: config.timeout.as_millis() as u64,
+            max_concurrent: config.max_concurrent_tasks,
+            rate_limit: config.rate_limit,
+            version_intensity: config.version_intensity,
+            flags: flags.iter().filter(|(_, enabled)| *enabled).map(|(flag, _)| flag.to_string()).collect(),
+        }
+    }
+}
+
+/// Which planned ports an incremental scan took from an earlier scan of the
+/// same target instead of probing them. Ports not listed here were freshly
+/// scanned.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IncrementalScan {
+    pub baseline_scan_id: String,
+    pub carried_over: Vec<(u16, u16)>, // Inclusive ranges; none of them was open in the baseline
+    pub carried_over_since: SystemTime, // When the oldest carried-over port was last actually probed
+}
+
+impl IncrementalScan {
+    pub fn carried_over_count(&self) -> usize {
+        self.carried_over.iter().map(|&(start, end)| (end - start) as usize + 1).sum()
+    }
+
+    pub fn is_carried_over(&self, port: u16) -> bool {
+        self.carried_over.iter().any(|&(start, end)| (start..=end).contains(&port))
+    }
+}
+
+/// Progress of an in-progress scan, persisted every
+/// `ScanConfig::checkpoint_interval` completed probes so an interrupted scan
+/// can be resumed through `ScanEngine::with_resume`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanCheckpoint {
+    pub scan_id: String, // The resumed scan keeps this ID
+    pub target: String,
+    pub scan_type: ScanType,
+    pub start_time: SystemTime,
+    pub completed: Vec<(u16, u16)>, // TCP ports already probed, as inclusive ranges
+    pub open_ports: Vec<PortInfo>, // Open among them, before service detection
+}
+
+impl ScanCheckpoint {
+    pub fn completed_ports(&self) -> std::collections::HashSet<u16> {
+        self.completed.iter().flat_map(|&(start, end)| start..=end).collect()
+    }
+}
+
+/// Sorted inclusive ranges covering exactly `ports`
+pub fn port_ranges(ports: &[u16]) -> Vec<(u16, u16)> {
+    let mut sorted = ports.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let mut ranges: Vec<(u16, u16)> = Vec::new();
+    for port in sorted {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == port => *end = port,
+            _ => ranges.push((port, port)),
+        }
+    }
+    ranges
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -93,10 +410,36 @@ pub enum ScanType {
     Quick,      // Top 100 ports
     Standard,   // Top 1000 ports
     Full,       // All 65535 ports
+    KnownServices, // Ports with a well-known service (ProtocolUtils::get_common_ports)
     CustomRange(u16, u16),
     Targeted(Vec<u16>),
 }
 
+impl std::fmt::Display for ScanType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScanType::Quick => write!(f, "Quick"),
+            ScanType::Standard => write!(f, "Standard"),
+            ScanType::Full => write!(f, "Full"),
+            ScanType::KnownServices => write!(f, "Known services"),
+            ScanType::CustomRange(start, end) => write!(f, "Custom range {}-{}", start, end),
+            ScanType::Targeted(ports) => write!(f, "Targeted ({} ports)", ports.len()),
+        }
+    }
+}
+
+impl ScanType {
+    /// Reject a custom range ending before it starts
+    pub fn validate(&self) -> crate::error::Result<()> {
+        match self {
+            ScanType::CustomRange(start, end) if start > end => Err(Error::Validation(format!(
+                "Port range {}-{} ends before it starts", start, end
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ScanProgress {
     pub current_port: u16,
@@ -111,13 +454,217 @@ pub struct ScanProgress {
 pub struct ScanConfig {
     pub timeout: Duration,
     pub max_concurrent_tasks: usize,
+    pub connect_max_concurrent: Option<usize>, // Per-host probes of connect sweeps; None = max_concurrent_tasks
+    pub syn_max_concurrent: Option<usize>, // Likewise for SYN sweeps, which skip the handshake and can go higher
+    pub udp_max_concurrent: Option<usize>, // Likewise for the UDP pass
+    pub rampup_duration: Duration, // Warm-up over which a sweep grows from 1 to max_concurrent_tasks probes; zero = all at once
     pub retry_count: u8,
     pub rate_limit: Option<u32>, // Scans per second
+    pub max_pps: Option<u32>, // Packet-rate ceiling over every probe of the engine, see PacketPacer
     pub enable_service_detection: bool,
     pub enable_banner_grabbing: bool,
     pub enable_os_detection: bool,
     pub enable_traceroute: bool,
+    pub enable_reverse_dns: bool, // Best-effort PTR lookup for the target and hops
+    pub dns_servers: Vec<IpAddr>, // Name servers for target and PTR lookups; empty = the system's
     pub stealth_mode: bool,
+    pub randomize_order: bool,
+    pub seed: Option<u64>, // Fixed seed makes the shuffled order reproducible
+    pub decoys: Vec<Decoy>, // Spoofed SYN sources; must contain Decoy::Me
+    pub adaptive_timeout: bool,
+    pub adaptive_timeout_floor: Duration,
+    pub adaptive_timeout_ceiling: Duration,
+    pub max_retries: u32, // Extra connect attempts for a port that timed out; refused ports are never retried
+    pub exclude: Vec<u16>, // Removed from every port list before dispatch
+    pub probe_allow: Vec<u16>, // Open ports that may get service detection/banner grabbing; empty = all
+    pub probe_deny: Vec<u16>, // Open ports that never do, even when in probe_allow
+    pub stop_on_first_open: bool, // Liveness check: report the first open port and stop
+    pub verify_open: bool, // Probe open ports a second time; those not open again become OpenFiltered, see ScanMetadata::notes
+    pub proxy: Option<ProxyConfig>, // TCP connect scans only; raw SYN/UDP can't be proxied
+    pub version_intensity: u8, // 0 (port guess + passive banner) to 9 (every probe)
+    pub enable_udp: bool, // Also probe the planned ports over UDP, merged into the same result
+    pub enable_sctp: bool, // Likewise with SCTP INIT probes; needs the `sctp` feature and raw sockets
+    pub technique_chain: Vec<ScanTechnique>, // Tried in order; empty derives it from stealth_mode
+    pub global_max_concurrent: usize, // Shared by every host of a scan_many; max_concurrent_tasks is per host
+    pub enrich_concurrency: usize, // Open ports in service detection/banner grabbing at once, across hosts
+    pub discovery_only: bool, // Port + status only: no service, banner, OS or traceroute work
+    pub host_down_threshold: usize, // Consecutive unreachable errors before giving up on the host; 0 = never
+    #[serde(default)]
+    pub geoip: crate::config::GeoIpSettings, // Offline ASN/country annotation of the target
+    pub source_addr: Option<IpAddr>, // Local address connect probes originate from; None = OS choice
+    pub source_port: Option<u16>, // Fixed local port, e.g. for egress rules keyed on source port
+    pub banner_buffer_size: usize, // Bytes read from a service per banner grab
+    pub banner_max_length: usize, // Characters kept in PortInfo::banner; the full capture goes to banner_raw
+    pub max_response_bytes: usize, // Hard cap on one service response, above banner_buffer_size
+    pub probe_identity: String, // HTTP User-Agent and SSH client banner of service probes
+    pub http_keep_alive: bool, // HTTP probes share one client, reusing connections per host; see ScanEngine::http_client
+    pub incremental: bool, // Skip ports a recent scan of the target saw not open; see ScanEngine::with_baselines
+    pub incremental_max_age: Duration, // How recent that scan's probes must be
+    pub auto_tune_concurrency: bool, // Cap both concurrency limits to a share of the open-file limit
+    pub record_all_statuses: bool, // Keep closed/filtered PortInfos in ScanResult::all_ports (memory grows with the port count)
+    pub host_discovery: bool, // Ping the host first (TCP 80/443/22, ICMP echo) and skip the port scan if it doesn't answer
+    pub tcp_nodelay: bool, // TCP_NODELAY on connect probes
+    pub tcp_linger_reset: bool, // SO_LINGER(0) on connect probes: close with a RST, no TIME_WAIT; see PortScanner::with_socket_options
+    pub checkpoint_interval: usize, // Completed probes between checkpoints sent to ScanEngine::with_checkpoints; 0 = never
+    pub dangerous_ports: Vec<u16>, // Ports that can upset the device behind them; probing one needs allow_dangerous_ports
+    pub allow_dangerous_ports: bool, // Probe dangerous_ports anyway, logged as a security event
+    pub allow_public_targets: bool, // Off = safe mode: refuse targets resolving outside private/loopback ranges
+    pub scan_window: Option<crate::config::ScanWindow>, // Scans only start inside it; see ScanEngine::with_clock
+    pub allow_outside_window: bool, // Start outside scan_window anyway, logged as a security event
+}
+
+/// Default `--max-age` of incremental scans
+pub const DEFAULT_INCREMENTAL_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Minimum per-host concurrency in discovery-only mode
+pub const DISCOVERY_MAX_CONCURRENT: usize = 1000;
+
+/// Open ports enriched at once unless configured otherwise; each can take
+/// several connections and a TLS handshake
+pub const DEFAULT_ENRICH_CONCURRENCY: usize = 16;
+
+/// Ports whose devices are known to misbehave when probed: raw printing
+/// (a connect can print garbage pages) and industrial control protocols
+/// (PLCs and RTUs that hang or fault on unexpected traffic)
+pub const DEFAULT_DANGEROUS_PORTS: &[u16] = &[
+    102,   // Siemens S7
+    502,   // Modbus/TCP
+    515,   // LPD printing
+    1911,  // Niagara Fox
+    2404,  // IEC 60870-5-104
+    4840,  // OPC UA
+    9100,  // JetDirect raw printing
+    9600,  // Omron FINS
+    18245, // GE SRTP
+    20000, // DNP3
+    44818, // EtherNet/IP
+    47808, // BACnet
+];
+
+/// TCP probing method, selectable through `ScanConfig::technique_chain`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanTechnique {
+    Syn,     // Raw SYN probes; needs root / CAP_NET_RAW
+    Connect, // Full TCP handshake; always available
+}
+
+impl std::fmt::Display for ScanTechnique {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScanTechnique::Syn => write!(f, "syn"),
+            ScanTechnique::Connect => write!(f, "connect"),
+        }
+    }
+}
+
+/// One source of decoy SYN probes, see `ScanConfig::decoys`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Decoy {
+    Me,           // Where the real source address is interleaved (`ME` on the command line)
+    Addr(IpAddr), // A spoofed source; never the unspecified address
+}
+
+impl std::str::FromStr for Decoy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("me") {
+            return Ok(Decoy::Me);
+        }
+
+        let address: IpAddr = s.parse().map_err(|_| format!("Invalid decoy address: {}", s))?;
+        if address.is_unspecified() {
+            return Err(format!("Invalid decoy address: {} (use ME for the real source)", s));
+        }
+        Ok(Decoy::Addr(address))
+    }
+}
+
+impl std::fmt::Display for Decoy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Decoy::Me => write!(f, "ME"),
+            Decoy::Addr(address) => write!(f, "{}", address),
+        }
+    }
+}
+
+impl std::str::FromStr for ScanTechnique {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "syn" => Ok(ScanTechnique::Syn),
+            "connect" => Ok(ScanTechnique::Connect),
+            other => Err(format!("Unknown scan technique '{}' (expected syn or connect)", other)),
+        }
+    }
+}
+
+/// SOCKS5 jump host for TCP connect scans
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProxyConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl ProxyConfig {
+    pub fn new(host: String, port: u16) -> Self {
+        Self {
+            host,
+            port,
+            username: None,
+            password: None,
+        }
+    }
+
+    pub fn with_auth(mut self, username: String, password: String) -> Self {
+        self.username = Some(username);
+        self.password = Some(password);
+        self
+    }
+
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+/// Parses `socks5://[user:pass@]host:port`
+impl std::str::FromStr for ProxyConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s.strip_prefix("socks5://")
+            .ok_or_else(|| "Proxy must be in format 'socks5://[user:pass@]host:port'".to_string())?;
+
+        let (auth, endpoint) = match rest.rsplit_once('@') {
+            Some((auth, endpoint)) => (Some(auth), endpoint),
+            None => (None, rest),
+        };
+
+        let (host, port) = endpoint.rsplit_once(':')
+            .ok_or_else(|| "Proxy address must include a port".to_string())?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() {
+            return Err("Proxy host is empty".to_string());
+        }
+        let port = port.parse::<u16>()
+            .map_err(|_| format!("Invalid proxy port: {}", port))?;
+
+        let proxy = ProxyConfig::new(host.to_string(), port);
+        match auth {
+            Some(auth) => {
+                let (username, password) = auth.split_once(':')
+                    .ok_or_else(|| "Proxy credentials must be 'user:pass'".to_string())?;
+                Ok(proxy.with_auth(username.to_string(), password.to_string()))
+            }
+            None => Ok(proxy),
+        }
+    }
 }
 
 impl Default for ScanConfig {
@@ -125,13 +672,178 @@ impl Default for ScanConfig {
         Self {
             timeout: Duration::from_millis(1000),
             max_concurrent_tasks: 200,
+            connect_max_concurrent: None,
+            syn_max_concurrent: None,
+            udp_max_concurrent: None,
+            rampup_duration: Duration::ZERO,
             retry_count: 1,
             rate_limit: None,
+            max_pps: None,
             enable_service_detection: true,
             enable_banner_grabbing: true,
             enable_os_detection: false,
             enable_traceroute: false,
+            enable_reverse_dns: true,
+            dns_servers: Vec::new(),
             stealth_mode: false,
+            randomize_order: false,
+            seed: None,
+            decoys: Vec::new(),
+            adaptive_timeout: false,
+            adaptive_timeout_floor: Duration::from_millis(50),
+            adaptive_timeout_ceiling: Duration::from_millis(5000),
+            max_retries: 0,
+            exclude: Vec::new(),
+            probe_allow: Vec::new(),
+            probe_deny: Vec::new(),
+            stop_on_first_open: false,
+            verify_open: false,
+            proxy: None,
+            version_intensity: crate::network::service_detector::DEFAULT_VERSION_INTENSITY,
+            enable_udp: false,
+            enable_sctp: false,
+            technique_chain: Vec::new(),
+            global_max_concurrent: 1000,
+            enrich_concurrency: DEFAULT_ENRICH_CONCURRENCY,
+            discovery_only: false,
+            host_down_threshold: 10,
+            geoip: crate::config::GeoIpSettings::default(),
+            source_addr: None,
+            source_port: None,
+            banner_buffer_size: crate::network::banner_grabber::DEFAULT_BANNER_BUFFER_SIZE,
+            banner_max_length: crate::network::banner_grabber::DEFAULT_BANNER_MAX_LENGTH,
+            max_response_bytes: crate::network::banner_grabber::DEFAULT_MAX_RESPONSE_BYTES,
+            probe_identity: crate::network::banner_grabber::DEFAULT_PROBE_IDENTITY.to_string(),
+            http_keep_alive: true,
+            incremental: false,
+            incremental_max_age: DEFAULT_INCREMENTAL_MAX_AGE,
+            auto_tune_concurrency: true,
+            record_all_statuses: false,
+            host_discovery: true,
+            tcp_nodelay: true,
+            tcp_linger_reset: true,
+            checkpoint_interval: 1000,
+            dangerous_ports: DEFAULT_DANGEROUS_PORTS.to_vec(),
+            allow_dangerous_ports: false,
+            allow_public_targets: false,
+            scan_window: None,
+            allow_outside_window: false,
+        }
+    }
+}
+
+impl ScanConfig {
+    /// The config file's scanner and security settings alone, for services
+    /// that start scans without CLI flags on top
+    pub fn from_settings(settings: &crate::config::Settings) -> Self {
+        let scanner = &settings.scanner;
+        Self {
+            timeout: Duration::from_millis(scanner.default_timeout_ms),
+            max_concurrent_tasks: scanner.max_threads,
+            connect_max_concurrent: scanner.connect_max_threads,
+            syn_max_concurrent: scanner.syn_max_threads,
+            udp_max_concurrent: scanner.udp_max_threads,
+            rampup_duration: Duration::from_secs(scanner.rampup_secs),
+            rate_limit: scanner.rate_limit,
+            max_pps: scanner.max_pps,
+            enable_service_detection: scanner.enable_service_detection,
+            enable_banner_grabbing: scanner.enable_banner_grabbing,
+            enable_os_detection: scanner.enable_os_detection,
+            enable_traceroute: scanner.enable_traceroute,
+            stealth_mode: scanner.stealth_mode,
+            dns_servers: scanner.dns_servers.clone(),
+            exclude: scanner.exclude_ports.clone(),
+            probe_allow: scanner.probe_allow_ports.clone(),
+            probe_deny: scanner.probe_deny_ports.clone(),
+            verify_open: scanner.verify_open_ports,
+            dangerous_ports: settings.security.dangerous_ports.clone(),
+            allow_public_targets: settings.security.allow_public_targets,
+            scan_window: settings.security.scan_window.clone(),
+            global_max_concurrent: scanner.global_max_concurrent,
+            banner_buffer_size: scanner.banner_buffer_size,
+            banner_max_length: scanner.banner_max_length,
+            max_response_bytes: scanner.max_response_bytes,
+            enrich_concurrency: scanner.enrich_concurrency,
+            probe_identity: scanner.probe_identity.clone(),
+            http_keep_alive: scanner.http_keep_alive,
+            auto_tune_concurrency: scanner.auto_tune_concurrency,
+            geoip: settings.geoip.clone(),
+            record_all_statuses: scanner.record_all_statuses,
+            host_discovery: scanner.host_discovery,
+            tcp_nodelay: scanner.tcp_nodelay,
+            tcp_linger_reset: scanner.tcp_linger_reset,
+            checkpoint_interval: scanner.checkpoint_interval,
+            ..Self::default()
+        }
+    }
+
+    /// Fast sweep for open ports; enrich the results later with `ScanEngine::enrich`
+    pub fn with_discovery_only(mut self) -> Self {
+        self.discovery_only = true;
+        self.max_concurrent_tasks = self.max_concurrent_tasks.max(DISCOVERY_MAX_CONCURRENT);
+        self
+    }
+
+    /// Reject settings the engine can't run with: a zero concurrency limit
+    /// would never start a probe, and one past the semaphore maximum would
+    /// panic
+    pub fn validate(&self) -> crate::error::Result<()> {
+        let limits = [
+            ("max_concurrent_tasks", Some(self.max_concurrent_tasks)),
+            ("connect_max_concurrent", self.connect_max_concurrent),
+            ("syn_max_concurrent", self.syn_max_concurrent),
+            ("udp_max_concurrent", self.udp_max_concurrent),
+            ("global_max_concurrent", Some(self.global_max_concurrent)),
+            ("enrich_concurrency", Some(self.enrich_concurrency)),
+        ];
+        for (name, limit) in limits {
+            match limit {
+                Some(0) => return Err(Error::Validation(format!("{} must be greater than 0", name))),
+                Some(limit) if limit > tokio::sync::Semaphore::MAX_PERMITS => {
+                    return Err(Error::Validation(format!(
+                        "{} of {} exceeds the maximum of {}", name, limit, tokio::sync::Semaphore::MAX_PERMITS
+                    )));
+                }
+                _ => {}
+            }
+        }
+        if let Some(window) = &self.scan_window {
+            window.validate()?;
+        }
+
+        Ok(())
+    }
+
+    /// Per-host probes in flight during a TCP sweep using `technique`
+    pub fn concurrency_for(&self, technique: ScanTechnique) -> usize {
+        let limit = match technique {
+            ScanTechnique::Connect => self.connect_max_concurrent,
+            ScanTechnique::Syn => self.syn_max_concurrent,
+        };
+        limit.unwrap_or(self.max_concurrent_tasks)
+    }
+
+    /// Per-host probes in flight during the UDP pass
+    pub fn udp_concurrency(&self) -> usize {
+        self.udp_max_concurrent.unwrap_or(self.max_concurrent_tasks)
+    }
+
+    /// Whether an open port may get service detection and banner grabbing;
+    /// the port scan itself ignores `probe_allow` and `probe_deny`
+    pub fn may_probe(&self, port: u16) -> bool {
+        !self.probe_deny.contains(&port)
+            && (self.probe_allow.is_empty() || self.probe_allow.contains(&port))
+    }
+
+    /// `technique_chain`, or the historical behavior when unset: SYN falling
+    /// back to connect in stealth mode, connect otherwise
+    pub fn effective_technique_chain(&self) -> Vec<ScanTechnique> {
+        if !self.technique_chain.is_empty() {
+            self.technique_chain.clone()
+        } else if self.stealth_mode {
+            vec![ScanTechnique::Syn, ScanTechnique::Connect]
+        } else {
+            vec![ScanTechnique::Connect]
         }
     }
 }
@@ -148,19 +860,190 @@ impl ScanResult {
             open_ports: Vec::new(),
             statistics: ScanStatistics::default(),
             metadata: ScanMetadata::default(),
+            partial: None,
+            all_ports: Vec::new(),
+        }
+    }
+
+    /// Stored status of the finished scan: `Down` when host discovery got
+    /// no answer, `Cancelled` when it was stopped with what it had found,
+    /// `Partial` when the host went down mid-scan
+    pub fn status(&self) -> crate::storage::models::ScanStatus {
+        use crate::storage::models::ScanStatus;
+
+        if self.is_host_down() {
+            ScanStatus::Down
+        } else if self.statistics.cancelled {
+            ScanStatus::Cancelled
+        } else if self.partial.is_some() {
+            ScanStatus::Partial
+        } else {
+            ScanStatus::Completed
         }
     }
 
+    /// Host discovery got no answer, so no port was probed
+    pub fn is_host_down(&self) -> bool {
+        self.metadata.host_up == Some(false)
+    }
+
     pub fn duration(&self) -> Duration {
         self.end_time.duration_since(self.start_time)
             .unwrap_or(Duration::from_secs(0))
     }
 
+    pub fn tcp_ports(&self) -> impl Iterator<Item = &PortInfo> {
+        self.open_ports.iter().filter(|p| p.protocol == Protocol::Tcp)
+    }
+
+    pub fn udp_ports(&self) -> impl Iterator<Item = &PortInfo> {
+        self.open_ports.iter().filter(|p| p.protocol == Protocol::Udp)
+    }
+
+    pub fn sctp_ports(&self) -> impl Iterator<Item = &PortInfo> {
+        self.open_ports.iter().filter(|p| p.protocol == Protocol::Sctp)
+    }
+
+    /// Ports this scan found not open and probed (itself or through its own
+    /// baseline) no earlier than `stale_before`. An incremental scan based on
+    /// this one may carry them over instead of probing them again.
+    pub fn vouched_ports(&self, stale_before: SystemTime) -> std::collections::HashSet<u16> {
+        let mut ranges = Vec::new();
+        if self.start_time >= stale_before {
+            ranges.extend(self.metadata.coverage.iter().copied());
+        }
+        if let Some(incremental) = &self.metadata.incremental {
+            if incremental.carried_over_since >= stale_before {
+                ranges.extend(incremental.carried_over.iter().copied());
+            }
+        }
+
+        let open: std::collections::HashSet<u16> = self.open_ports.iter().map(|p| p.port).collect();
+        ranges.into_iter()
+            .flat_map(|(start, end)| start..=end)
+            .filter(|port| !open.contains(port))
+            .collect()
+    }
+
     pub fn add_open_port(&mut self, port_info: PortInfo) {
         self.open_ports.push(port_info);
         self.open_ports.sort_by_key(|p| p.port);
     }
 
+    /// A copy with `open_ports` in `order`, for displaying or exporting;
+    /// `self` keeps the port order it is stored in
+    pub fn sorted_by(&self, order: PortOrder) -> ScanResult {
+        let mut sorted = self.clone();
+        order.sort(&mut sorted.open_ports);
+        sorted
+    }
+
+    /// Every recorded port, open or not, ordered by port. Without
+    /// `record_all_statuses` this is just the open ports.
+    pub fn port_table(&self) -> Vec<&PortInfo> {
+        let mut table: Vec<&PortInfo> = self.open_ports.iter().chain(&self.all_ports).collect();
+        table.sort_by_key(|p| p.port);
+        table
+    }
+
+    /// Combine two scans of the same host into a new result with a fresh ID.
+    ///
+    /// Open ports are unioned by (port, protocol). When both scans report the
+    /// same port, the entry with a detected service wins; if both detected
+    /// one, the higher confidence wins and ties keep `self`. Banner, TLS and
+    /// response time missing from the winner are filled in from the loser.
+    /// The time window spans both scans and the scan type of the larger scan
+    /// is kept so statistics are recomputed against the wider port set.
+    pub fn merge(&self, other: &ScanResult) -> crate::error::Result<ScanResult> {
+        if self.target_ip != other.target_ip {
+            return Err(Error::Validation(format!(
+                "Cannot merge scans of different hosts: {} and {}",
+                self.target_ip, other.target_ip
+            )));
+        }
+
+        let wider = if other.statistics.total_ports > self.statistics.total_ports { other } else { self };
+        let mut merged = ScanResult::new(self.target.clone(), self.target_ip, wider.scan_type.clone());
+        merged.start_time = self.start_time.min(other.start_time);
+        merged.end_time = self.end_time.max(other.end_time);
+        merged.metadata = self.metadata.clone();
+        merged.metadata.hostname = merged.metadata.hostname.or_else(|| other.metadata.hostname.clone());
+        merged.metadata.os_detection = merged.metadata.os_detection.or_else(|| other.metadata.os_detection.clone());
+        merged.metadata.traceroute = merged.metadata.traceroute.or_else(|| other.metadata.traceroute.clone());
+        if merged.metadata.asn.is_none() && merged.metadata.country_code.is_none() {
+            merged.metadata.asn = other.metadata.asn;
+            merged.metadata.as_org = other.metadata.as_org.clone();
+            merged.metadata.country_code = other.metadata.country_code.clone();
+        }
+
+        for port_info in self.open_ports.iter().chain(&other.open_ports) {
+            let existing = merged.open_ports.iter_mut()
+                .find(|p| p.port == port_info.port && p.protocol == port_info.protocol);
+            match existing {
+                Some(existing) => Self::merge_port(existing, port_info),
+                None => merged.open_ports.push(port_info.clone()),
+            }
+        }
+        merged.open_ports.sort_by_key(|p| p.port);
+
+        // A port either scan found open is open in the merge
+        for port_info in self.all_ports.iter().chain(&other.all_ports) {
+            let known = merged.open_ports.iter().chain(&merged.all_ports)
+                .any(|p| p.port == port_info.port && p.protocol == port_info.protocol);
+            if !known {
+                merged.all_ports.push(port_info.clone());
+            }
+        }
+        merged.all_ports.sort_by_key(|p| p.port);
+
+        merged.statistics.excluded_ports = wider.statistics.excluded_ports;
+        merged.statistics.udp_ports_scanned = self.statistics.udp_ports_scanned
+            .max(other.statistics.udp_ports_scanned);
+        merged.statistics.sctp_ports_scanned = self.statistics.sctp_ports_scanned
+            .max(other.statistics.sctp_ports_scanned);
+        merged.statistics.stopped_early = self.statistics.stopped_early && other.statistics.stopped_early;
+        merged.update_statistics();
+
+        Ok(merged)
+    }
+
+    fn merge_port(existing: &mut PortInfo, incoming: &PortInfo) {
+        let incoming_wins = match (&existing.service, &incoming.service) {
+            (None, Some(_)) => true,
+            (Some(current), Some(candidate)) => candidate.confidence > current.confidence,
+            _ => false,
+        };
+
+        let loser = if incoming_wins {
+            std::mem::replace(existing, incoming.clone())
+        } else {
+            incoming.clone()
+        };
+
+        if existing.banner.is_none() {
+            existing.banner = loser.banner;
+            existing.banner_raw = loser.banner_raw;
+        }
+        existing.tls = existing.tls.take().or(loser.tls);
+        existing.response_time = existing.response_time.or(loser.response_time);
+    }
+
+    /// The listing view `ScanRepository::get_scan_summaries` returns once
+    /// this result is saved; `created_at` is the scan's end time
+    pub fn to_summary(&self) -> crate::storage::models::ScanSummary {
+        use crate::storage::repository::scan_type_to_string;
+
+        crate::storage::models::ScanSummary {
+            id: self.id.clone(),
+            target: self.target.clone(),
+            scan_type: scan_type_to_string(&self.scan_type),
+            open_count: self.open_ports.len() as i32,
+            duration_ms: self.duration().as_millis() as i64,
+            created_at: self.end_time.into(),
+            status: self.status().to_string(),
+        }
+    }
+
     pub fn finalize(&mut self) {
         self.end_time = SystemTime::now();
         self.update_statistics();
@@ -171,12 +1054,42 @@ impl ScanResult {
             ScanType::Quick => 100,
             ScanType::Standard => 1000,
             ScanType::Full => 65535,
-            ScanType::CustomRange(start, end) => (end - start + 1),
+            ScanType::KnownServices => crate::network::protocols::ProtocolUtils::known_service_ports().len() as u16,
+            // 0-65535 holds one port more than u16 counts
+            ScanType::CustomRange(start, end) => u16::try_from(u32::from(*end).saturating_sub(u32::from(*start)) + 1).unwrap_or(u16::MAX),
             ScanType::Targeted(ports) => ports.len() as u16,
         };
 
+        let tcp_total = if self.is_host_down() {
+            0
+        } else {
+            total.saturating_sub(self.statistics.excluded_ports)
+        };
+        let udp_total = self.statistics.udp_ports_scanned;
+        let sctp_total = self.statistics.sctp_ports_scanned;
+        let total = tcp_total.saturating_add(udp_total).saturating_add(sctp_total);
         let open = self.open_ports.len() as u16;
-        let closed = total - open; // Simplified
+        let closed = total.saturating_sub(open); // Simplified
+
+        let mut per_protocol = vec![ProtocolStatistics {
+            protocol: Protocol::Tcp,
+            total_ports: tcp_total,
+            open_ports: self.tcp_ports().count() as u16,
+        }];
+        if udp_total > 0 {
+            per_protocol.push(ProtocolStatistics {
+                protocol: Protocol::Udp,
+                total_ports: udp_total,
+                open_ports: self.udp_ports().count() as u16,
+            });
+        }
+        if sctp_total > 0 {
+            per_protocol.push(ProtocolStatistics {
+                protocol: Protocol::Sctp,
+                total_ports: sctp_total,
+                open_ports: self.sctp_ports().count() as u16,
+            });
+        }
 
         self.statistics = ScanStatistics {
             total_ports: total,
@@ -187,18 +1100,53 @@ impl ScanResult {
             packets_sent: total as u64,
             packets_received: open as u64,
             success_rate: if total > 0 { (open as f64 / total as f64) * 100.0 } else { 0.0 },
+            effective_timeout: self.statistics.effective_timeout,
+            excluded_ports: self.statistics.excluded_ports,
+            // Every port that answered, the RST of a closed one included; a
+            // filtered port's time is only the timeout running out
+            latency: LatencyStats::from_samples(
+                self.open_ports.iter()
+                    .chain(&self.all_ports)
+                    .filter(|p| matches!(p.status, PortStatus::Open | PortStatus::Closed))
+                    .filter_map(|p| p.response_time)
+                    .collect()
+            ),
+            stopped_early: self.statistics.stopped_early,
+            cancelled: self.statistics.cancelled,
+            udp_ports_scanned: udp_total,
+            sctp_ports_scanned: sctp_total,
+            per_protocol,
+            effective_concurrency: self.statistics.effective_concurrency,
+            probe_packets: self.statistics.probe_packets,
+            average_pps: average_pps(self.statistics.probe_packets, self.duration()),
         };
     }
 }
 
+fn average_pps(packets: u64, duration: Duration) -> f64 {
+    if duration.is_zero() {
+        0.0
+    } else {
+        packets as f64 / duration.as_secs_f64()
+    }
+}
+
 impl Default for ScanMetadata {
     fn default() -> Self {
         Self {
             scanner_version: env!("CARGO_PKG_VERSION").to_string(),
-            arguments: std::env::args().collect(),
+            arguments: None,
             hostname: None,
             os_detection: None,
             traceroute: None,
+            technique: None,
+            asn: None,
+            as_org: None,
+            country_code: None,
+            coverage: Vec::new(),
+            incremental: None,
+            host_up: None,
+            notes: Vec::new(),
         }
     }
 }
@@ -214,6 +1162,17 @@ impl Default for ScanStatistics {
             packets_sent: 0,
             packets_received: 0,
             success_rate: 0.0,
+            effective_timeout: None,
+            excluded_ports: 0,
+            latency: None,
+            stopped_early: false,
+            cancelled: false,
+            udp_ports_scanned: 0,
+            sctp_ports_scanned: 0,
+            per_protocol: Vec::new(),
+            effective_concurrency: 0,
+            probe_packets: 0,
+            average_pps: 0.0,
         }
     }
 }
@@ -241,3 +1200,231 @@ impl CommonPorts {
         (1..=65535).collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_percentiles() {
+        // 1..=100 ms, shuffled so the sort is exercised
+        let mut samples: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+        samples.reverse();
+
+        let stats = LatencyStats::from_samples(samples).unwrap();
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.median, Duration::from_millis(50));
+        assert_eq!(stats.p90, Duration::from_millis(90));
+        assert_eq!(stats.p99, Duration::from_millis(99));
+        assert_eq!(stats.max, Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_latency_empty() {
+        assert_eq!(LatencyStats::from_samples(Vec::new()), None);
+    }
+
+    #[test]
+    fn test_latency_counts_closed_ports_but_not_timeouts() {
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        let port = |port: u16, status: PortStatus, ms: u64| PortInfo {
+            status,
+            response_time: Some(Duration::from_millis(ms)),
+            ..open_port(port, Protocol::Tcp)
+        };
+        scan.add_open_port(port(22, PortStatus::Open, 10));
+        scan.all_ports.push(port(23, PortStatus::Closed, 2));
+        scan.all_ports.push(port(25, PortStatus::Filtered, 1000));
+        scan.finalize();
+
+        let latency = scan.statistics.latency.unwrap();
+        assert_eq!(latency.min, Duration::from_millis(2));
+        assert_eq!(latency.max, Duration::from_millis(10));
+    }
+
+    fn open_port(port: u16, protocol: Protocol) -> PortInfo {
+        PortInfo {
+            port,
+            status: PortStatus::Open,
+            service: None,
+            banner: None,
+            banner_raw: None,
+            response_time: None,
+            protocol,
+            tls: None,
+        }
+    }
+
+    fn service(name: &str, confidence: u8) -> ServiceInfo {
+        ServiceInfo {
+            name: name.to_string(),
+            version: None,
+            product: None,
+            extra_info: None,
+            confidence,
+            tunnel: None,
+            alpn: None,
+        }
+    }
+
+    #[test]
+    fn test_merge_conflict_policy() {
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let mut connect = ScanResult::new("host".to_string(), ip, ScanType::Targeted(vec![22, 80, 443]));
+        let mut syn = ScanResult::new("host".to_string(), ip, ScanType::Standard);
+        syn.start_time = connect.start_time - Duration::from_secs(60);
+        connect.end_time = syn.end_time + Duration::from_secs(60);
+
+        // 22: only the connect scan detected a service, the SYN scan has the banner
+        let mut ssh = open_port(22, Protocol::Tcp);
+        ssh.service = Some(service("ssh", 80));
+        connect.add_open_port(ssh);
+        let mut ssh_banner = open_port(22, Protocol::Tcp);
+        ssh_banner.banner = Some("SSH-2.0-OpenSSH_9.6".to_string());
+        syn.add_open_port(ssh_banner);
+
+        // 80: both detected, higher confidence wins
+        let mut guessed = open_port(80, Protocol::Tcp);
+        guessed.service = Some(service("http", 60));
+        connect.add_open_port(guessed);
+        let mut confirmed = open_port(80, Protocol::Tcp);
+        confirmed.service = Some(service("http-proxy", 90));
+        syn.add_open_port(confirmed);
+
+        // 443: equal confidence keeps the receiver's detection
+        let mut https = open_port(443, Protocol::Tcp);
+        https.service = Some(service("https", 80));
+        connect.add_open_port(https);
+        let mut other_https = open_port(443, Protocol::Tcp);
+        other_https.service = Some(service("http", 80));
+        syn.add_open_port(other_https);
+
+        // Same port number over UDP is a separate entry
+        syn.add_open_port(open_port(443, Protocol::Udp));
+
+        let merged = connect.merge(&syn).unwrap();
+
+        assert_ne!(merged.id, connect.id);
+        assert_eq!(merged.open_ports.len(), 4);
+        assert_eq!(merged.start_time, syn.start_time);
+        assert_eq!(merged.end_time, connect.end_time);
+
+        let tcp = |port: u16| merged.tcp_ports().find(|p| p.port == port).unwrap();
+        assert_eq!(tcp(22).service.as_ref().unwrap().name, "ssh");
+        assert_eq!(tcp(22).banner.as_deref(), Some("SSH-2.0-OpenSSH_9.6"));
+        assert_eq!(tcp(80).service.as_ref().unwrap().name, "http-proxy");
+        assert_eq!(tcp(443).service.as_ref().unwrap().name, "https");
+        assert_eq!(merged.udp_ports().count(), 1);
+        assert_eq!(merged.statistics.open_ports, 4);
+    }
+
+    #[test]
+    fn test_services_below_the_threshold_are_reported_as_guesses() {
+        // The detector's port-number guess and banner match, each exactly at
+        // and one point below a threshold
+        let guess = service("ssh", 60);
+        assert!(guess.is_confirmed(60));
+        assert_eq!(guess.reported_name(60), "ssh");
+        assert_eq!(guess.certainty(60), "confirmed");
+        assert!(!guess.is_confirmed(61));
+        assert_eq!(guess.reported_name(61), "unknown (guessed: ssh)");
+        assert_eq!(guess.certainty(61), "guessed");
+
+        let banner_match = service("http", 90);
+        assert!(banner_match.is_confirmed(90));
+        assert_eq!(banner_match.reported_name(91), "unknown (guessed: http)");
+
+        // 0, the default, confirms everything; 100 only certainties
+        assert!(service("telnet", 0).is_confirmed(0));
+        assert!(!service("ftp", 99).is_confirmed(100));
+        assert!(service("ftp", 100).is_confirmed(100));
+    }
+
+    #[test]
+    fn test_merge_rejects_different_hosts() {
+        let a = ScanResult::new("a".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        let b = ScanResult::new("b".to_string(), "10.0.0.2".parse().unwrap(), ScanType::Quick);
+        assert!(a.merge(&b).is_err());
+    }
+
+    #[test]
+    fn test_mixed_protocol_grouping() {
+        let mut result = ScanResult::new(
+            "127.0.0.1".to_string(),
+            "127.0.0.1".parse().unwrap(),
+            ScanType::Targeted(vec![22, 53, 80, 123]),
+        );
+        result.add_open_port(open_port(80, Protocol::Tcp));
+        result.add_open_port(open_port(53, Protocol::Udp));
+        result.add_open_port(open_port(22, Protocol::Tcp));
+        result.statistics.udp_ports_scanned = 4;
+        result.finalize();
+
+        let tcp: Vec<u16> = result.tcp_ports().map(|p| p.port).collect();
+        let udp: Vec<u16> = result.udp_ports().map(|p| p.port).collect();
+        assert_eq!(tcp, vec![22, 80]);
+        assert_eq!(udp, vec![53]);
+
+        assert_eq!(result.statistics.total_ports, 8);
+        assert_eq!(result.statistics.open_ports, 3);
+        assert_eq!(
+            result.statistics.per_protocol,
+            vec![
+                ProtocolStatistics { protocol: Protocol::Tcp, total_ports: 4, open_ports: 2 },
+                ProtocolStatistics { protocol: Protocol::Udp, total_ports: 4, open_ports: 1 },
+            ]
+        );
+    }
+
+    fn audit_scan() -> ScanResult {
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Standard);
+        for (port, name, millis) in [
+            (9999, None, Some(10)),
+            (443, Some("https"), None),
+            (3306, Some("mysql"), Some(2)),
+            (80, Some("http"), Some(40)),
+            (22, Some("ssh"), Some(5)),
+        ] {
+            let mut info = open_port(port, Protocol::Tcp);
+            info.service = name.map(|name| service(name, 90));
+            info.response_time = millis.map(Duration::from_millis);
+            scan.add_open_port(info);
+        }
+        scan
+    }
+
+    fn ports(scan: &ScanResult) -> Vec<u16> {
+        scan.open_ports.iter().map(|p| p.port).collect()
+    }
+
+    #[test]
+    fn test_sorted_by_port() {
+        let scan = audit_scan();
+        assert_eq!(ports(&scan.sorted_by(PortOrder::Port)), vec![22, 80, 443, 3306, 9999]);
+    }
+
+    #[test]
+    fn test_sorted_by_service_groups_web_then_database_then_remote_access() {
+        let scan = audit_scan();
+        let sorted = scan.sorted_by(PortOrder::Service);
+        assert_eq!(ports(&sorted), vec![80, 443, 3306, 22, 9999]);
+
+        let groups: Vec<ServiceGroup> = sorted.open_ports.iter().map(ServiceGroup::of).collect();
+        assert_eq!(groups, vec![
+            ServiceGroup::Web,
+            ServiceGroup::Web,
+            ServiceGroup::Database,
+            ServiceGroup::RemoteAccess,
+            ServiceGroup::Other,
+        ]);
+
+        // The scan itself keeps its stored order
+        assert_eq!(ports(&scan), vec![22, 80, 443, 3306, 9999]);
+    }
+
+    #[test]
+    fn test_sorted_by_response_time_puts_untimed_ports_last() {
+        let scan = audit_scan();
+        assert_eq!(ports(&scan.sorted_by(PortOrder::ResponseTime)), vec![3306, 22, 9999, 80, 443]);
+    }
+}
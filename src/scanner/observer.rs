@@ -0,0 +1,22 @@
+//! Callbacks for library embedders that act on scan results (push to an
+//! asset inventory, trigger a follow-up) without forking the engine.
+
+use super::{PortInfo, ScanResult};
+use async_trait::async_trait;
+
+/// Registered with `ScanEngine::with_observer`. The callbacks default to
+/// doing nothing; they are awaited inline, so slow work belongs in a task.
+#[async_trait]
+pub trait ScanObserver: Send + Sync {
+    /// Once per scan, after the target resolved and before any probe. The
+    /// result has its final ID (a resumed scan's, too) but no ports yet.
+    async fn on_scan_start(&self, _result: &ScanResult) {}
+
+    /// Once per open port (TCP or UDP) as it is added to the result, after
+    /// service detection
+    async fn on_port_open(&self, _port: &PortInfo) {}
+
+    /// Once per scan, with the finalized result. Also fires for a host that
+    /// failed discovery, whose result has no ports.
+    async fn on_scan_complete(&self, _result: &ScanResult) {}
+}
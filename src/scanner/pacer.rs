@@ -0,0 +1,49 @@
+//! Packets-per-second ceiling on probe dispatch. Independent of concurrency:
+//! many probes may be in flight, but new ones only start once the link has
+//! room for their packets.
+
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Estimated packets of a TCP connect probe: SYN, SYN-ACK, ACK and the
+/// closing RST (a closed port only answers the SYN with a RST)
+pub const CONNECT_PROBE_PACKETS: u32 = 4;
+
+/// SYN out, SYN-ACK or RST back, and the kernel's RST to a SYN-ACK
+pub const SYN_PROBE_PACKETS: u32 = 3;
+
+/// Datagram out, at most one answer back
+pub const UDP_PROBE_PACKETS: u32 = 2;
+
+/// INIT out, INIT-ACK or ABORT back, and our ABORT to an INIT-ACK
+pub const SCTP_PROBE_PACKETS: u32 = 3;
+
+/// Spaces probes so that their packets stay under `max_pps`. Shared by every
+/// scan of an engine, so the cap holds for the uplink, not per host.
+pub struct PacketPacer {
+    per_packet: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl PacketPacer {
+    pub fn new(max_pps: u32) -> Self {
+        Self {
+            per_packet: Duration::from_secs(1) / max_pps.max(1),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait for room to send `packets` packets. Slots are reserved in call
+    /// order, so concurrent callers are spread out rather than bunched.
+    pub async fn wait(&self, packets: u32) {
+        let start = {
+            let mut next_slot = self.next_slot.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            // Idle time doesn't bank up into a burst
+            let start = (*next_slot).max(Instant::now());
+            *next_slot = start + self.per_packet * packets;
+            start
+        };
+        tokio::time::sleep_until(start).await;
+    }
+}
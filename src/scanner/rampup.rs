@@ -0,0 +1,65 @@
+//! Gradual start for a sweep's concurrency. Hundreds of connects in the
+//! first millisecond look like a SYN flood to the target and to firewalls
+//! on the way; growing from one probe spreads them over a warm-up window.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio::time::Instant;
+
+/// Per-sweep permits that grow linearly from 1 to `max` over `warm_up`,
+/// then hold steady. With a zero `warm_up` all of them are there at once.
+pub struct RampUp {
+    permits: Semaphore,
+    granted: AtomicUsize,
+    max: usize,
+    start: Instant,
+    warm_up: Duration,
+}
+
+impl RampUp {
+    pub fn new(max: usize, warm_up: Duration) -> Self {
+        let max = max.max(1);
+        let initial = if warm_up.is_zero() { max } else { 1 };
+        Self {
+            permits: Semaphore::new(initial),
+            granted: AtomicUsize::new(initial),
+            max,
+            start: Instant::now(),
+            warm_up,
+        }
+    }
+
+    /// Probes allowed in flight `elapsed` into the sweep
+    pub fn limit(&self, elapsed: Duration) -> usize {
+        if elapsed >= self.warm_up {
+            return self.max;
+        }
+        let grown = (self.max - 1) as f64 * elapsed.as_secs_f64() / self.warm_up.as_secs_f64();
+        1 + grown as usize
+    }
+
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        loop {
+            self.grow();
+            if self.granted.load(Ordering::SeqCst) >= self.max {
+                return self.permits.acquire().await.expect("ramp-up semaphore is never closed");
+            }
+
+            // Wake up for the next permit even if no probe finishes before it
+            let step = self.warm_up / self.max as u32;
+            if let Ok(permit) = tokio::time::timeout(step, self.permits.acquire()).await {
+                return permit.expect("ramp-up semaphore is never closed");
+            }
+        }
+    }
+
+    /// Add the permits that came due since the last call
+    fn grow(&self) {
+        let due = self.limit(self.start.elapsed());
+        let granted = self.granted.fetch_max(due, Ordering::SeqCst);
+        if due > granted {
+            self.permits.add_permits(due - granted);
+        }
+    }
+}
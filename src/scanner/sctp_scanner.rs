@@ -0,0 +1,338 @@
+//! SCTP INIT scan, built with the `sctp` feature.
+//!
+//! INITs go out on a raw IPv4 socket: root or CAP_NET_RAW on Linux, root on
+//! macOS and the BSDs. Windows has no raw SCTP sockets, and IPv6 targets are
+//! not supported yet. An INIT-ACK answer means open (the half-open
+//! association is aborted at once), an ABORT closed, and silence filtered.
+
+use super::models::{PortInfo, PortStatus, Protocol};
+use super::pacer::{PacketPacer, SCTP_PROBE_PACKETS};
+use crate::error::{Error, Result};
+use async_trait::async_trait;
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::Packet;
+use pnet::transport::{
+    ipv4_packet_iter, transport_channel, TransportChannelType, TransportProtocol, TransportReceiver, TransportSender,
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+const COMMON_HEADER_LEN: usize = 12;
+const CHUNK_INIT: u8 = 1;
+const CHUNK_INIT_ACK: u8 = 2;
+const CHUNK_ABORT: u8 = 6;
+/// ABORT flag: the verification tag is the one the receiver sent, not its own
+const FLAG_T: u8 = 0x01;
+
+/// Probes sent before the replies queued so far are read, so the socket's
+/// receive buffer doesn't overflow on long port lists
+const DRAIN_EVERY: usize = 64;
+
+const SCTP_CHANNEL: TransportChannelType =
+    TransportChannelType::Layer4(TransportProtocol::Ipv4(IpNextHeaderProtocols::Sctp));
+
+pub struct SctpScanner {
+    timeout: Duration,
+    pacer: Option<Arc<PacketPacer>>,
+}
+
+/// Answer to one of our INITs
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Reply {
+    InitAck { port: u16, peer_tag: u32 },
+    Abort { port: u16 },
+}
+
+/// An SCTP packet already laid out in bytes, for the transport channel
+struct RawSctp<'a>(&'a [u8]);
+
+impl Packet for RawSctp<'_> {
+    fn packet(&self) -> &[u8] {
+        self.0
+    }
+
+    fn payload(&self) -> &[u8] {
+        &self.0[COMMON_HEADER_LEN..]
+    }
+}
+
+impl SctpScanner {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout, pacer: None }
+    }
+
+    /// Hold INITs to the pacer's packet rate, e.g. one shared with the TCP pass
+    pub fn with_pacer(mut self, pacer: Arc<PacketPacer>) -> Self {
+        self.pacer = Some(pacer);
+        self
+    }
+
+    /// Whether this process may open the raw socket SCTP probes need
+    pub fn raw_sockets_available() -> bool {
+        match transport_channel(4096, SCTP_CHANNEL) {
+            Ok(_) => true,
+            Err(e) => {
+                debug!("Raw SCTP sockets unavailable: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Every port's status, in `ports` order. Blocking: run off the runtime.
+    fn sweep(
+        timeout: Duration,
+        pacer: Option<Arc<PacketPacer>>,
+        runtime: tokio::runtime::Handle,
+        target: Ipv4Addr,
+        ports: &[u16],
+    ) -> Result<Vec<PortInfo>> {
+        let (mut tx, mut rx) = transport_channel(65536, SCTP_CHANNEL)
+            .map_err(|e| Error::Scan(format!("Raw SCTP socket unavailable (needs root or CAP_NET_RAW): {}", e)))?;
+        let source_port: u16 = rand::random::<u16>() | 0x8000;
+
+        // Initiate tag and send time per port still waiting for an answer
+        let mut pending: HashMap<u16, (u32, Instant)> = HashMap::with_capacity(ports.len());
+        let mut statuses: HashMap<u16, (PortStatus, Duration)> = HashMap::with_capacity(ports.len());
+
+        for (sent, &port) in ports.iter().enumerate() {
+            if let Some(pacer) = &pacer {
+                runtime.block_on(pacer.wait(SCTP_PROBE_PACKETS));
+            }
+            let tag = rand::random::<u32>().max(1); // Zero is reserved
+            tx.send_to(RawSctp(&init_packet(source_port, port, tag)), IpAddr::V4(target))?;
+            pending.insert(port, (tag, Instant::now()));
+
+            if (sent + 1) % DRAIN_EVERY == 0 {
+                Self::collect(&mut tx, &mut rx, target, source_port, &mut pending, &mut statuses, Duration::from_millis(1))?;
+            }
+        }
+        Self::collect(&mut tx, &mut rx, target, source_port, &mut pending, &mut statuses, timeout)?;
+
+        Ok(ports.iter()
+            .map(|&port| {
+                let (status, response_time) = match statuses.get(&port) {
+                    Some(&(status, rtt)) => (status, Some(rtt)),
+                    None => (PortStatus::Filtered, None),
+                };
+                PortInfo {
+                    port,
+                    status,
+                    service: None,
+                    banner: None,
+                    banner_raw: None,
+                    response_time,
+                    protocol: Protocol::Sctp,
+                    tls: None,
+                }
+            })
+            .collect())
+    }
+
+    /// Read answers until every probe has one or `wait` passes without any
+    #[allow(clippy::too_many_arguments)]
+    fn collect(
+        tx: &mut TransportSender,
+        rx: &mut TransportReceiver,
+        target: Ipv4Addr,
+        source_port: u16,
+        pending: &mut HashMap<u16, (u32, Instant)>,
+        statuses: &mut HashMap<u16, (PortStatus, Duration)>,
+        wait: Duration,
+    ) -> Result<()> {
+        let deadline = Instant::now() + wait;
+        let mut packets = ipv4_packet_iter(rx);
+
+        while !pending.is_empty() {
+            let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+                break;
+            };
+            let Some((packet, _)) = packets.next_with_timeout(remaining)? else {
+                break;
+            };
+            if packet.get_source() != target {
+                continue;
+            }
+
+            let Some(reply) = parse_reply(packet.payload(), source_port, pending) else {
+                continue;
+            };
+            match reply {
+                Reply::InitAck { port, peer_tag } => {
+                    let (_, sent_at) = pending.remove(&port).expect("parse_reply only matches pending ports");
+                    statuses.insert(port, (PortStatus::Open, sent_at.elapsed()));
+                    // Don't leave the target holding a half-open association
+                    tx.send_to(RawSctp(&abort_packet(source_port, port, peer_tag)), IpAddr::V4(target))?;
+                }
+                Reply::Abort { port } => {
+                    let (_, sent_at) = pending.remove(&port).expect("parse_reply only matches pending ports");
+                    statuses.insert(port, (PortStatus::Closed, sent_at.elapsed()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl super::Scanner for SctpScanner {
+    fn packets_per_probe(&self) -> u32 {
+        SCTP_PROBE_PACKETS
+    }
+
+    async fn scan_port(&self, target: IpAddr, port: u16) -> Result<PortInfo> {
+        self.scan_ports(target, &[port])
+            .await?
+            .pop()
+            .ok_or_else(|| Error::Scan(format!("No SCTP result for port {}", port)))
+    }
+
+    /// Every port of `ports`, open or not
+    async fn scan_ports(&self, target: IpAddr, ports: &[u16]) -> Result<Vec<PortInfo>> {
+        let IpAddr::V4(target) = target else {
+            return Err(Error::Validation("SCTP scanning only supports IPv4 targets".to_string()));
+        };
+
+        let timeout = self.timeout;
+        let pacer = self.pacer.clone();
+        let runtime = tokio::runtime::Handle::current();
+        let ports = ports.to_vec();
+        tokio::task::spawn_blocking(move || Self::sweep(timeout, pacer, runtime, target, &ports))
+            .await
+            .map_err(|e| Error::Scan(format!("SCTP sweep aborted: {}", e)))?
+    }
+}
+
+/// Common header (verification tag 0, as INITs require) and one INIT chunk
+fn init_packet(source_port: u16, dest_port: u16, initiate_tag: u32) -> Vec<u8> {
+    let mut packet = common_header(source_port, dest_port, 0);
+    packet.extend_from_slice(&[CHUNK_INIT, 0]);
+    packet.extend_from_slice(&20u16.to_be_bytes()); // Chunk length
+    packet.extend_from_slice(&initiate_tag.to_be_bytes());
+    packet.extend_from_slice(&65535u32.to_be_bytes()); // Advertised receiver window
+    packet.extend_from_slice(&10u16.to_be_bytes()); // Outbound streams
+    packet.extend_from_slice(&2048u16.to_be_bytes()); // Maximum inbound streams
+    packet.extend_from_slice(&rand::random::<u32>().to_be_bytes()); // Initial TSN
+    set_checksum(&mut packet);
+    packet
+}
+
+/// ABORT of the association an INIT-ACK started, under the peer's tag
+fn abort_packet(source_port: u16, dest_port: u16, peer_tag: u32) -> Vec<u8> {
+    let mut packet = common_header(source_port, dest_port, peer_tag);
+    packet.extend_from_slice(&[CHUNK_ABORT, 0]);
+    packet.extend_from_slice(&4u16.to_be_bytes());
+    set_checksum(&mut packet);
+    packet
+}
+
+fn common_header(source_port: u16, dest_port: u16, verification_tag: u32) -> Vec<u8> {
+    let mut header = Vec::with_capacity(32);
+    header.extend_from_slice(&source_port.to_be_bytes());
+    header.extend_from_slice(&dest_port.to_be_bytes());
+    header.extend_from_slice(&verification_tag.to_be_bytes());
+    header.extend_from_slice(&[0; 4]); // Checksum, filled in last
+    header
+}
+
+/// CRC32c over the packet with a zeroed checksum field. Unlike the rest of
+/// the header it goes on the wire least significant byte first (RFC 4960
+/// appendix B).
+fn set_checksum(packet: &mut [u8]) {
+    packet[8..12].fill(0);
+    let checksum = crc32c(packet);
+    packet[8..12].copy_from_slice(&checksum.to_le_bytes());
+}
+
+fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0x82f6_3b78 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// The answer `sctp` carries to one of our pending INITs, if any
+fn parse_reply(sctp: &[u8], source_port: u16, pending: &HashMap<u16, (u32, Instant)>) -> Option<Reply> {
+    let read_u16 = |pos: usize| sctp.get(pos..pos + 2).map(|b| u16::from_be_bytes([b[0], b[1]]));
+    let read_u32 = |pos: usize| sctp.get(pos..pos + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+
+    let port = read_u16(0)?;
+    if read_u16(2)? != source_port {
+        return None;
+    }
+    let (initiate_tag, _) = pending.get(&port)?;
+    let verification_tag = read_u32(4)?;
+    let chunk_type = *sctp.get(COMMON_HEADER_LEN)?;
+    let chunk_flags = *sctp.get(COMMON_HEADER_LEN + 1)?;
+
+    match chunk_type {
+        CHUNK_INIT_ACK if verification_tag == *initiate_tag => Some(Reply::InitAck {
+            port,
+            peer_tag: read_u32(COMMON_HEADER_LEN + 4)?,
+        }),
+        // With the T flag the ABORT reflects the INIT's own (zero) tag
+        CHUNK_ABORT if verification_tag == *initiate_tag || (chunk_flags & FLAG_T != 0 && verification_tag == 0) => {
+            Some(Reply::Abort { port })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_init_packet_layout_and_checksum() {
+        // RFC 3720 B.4 check value
+        assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+
+        let packet = init_packet(40000, 2905, 0xdead_beef);
+        assert_eq!(packet.len(), COMMON_HEADER_LEN + 20);
+        assert_eq!(&packet[0..4], &[0x9c, 0x40, 0x0b, 0x59]);
+        assert_eq!(&packet[4..8], &[0; 4], "INIT must carry a zero verification tag");
+        assert_eq!(packet[12], CHUNK_INIT);
+        assert_eq!(&packet[16..20], &0xdead_beefu32.to_be_bytes());
+
+        let mut zeroed = packet.clone();
+        zeroed[8..12].fill(0);
+        assert_eq!(packet[8..12], crc32c(&zeroed).to_le_bytes());
+    }
+
+    #[test]
+    fn test_replies_classified_by_chunk_and_tag() {
+        let pending = HashMap::from([(2905, (0x1111_1111, Instant::now())), (3868, (0x2222_2222, Instant::now()))]);
+        let reply = |from: u16, to: u16, tag: u32, chunk: u8, flags: u8, peer_tag: u32| {
+            let mut packet = common_header(from, to, tag);
+            packet.extend_from_slice(&[chunk, flags, 0, 20]);
+            packet.extend_from_slice(&peer_tag.to_be_bytes());
+            packet
+        };
+
+        assert_eq!(
+            parse_reply(&reply(2905, 40000, 0x1111_1111, CHUNK_INIT_ACK, 0, 0xabcd), 40000, &pending),
+            Some(Reply::InitAck { port: 2905, peer_tag: 0xabcd })
+        );
+        assert_eq!(
+            parse_reply(&reply(3868, 40000, 0x2222_2222, CHUNK_ABORT, 0, 0), 40000, &pending),
+            Some(Reply::Abort { port: 3868 })
+        );
+        assert_eq!(
+            parse_reply(&reply(3868, 40000, 0, CHUNK_ABORT, FLAG_T, 0), 40000, &pending),
+            Some(Reply::Abort { port: 3868 })
+        );
+
+        // Wrong tag, another probe's source port, a port we never probed
+        assert_eq!(parse_reply(&reply(2905, 40000, 0x2222_2222, CHUNK_INIT_ACK, 0, 1), 40000, &pending), None);
+        assert_eq!(parse_reply(&reply(2905, 40001, 0x1111_1111, CHUNK_INIT_ACK, 0, 1), 40000, &pending), None);
+        assert_eq!(parse_reply(&reply(80, 40000, 0x1111_1111, CHUNK_INIT_ACK, 0, 1), 40000, &pending), None);
+        assert_eq!(parse_reply(&[0; 6], 40000, &pending), None);
+    }
+}
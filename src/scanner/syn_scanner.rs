@@ -1,17 +1,44 @@
-use super::models::{PortInfo, PortStatus, Protocol};
+use super::models::{Decoy, PortInfo, PortStatus, Protocol};
 use crate::error::{Error, Result};
 use async_trait::async_trait;
 use pnet::packet::ip::IpNextHeaderProtocols;
-use pnet::packet::tcp::{TcpFlags, TcpPacket, MutableTcpPacket};
+use pnet::packet::ipv4::{self, Ipv4Packet, MutableIpv4Packet};
+use pnet::packet::tcp::{self as tcp, TcpFlags, TcpPacket, MutableTcpPacket};
 use pnet::packet::Packet;
-use pnet::transport::{transport_channel, TransportChannelType, TransportReceiver, TransportSender};
-use std::net::IpAddr;
-use std::time::Duration;
-use tracing::{debug, warn};
+use pnet::transport::{ipv4_packet_iter, transport_channel, TransportChannelType, TransportReceiver, TransportSender};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, OnceCell};
+use tracing::debug;
 
+const IPV4_HEADER_LEN: usize = 20;
+const TCP_HEADER_LEN: usize = 20;
+
+/// How long the receive thread blocks before checking whether the scanner
+/// is gone
+const RECEIVE_POLL: Duration = Duration::from_millis(200);
+
+/// (target, target port, our source port) of a probe awaiting its reply
+type ProbeKey = (Ipv4Addr, u16, u16);
+type PendingProbes = Mutex<HashMap<ProbeKey, oneshot::Sender<PortStatus>>>;
+
+/// The one raw socket pair of a scanner: every probe sends through it, and a
+/// single receive thread hands each reply to the probe awaiting it, so
+/// concurrency costs no file descriptors
+struct RawChannel {
+    tx: Mutex<TransportSender>,
+    pending: Arc<PendingProbes>,
+    sources: Mutex<HashMap<Ipv4Addr, Ipv4Addr>>, // Real source address per target
+}
+
+#[derive(Clone)]
 pub struct SynScanner {
     timeout: Duration,
     max_concurrent: usize,
+    decoys: Vec<Decoy>,
+    channel: Arc<OnceCell<RawChannel>>, // Opened by the first probe
 }
 
 impl SynScanner {
@@ -20,9 +47,54 @@ impl SynScanner {
         Ok(Self {
             timeout,
             max_concurrent,
+            decoys: Vec::new(),
+            channel: Arc::new(OnceCell::new()),
         })
     }
 
+    /// Whether this process may open the raw sockets SYN probes need
+    pub fn raw_sockets_available() -> bool {
+        match transport_channel(4096, TransportChannelType::Layer3(IpNextHeaderProtocols::Tcp)) {
+            Ok(_) => true,
+            Err(e) => {
+                debug!("Raw sockets unavailable: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Send every probe from each decoy address as well as from the real source.
+    ///
+    /// Decoy probes are crafted on a raw IPv4 socket (root / CAP_NET_RAW required).
+    /// The list must contain [`Decoy::Me`], otherwise no reply would ever reach
+    /// us and every port would look filtered.
+    pub fn with_decoys(mut self, decoys: Vec<Decoy>) -> Result<Self> {
+        if !decoys.is_empty() {
+            if !decoys.contains(&Decoy::Me) {
+                return Err(Error::Validation(
+                    "Decoy list must include ME (the real source) to collect results".to_string()
+                ));
+            }
+
+            for decoy in &decoys {
+                match decoy {
+                    Decoy::Addr(IpAddr::V6(_)) => {
+                        return Err(Error::Validation("Decoy scanning only supports IPv4 addresses".to_string()));
+                    }
+                    Decoy::Addr(address) if address.is_unspecified() => {
+                        return Err(Error::Validation(format!(
+                            "{} is not a usable decoy address; ME marks the real source", address
+                        )));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        self.decoys = decoys;
+        Ok(self)
+    }
+
     fn create_syn_packet(&self, source_port: u16, dest_port: u16) -> Vec<u8> {
         let mut tcp_buffer = vec![0u8; 20]; // TCP header size
         let mut tcp_packet = MutableTcpPacket::new(&mut tcp_buffer).unwrap();
@@ -39,29 +111,299 @@ impl SynScanner {
         // Calculate checksum would go here
         tcp_packet.to_immutable().packet().to_vec()
     }
+
+    fn create_ipv4_syn_packet(
+        &self,
+        source: Ipv4Addr,
+        destination: Ipv4Addr,
+        source_port: u16,
+        dest_port: u16,
+    ) -> Vec<u8> {
+        let mut buffer = vec![0u8; IPV4_HEADER_LEN + TCP_HEADER_LEN];
+
+        {
+            let mut tcp_packet = MutableTcpPacket::new(&mut buffer[IPV4_HEADER_LEN..]).unwrap();
+            tcp_packet.set_source(source_port);
+            tcp_packet.set_destination(dest_port);
+            tcp_packet.set_sequence(rand::random());
+            tcp_packet.set_acknowledgement(0);
+            tcp_packet.set_data_offset(5);
+            tcp_packet.set_flags(TcpFlags::SYN);
+            tcp_packet.set_window(5840);
+            tcp_packet.set_urgent_ptr(0);
+            let checksum = tcp::ipv4_checksum(&tcp_packet.to_immutable(), &source, &destination);
+            tcp_packet.set_checksum(checksum);
+        }
+
+        let mut ip_packet = MutableIpv4Packet::new(&mut buffer).unwrap();
+        ip_packet.set_version(4);
+        ip_packet.set_header_length(5);
+        ip_packet.set_total_length((IPV4_HEADER_LEN + TCP_HEADER_LEN) as u16);
+        ip_packet.set_identification(rand::random());
+        ip_packet.set_ttl(64);
+        ip_packet.set_next_level_protocol(IpNextHeaderProtocols::Tcp);
+        ip_packet.set_source(source);
+        ip_packet.set_destination(destination);
+        let checksum = ipv4::checksum(&ip_packet.to_immutable());
+        ip_packet.set_checksum(checksum);
+
+        buffer
+    }
+
+    /// Determine which local address the kernel would use to reach `target`.
+    fn real_source_for(target: Ipv4Addr) -> Result<Ipv4Addr> {
+        let socket = std::net::UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect((target, 80))?;
+
+        match socket.local_addr()?.ip() {
+            IpAddr::V4(ip) => Ok(ip),
+            IpAddr::V6(_) => Err(Error::Scan("Could not determine IPv4 source address".to_string())),
+        }
+    }
+
+    async fn channel(&self) -> Result<&RawChannel> {
+        self.channel.get_or_try_init(|| async {
+            let (tx, rx) = transport_channel(
+                4096,
+                TransportChannelType::Layer3(IpNextHeaderProtocols::Tcp),
+            ).map_err(|e| Error::Scan(format!("Raw socket unavailable (SYN scans need root or CAP_NET_RAW): {}", e)))?;
+
+            let pending = Arc::new(PendingProbes::default());
+            let replies = Arc::downgrade(&pending);
+            std::thread::Builder::new()
+                .name("syn-receiver".to_string())
+                .spawn(move || Self::receive_replies(rx, replies))?;
+
+            Ok::<_, Error>(RawChannel {
+                tx: Mutex::new(tx),
+                pending,
+                sources: Mutex::new(HashMap::new()),
+            })
+        }).await
+    }
+
+    /// Hand every SYN-ACK or RST to the probe awaiting it, until the scanner
+    /// (and with it the pending table) is dropped
+    fn receive_replies(mut rx: TransportReceiver, pending: Weak<PendingProbes>) {
+        let mut packets = ipv4_packet_iter(&mut rx);
+        loop {
+            let reply = packets.next_with_timeout(RECEIVE_POLL);
+            let Some(pending) = pending.upgrade() else {
+                return;
+            };
+
+            let packet = match reply {
+                Ok(Some((packet, _))) => packet,
+                Ok(None) => continue,
+                Err(e) => {
+                    debug!("Raw socket receive failed: {}", e);
+                    std::thread::sleep(RECEIVE_POLL);
+                    continue;
+                }
+            };
+            let Some(tcp_packet) = TcpPacket::new(packet.payload()) else {
+                continue;
+            };
+
+            let flags = tcp_packet.get_flags();
+            let status = if flags & (TcpFlags::SYN | TcpFlags::ACK) == TcpFlags::SYN | TcpFlags::ACK {
+                PortStatus::Open
+            } else if flags & TcpFlags::RST != 0 {
+                PortStatus::Closed
+            } else {
+                continue;
+            };
+
+            // Replies to decoy sources never reach us, and anything that isn't
+            // a reply to one of our probes has no entry
+            let key = (packet.get_source(), tcp_packet.get_source(), tcp_packet.get_destination());
+            let waiter = pending.lock().ok().and_then(|mut pending| pending.remove(&key));
+            if let Some(waiter) = waiter {
+                let _ = waiter.send(status);
+            }
+        }
+    }
+
+    /// Real source address towards `target`, looked up once per target
+    fn real_source(channel: &RawChannel, target: Ipv4Addr) -> Result<Ipv4Addr> {
+        let mut sources = channel.sources.lock()
+            .map_err(|_| Error::Scan("SYN source cache lock poisoned".to_string()))?;
+        if let Some(source) = sources.get(&target) {
+            return Ok(*source);
+        }
+        let source = Self::real_source_for(target)?;
+        sources.insert(target, source);
+        Ok(source)
+    }
+
+    /// One raw SYN from the real source, and from every decoy if there are
+    /// any, then wait for the receive thread to match the reply. A probe
+    /// that couldn't be sent is Unknown; one never answered is Filtered.
+    async fn probe(&self, channel: &RawChannel, real_source: Ipv4Addr, target: Ipv4Addr, port: u16) -> PortInfo {
+        let start_time = Instant::now();
+        let source_port: u16 = rand::random::<u16>() | 0x8000;
+        let key = (target, port, source_port);
+        let forget = || {
+            if let Ok(mut pending) = channel.pending.lock() {
+                pending.remove(&key);
+            }
+        };
+
+        let (reply_tx, reply_rx) = oneshot::channel();
+        if let Ok(mut pending) = channel.pending.lock() {
+            pending.insert(key, reply_tx);
+        }
+
+        let sent = match channel.tx.lock() {
+            Ok(mut tx) => self.send_probes(&mut tx, real_source, target, source_port, port),
+            Err(_) => Err(Error::Scan("SYN sender lock poisoned".to_string())),
+        };
+        let status = match sent {
+            Err(e) => {
+                debug!("SYN probe of {}:{} not sent: {}", target, port, e);
+                forget();
+                PortStatus::Unknown
+            }
+            Ok(()) => match tokio::time::timeout(self.timeout, reply_rx).await {
+                Ok(Ok(status)) => status,
+                _ => {
+                    forget();
+                    PortStatus::Filtered
+                }
+            },
+        };
+
+        PortInfo {
+            port,
+            status,
+            service: None,
+            banner: None,
+            banner_raw: None,
+            response_time: Some(start_time.elapsed()),
+            protocol: Protocol::Tcp,
+            tls: None,
+        }
+    }
+
+    fn ipv4_target(target: IpAddr) -> Result<Ipv4Addr> {
+        match target {
+            IpAddr::V4(target) => Ok(target),
+            IpAddr::V6(_) => Err(Error::Validation(
+                "SYN scanning only supports IPv4 targets; use --technique-chain connect".to_string()
+            )),
+        }
+    }
+
+    fn send_probes(
+        &self,
+        tx: &mut TransportSender,
+        real_source: Ipv4Addr,
+        target: Ipv4Addr,
+        source_port: u16,
+        port: u16,
+    ) -> Result<()> {
+        let sources: &[Decoy] = if self.decoys.is_empty() { &[Decoy::Me] } else { &self.decoys };
+        for decoy in sources {
+            let source = match *decoy {
+                Decoy::Me => real_source,
+                Decoy::Addr(IpAddr::V4(ip)) => ip,
+                Decoy::Addr(IpAddr::V6(_)) => continue,
+            };
+
+            let packet = self.create_ipv4_syn_packet(source, target, source_port, port);
+            let ip_packet = Ipv4Packet::new(&packet).unwrap();
+            tx.send_to(ip_packet, IpAddr::V4(target))?;
+            debug!("Sent SYN to {}:{} from {}", target, port, source);
+        }
+
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl super::Scanner for SynScanner {
+    /// Every source sends a SYN and draws an answer, and only the real one
+    /// sends the RST
+    fn packets_per_probe(&self) -> u32 {
+        let sources = self.decoys.len().max(1) as u32;
+        super::pacer::SYN_PROBE_PACKETS + 2 * (sources - 1)
+    }
+
     async fn scan_port(&self, target: IpAddr, port: u16) -> Result<PortInfo> {
-        // SYN scanning implementation requires raw sockets
-        // This is a simplified version - real implementation would be more complex
-        
-        warn!("SYN scanning not fully implemented - falling back to TCP connect");
-        
-        // Fallback to TCP connect for now
-        let tcp_scanner = super::PortScanner::new(self.timeout, self.max_concurrent);
-        let mut result = tcp_scanner.scan_port(target, port).await?;
-        
-        // Mark as SYN scan result
-        result.protocol = Protocol::Tcp; // Still TCP, but could be marked differently
-        
-        Ok(result)
+        let target = Self::ipv4_target(target)?;
+        let channel = self.channel().await?;
+        let real_source = Self::real_source(channel, target)?;
+        Ok(self.probe(channel, real_source, target, port).await)
     }
 
+    /// Only a missing raw socket or route fails the sweep; a probe that
+    /// fails on its own is recorded against its port
     async fn scan_ports(&self, target: IpAddr, ports: &[u16]) -> Result<Vec<PortInfo>> {
-        // Fallback to TCP connect scanning
-        let tcp_scanner = super::PortScanner::new(self.timeout, self.max_concurrent);
-        tcp_scanner.scan_ports(target, ports).await
+        use futures::stream::{self, StreamExt};
+
+        let target = Self::ipv4_target(target)?;
+        let channel = self.channel().await?;
+        let real_source = Self::real_source(channel, target)?;
+
+        Ok(stream::iter(ports.iter().copied())
+            .map(|port| self.probe(channel, real_source, target, port))
+            .buffered(self.max_concurrent.max(1))
+            .collect()
+            .await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    #[tokio::test]
+    async fn test_syn_probes_never_fall_back_to_connect() {
+        let scanner = SynScanner::new(Duration::from_millis(200), 10).unwrap();
+        assert_eq!(scanner.packets_per_probe(), crate::scanner::pacer::SYN_PROBE_PACKETS);
+
+        assert!(matches!(scanner.scan_port("::1".parse().unwrap(), 22).await, Err(Error::Validation(_))));
+
+        // A connect fallback would report this listener open
+        if !SynScanner::raw_sockets_available() {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            assert!(matches!(scanner.scan_port("127.0.0.1".parse().unwrap(), port).await, Err(Error::Scan(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shared_channel_matches_replies_to_ports() {
+        if !SynScanner::raw_sockets_available() {
+            return;
+        }
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let open = listener.local_addr().unwrap().port();
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+        let scanner = SynScanner::new(Duration::from_secs(2), 10).unwrap();
+        let ports = scanner.scan_ports("127.0.0.1".parse().unwrap(), &[open, closed]).await.unwrap();
+        let statuses: Vec<(u16, PortStatus)> = ports.into_iter().map(|p| (p.port, p.status)).collect();
+        assert_eq!(statuses, vec![(open, PortStatus::Open), (closed, PortStatus::Closed)]);
+
+        // Later probes reuse the channel opened by the sweep
+        let again = scanner.scan_port("127.0.0.1".parse().unwrap(), open).await.unwrap();
+        assert_eq!(again.status, PortStatus::Open);
+    }
+
+    #[test]
+    fn test_unspecified_address_is_not_a_decoy() {
+        let scanner = || SynScanner::new(Duration::from_millis(200), 10).unwrap();
+        let decoy = |address: &str| Decoy::Addr(address.parse().unwrap());
+
+        // ME is its own variant, so a literal 0.0.0.0 can't stand in for it
+        assert!("0.0.0.0".parse::<Decoy>().is_err());
+        assert_eq!("me".parse::<Decoy>(), Ok(Decoy::Me));
+        assert!(matches!(scanner().with_decoys(vec![decoy("0.0.0.0"), Decoy::Me]), Err(Error::Validation(_))));
+        assert!(matches!(scanner().with_decoys(vec![decoy("0.0.0.0")]), Err(Error::Validation(_))));
+
+        let scanner = scanner().with_decoys(vec![decoy("10.0.0.5"), Decoy::Me, decoy("10.0.0.9")]).unwrap();
+        assert_eq!(scanner.packets_per_probe(), crate::scanner::pacer::SYN_PROBE_PACKETS + 4);
     }
 }
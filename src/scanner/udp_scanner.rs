@@ -1,7 +1,9 @@
 use super::models::{PortInfo, PortStatus, Protocol};
+use super::pacer::{PacketPacer, UDP_PROBE_PACKETS};
 use crate::error::{Error, Result};
 use async_trait::async_trait;
 use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::timeout;
 use tracing::{debug, trace};
@@ -9,6 +11,7 @@ use tracing::{debug, trace};
 pub struct UdpScanner {
     timeout: Duration,
     max_concurrent: usize,
+    pacer: Option<Arc<PacketPacer>>,
 }
 
 impl UdpScanner {
@@ -16,9 +19,16 @@ impl UdpScanner {
         Ok(Self {
             timeout,
             max_concurrent,
+            pacer: None,
         })
     }
 
+    /// Hold probes to the pacer's packet rate, e.g. one shared with the TCP pass
+    pub fn with_pacer(mut self, pacer: Arc<PacketPacer>) -> Self {
+        self.pacer = Some(pacer);
+        self
+    }
+
     async fn probe_udp_port(&self, target: IpAddr, port: u16) -> Result<bool> {
         // UDP scanning is inherently unreliable as UDP is connectionless
         // We send a probe packet and see if we get any response
@@ -57,6 +67,10 @@ impl UdpScanner {
 
 #[async_trait]
 impl super::Scanner for UdpScanner {
+    fn packets_per_probe(&self) -> u32 {
+        UDP_PROBE_PACKETS
+    }
+
     async fn scan_port(&self, target: IpAddr, port: u16) -> Result<PortInfo> {
         let is_open = self.probe_udp_port(target, port).await?;
         
@@ -71,15 +85,16 @@ impl super::Scanner for UdpScanner {
             status,
             service: None, // UDP service detection would be separate
             banner: None,
+            banner_raw: None,
             response_time: None,
             protocol: Protocol::Udp,
+            tls: None,
         })
     }
 
     async fn scan_ports(&self, target: IpAddr, ports: &[u16]) -> Result<Vec<PortInfo>> {
         use tokio::sync::Semaphore;
         use futures::stream::{self, StreamExt};
-        use std::sync::Arc;
         
         let semaphore = Arc::new(Semaphore::new(self.max_concurrent));
         let mut results = Vec::new();
@@ -90,6 +105,9 @@ impl super::Scanner for UdpScanner {
                 let semaphore = Arc::clone(&semaphore);
                 async move {
                     let _permit = semaphore.acquire().await?;
+                    if let Some(pacer) = &self.pacer {
+                        pacer.wait(UDP_PROBE_PACKETS).await;
+                    }
                     self.scan_port(target, port).await
                 }
             })
@@ -3,6 +3,60 @@ use sqlx::FromRow;
 use std::net::IpAddr;
 use chrono::{DateTime, Utc};
 
+/// Where a scan is in its lifecycle, as stored in `scans.status`. A row is
+/// created `Running` when the scan starts and ends in one of the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanStatus {
+    Running,
+    Completed,
+    Partial, // The host went down mid-scan; see `ScanRecord::partial_reason`
+    Down, // Host discovery got no answer; no port was probed
+    Failed, // See `ScanRecord::failure_reason`
+    Cancelled, // Stopped by the user; a checkpointed scan can be resumed
+}
+
+impl ScanStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScanStatus::Running => "running",
+            ScanStatus::Completed => "completed",
+            ScanStatus::Partial => "partial",
+            ScanStatus::Down => "down",
+            ScanStatus::Failed => "failed",
+            ScanStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// Statuses a scan that was started can be picked up from again:
+    /// a crashed process leaves it `Running`, and a resumed one was cut short
+    pub fn is_resumable(&self) -> bool {
+        matches!(self, ScanStatus::Running | ScanStatus::Failed | ScanStatus::Cancelled)
+    }
+}
+
+impl std::fmt::Display for ScanStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ScanStatus {
+    type Err = crate::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "running" => Ok(ScanStatus::Running),
+            "completed" => Ok(ScanStatus::Completed),
+            "partial" => Ok(ScanStatus::Partial),
+            "down" => Ok(ScanStatus::Down),
+            "failed" => Ok(ScanStatus::Failed),
+            "cancelled" => Ok(ScanStatus::Cancelled),
+            other => Err(crate::error::Error::Validation(format!("Unknown scan status: {}", other))),
+        }
+    }
+}
+
 // Scan database models
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct ScanRecord {
@@ -16,8 +70,29 @@ pub struct ScanRecord {
     pub open_ports: i32,
     pub scan_duration_ms: i64,
     pub status: String,
+    pub partial_reason: Option<String>,
+    pub last_port: Option<i32>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub failure_reason: Option<String>,
+}
+
+impl ScanRecord {
+    pub fn scan_status(&self) -> crate::error::Result<ScanStatus> {
+        self.status.parse()
+    }
+}
+
+/// One-line view of a scan for listings; avoids loading ports and metadata
+#[derive(Debug, Clone, PartialEq, FromRow, Serialize, Deserialize)]
+pub struct ScanSummary {
+    pub id: String,
+    pub target: String,
+    pub scan_type: String,
+    pub open_count: i32,
+    pub duration_ms: i64,
+    pub created_at: DateTime<Utc>,
+    pub status: String,
 }
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
@@ -29,7 +104,10 @@ pub struct ScanPortRecord {
     pub service_name: Option<String>,
     pub service_version: Option<String>,
     pub service_product: Option<String>,
+    pub service_tunnel: Option<String>,
+    pub service_alpn: Option<String>,
     pub banner: Option<String>,
+    pub banner_raw: Option<String>,
     pub response_time_ms: Option<i64>,
     pub protocol: String,
     pub created_at: DateTime<Utc>,
@@ -56,6 +134,33 @@ pub struct VulnerabilityRecord {
     pub impact: Option<String>,
     pub certainty: i32,
     pub tags_json: Option<String>,
+    pub report_id: Option<String>, // None for findings saved before reports had headers
+    pub created_at: DateTime<Utc>,
+}
+
+/// Report-level fields of a saved `VulnerabilityReport`
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct VulnerabilityReportRecord {
+    pub id: String,
+    pub scan_id: String,
+    pub target: String,
+    pub target_ip: String,
+    pub generated_at: DateTime<Utc>,
+    pub summary_json: String,
+    pub risk_assessment_json: String,
+    pub recommendations_json: String,
+    pub include_suppressed: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SuppressionRecord {
+    pub id: String,
+    pub target: String,
+    pub port: Option<i32>,
+    pub rule_id: String,
+    pub reason: String,
+    pub expires_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -81,24 +186,32 @@ pub struct ScanMetadataRecord {
     pub os_version: Option<String>,
     pub os_accuracy: Option<i32>,
     pub traceroute_json: Option<String>,
+    pub asn: Option<i64>,
+    pub as_org: Option<String>,
+    pub country_code: Option<String>,
+    pub coverage_json: Option<String>,
+    pub incremental_json: Option<String>,
+    pub notes_json: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
 // Query parameters
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ScanQuery {
     pub target: Option<String>,
     pub date_from: Option<DateTime<Utc>>,
     pub date_to: Option<DateTime<Utc>>,
     pub status: Option<String>,
+    pub tag: Option<String>, // Exact match
     pub limit: Option<i64>,
     pub offset: Option<i64>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct VulnerabilityQuery {
     pub scan_id: Option<String>,
-    pub level: Option<String>,
+    pub level: Option<String>, // Exact match, as stored ("high")
+    pub min_level: Option<crate::vulnerability::VulnerabilityLevel>, // This level or more severe
     pub port: Option<i32>,
     pub service: Option<String>,
     pub date_from: Option<DateTime<Utc>>,
@@ -117,7 +230,36 @@ pub struct PaginatedResults<T> {
     pub total_pages: i64,
 }
 
+impl<T> PaginatedResults<T> {
+    /// Convert each item, keeping the paging information
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> PaginatedResults<U> {
+        PaginatedResults {
+            data: self.data.into_iter().map(f).collect(),
+            total: self.total,
+            page: self.page,
+            page_size: self.page_size,
+            total_pages: self.total_pages,
+        }
+    }
+}
+
 // Statistics and analytics
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct TargetTimelinePoint {
+    pub timestamp: DateTime<Utc>,
+    pub open_ports: i32,
+    pub scan_id: String,
+}
+
+/// A target's scan history in aggregate, for "top talkers" rankings
+#[derive(Debug, Clone, PartialEq, FromRow, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct TopTarget {
+    pub target: String,
+    pub scan_count: i64,
+    pub max_open_ports: i32, // Most open ports any one scan found
+    pub last_scanned: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct ScanStats {
     pub total_scans: i64,
@@ -149,3 +291,10 @@ pub trait ToDatabase {
     type Output;
     fn to_database(self) -> Self::Output;
   }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseHealth {
+    pub reachable: bool,
+    pub db_latency_ms: Option<f64>, // Round trip of the probe query; None when unreachable
+    pub schema_ok: bool,
+}
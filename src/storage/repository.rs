@@ -1,10 +1,30 @@
 use super::{database::Database, models::*};
 use crate::error::{Error, Result};
-use crate::scanner::{ScanResult, PortInfo, ScanType};
-use crate::vulnerability::{VulnerabilityReport, Vulnerability};
-use sqlx::{query, query_as, QueryBuilder, Sqlite};
+use crate::scanner::{ScanResult, ScanCheckpoint, PortInfo, ScanType, ScanObserver};
+use crate::vulnerability::{VulnerabilityReport, Vulnerability, VulnDiff, Suppression, SuppressionList};
+use chrono::{DateTime, Utc};
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
+use sqlx::{query, query_as, Sqlite};
 use std::collections::HashMap;
-use tracing::{info, debug, instrument};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, debug, warn, instrument};
+
+/// Tables created by the migrations; all must exist for `schema_ok`
+const EXPECTED_TABLES: &[&str] = &[
+    "scans",
+    "scan_ports",
+    "vulnerability_reports",
+    "vulnerabilities",
+    "vulnerability_references",
+    "vuln_suppressions",
+    "scan_statistics",
+    "scan_metadata",
+    "scan_checkpoints",
+    "scan_tags",
+];
+
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
 
 #[derive(Clone)]
 pub struct ScanRepository {
@@ -16,6 +36,134 @@ impl ScanRepository {
         Self { db }
     }
 
+    pub fn database(&self) -> &Database {
+        &self.db
+    }
+
+    /// Whether the database answers a trivial query within a short timeout
+    #[instrument(skip(self))]
+    pub async fn health_check(&self) -> Result<bool> {
+        Ok(self.health_details().await.reachable)
+    }
+
+    /// Reachability, probe latency and whether every expected table exists
+    #[instrument(skip(self))]
+    pub async fn health_details(&self) -> DatabaseHealth {
+        let pool = self.db.get_pool();
+        let start = Instant::now();
+
+        let probe = tokio::time::timeout(
+            HEALTH_CHECK_TIMEOUT,
+            query("SELECT 1").execute(pool),
+        ).await;
+
+        let db_latency_ms = match probe {
+            Ok(Ok(_)) => start.elapsed().as_secs_f64() * 1000.0,
+            Ok(Err(e)) => {
+                warn!("Database health probe failed: {}", e);
+                return DatabaseHealth { reachable: false, db_latency_ms: None, schema_ok: false };
+            }
+            Err(_) => {
+                warn!("Database health probe timed out after {:?}", HEALTH_CHECK_TIMEOUT);
+                return DatabaseHealth { reachable: false, db_latency_ms: None, schema_ok: false };
+            }
+        };
+
+        let tables: Vec<(String,)> = query_as(
+            "SELECT name FROM sqlite_master WHERE type = 'table'"
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap_or_default();
+
+        let schema_ok = EXPECTED_TABLES.iter()
+            .all(|expected| tables.iter().any(|(name,)| name == expected));
+
+        DatabaseHealth {
+            reachable: true,
+            db_latency_ms: Some(db_latency_ms),
+            schema_ok,
+        }
+    }
+
+    /// Record `scan_result` as running, before any port is probed. A scan
+    /// resumed after a crash, failure or cancellation goes back to running;
+    /// one that already finished can't be started again.
+    #[instrument(skip(self, scan_result), fields(scan_id = %scan_result.id))]
+    pub async fn start_scan(&self, scan_result: &ScanResult) -> Result<()> {
+        let started = query(
+            r#"
+            INSERT INTO scans (
+                id, target, target_ip, scan_type, start_time, end_time,
+                total_ports, open_ports, scan_duration_ms, status
+            ) VALUES (?, ?, ?, ?, ?, ?, 0, 0, 0, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                status = excluded.status,
+                failure_reason = NULL
+            WHERE scans.status IN ('running', 'failed', 'cancelled')
+            "#
+        )
+        .bind(&scan_result.id)
+        .bind(&scan_result.target)
+        .bind(scan_result.target_ip.to_string())
+        .bind(scan_type_to_string(&scan_result.scan_type))
+        .bind(scan_result.start_time)
+        .bind(scan_result.start_time)
+        .bind(ScanStatus::Running.as_str())
+        .execute(self.db.get_pool())
+        .await?;
+
+        if started.rows_affected() == 0 {
+            return Err(self.not_running(&scan_result.id, "start").await);
+        }
+        debug!("Scan {} is running", scan_result.id);
+        Ok(())
+    }
+
+    /// End a running scan as failed, keeping why for the history
+    #[instrument(skip(self))]
+    pub async fn fail_scan(&self, scan_id: &str, reason: &str) -> Result<()> {
+        self.end_running_scan(scan_id, ScanStatus::Failed, Some(reason)).await
+    }
+
+    /// End a running scan as cancelled by the user
+    #[instrument(skip(self))]
+    pub async fn cancel_scan(&self, scan_id: &str) -> Result<()> {
+        self.end_running_scan(scan_id, ScanStatus::Cancelled, None).await
+    }
+
+    async fn end_running_scan(&self, scan_id: &str, status: ScanStatus, reason: Option<&str>) -> Result<()> {
+        let ended = query(
+            r#"
+            UPDATE scans SET status = ?, failure_reason = ?, end_time = ?
+            WHERE id = ? AND status = 'running'
+            "#
+        )
+        .bind(status.as_str())
+        .bind(reason)
+        .bind(Utc::now())
+        .bind(scan_id)
+        .execute(self.db.get_pool())
+        .await?;
+
+        if ended.rows_affected() == 0 {
+            return Err(self.not_running(scan_id, &format!("mark {} as", status)).await);
+        }
+        info!("Scan {} {}", scan_id, status);
+        Ok(())
+    }
+
+    /// Why `action` was refused for a scan that isn't running
+    async fn not_running(&self, scan_id: &str, action: &str) -> Error {
+        match self.get_scan(scan_id).await {
+            Ok(Some(record)) => Error::Validation(format!("Cannot {} scan {}: it is {}", action, scan_id, record.status)),
+            Ok(None) => Error::Validation(format!("Cannot {} scan {}: no such scan", action, scan_id)),
+            Err(e) => e,
+        }
+    }
+
+    /// Store a finished scan with its ports, either as a new row or over the
+    /// row `start_scan` created for it
     #[instrument(skip(self))]
     pub async fn save_scan(&self, scan_result: &ScanResult) -> Result<String> {
         let mut transaction = self.db.begin_transaction().await?;
@@ -23,12 +171,24 @@ impl ScanRepository {
         // Insert main scan record
         let scan_id = scan_result.id.clone();
         
-        query(
+        let saved = query(
             r#"
             INSERT INTO scans (
                 id, target, target_ip, scan_type, start_time, end_time, 
-                total_ports, open_ports, scan_duration_ms, status
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                total_ports, open_ports, scan_duration_ms, status, partial_reason, last_port
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                target_ip = excluded.target_ip,
+                scan_type = excluded.scan_type,
+                start_time = excluded.start_time,
+                end_time = excluded.end_time,
+                total_ports = excluded.total_ports,
+                open_ports = excluded.open_ports,
+                scan_duration_ms = excluded.scan_duration_ms,
+                status = excluded.status,
+                partial_reason = excluded.partial_reason,
+                last_port = excluded.last_port
+            WHERE scans.status = 'running'
             "#
         )
         .bind(&scan_id)
@@ -40,12 +200,26 @@ impl ScanRepository {
         .bind(scan_result.statistics.total_ports as i32)
         .bind(scan_result.open_ports.len() as i32)
         .bind(scan_result.duration().as_millis() as i64)
-        .bind("completed")
+        .bind(scan_result.status().as_str())
+        .bind(scan_result.partial.as_ref().map(|p| &p.reason))
+        .bind(scan_result.partial.as_ref().and_then(|p| p.last_reached_port).map(|port| port as i32))
         .execute(&mut *transaction)
         .await?;
+        if saved.rows_affected() == 0 {
+            drop(transaction);
+            return Err(self.not_running(&scan_id, "save").await);
+        }
 
-        // Insert port information
-        for port_info in &scan_result.open_ports {
+        // A resumed scan replaces whatever its interrupted run saved
+        for table in ["scan_ports", "scan_statistics", "scan_metadata"] {
+            query(&format!("DELETE FROM {} WHERE scan_id = ?", table))
+                .bind(&scan_id)
+                .execute(&mut *transaction)
+                .await?;
+        }
+
+        // Insert port information, closed/filtered ones too when they were recorded
+        for port_info in scan_result.open_ports.iter().chain(&scan_result.all_ports) {
             self.insert_port_info(&mut transaction, &scan_id, port_info).await?;
         }
 
@@ -71,8 +245,9 @@ impl ScanRepository {
             r#"
             INSERT INTO scan_ports (
                 scan_id, port, status, service_name, service_version, 
-                service_product, banner, response_time_ms, protocol
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                service_product, service_tunnel, service_alpn, banner, banner_raw,
+                response_time_ms, protocol
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#
         )
         .bind(scan_id)
@@ -81,7 +256,10 @@ impl ScanRepository {
         .bind(port_info.service.as_ref().map(|s| &s.name))
         .bind(port_info.service.as_ref().and_then(|s| s.version.as_deref()))
         .bind(port_info.service.as_ref().and_then(|s| s.product.as_deref()))
+        .bind(port_info.service.as_ref().and_then(|s| s.tunnel.as_deref()))
+        .bind(port_info.service.as_ref().and_then(|s| s.alpn.as_deref()))
         .bind(port_info.banner.as_deref())
+        .bind(port_info.banner_raw.as_deref())
         .bind(port_info.response_time.map(|d| d.as_millis() as i64))
         .bind(protocol_to_string(&port_info.protocol))
         .execute(&mut **transaction)
@@ -120,17 +298,25 @@ impl ScanRepository {
         scan_id: &str,
         metadata: &crate::scanner::ScanMetadata,
     ) -> Result<()> {
-        let arguments_json = serde_json::to_string(&metadata.arguments)?;
+        let arguments_json = metadata.arguments.as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
         let traceroute_json = metadata.traceroute.as_ref()
             .map(|t| serde_json::to_string(t))
             .transpose()?;
+        let coverage_json = serde_json::to_string(&metadata.coverage)?;
+        let incremental_json = metadata.incremental.as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+        let notes_json = serde_json::to_string(&metadata.notes)?;
 
         query(
             r#"
             INSERT INTO scan_metadata (
                 scan_id, scanner_version, arguments_json, hostname,
-                os_name, os_version, os_accuracy, traceroute_json
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                os_name, os_version, os_accuracy, traceroute_json,
+                asn, as_org, country_code, coverage_json, incremental_json, notes_json
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#
         )
         .bind(scan_id)
@@ -141,6 +327,12 @@ impl ScanRepository {
         .bind(metadata.os_detection.as_ref().and_then(|os| os.version.as_deref()))
         .bind(metadata.os_detection.as_ref().map(|os| os.accuracy as i32))
         .bind(traceroute_json.as_deref())
+        .bind(metadata.asn.map(i64::from))
+        .bind(metadata.as_org.as_deref())
+        .bind(metadata.country_code.as_deref())
+        .bind(&coverage_json)
+        .bind(incremental_json.as_deref())
+        .bind(&notes_json)
         .execute(&mut **transaction)
         .await?;
 
@@ -173,6 +365,25 @@ impl ScanRepository {
         Ok(scans)
     }
 
+    /// Most recent scans as summaries, reading only the columns they need
+    #[instrument(skip(self))]
+    pub async fn get_scan_summaries(&self, limit: Option<usize>) -> Result<Vec<ScanSummary>> {
+        let limit = limit.unwrap_or(50) as i64;
+        
+        let summaries = query_as::<_, ScanSummary>(
+            r#"
+            SELECT id, target, scan_type, open_ports AS open_count,
+                   scan_duration_ms AS duration_ms, created_at, status
+            FROM scans ORDER BY created_at DESC LIMIT ?
+            "#
+        )
+        .bind(limit)
+        .fetch_all(self.db.get_pool())
+        .await?;
+
+        Ok(summaries)
+    }
+
     #[instrument(skip(self))]
     pub async fn search_scans(&self, query: ScanQuery) -> Result<PaginatedResults<ScanRecord>> {
         let mut sql = "SELECT * FROM scans WHERE 1=1".to_string();
@@ -198,39 +409,31 @@ impl ScanRepository {
             params.push(status.clone());
         }
 
-        sql.push_str(" ORDER BY created_at DESC");
+        if let Some(tag) = &query.tag {
+            sql.push_str(" AND id IN (SELECT scan_id FROM scan_tags WHERE tag = ?)");
+            params.push(tag.trim().to_string());
+        }
 
         // Count total
-        let count_sql = format!("SELECT COUNT(*) FROM ({})", sql.replace("*", "1"));
-        let mut count_query = QueryBuilder::new(&count_sql);
-        
+        let count_sql = format!("SELECT COUNT(*) FROM ({})", sql.replacen("*", "1", 1));
+        let mut count_query = query_as::<_, (i64,)>(&count_sql);
         for param in &params {
-            count_query.push_bind(param);
+            count_query = count_query.bind(param);
         }
 
-        let total: (i64,) = count_query.build_query_as()
-            .fetch_one(self.db.get_pool())
-            .await?;
+        let total = count_query.fetch_one(self.db.get_pool()).await?;
 
-        // Apply pagination
-        if let Some(limit) = query.limit {
-            sql.push_str(" LIMIT ?");
-            params.push(limit.to_string());
-        }
+        // id breaks created_at ties so pages never overlap or skip rows
+        sql.push_str(" ORDER BY created_at DESC, id DESC LIMIT ? OFFSET ?");
 
-        if let Some(offset) = query.offset {
-            sql.push_str(" OFFSET ?");
-            params.push(offset.to_string());
-        }
-
-        // Execute query
-        let mut data_query = QueryBuilder::new(&sql);
-        
+        let mut data_query = query_as::<_, ScanRecord>(&sql);
         for param in &params {
-            data_query.push_bind(param);
+            data_query = data_query.bind(param);
         }
 
-        let data = data_query.build_query_as()
+        let data = data_query
+            .bind(query.limit.unwrap_or(-1))
+            .bind(query.offset.unwrap_or(0))
             .fetch_all(self.db.get_pool())
             .await?;
 
@@ -247,6 +450,72 @@ impl ScanRepository {
         })
     }
 
+    /// Label a scan; tags are free-form, trimmed, and adding one twice is a
+    /// no-op
+    #[instrument(skip(self))]
+    pub async fn add_tag(&self, scan_id: &str, tag: &str) -> Result<()> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(Error::Validation("Tag cannot be empty".to_string()));
+        }
+        if self.get_scan(scan_id).await?.is_none() {
+            return Err(Error::Validation(format!("Scan not found: {}", scan_id)));
+        }
+
+        query("INSERT OR IGNORE INTO scan_tags (scan_id, tag) VALUES (?, ?)")
+            .bind(scan_id)
+            .bind(tag)
+            .execute(self.db.get_pool())
+            .await?;
+
+        debug!("Tagged scan {} with {}", scan_id, tag);
+        Ok(())
+    }
+
+    /// Returns whether the scan carried the tag
+    #[instrument(skip(self))]
+    pub async fn remove_tag(&self, scan_id: &str, tag: &str) -> Result<bool> {
+        let result = query("DELETE FROM scan_tags WHERE scan_id = ? AND tag = ?")
+            .bind(scan_id)
+            .bind(tag.trim())
+            .execute(self.db.get_pool())
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_tags(&self, scan_id: &str) -> Result<Vec<String>> {
+        let tags = query_as::<_, (String,)>("SELECT tag FROM scan_tags WHERE scan_id = ? ORDER BY tag")
+            .bind(scan_id)
+            .fetch_all(self.db.get_pool())
+            .await?;
+
+        Ok(tags.into_iter().map(|(tag,)| tag).collect())
+    }
+
+    /// Summaries of the scans carrying `tag`, newest first
+    #[instrument(skip(self))]
+    pub async fn list_by_tag(&self, tag: &str, limit: Option<usize>) -> Result<Vec<ScanSummary>> {
+        let limit = limit.unwrap_or(50) as i64;
+
+        let summaries = query_as::<_, ScanSummary>(
+            r#"
+            SELECT id, target, scan_type, open_ports AS open_count,
+                   scan_duration_ms AS duration_ms, created_at, status
+            FROM scans
+            WHERE id IN (SELECT scan_id FROM scan_tags WHERE tag = ?)
+            ORDER BY created_at DESC LIMIT ?
+            "#
+        )
+        .bind(tag.trim())
+        .bind(limit)
+        .fetch_all(self.db.get_pool())
+        .await?;
+
+        Ok(summaries)
+    }
+
     #[instrument(skip(self))]
     pub async fn get_scan_ports(&self, scan_id: &str) -> Result<Vec<ScanPortRecord>> {
         let ports = query_as::<_, ScanPortRecord>(
@@ -259,24 +528,256 @@ impl ScanRepository {
         Ok(ports)
     }
 
+    /// `get_scan_ports` row by row, for exports of scans too large to hold
+    /// in memory. The stream keeps a pooled connection until it is dropped.
+    pub fn stream_scan_ports<'a>(&'a self, scan_id: &'a str) -> BoxStream<'a, Result<ScanPortRecord>> {
+        query_as::<_, ScanPortRecord>(
+            "SELECT * FROM scan_ports WHERE scan_id = ? ORDER BY port"
+        )
+        .bind(scan_id)
+        .fetch(self.db.get_pool())
+        .map_err(Error::from)
+        .boxed()
+    }
+
+    /// Open ports of several scans in one query, by scan ID and in port
+    /// order. Scans without open ports are absent from the map.
+    #[instrument(skip(self, scan_ids), fields(scans = scan_ids.len()))]
+    pub async fn get_open_ports_for_scans(&self, scan_ids: &[String]) -> Result<HashMap<String, Vec<ScanPortRecord>>> {
+        if scan_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let sql = format!(
+            "SELECT * FROM scan_ports WHERE status = 'open' AND scan_id IN ({}) ORDER BY scan_id, port",
+            vec!["?"; scan_ids.len()].join(", ")
+        );
+        let mut ports_query = query_as::<_, ScanPortRecord>(&sql);
+        for scan_id in scan_ids {
+            ports_query = ports_query.bind(scan_id);
+        }
+        let ports = ports_query.fetch_all(self.db.get_pool()).await?;
+
+        let mut by_scan: HashMap<String, Vec<ScanPortRecord>> = HashMap::new();
+        for port in ports {
+            by_scan.entry(port.scan_id.clone()).or_default().push(port);
+        }
+        Ok(by_scan)
+    }
+
+    /// IDs of every scan recorded for exactly this target, oldest first
+    #[instrument(skip(self))]
+    pub async fn get_scan_ids_for_target(&self, target: &str) -> Result<Vec<String>> {
+        let ids: Vec<(String,)> = query_as(
+            "SELECT id FROM scans WHERE target = ? ORDER BY created_at ASC"
+        )
+        .bind(target)
+        .fetch_all(self.db.get_pool())
+        .await?;
+
+        Ok(ids.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// Most recent scan of `target` saved at or after `since`: the baseline of
+    /// an incremental scan
+    #[instrument(skip(self))]
+    pub async fn get_latest_scan_result(
+        &self,
+        target: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<ScanResult>> {
+        let latest: Option<(String,)> = query_as(
+            r#"
+            SELECT id FROM scans
+            WHERE target = ? AND created_at >= datetime(?)
+            ORDER BY created_at DESC, rowid DESC
+            LIMIT 1
+            "#
+        )
+        .bind(target)
+        .bind(since.to_rfc3339())
+        .fetch_optional(self.db.get_pool())
+        .await?;
+
+        match latest {
+            Some((scan_id,)) => self.get_scan_result(&scan_id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Rebuild the domain ScanResult (ports, statistics, metadata) for a stored scan
+    #[instrument(skip(self))]
+    pub async fn get_scan_result(&self, scan_id: &str) -> Result<Option<ScanResult>> {
+        let record = match self.get_scan(scan_id).await? {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+
+        let ports = self.get_scan_ports(scan_id).await?;
+
+        let stats = query_as::<_, ScanStatisticsRecord>(
+            "SELECT * FROM scan_statistics WHERE scan_id = ?"
+        )
+        .bind(scan_id)
+        .fetch_optional(self.db.get_pool())
+        .await?;
+
+        let metadata = query_as::<_, ScanMetadataRecord>(
+            "SELECT * FROM scan_metadata WHERE scan_id = ?"
+        )
+        .bind(scan_id)
+        .fetch_optional(self.db.get_pool())
+        .await?;
+
+        let target_ip = record.target_ip.parse()
+            .map_err(|_| Error::Validation(format!("Invalid stored target IP: {}", record.target_ip)))?;
+
+        let mut scan_result = ScanResult::new(
+            record.target.clone(),
+            target_ip,
+            string_to_scan_type(&record.scan_type),
+        );
+        scan_result.id = record.id.clone();
+        scan_result.start_time = record.start_time.into();
+        scan_result.end_time = record.end_time.into();
+        (scan_result.open_ports, scan_result.all_ports) = ports.into_iter()
+            .map(port_record_to_info)
+            .partition(|p| p.status == crate::scanner::PortStatus::Open);
+        let status = record.scan_status()?;
+        if status == ScanStatus::Partial {
+            scan_result.partial = Some(crate::scanner::PartialScan {
+                reason: record.partial_reason.clone().unwrap_or_default(),
+                last_reached_port: record.last_port.map(|port| port as u16),
+            });
+        }
+
+        let open_ports = record.open_ports as u16;
+        let total_ports = record.total_ports as u16;
+        scan_result.statistics = crate::scanner::ScanStatistics {
+            total_ports,
+            open_ports,
+            closed_ports: total_ports.saturating_sub(open_ports),
+            scan_duration: std::time::Duration::from_millis(record.scan_duration_ms as u64),
+            packets_sent: stats.as_ref().map(|s| s.packets_sent as u64).unwrap_or(0),
+            packets_received: stats.as_ref().map(|s| s.packets_received as u64).unwrap_or(0),
+            success_rate: stats.as_ref().map(|s| s.success_rate).unwrap_or(0.0),
+            cancelled: status == ScanStatus::Cancelled,
+            ..Default::default()
+        };
+
+        if let Some(metadata) = metadata {
+            scan_result.metadata.scanner_version = metadata.scanner_version;
+            // Rows from before ScanArguments hold the raw argv, which is dropped
+            scan_result.metadata.arguments = metadata.arguments_json
+                .and_then(|json| serde_json::from_str(&json).ok());
+            scan_result.metadata.hostname = metadata.hostname;
+            scan_result.metadata.os_detection = metadata.os_name.map(|name| crate::scanner::models::OsInfo {
+                name,
+                version: metadata.os_version,
+                device_type: None,
+                accuracy: metadata.os_accuracy.unwrap_or(0) as u8,
+            });
+            scan_result.metadata.traceroute = metadata.traceroute_json
+                .map(|json| serde_json::from_str(&json))
+                .transpose()?;
+            scan_result.metadata.asn = metadata.asn.map(|asn| asn as u32);
+            scan_result.metadata.as_org = metadata.as_org;
+            scan_result.metadata.country_code = metadata.country_code;
+            scan_result.metadata.coverage = metadata.coverage_json
+                .map(|json| serde_json::from_str(&json))
+                .transpose()?
+                .unwrap_or_default();
+            scan_result.metadata.incremental = metadata.incremental_json
+                .map(|json| serde_json::from_str(&json))
+                .transpose()?;
+            scan_result.metadata.notes = metadata.notes_json
+                .map(|json| serde_json::from_str(&json))
+                .transpose()?
+                .unwrap_or_default();
+        }
+        if status == ScanStatus::Down {
+            scan_result.metadata.host_up = Some(false);
+        }
+
+        Ok(Some(scan_result))
+    }
+
+    /// Load the given scans and fold them into one merged result (see
+    /// `ScanResult::merge`). The merge is not saved; pass it to `save_scan`.
+    /// Needs at least two scans: one would come back under its own ID and
+    /// saving it would overwrite the original.
+    #[instrument(skip(self))]
+    pub async fn merge_scans(&self, scan_ids: &[String]) -> Result<ScanResult> {
+        if scan_ids.len() < 2 {
+            return Err(Error::Validation(format!(
+                "Merging needs at least two scans, got {}", scan_ids.len()
+            )));
+        }
+        let (first, rest) = scan_ids.split_first()
+            .ok_or_else(|| Error::Validation("No scans to merge".to_string()))?;
+
+        let mut merged = self.get_scan_result(first).await?
+            .ok_or_else(|| Error::Validation(format!("Scan not found: {}", first)))?;
+
+        for scan_id in rest {
+            let scan = self.get_scan_result(scan_id).await?
+                .ok_or_else(|| Error::Validation(format!("Scan not found: {}", scan_id)))?;
+            merged = merged.merge(&scan)?;
+        }
+
+        info!("Merged {} scans into {}", scan_ids.len(), merged.id);
+        Ok(merged)
+    }
+
     #[instrument(skip(self))]
     pub async fn save_vulnerability_report(&self, report: &VulnerabilityReport) -> Result<String> {
+        // Dropping the transaction on any error rolls back the header with the findings
         let mut transaction = self.db.begin_transaction().await?;
 
+        // Saving the same report again replaces it, so a failed save can simply be retried
+        query("DELETE FROM vulnerabilities WHERE report_id = ?")
+            .bind(&report.id)
+            .execute(&mut *transaction)
+            .await?;
+        query("DELETE FROM vulnerability_reports WHERE id = ?")
+            .bind(&report.id)
+            .execute(&mut *transaction)
+            .await?;
+
+        query(
+            r#"
+            INSERT INTO vulnerability_reports (
+                id, scan_id, target, target_ip, generated_at, summary_json,
+                risk_assessment_json, recommendations_json, include_suppressed
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&report.id)
+        .bind(&report.scan_id)
+        .bind(&report.target)
+        .bind(report.target_ip.to_string())
+        .bind(report.generated_at)
+        .bind(serde_json::to_string(&report.summary)?)
+        .bind(serde_json::to_string(&report.risk_assessment)?)
+        .bind(serde_json::to_string(&report.recommendations)?)
+        .bind(report.include_suppressed)
+        .execute(&mut *transaction)
+        .await?;
+
         for vulnerability in &report.vulnerabilities {
-            self.insert_vulnerability(&mut transaction, &report.scan_id, vulnerability).await?;
+            self.insert_vulnerability(&mut transaction, report, vulnerability).await?;
         }
 
         transaction.commit().await?;
         
-        info!("Vulnerability report saved for scan: {}", report.scan_id);
+        info!("Vulnerability report {} saved for scan: {}", report.id, report.scan_id);
         Ok(report.id.clone())
     }
 
     async fn insert_vulnerability(
         &self,
         transaction: &mut sqlx::Transaction<'_, Sqlite>,
-        scan_id: &str,
+        report: &VulnerabilityReport,
         vulnerability: &Vulnerability,
     ) -> Result<()> {
         let references_json = serde_json::to_string(&vulnerability.references)?;
@@ -287,12 +788,12 @@ impl ScanRepository {
             INSERT INTO vulnerabilities (
                 id, scan_id, cve_id, title, description, level, cvss_score, cvss_vector,
                 port, service, protocol, evidence, references_json, discovered_at,
-                mitigation, exploit_available, impact, certainty, tags_json
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                mitigation, exploit_available, impact, certainty, tags_json, report_id
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#
         )
         .bind(&vulnerability.id)
-        .bind(scan_id)
+        .bind(&report.scan_id)
         .bind(vulnerability.cve_id.as_deref())
         .bind(&vulnerability.title)
         .bind(&vulnerability.description)
@@ -310,67 +811,176 @@ impl ScanRepository {
         .bind(&vulnerability.impact)
         .bind(vulnerability.certainty as i32)
         .bind(&tags_json)
+        .bind(&report.id)
         .execute(&mut **transaction)
         .await?;
 
         Ok(())
     }
 
+    /// Rebuild the assessment saved for a scan. Suppression flags aren't
+    /// stored; apply the target's suppressions again if they matter.
     #[instrument(skip(self))]
-    pub async fn get_vulnerabilities(&self, query: VulnerabilityQuery) -> Result<Vec<VulnerabilityRecord>> {
-        let mut sql = "SELECT * FROM vulnerabilities WHERE 1=1".to_string();
-        let mut params: Vec<String> = Vec::new();
-
-        if let Some(scan_id) = &query.scan_id {
-            sql.push_str(" AND scan_id = ?");
-            params.push(scan_id.clone());
-        }
+    pub async fn get_vulnerability_report(&self, scan_id: &str) -> Result<Option<VulnerabilityReport>> {
+        let record = match self.get_scan(scan_id).await? {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        let target_ip = record.target_ip.parse()
+            .map_err(|_| Error::Validation(format!("Invalid stored target IP: {}", record.target_ip)))?;
+
+        let records = query_as::<_, VulnerabilityRecord>(
+            "SELECT * FROM vulnerabilities WHERE scan_id = ? ORDER BY discovered_at"
+        )
+        .bind(scan_id)
+        .fetch_all(self.db.get_pool())
+        .await?;
 
-        if let Some(level) = &query.level {
-            sql.push_str(" AND level = ?");
-            params.push(level.clone());
-        }
+        let mut report = VulnerabilityReport::new(record.id, record.target, target_ip);
+        report.vulnerabilities = records.into_iter()
+            .map(vulnerability_from_record)
+            .collect::<Result<_>>()?;
+        report.refresh();
+        Ok(Some(report))
+    }
 
-        if let Some(port) = query.port {
-            sql.push_str(" AND port = ?");
-            params.push(port.to_string());
-        }
+    /// The report exactly as saved, header and findings, e.g. for re-export.
+    /// Unlike `get_vulnerability_report` nothing is recomputed.
+    #[instrument(skip(self))]
+    pub async fn load_vulnerability_report(&self, report_id: &str) -> Result<Option<VulnerabilityReport>> {
+        let header = query_as::<_, VulnerabilityReportRecord>(
+            "SELECT * FROM vulnerability_reports WHERE id = ?"
+        )
+        .bind(report_id)
+        .fetch_optional(self.db.get_pool())
+        .await?;
+        let header = match header {
+            Some(header) => header,
+            None => return Ok(None),
+        };
 
-        if let Some(service) = &query.service {
-            sql.push_str(" AND service = ?");
-            params.push(service.clone());
-        }
+        let records = query_as::<_, VulnerabilityRecord>(
+            "SELECT * FROM vulnerabilities WHERE report_id = ? ORDER BY discovered_at, rowid"
+        )
+        .bind(report_id)
+        .fetch_all(self.db.get_pool())
+        .await?;
 
-        if let Some(date_from) = &query.date_from {
-            sql.push_str(" AND discovered_at >= ?");
-            params.push(date_from.to_rfc3339());
-        }
+        let target_ip = header.target_ip.parse()
+            .map_err(|_| Error::Validation(format!("Invalid stored target IP: {}", header.target_ip)))?;
+
+        Ok(Some(VulnerabilityReport {
+            id: header.id,
+            scan_id: header.scan_id,
+            target: header.target,
+            target_ip,
+            generated_at: header.generated_at,
+            vulnerabilities: records.into_iter()
+                .map(vulnerability_from_record)
+                .collect::<Result<_>>()?,
+            summary: serde_json::from_str(&header.summary_json)?,
+            risk_assessment: serde_json::from_str(&header.risk_assessment_json)?,
+            recommendations: serde_json::from_str(&header.recommendations_json)?,
+            include_suppressed: header.include_suppressed,
+        }))
+    }
 
-        if let Some(date_to) = &query.date_to {
-            sql.push_str(" AND discovered_at <= ?");
-            params.push(date_to.to_rfc3339());
+    /// Compare the stored assessments of two scans, each with its target's
+    /// active suppressions applied (see `VulnerabilityReport::diff`)
+    #[instrument(skip(self))]
+    pub async fn diff_vulnerability_reports(
+        &self,
+        previous_scan_id: &str,
+        current_scan_id: &str,
+        include_suppressed: bool,
+    ) -> Result<VulnDiff> {
+        let mut reports = Vec::with_capacity(2);
+        for scan_id in [previous_scan_id, current_scan_id] {
+            let mut report = self.get_vulnerability_report(scan_id).await?
+                .ok_or_else(|| Error::Validation(format!("Scan not found: {}", scan_id)))?;
+            let suppressions = self.get_active_suppressions(&report.target).await?;
+            SuppressionList::new(suppressions).apply(&mut report, include_suppressed);
+            reports.push(report);
         }
 
-        sql.push_str(" ORDER BY discovered_at DESC");
-
-        if let Some(limit) = query.limit {
-            sql.push_str(" LIMIT ?");
-            params.push(limit.to_string());
-        }
+        reports[1].diff(&reports[0])
+    }
 
-        let mut db_query = QueryBuilder::new(&sql);
-        
+    #[instrument(skip(self))]
+    pub async fn get_vulnerabilities(&self, query: VulnerabilityQuery) -> Result<Vec<VulnerabilityRecord>> {
+        let (sql, params) = vulnerability_query_sql(&query);
+        let mut db_query = query_as::<_, VulnerabilityRecord>(&sql);
         for param in &params {
-            db_query.push_bind(param);
+            db_query = db_query.bind(param);
         }
 
-        let vulnerabilities = db_query.build_query_as()
+        let vulnerabilities = db_query
             .fetch_all(self.db.get_pool())
             .await?;
 
         Ok(vulnerabilities)
     }
 
+    #[instrument(skip(self))]
+    pub async fn add_suppression(&self, suppression: &Suppression) -> Result<String> {
+        query(
+            r#"
+            INSERT INTO vuln_suppressions (id, target, port, rule_id, reason, expires_at, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&suppression.id)
+        .bind(&suppression.target)
+        .bind(suppression.port.map(|p| p as i32))
+        .bind(&suppression.rule_id)
+        .bind(&suppression.reason)
+        .bind(suppression.expires_at)
+        .bind(suppression.created_at)
+        .execute(self.db.get_pool())
+        .await?;
+
+        info!("Suppression added: {} on {}", suppression.rule_id, suppression.target);
+        Ok(suppression.id.clone())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn list_suppressions(&self, target: Option<&str>) -> Result<Vec<Suppression>> {
+        let records = match target {
+            Some(target) => query_as::<_, SuppressionRecord>(
+                "SELECT * FROM vuln_suppressions WHERE target = ? ORDER BY created_at DESC"
+            )
+            .bind(target)
+            .fetch_all(self.db.get_pool())
+            .await?,
+            None => query_as::<_, SuppressionRecord>(
+                "SELECT * FROM vuln_suppressions ORDER BY created_at DESC"
+            )
+            .fetch_all(self.db.get_pool())
+            .await?,
+        };
+
+        Ok(records.into_iter().map(suppression_from_record).collect())
+    }
+
+    /// Suppressions for a target that have not expired yet
+    #[instrument(skip(self))]
+    pub async fn get_active_suppressions(&self, target: &str) -> Result<Vec<Suppression>> {
+        let now = Utc::now();
+        let suppressions = self.list_suppressions(Some(target)).await?;
+
+        Ok(suppressions.into_iter().filter(|s| !s.is_expired(now)).collect())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn delete_suppression(&self, suppression_id: &str) -> Result<bool> {
+        let result = query("DELETE FROM vuln_suppressions WHERE id = ?")
+            .bind(suppression_id)
+            .execute(self.db.get_pool())
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
     #[instrument(skip(self))]
     pub async fn get_scan_stats(&self) -> Result<ScanStats> {
         let stats = query_as::<_, (i64, i64, i64, f64, i64, f64)>(
@@ -398,6 +1008,55 @@ impl ScanRepository {
         })
     }
 
+    /// Open-port count per scan for one target since `since`, oldest first
+    #[instrument(skip(self))]
+    pub async fn get_target_timeline(
+        &self,
+        target: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<TargetTimelinePoint>> {
+        let timeline = query_as::<_, TargetTimelinePoint>(
+            r#"
+            SELECT created_at AS timestamp, open_ports, id AS scan_id
+            FROM scans
+            WHERE target = ? AND created_at >= datetime(?)
+            ORDER BY created_at ASC
+            "#
+        )
+        .bind(target)
+        .bind(since.to_rfc3339())
+        .fetch_all(self.db.get_pool())
+        .await?;
+
+        debug!("Loaded {} timeline points for {}", timeline.len(), target);
+        Ok(timeline)
+    }
+
+    /// Most-scanned targets first; among equally scanned ones, the most
+    /// exposed. Served by `idx_scans_target`.
+    #[instrument(skip(self))]
+    pub async fn top_targets(&self, limit: usize) -> Result<Vec<TopTarget>> {
+        let targets = query_as::<_, TopTarget>(
+            r#"
+            SELECT
+                target,
+                COUNT(*) AS scan_count,
+                MAX(open_ports) AS max_open_ports,
+                MAX(created_at) AS last_scanned
+            FROM scans
+            GROUP BY target
+            ORDER BY scan_count DESC, max_open_ports DESC, last_scanned DESC
+            LIMIT ?
+            "#
+        )
+        .bind(limit as i64)
+        .fetch_all(self.db.get_pool())
+        .await?;
+
+        debug!("Loaded {} top targets", targets.len());
+        Ok(targets)
+    }
+
     #[instrument(skip(self))]
     pub async fn get_vulnerability_stats(&self) -> Result<VulnerabilityStats> {
         let stats = query_as::<_, (i64, i64, i64, i64, i64, i64, f64)>(
@@ -427,41 +1086,387 @@ impl ScanRepository {
         })
     }
 
+    /// Rewrite the stored ports of a scan, e.g. after `ScanEngine::enrich`
+    #[instrument(skip(self, scan_result))]
+    pub async fn replace_scan_ports(&self, scan_result: &ScanResult) -> Result<()> {
+        let mut transaction = self.db.begin_transaction().await?;
+
+        query("DELETE FROM scan_ports WHERE scan_id = ?")
+            .bind(&scan_result.id)
+            .execute(&mut *transaction)
+            .await?;
+
+        for port_info in scan_result.open_ports.iter().chain(&scan_result.all_ports) {
+            self.insert_port_info(&mut transaction, &scan_result.id, port_info).await?;
+        }
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Store the latest checkpoint of a running scan, replacing the previous one
+    #[instrument(skip(self, checkpoint), fields(scan_id = %checkpoint.scan_id))]
+    pub async fn save_checkpoint(&self, checkpoint: &ScanCheckpoint) -> Result<()> {
+        query(
+            "INSERT OR REPLACE INTO scan_checkpoints (scan_id, target, checkpoint_json, updated_at) \
+             VALUES (?, ?, ?, CURRENT_TIMESTAMP)"
+        )
+        .bind(&checkpoint.scan_id)
+        .bind(&checkpoint.target)
+        .bind(serde_json::to_string(checkpoint)?)
+        .execute(self.db.get_pool())
+        .await?;
+
+        debug!("Checkpointed {} ({} ranges done)", checkpoint.scan_id, checkpoint.completed.len());
+        Ok(())
+    }
+
     #[instrument(skip(self))]
-    pub async fn delete_scan(&self, scan_id: &str) -> Result<bool> {
-        let result = query("DELETE FROM scans WHERE id = ?")
+    pub async fn get_checkpoint(&self, scan_id: &str) -> Result<Option<ScanCheckpoint>> {
+        let row: Option<(String,)> = query_as("SELECT checkpoint_json FROM scan_checkpoints WHERE scan_id = ?")
+            .bind(scan_id)
+            .fetch_optional(self.db.get_pool())
+            .await?;
+
+        Ok(row.map(|(json,)| serde_json::from_str(&json)).transpose()?)
+    }
+
+    /// Drop the checkpoint of a scan once its result is saved
+    #[instrument(skip(self))]
+    pub async fn delete_checkpoint(&self, scan_id: &str) -> Result<()> {
+        query("DELETE FROM scan_checkpoints WHERE scan_id = ?")
             .bind(scan_id)
             .execute(self.db.get_pool())
             .await?;
+        Ok(())
+    }
 
-        Ok(result.rows_affected() > 0)
+    /// Remove one scan with its child rows, as `delete_by_target` does;
+    /// false when there was no such scan
+    #[instrument(skip(self))]
+    pub async fn delete_scan(&self, scan_id: &str) -> Result<bool> {
+        Ok(self.delete_scans_where("id = ?", scan_id, &[]).await? > 0)
+    }
+
+    /// Remove every scan of exactly `target` with all of its child rows
+    /// (ports, statistics, metadata, tags, vulnerability reports and
+    /// findings) and any unfinished checkpoint, e.g. when a host is retired.
+    /// Suppressions are kept: they are configuration, not scan data. Returns
+    /// the number of scans removed.
+    #[instrument(skip(self))]
+    pub async fn delete_by_target(&self, target: &str) -> Result<u64> {
+        let removed = self.delete_scans_where(
+            "target = ?",
+            target,
+            &["DELETE FROM scan_checkpoints WHERE target = ?"],
+        ).await?;
+
+        info!("Purged {} scans of {}", removed, target);
+        Ok(removed)
     }
 
+    /// Remove the scans stored more than `older_than_days` ago, with their
+    /// child rows as `delete_by_target` does
     #[instrument(skip(self))]
     pub async fn cleanup_old_scans(&self, older_than_days: i64) -> Result<u64> {
-        let result = query(
-            "DELETE FROM scans WHERE created_at < datetime('now', ?)"
-        )
-        .bind(format!("-{} days", older_than_days))
-        .execute(self.db.get_pool())
-        .await?;
+        let removed = self.delete_scans_where(
+            "created_at < datetime('now', ?)",
+            &format!("-{} days", older_than_days),
+            &[],
+        ).await?;
+
+        info!("Cleaned up {} old scans", removed);
+        Ok(removed)
+    }
+
+    /// Delete the scans matching `condition` (a `WHERE` clause on `scans`,
+    /// every `?` bound to `value`), their child rows and checkpoints, plus
+    /// the `extra` statements, in one transaction. Children are deleted
+    /// explicitly rather than through `ON DELETE CASCADE`, since `PRAGMA
+    /// foreign_keys` is only set on the connection that ran the migrations.
+    /// Returns the number of scans removed.
+    async fn delete_scans_where(&self, condition: &str, value: &str, extra: &[&str]) -> Result<u64> {
+        let scans = format!("SELECT id FROM scans WHERE {}", condition);
+        let mut transaction = self.db.begin_transaction().await?;
+
+        let children = [
+            format!(
+                "DELETE FROM vulnerability_references WHERE vulnerability_id IN \
+                 (SELECT id FROM vulnerabilities WHERE scan_id IN ({0}) \
+                 OR report_id IN (SELECT id FROM vulnerability_reports WHERE scan_id IN ({0})))",
+                scans
+            ),
+            format!(
+                "DELETE FROM vulnerabilities WHERE scan_id IN ({0}) \
+                 OR report_id IN (SELECT id FROM vulnerability_reports WHERE scan_id IN ({0}))",
+                scans
+            ),
+            format!("DELETE FROM vulnerability_reports WHERE scan_id IN ({})", scans),
+            format!("DELETE FROM scan_ports WHERE scan_id IN ({})", scans),
+            format!("DELETE FROM scan_statistics WHERE scan_id IN ({})", scans),
+            format!("DELETE FROM scan_metadata WHERE scan_id IN ({})", scans),
+            format!("DELETE FROM scan_tags WHERE scan_id IN ({})", scans),
+            format!("DELETE FROM scan_checkpoints WHERE scan_id IN ({})", scans),
+        ];
+        for statement in children.iter().map(String::as_str).chain(extra.iter().copied()) {
+            let mut delete = query(statement);
+            for _ in 0..statement.matches('?').count() {
+                delete = delete.bind(value);
+            }
+            delete.execute(&mut *transaction).await?;
+        }
+
+        let delete_scans = format!("DELETE FROM scans WHERE {}", condition);
+        let mut delete = query(&delete_scans);
+        for _ in 0..condition.matches('?').count() {
+            delete = delete.bind(value);
+        }
+        let result = delete.execute(&mut *transaction).await?;
 
-        info!("Cleaned up {} old scans", result.rows_affected());
+        transaction.commit().await?;
         Ok(result.rows_affected())
     }
 }
 
+/// Periodic `cleanup_old_scans` for long-running modes, so the database
+/// doesn't grow without bound. A run never overlaps another: the loop waits
+/// for each cleanup, and `run_once` skips while one is in progress.
+#[derive(Clone)]
+pub struct RetentionTask {
+    repository: ScanRepository,
+    retain_days: u32,
+    interval: Duration,
+    running: std::sync::Arc<tokio::sync::Mutex<()>>,
+}
+
+impl RetentionTask {
+    pub fn new(repository: ScanRepository, retain_days: u32, interval: Duration) -> Self {
+        Self {
+            repository,
+            retain_days,
+            interval,
+            running: std::sync::Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    /// From `database.retain_days`; `None` when retention is off
+    pub fn from_settings(repository: ScanRepository, settings: &crate::config::DatabaseSettings) -> Option<Self> {
+        (settings.retain_days > 0).then(|| Self::new(
+            repository,
+            settings.retain_days,
+            Duration::from_secs(u64::from(settings.retention_interval_hours.max(1)) * 3600),
+        ))
+    }
+
+    /// Delete the scans past the retention window; `None` if a cleanup was
+    /// already running
+    pub async fn run_once(&self) -> Result<Option<u64>> {
+        let Ok(_running) = self.running.try_lock() else {
+            debug!("Retention cleanup already running, skipping");
+            return Ok(None);
+        };
+        let removed = self.repository.cleanup_old_scans(i64::from(self.retain_days)).await?;
+        info!("🧹 Retention: removed {} scans older than {} days", removed, self.retain_days);
+        Ok(Some(removed))
+    }
+
+    /// Clean up now, then every `interval`, until the runtime shuts down
+    pub fn spawn(self) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(self.interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                if let Err(e) = self.run_once().await {
+                    warn!("Retention cleanup failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
 // Conversion helper functions
-fn scan_type_to_string(scan_type: &ScanType) -> String {
+pub(crate) fn scan_type_to_string(scan_type: &ScanType) -> String {
     match scan_type {
         ScanType::Quick => "quick".to_string(),
         ScanType::Standard => "standard".to_string(),
         ScanType::Full => "full".to_string(),
+        ScanType::KnownServices => "known_services".to_string(),
         ScanType::CustomRange(start, end) => format!("custom_{}_{}", start, end),
         ScanType::Targeted(_) => "targeted".to_string(),
     }
 }
 
+fn string_to_scan_type(value: &str) -> ScanType {
+    match value {
+        "quick" => ScanType::Quick,
+        "standard" => ScanType::Standard,
+        "full" => ScanType::Full,
+        "known_services" => ScanType::KnownServices,
+        custom if custom.starts_with("custom_") => {
+            let mut bounds = custom["custom_".len()..].split('_').filter_map(|p| p.parse().ok());
+            match (bounds.next(), bounds.next()) {
+                (Some(start), Some(end)) => ScanType::CustomRange(start, end),
+                _ => ScanType::Targeted(Vec::new()),
+            }
+        }
+        _ => ScanType::Targeted(Vec::new()),
+    }
+}
+
+pub(crate) fn port_record_to_info(record: ScanPortRecord) -> PortInfo {
+    PortInfo {
+        port: record.port as u16,
+        status: string_to_port_status(&record.status),
+        service: record.service_name.map(|name| crate::scanner::ServiceInfo {
+            name,
+            version: record.service_version,
+            product: record.service_product,
+            extra_info: None,
+            confidence: 80,
+            tunnel: record.service_tunnel,
+            alpn: record.service_alpn,
+        }),
+        banner: record.banner,
+        banner_raw: record.banner_raw,
+        response_time: record.response_time_ms.map(|ms| std::time::Duration::from_millis(ms as u64)),
+        protocol: string_to_protocol(&record.protocol),
+        tls: None,
+    }
+}
+
+fn string_to_port_status(value: &str) -> crate::scanner::PortStatus {
+    match value {
+        "open" => crate::scanner::PortStatus::Open,
+        "closed" => crate::scanner::PortStatus::Closed,
+        "filtered" => crate::scanner::PortStatus::Filtered,
+        "open_filtered" => crate::scanner::PortStatus::OpenFiltered,
+        _ => crate::scanner::PortStatus::Unknown,
+    }
+}
+
+fn string_to_protocol(value: &str) -> crate::scanner::Protocol {
+    match value {
+        "udp" => crate::scanner::Protocol::Udp,
+        "sctp" => crate::scanner::Protocol::Sctp,
+        _ => crate::scanner::Protocol::Tcp,
+    }
+}
+
+fn suppression_from_record(record: SuppressionRecord) -> Suppression {
+    Suppression {
+        id: record.id,
+        target: record.target,
+        port: record.port.map(|p| p as u16),
+        rule_id: record.rule_id,
+        reason: record.reason,
+        expires_at: record.expires_at,
+        created_at: record.created_at,
+    }
+}
+
+/// SQL and bind parameters selecting the vulnerabilities `query` asks for
+fn vulnerability_query_sql(query: &VulnerabilityQuery) -> (String, Vec<String>) {
+    let mut sql = "SELECT * FROM vulnerabilities WHERE 1=1".to_string();
+    let mut params: Vec<String> = Vec::new();
+
+    if let Some(scan_id) = &query.scan_id {
+        sql.push_str(" AND scan_id = ?");
+        params.push(scan_id.clone());
+    }
+
+    if let Some(level) = &query.level {
+        sql.push_str(" AND level = ?");
+        params.push(level.clone());
+    }
+
+    if let Some(min_level) = &query.min_level {
+        use crate::vulnerability::VulnerabilityLevel::*;
+        let levels: Vec<String> = [Info, Low, Medium, High, Critical]
+            .iter()
+            .filter(|level| *level >= min_level)
+            .map(vulnerability_level_to_string)
+            .collect();
+        sql.push_str(&format!(" AND level IN ({})", vec!["?"; levels.len()].join(", ")));
+        params.extend(levels);
+    }
+
+    if let Some(port) = query.port {
+        sql.push_str(" AND port = ?");
+        params.push(port.to_string());
+    }
+
+    if let Some(service) = &query.service {
+        sql.push_str(" AND service = ?");
+        params.push(service.clone());
+    }
+
+    if let Some(date_from) = &query.date_from {
+        sql.push_str(" AND discovered_at >= ?");
+        params.push(date_from.to_rfc3339());
+    }
+
+    if let Some(date_to) = &query.date_to {
+        sql.push_str(" AND discovered_at <= ?");
+        params.push(date_to.to_rfc3339());
+    }
+
+    sql.push_str(" ORDER BY discovered_at DESC");
+
+    if let Some(limit) = query.limit {
+        sql.push_str(" LIMIT ?");
+        params.push(limit.to_string());
+    }
+
+    (sql, params)
+}
+
+fn vulnerability_from_record(record: VulnerabilityRecord) -> Result<Vulnerability> {
+    let references = record.references_json
+        .map(|json| serde_json::from_str(&json))
+        .transpose()?
+        .unwrap_or_default();
+    let tags = record.tags_json
+        .map(|json| serde_json::from_str(&json))
+        .transpose()?
+        .unwrap_or_default();
+
+    Ok(Vulnerability {
+        id: record.id,
+        cve_id: record.cve_id,
+        title: record.title,
+        description: record.description,
+        level: string_to_vulnerability_level(&record.level),
+        cvss_score: record.cvss_score.map(|score| score as f32),
+        cvss_vector: record.cvss_vector,
+        port: record.port as u16,
+        service: record.service,
+        protocol: record.protocol,
+        evidence: record.evidence,
+        references,
+        discovered_at: record.discovered_at,
+        mitigation: record.mitigation,
+        exploit_available: record.exploit_available,
+        exploit_maturity: None,
+        impact: record.impact.unwrap_or_default(),
+        certainty: record.certainty.clamp(0, 100) as u8,
+        tags,
+        suppressed: false,
+    })
+}
+
+fn string_to_vulnerability_level(value: &str) -> crate::vulnerability::VulnerabilityLevel {
+    use crate::vulnerability::VulnerabilityLevel;
+
+    match value {
+        "critical" => VulnerabilityLevel::Critical,
+        "high" => VulnerabilityLevel::High,
+        "medium" => VulnerabilityLevel::Medium,
+        "low" => VulnerabilityLevel::Low,
+        _ => VulnerabilityLevel::Info,
+    }
+}
+
 fn port_status_to_string(status: &crate::scanner::PortStatus) -> String {
     match status {
         crate::scanner::PortStatus::Open => "open",
@@ -489,3 +1494,834 @@ fn vulnerability_level_to_string(level: &crate::vulnerability::VulnerabilityLeve
         crate::vulnerability::VulnerabilityLevel::Critical => "critical",
     }.to_string()
       }
+
+/// Keeps the `scans` row of every scan an engine runs in step with it.
+/// Registered with `ScanEngine::with_observer`, it stores each scan as
+/// running when it starts; the caller then ends the row with `save` once a
+/// result came, or with `fail` / `cancel` if none did. A scan stays in
+/// `running_scan` until its row has been ended.
+pub struct ScanStatusRecorder {
+    repository: ScanRepository,
+    running: Mutex<HashMap<String, String>>, // Scan ID → target
+}
+
+impl ScanStatusRecorder {
+    pub fn new(repository: ScanRepository) -> Self {
+        Self { repository, running: Mutex::new(HashMap::new()) }
+    }
+
+    /// ID of a scan of `target` that started and whose row is still running;
+    /// for callers that run one scan per target and learn of a failure
+    /// without its ID
+    pub fn running_scan(&self, target: &str) -> Option<String> {
+        self.running.lock().unwrap()
+            .iter()
+            .find(|(_, running_target)| running_target.as_str() == target)
+            .map(|(scan_id, _)| scan_id.clone())
+    }
+
+    /// Store the finished scan with `save_scan`, ending its row. A failed
+    /// save stores the scan as failed instead, so the row isn't left running.
+    pub async fn save(&self, result: &ScanResult) -> Result<String> {
+        match self.repository.save_scan(result).await {
+            Ok(scan_id) => {
+                self.running.lock().unwrap().remove(&result.id);
+                Ok(scan_id)
+            }
+            Err(e) => {
+                self.fail(&result.id, &format!("Could not save the result: {}", e)).await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Store the scan as failed; false when it never started (e.g. the
+    /// target didn't resolve), so there is no row to update
+    pub async fn fail(&self, scan_id: &str, reason: &str) -> Result<bool> {
+        if !self.running.lock().unwrap().contains_key(scan_id) {
+            return Ok(false);
+        }
+        self.repository.fail_scan(scan_id, reason).await?;
+        self.running.lock().unwrap().remove(scan_id);
+        Ok(true)
+    }
+
+    /// Store the scan as cancelled; false when it never started
+    pub async fn cancel(&self, scan_id: &str) -> Result<bool> {
+        if !self.running.lock().unwrap().contains_key(scan_id) {
+            return Ok(false);
+        }
+        self.repository.cancel_scan(scan_id).await?;
+        self.running.lock().unwrap().remove(scan_id);
+        Ok(true)
+    }
+}
+
+#[async_trait::async_trait]
+impl ScanObserver for ScanStatusRecorder {
+    async fn on_scan_start(&self, result: &ScanResult) {
+        // Bookkeeping only: a database hiccup must not stop the scan
+        match self.repository.start_scan(result).await {
+            Ok(()) => {
+                self.running.lock().unwrap().insert(result.id.clone(), result.target.clone());
+            }
+            Err(e) => warn!("Could not record scan {} as running: {}", result.id, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_health_check_in_memory() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        let repository = ScanRepository::new(db.clone());
+
+        assert!(repository.health_check().await.unwrap());
+        let health = repository.health_details().await;
+        assert!(health.schema_ok);
+        assert!(health.db_latency_ms.is_some());
+
+        db.close().await.unwrap();
+        assert!(!repository.health_check().await.unwrap());
+        assert!(!repository.health_details().await.schema_ok);
+    }
+
+    #[tokio::test]
+    async fn test_summary_matches_full_record() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        let repository = ScanRepository::new(db);
+
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::CustomRange(1, 100));
+        scan.add_open_port(PortInfo {
+            port: 22,
+            status: crate::scanner::PortStatus::Open,
+            service: None,
+            banner: None,
+            banner_raw: None,
+            response_time: None,
+            protocol: crate::scanner::Protocol::Tcp,
+            tls: None,
+        });
+        scan.finalize();
+        repository.save_scan(&scan).await.unwrap();
+
+        let stored = repository.get_scan_summaries(None).await.unwrap();
+        let record = repository.get_scan(&scan.id).await.unwrap().unwrap();
+        let in_memory = scan.to_summary();
+
+        assert_eq!(stored.len(), 1);
+        let summary = &stored[0];
+        assert_eq!(summary.id, record.id);
+        assert_eq!(summary.target, record.target);
+        assert_eq!(summary.scan_type, record.scan_type);
+        assert_eq!(summary.open_count, record.open_ports);
+        assert_eq!(summary.duration_ms, record.scan_duration_ms);
+        assert_eq!(summary.created_at, record.created_at);
+        assert_eq!(summary.status, record.status);
+
+        // created_at is the insert time in the database, the end time in memory
+        assert_eq!(
+            ScanSummary { created_at: in_memory.created_at, ..summary.clone() },
+            in_memory
+        );
+        assert!((summary.created_at - in_memory.created_at).num_seconds().abs() <= 5);
+    }
+
+    #[tokio::test]
+    async fn test_tls_service_details_survive_a_reload() {
+        let repository = ScanRepository::new(Database::new("sqlite::memory:").await.unwrap());
+
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        scan.add_open_port(PortInfo {
+            port: 8443,
+            status: crate::scanner::PortStatus::Open,
+            service: Some(crate::scanner::ServiceInfo {
+                name: "http".to_string(),
+                version: None,
+                product: Some("nginx".to_string()),
+                extra_info: None,
+                confidence: 80,
+                tunnel: Some("ssl".to_string()),
+                alpn: Some("h2".to_string()),
+            }),
+            banner: None,
+            banner_raw: None,
+            response_time: None,
+            protocol: crate::scanner::Protocol::Tcp,
+            tls: None,
+        });
+        scan.finalize();
+        repository.save_scan(&scan).await.unwrap();
+
+        let reloaded = repository.get_scan_result(&scan.id).await.unwrap().unwrap();
+        let service = reloaded.open_ports[0].service.as_ref().unwrap();
+        assert_eq!(service.tunnel.as_deref(), Some("ssl"));
+        assert_eq!(service.alpn.as_deref(), Some("h2"));
+        assert_eq!(service.description(), "http over ssl/tls");
+    }
+
+    #[tokio::test]
+    async fn test_rechecked_scan_survives_a_reload() {
+        let repository = ScanRepository::new(Database::new("sqlite::memory:").await.unwrap());
+        let port = |port: u16, status: crate::scanner::PortStatus| PortInfo {
+            port,
+            status,
+            service: None,
+            banner: None,
+            banner_raw: None,
+            response_time: None,
+            protocol: crate::scanner::Protocol::Tcp,
+            tls: None,
+        };
+
+        // As verify_open leaves it: 8080 was open once, then not again
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        scan.add_open_port(port(22, crate::scanner::PortStatus::Open));
+        scan.all_ports.push(port(8080, crate::scanner::PortStatus::OpenFiltered));
+        scan.metadata.notes.push("Port 8080 was open, then didn't answer the recheck".to_string());
+        scan.finalize();
+        repository.save_scan(&scan).await.unwrap();
+
+        let reloaded = repository.get_scan_result(&scan.id).await.unwrap().unwrap();
+        assert_eq!(reloaded.open_ports.iter().map(|p| p.port).collect::<Vec<_>>(), vec![22]);
+        assert_eq!(reloaded.all_ports.len(), 1);
+        assert_eq!(reloaded.all_ports[0].port, 8080);
+        assert_eq!(reloaded.all_ports[0].status, crate::scanner::PortStatus::OpenFiltered);
+        assert_eq!(reloaded.metadata.notes, scan.metadata.notes);
+    }
+
+    #[tokio::test]
+    async fn test_scan_status_transitions() {
+        let repository = ScanRepository::new(Database::new("sqlite::memory:").await.unwrap());
+        let status = |record: Option<ScanRecord>| record.unwrap().scan_status().unwrap();
+
+        // Started, then saved: running → completed
+        let mut finished = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        repository.start_scan(&finished).await.unwrap();
+        assert_eq!(status(repository.get_scan(&finished.id).await.unwrap()), ScanStatus::Running);
+        finished.finalize();
+        repository.save_scan(&finished).await.unwrap();
+        assert_eq!(status(repository.get_scan(&finished.id).await.unwrap()), ScanStatus::Completed);
+        assert!(repository.get_scan_result(&finished.id).await.unwrap().is_some());
+
+        // A finished scan is never reopened or overwritten
+        assert!(matches!(repository.start_scan(&finished).await, Err(Error::Validation(_))));
+        assert!(matches!(repository.save_scan(&finished).await, Err(Error::Validation(_))));
+        assert!(matches!(repository.fail_scan(&finished.id, "late").await, Err(Error::Validation(_))));
+
+        // running → failed, with the reason kept
+        let failed = ScanResult::new("10.0.0.2".to_string(), "10.0.0.2".parse().unwrap(), ScanType::Quick);
+        repository.start_scan(&failed).await.unwrap();
+        repository.fail_scan(&failed.id, "Too many open files").await.unwrap();
+        let record = repository.get_scan(&failed.id).await.unwrap().unwrap();
+        assert_eq!(record.scan_status().unwrap(), ScanStatus::Failed);
+        assert_eq!(record.failure_reason.as_deref(), Some("Too many open files"));
+        assert!(matches!(repository.cancel_scan(&failed.id).await, Err(Error::Validation(_))));
+
+        // running → cancelled → running again when resumed → partial
+        let mut resumed = ScanResult::new("10.0.0.3".to_string(), "10.0.0.3".parse().unwrap(), ScanType::Quick);
+        repository.start_scan(&resumed).await.unwrap();
+        repository.cancel_scan(&resumed.id).await.unwrap();
+        assert_eq!(status(repository.get_scan(&resumed.id).await.unwrap()), ScanStatus::Cancelled);
+        repository.start_scan(&resumed).await.unwrap();
+        assert_eq!(status(repository.get_scan(&resumed.id).await.unwrap()), ScanStatus::Running);
+        resumed.partial = Some(crate::scanner::PartialScan {
+            reason: "Host unreachable".to_string(),
+            last_reached_port: Some(443),
+        });
+        resumed.finalize();
+        repository.save_scan(&resumed).await.unwrap();
+        let record = repository.get_scan(&resumed.id).await.unwrap().unwrap();
+        assert_eq!(record.scan_status().unwrap(), ScanStatus::Partial);
+        assert_eq!(record.failure_reason, None);
+
+        // Interrupted with its findings saved, then resumed: the resumed
+        // run's ports replace the interrupted run's
+        let mut interrupted = ScanResult::new("10.0.0.5".to_string(), "10.0.0.5".parse().unwrap(), ScanType::Quick);
+        interrupted.add_open_port(PortInfo {
+            port: 22,
+            status: crate::scanner::PortStatus::Open,
+            service: None,
+            banner: None,
+            banner_raw: None,
+            response_time: None,
+            protocol: crate::scanner::Protocol::Tcp,
+            tls: None,
+        });
+        repository.start_scan(&interrupted).await.unwrap();
+        interrupted.statistics.cancelled = true;
+        interrupted.finalize();
+        repository.save_scan(&interrupted).await.unwrap();
+        assert_eq!(repository.get_scan_result(&interrupted.id).await.unwrap().unwrap().status(), ScanStatus::Cancelled);
+        repository.start_scan(&interrupted).await.unwrap();
+        interrupted.statistics.cancelled = false;
+        interrupted.finalize();
+        repository.save_scan(&interrupted).await.unwrap();
+        let reloaded = repository.get_scan_result(&interrupted.id).await.unwrap().unwrap();
+        assert_eq!(reloaded.status(), ScanStatus::Completed);
+        assert_eq!(reloaded.open_ports.len(), 1);
+
+        // Hosts that failed discovery can be stored too
+        let mut down = ScanResult::new("10.0.0.4".to_string(), "10.0.0.4".parse().unwrap(), ScanType::Quick);
+        down.metadata.host_up = Some(false);
+        down.finalize();
+        repository.save_scan(&down).await.unwrap();
+        let reloaded = repository.get_scan_result(&down.id).await.unwrap().unwrap();
+        assert!(reloaded.is_host_down());
+
+        assert!(matches!(repository.cancel_scan("no-such-scan").await, Err(Error::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_recorder_tracks_scans_run_by_an_engine() {
+        use crate::scanner::{ScanConfig, ScanEngine};
+
+        let repository = ScanRepository::new(Database::new("sqlite::memory:").await.unwrap());
+        let recorder = std::sync::Arc::new(ScanStatusRecorder::new(repository.clone()));
+        let open = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = open.local_addr().unwrap().port();
+        let engine = ScanEngine::new(ScanConfig {
+            enable_reverse_dns: false,
+            host_discovery: false,
+            ..ScanConfig::default()
+        }.with_discovery_only()).unwrap().with_observer(recorder.clone());
+
+        let result = engine.scan("127.0.0.1", ScanType::Targeted(vec![port])).await.unwrap();
+        let record = repository.get_scan(&result.id).await.unwrap().unwrap();
+        assert_eq!(record.scan_status().unwrap(), ScanStatus::Running);
+        // Still running until the result is stored
+        assert_eq!(recorder.running_scan("127.0.0.1"), Some(result.id.clone()));
+        recorder.save(&result).await.unwrap();
+        assert_eq!(repository.get_scan(&result.id).await.unwrap().unwrap().scan_status().unwrap(), ScanStatus::Completed);
+        assert_eq!(recorder.running_scan("127.0.0.1"), None);
+
+        // Nothing started, nothing to fail
+        assert!(!recorder.fail("no-such-scan", "unreachable").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_recorder_keys_concurrent_scans_of_a_target_by_id() {
+        use crate::scanner::{ScanConfig, ScanEngine};
+
+        let repository = ScanRepository::new(Database::new("sqlite::memory:").await.unwrap());
+        let recorder = std::sync::Arc::new(ScanStatusRecorder::new(repository.clone()));
+        let open = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = open.local_addr().unwrap().port();
+        let engine = ScanEngine::new(ScanConfig {
+            enable_reverse_dns: false,
+            host_discovery: false,
+            ..ScanConfig::default()
+        }.with_discovery_only()).unwrap().with_observer(recorder.clone());
+
+        let first = engine.scan_with_id("first", "127.0.0.1", ScanType::Targeted(vec![port])).await.unwrap();
+        let second = engine.scan_with_id("second", "127.0.0.1", ScanType::Targeted(vec![port])).await.unwrap();
+        assert_eq!((first.id.as_str(), second.id.as_str()), ("first", "second"));
+
+        assert!(recorder.cancel("first").await.unwrap());
+        recorder.save(&second).await.unwrap();
+        let status = |scan_id: &'static str| {
+            let repository = repository.clone();
+            async move { repository.get_scan(scan_id).await.unwrap().unwrap().scan_status().unwrap() }
+        };
+        assert_eq!(status("first").await, ScanStatus::Cancelled);
+        assert_eq!(status("second").await, ScanStatus::Completed);
+        assert_eq!(recorder.running_scan("127.0.0.1"), None);
+    }
+
+    #[tokio::test]
+    async fn test_latest_scan_keeps_incremental_coverage() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        let repository = ScanRepository::new(db);
+        let since = Utc::now() - chrono::Duration::hours(1);
+        assert!(repository.get_latest_scan_result("10.0.0.1", since).await.unwrap().is_none());
+
+        let mut older = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::CustomRange(1, 100));
+        older.metadata.coverage = vec![(1, 100)];
+        older.finalize();
+        repository.save_scan(&older).await.unwrap();
+
+        let mut newer = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::CustomRange(1, 100));
+        newer.metadata.coverage = vec![(22, 22)];
+        newer.metadata.incremental = Some(crate::scanner::IncrementalScan {
+            baseline_scan_id: older.id.clone(),
+            carried_over: vec![(1, 21), (23, 100)],
+            carried_over_since: older.start_time,
+        });
+        newer.finalize();
+        repository.save_scan(&newer).await.unwrap();
+
+        let latest = repository.get_latest_scan_result("10.0.0.1", since).await.unwrap().unwrap();
+        assert_eq!(latest.id, newer.id);
+        assert_eq!(latest.metadata.coverage, newer.metadata.coverage);
+        assert_eq!(latest.metadata.incremental, newer.metadata.incremental);
+        let stale_before = older.start_time - std::time::Duration::from_secs(60);
+        assert_eq!(latest.vouched_ports(stale_before).len(), 100);
+
+        assert!(repository.get_latest_scan_result("10.0.0.2", since).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_merge_needs_two_scans_and_mints_a_new_id() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        let repository = ScanRepository::new(db);
+        let first = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        let second = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        repository.save_scan(&first).await.unwrap();
+        repository.save_scan(&second).await.unwrap();
+
+        assert!(matches!(repository.merge_scans(&[]).await, Err(Error::Validation(_))));
+        assert!(matches!(repository.merge_scans(&[first.id.clone()]).await, Err(Error::Validation(_))));
+
+        let merged = repository.merge_scans(&[first.id.clone(), second.id.clone()]).await.unwrap();
+        assert_ne!(merged.id, first.id);
+        assert_ne!(merged.id, second.id);
+    }
+
+    #[tokio::test]
+    async fn test_vulnerability_report_round_trip() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        let repository = ScanRepository::new(db);
+
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        scan.finalize();
+        repository.save_scan(&scan).await.unwrap();
+
+        let mut report = VulnerabilityReport::new(scan.id.clone(), scan.target.clone(), scan.target_ip);
+        let mut finding = Vulnerability::new(
+            "Outdated OpenSSH".to_string(),
+            "desc".to_string(),
+            crate::vulnerability::VulnerabilityLevel::High,
+            22,
+            "SSH".to_string(),
+            "SSH-2.0-OpenSSH_7.2".to_string(),
+        );
+        finding.cve_id = Some("CVE-2016-6210".to_string());
+        finding.tags = vec!["ssh".to_string()];
+        report.add_vulnerability(finding);
+        repository.save_vulnerability_report(&report).await.unwrap();
+
+        let loaded = repository.get_vulnerability_report(&scan.id).await.unwrap().unwrap();
+
+        assert_eq!(loaded.vulnerabilities.len(), 1);
+        assert_eq!(loaded.vulnerabilities[0].rule_key(), "CVE-2016-6210");
+        assert_eq!(loaded.vulnerabilities[0].tags, vec!["ssh".to_string()]);
+        assert_eq!(loaded.summary.high_count, 1);
+        assert_eq!(loaded.summary.risk_score, report.summary.risk_score);
+        assert!(repository.get_vulnerability_report("missing").await.unwrap().is_none());
+
+        let reloaded = repository.load_vulnerability_report(&report.id).await.unwrap().unwrap();
+        assert_eq!(reloaded.id, report.id);
+        assert_eq!(reloaded.generated_at, report.generated_at);
+        assert_eq!(reloaded.vulnerabilities.len(), 1);
+        assert_eq!(reloaded.summary.risk_score, report.summary.risk_score);
+        assert_eq!(reloaded.risk_assessment.overall_risk, report.risk_assessment.overall_risk);
+        assert_eq!(reloaded.recommendations.len(), report.recommendations.len());
+
+        // Saving again replaces rather than duplicating
+        repository.save_vulnerability_report(&report).await.unwrap();
+        let reloaded = repository.load_vulnerability_report(&report.id).await.unwrap().unwrap();
+        assert_eq!(reloaded.vulnerabilities.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_finding_rolls_back_report_header() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        let repository = ScanRepository::new(db);
+
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        scan.finalize();
+        repository.save_scan(&scan).await.unwrap();
+
+        let finding = Vulnerability::new(
+            "Telnet enabled".to_string(),
+            "desc".to_string(),
+            crate::vulnerability::VulnerabilityLevel::Medium,
+            23,
+            "Telnet".to_string(),
+            String::new(),
+        );
+        let mut report = VulnerabilityReport::new(scan.id.clone(), scan.target.clone(), scan.target_ip);
+        report.add_vulnerability(finding.clone());
+        report.add_vulnerability(finding); // Same ID: the second insert violates the primary key
+
+        assert!(repository.save_vulnerability_report(&report).await.is_err());
+        assert!(repository.load_vulnerability_report(&report.id).await.unwrap().is_none());
+        let orphans = repository.get_vulnerabilities(VulnerabilityQuery {
+            scan_id: Some(scan.id.clone()),
+            ..Default::default()
+        }).await.unwrap();
+        assert!(orphans.is_empty());
+
+        // The retry goes through once the bad finding is gone
+        report.vulnerabilities.pop();
+        repository.save_vulnerability_report(&report).await.unwrap();
+        assert_eq!(repository.load_vulnerability_report(&report.id).await.unwrap().unwrap().vulnerabilities.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_vulnerability_level_exact_vs_threshold() {
+        use crate::vulnerability::VulnerabilityLevel;
+
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        let repository = ScanRepository::new(db);
+
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        scan.finalize();
+        repository.save_scan(&scan).await.unwrap();
+
+        let mut report = VulnerabilityReport::new(scan.id.clone(), scan.target.clone(), scan.target_ip);
+        for (port, level) in [
+            (21, VulnerabilityLevel::Critical),
+            (22, VulnerabilityLevel::High),
+            (23, VulnerabilityLevel::Medium),
+            (25, VulnerabilityLevel::Info),
+        ] {
+            report.add_vulnerability(Vulnerability::new(
+                format!("Finding on {}", port),
+                "desc".to_string(),
+                level,
+                port,
+                "test".to_string(),
+                String::new(),
+            ));
+        }
+        repository.save_vulnerability_report(&report).await.unwrap();
+
+        let ports = |records: Vec<VulnerabilityRecord>| {
+            let mut ports: Vec<i32> = records.into_iter().map(|r| r.port).collect();
+            ports.sort();
+            ports
+        };
+
+        let exact = repository.get_vulnerabilities(VulnerabilityQuery {
+            level: Some("high".to_string()),
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!(ports(exact), vec![22]);
+
+        let threshold = repository.get_vulnerabilities(VulnerabilityQuery {
+            min_level: Some(VulnerabilityLevel::High),
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!(ports(threshold), vec![21, 22]);
+
+        let everything = repository.get_vulnerabilities(VulnerabilityQuery {
+            min_level: Some(VulnerabilityLevel::Info),
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!(ports(everything), vec![21, 22, 23, 25]);
+    }
+
+    #[tokio::test]
+    async fn test_port_stream_yields_the_same_rows_as_fetch_all() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        let repository = ScanRepository::new(db);
+
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Full);
+        for port in 1..=2000u16 {
+            scan.add_open_port(PortInfo {
+                port,
+                status: crate::scanner::PortStatus::Open,
+                service: None,
+                banner: None,
+                banner_raw: None,
+                response_time: None,
+                protocol: crate::scanner::Protocol::Tcp,
+                tls: None,
+            });
+        }
+        scan.finalize();
+        repository.save_scan(&scan).await.unwrap();
+
+        // The stream is drained (and its connection released) before the
+        // fetch_all runs
+        let mut streamed_ports = 0;
+        let mut ports = repository.stream_scan_ports(&scan.id);
+        while let Some(port) = ports.next().await {
+            assert_eq!(port.unwrap().scan_id, scan.id);
+            streamed_ports += 1;
+        }
+        drop(ports);
+        assert_eq!(streamed_ports, repository.get_scan_ports(&scan.id).await.unwrap().len());
+        assert_eq!(streamed_ports, 2000);
+
+        assert_eq!(repository.stream_scan_ports("missing").count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_delete_by_target_leaves_no_orphans() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        let repository = ScanRepository::new(db);
+
+        for target in ["10.0.0.1", "10.0.0.1", "10.0.0.2"] {
+            let mut scan = ScanResult::new(target.to_string(), target.parse().unwrap(), ScanType::Quick);
+            scan.add_open_port(PortInfo {
+                port: 23,
+                status: crate::scanner::PortStatus::Open,
+                service: None,
+                banner: None,
+                banner_raw: None,
+                response_time: None,
+                protocol: crate::scanner::Protocol::Tcp,
+                tls: None,
+            });
+            scan.finalize();
+            repository.save_scan(&scan).await.unwrap();
+
+            let mut report = VulnerabilityReport::new(scan.id.clone(), scan.target.clone(), scan.target_ip);
+            report.add_vulnerability(Vulnerability::new(
+                "Telnet enabled".to_string(),
+                "desc".to_string(),
+                crate::vulnerability::VulnerabilityLevel::Medium,
+                23,
+                "Telnet".to_string(),
+                String::new(),
+            ));
+            repository.save_vulnerability_report(&report).await.unwrap();
+        }
+
+        assert_eq!(repository.delete_by_target("10.0.0.1").await.unwrap(), 2);
+        assert_eq!(repository.delete_by_target("10.0.0.1").await.unwrap(), 0);
+
+        let pool = repository.db.get_pool();
+        for (table, key) in [
+            ("scan_ports", "scan_id"),
+            ("scan_statistics", "scan_id"),
+            ("scan_metadata", "scan_id"),
+            ("vulnerability_reports", "scan_id"),
+            ("vulnerabilities", "scan_id"),
+        ] {
+            let (orphans,): (i64,) = query_as(&format!(
+                "SELECT COUNT(*) FROM {table} WHERE {key} NOT IN (SELECT id FROM scans)"
+            ))
+            .fetch_one(pool)
+            .await
+            .unwrap();
+            assert_eq!(orphans, 0, "orphan rows left in {}", table);
+
+            // The other target's history is untouched
+            let (kept,): (i64,) = query_as(&format!("SELECT COUNT(*) FROM {table}"))
+                .fetch_one(pool)
+                .await
+                .unwrap();
+            assert_eq!(kept, 1, "{} of 10.0.0.2", table);
+        }
+        assert!(repository.get_scan_ids_for_target("10.0.0.1").await.unwrap().is_empty());
+        assert_eq!(repository.get_scan_ids_for_target("10.0.0.2").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_open_ports_batched_by_scan() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        let repository = ScanRepository::new(db);
+        assert!(repository.get_open_ports_for_scans(&[]).await.unwrap().is_empty());
+
+        let mut scan_ids = Vec::new();
+        for ports in [vec![443, 22], vec![], vec![3306]] {
+            let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+            for port in ports {
+                scan.add_open_port(PortInfo {
+                    port,
+                    status: crate::scanner::PortStatus::Open,
+                    service: None,
+                    banner: None,
+                    banner_raw: None,
+                    response_time: None,
+                    protocol: crate::scanner::Protocol::Tcp,
+                    tls: None,
+                });
+            }
+            scan.finalize();
+            repository.save_scan(&scan).await.unwrap();
+            scan_ids.push(scan.id);
+        }
+
+        let by_scan = repository.get_open_ports_for_scans(&scan_ids).await.unwrap();
+        let ports = |scan_id: &String| by_scan[scan_id].iter().map(|p| p.port).collect::<Vec<_>>();
+        assert_eq!(by_scan.len(), 2);
+        assert_eq!(ports(&scan_ids[0]), vec![22, 443]);
+        assert!(!by_scan.contains_key(&scan_ids[1]));
+        assert_eq!(ports(&scan_ids[2]), vec![3306]);
+
+        // Only the scans asked for
+        let first_only = repository.get_open_ports_for_scans(&scan_ids[..1]).await.unwrap();
+        assert_eq!(first_only.keys().collect::<Vec<_>>(), vec![&scan_ids[0]]);
+    }
+
+    #[tokio::test]
+    async fn test_tag_filter_returns_exactly_the_tagged_scans() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        let repository = ScanRepository::new(db);
+
+        let mut scan_ids = Vec::new();
+        for target in ["10.0.0.1", "10.0.0.2", "10.0.0.3"] {
+            let mut scan = ScanResult::new(target.to_string(), target.parse().unwrap(), ScanType::Quick);
+            scan.finalize();
+            repository.save_scan(&scan).await.unwrap();
+            scan_ids.push(scan.id);
+        }
+        repository.add_tag(&scan_ids[0], "q3-audit").await.unwrap();
+        repository.add_tag(&scan_ids[0], " q3-audit ").await.unwrap();
+        repository.add_tag(&scan_ids[0], "prod").await.unwrap();
+        repository.add_tag(&scan_ids[2], "q3-audit").await.unwrap();
+        assert!(repository.add_tag(&scan_ids[1], " ").await.is_err());
+        assert!(repository.add_tag("no-such-scan", "prod").await.is_err());
+        assert_eq!(repository.get_tags(&scan_ids[0]).await.unwrap(), vec!["prod".to_string(), "q3-audit".to_string()]);
+
+        let tagged = |tag: &str| ScanQuery { tag: Some(tag.to_string()), ..ScanQuery::default() };
+        let mut found: Vec<String> = repository.search_scans(tagged("q3-audit")).await.unwrap()
+            .data.into_iter().map(|scan| scan.id).collect();
+        found.sort();
+        let mut expected = vec![scan_ids[0].clone(), scan_ids[2].clone()];
+        expected.sort();
+        assert_eq!(found, expected);
+        assert_eq!(repository.search_scans(tagged("q3-audit")).await.unwrap().total, 2);
+        assert!(repository.search_scans(tagged("q3")).await.unwrap().data.is_empty());
+
+        let mut listed: Vec<String> = repository.list_by_tag("q3-audit", None).await.unwrap()
+            .into_iter().map(|summary| summary.id).collect();
+        listed.sort();
+        assert_eq!(listed, expected);
+
+        assert!(repository.remove_tag(&scan_ids[2], "q3-audit").await.unwrap());
+        assert!(!repository.remove_tag(&scan_ids[2], "q3-audit").await.unwrap());
+        let remaining = repository.list_by_tag("q3-audit", None).await.unwrap();
+        assert_eq!(remaining.iter().map(|s| &s.id).collect::<Vec<_>>(), vec![&scan_ids[0]]);
+
+        // A deleted scan takes its tags along
+        assert!(repository.delete_scan(&scan_ids[0]).await.unwrap());
+        assert!(!repository.delete_scan(&scan_ids[0]).await.unwrap());
+        let (tags,): (i64,) = query_as("SELECT COUNT(*) FROM scan_tags WHERE scan_id = ?")
+            .bind(&scan_ids[0])
+            .fetch_one(repository.db.get_pool())
+            .await
+            .unwrap();
+        assert_eq!(tags, 0);
+        assert!(repository.list_by_tag("q3-audit", None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_top_targets_ranked_by_scans_then_exposure() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        let repository = ScanRepository::new(db);
+        assert!(repository.top_targets(5).await.unwrap().is_empty());
+
+        // (target, open ports) per scan
+        let scans = [
+            ("10.0.0.1", 1), ("10.0.0.1", 4), ("10.0.0.1", 2),
+            ("10.0.0.2", 0), ("10.0.0.2", 3),
+            ("10.0.0.3", 9), ("10.0.0.3", 1),
+            ("10.0.0.4", 7),
+        ];
+        for (target, open) in scans {
+            let mut scan = ScanResult::new(target.to_string(), target.parse().unwrap(), ScanType::Quick);
+            for port in 1..=open {
+                scan.add_open_port(PortInfo {
+                    port,
+                    status: crate::scanner::PortStatus::Open,
+                    service: None,
+                    banner: None,
+                    banner_raw: None,
+                    response_time: None,
+                    protocol: crate::scanner::Protocol::Tcp,
+                    tls: None,
+                });
+            }
+            scan.finalize();
+            repository.save_scan(&scan).await.unwrap();
+        }
+
+        let top = repository.top_targets(3).await.unwrap();
+        let ranking: Vec<_> = top.iter().map(|t| (t.target.as_str(), t.scan_count, t.max_open_ports)).collect();
+        // 10.0.0.3 ties 10.0.0.2 on scans but found more open ports
+        assert_eq!(ranking, vec![("10.0.0.1", 3, 4), ("10.0.0.3", 2, 9), ("10.0.0.2", 2, 3)]);
+
+        let latest = repository.get_scan_summaries(None).await.unwrap()
+            .into_iter()
+            .find(|s| s.target == "10.0.0.1")
+            .unwrap();
+        assert_eq!(top[0].last_scanned, latest.created_at);
+    }
+
+    #[tokio::test]
+    async fn test_retention_removes_only_scans_past_the_window() {
+        let db = Database::new("sqlite::memory:").await.unwrap();
+        let repository = ScanRepository::new(db);
+
+        let mut ids = Vec::new();
+        for target in ["10.0.0.1", "10.0.0.2", "10.0.0.3"] {
+            let mut scan = ScanResult::new(target.to_string(), target.parse().unwrap(), ScanType::Quick);
+            scan.add_open_port(PortInfo {
+                port: 23,
+                status: crate::scanner::PortStatus::Open,
+                service: None,
+                banner: None,
+                banner_raw: None,
+                response_time: None,
+                protocol: crate::scanner::Protocol::Tcp,
+                tls: None,
+            });
+            scan.finalize();
+            ids.push(repository.save_scan(&scan).await.unwrap());
+            repository.add_tag(&scan.id, "nightly").await.unwrap();
+
+            let mut report = VulnerabilityReport::new(scan.id.clone(), scan.target.clone(), scan.target_ip);
+            report.add_vulnerability(Vulnerability::new(
+                "Telnet enabled".to_string(),
+                "desc".to_string(),
+                crate::vulnerability::VulnerabilityLevel::Medium,
+                23,
+                "Telnet".to_string(),
+                String::new(),
+            ));
+            repository.save_vulnerability_report(&report).await.unwrap();
+        }
+        for (id, age) in [(&ids[0], "-45 days"), (&ids[1], "-29 days")] {
+            query("UPDATE scans SET created_at = datetime('now', ?) WHERE id = ?")
+                .bind(age)
+                .bind(id)
+                .execute(repository.db.get_pool())
+                .await
+                .unwrap();
+        }
+
+        let retention = RetentionTask::new(repository.clone(), 30, Duration::from_secs(3600));
+        assert_eq!(retention.run_once().await.unwrap(), Some(1));
+        assert!(repository.get_scan(&ids[0]).await.unwrap().is_none());
+        assert!(repository.get_scan(&ids[1]).await.unwrap().is_some());
+        assert!(repository.get_scan(&ids[2]).await.unwrap().is_some());
+
+        // Nothing of the removed scan is left behind; the kept scans keep theirs
+        let pool = repository.db.get_pool();
+        for table in [
+            "scan_ports",
+            "scan_statistics",
+            "scan_metadata",
+            "scan_tags",
+            "vulnerability_reports",
+            "vulnerabilities",
+        ] {
+            let (orphans,): (i64,) = query_as(&format!(
+                "SELECT COUNT(*) FROM {table} WHERE scan_id NOT IN (SELECT id FROM scans)"
+            ))
+            .fetch_one(pool)
+            .await
+            .unwrap();
+            assert_eq!(orphans, 0, "orphan rows left in {}", table);
+
+            let (kept,): (i64,) = query_as(&format!("SELECT COUNT(*) FROM {table} WHERE scan_id = ?"))
+                .bind(&ids[1])
+                .fetch_one(pool)
+                .await
+                .unwrap();
+            assert_eq!(kept, 1, "{} rows of a kept scan", table);
+        }
+
+        // A second cleanup while one holds the lock is skipped
+        let _running = retention.running.lock().await;
+        assert_eq!(retention.run_once().await.unwrap(), None);
+    }
+}
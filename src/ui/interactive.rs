@@ -0,0 +1,293 @@
+//! Menu-driven front end for `portzilla interactive`. Prompts re-ask on
+//! invalid input instead of aborting, and apply the same checks as `scan`.
+
+use super::PortZiLLAUI;
+use crate::cli::{validate_port_range, validate_preset_size, PortRange};
+use crate::config::{ScanPreset, SecuritySettings, Settings};
+use crate::error::{Error, Result};
+use crate::scanner::{ScanConfig, ScanEngine, ScanTechnique, ScanType};
+use crate::storage::ScanRepository;
+use super::theme::theme;
+use dialoguer::{Confirm, Input, Select};
+use std::time::Duration;
+
+/// Label of the picker entry that asks for a port range instead of a preset
+const CUSTOM_RANGE_ITEM: &str = "Custom port range";
+
+/// Ports chosen in the custom scan picker
+#[derive(Debug, Clone)]
+pub enum PortSelection {
+    Range(PortRange),
+    Preset(ScanPreset),
+}
+
+impl PortSelection {
+    pub fn scan_type(&self) -> Result<ScanType> {
+        match self {
+            PortSelection::Range(range) => Ok(ScanType::CustomRange(range.start, range.end)),
+            PortSelection::Preset(preset) => preset.scan_type(),
+        }
+    }
+}
+
+pub async fn run(settings: &Settings, repository: ScanRepository) -> Result<()> {
+    let theme = theme();
+    PortZiLLAUI::print_banner();
+
+    loop {
+        PortZiLLAUI::print_menu();
+        let choice: String = Input::new().with_prompt("Select an option").interact_text()?;
+
+        let outcome = match choice.trim() {
+            "0" => break,
+            "1" => quick_scan(settings, &repository, ScanType::Quick).await,
+            "2" => quick_scan(settings, &repository, ScanType::Standard).await,
+            "3" => quick_scan(settings, &repository, ScanType::Full).await,
+            "4" => custom_scan(settings, &repository).await,
+            "8" => repository.get_scan_summaries(Some(20)).await
+                .and_then(|scans| super::display_scan_history(&scans, true, None)),
+            "10" => {
+                PortZiLLAUI::print_help();
+                Ok(())
+            }
+            "5" | "6" | "7" | "9" => {
+                println!("  {}", theme.warn("Not available here yet; use the portzilla CLI subcommands"));
+                Ok(())
+            }
+            other => {
+                println!("  {} {}", theme.bad("Unknown option:"), other);
+                Ok(())
+            }
+        };
+
+        // A failed scan returns to the menu
+        if let Err(e) = outcome {
+            println!("  {} {}", theme.bad("❌"), theme.bad(&e.to_string()));
+        }
+    }
+
+    println!("{}", theme.label("👋 Goodbye"));
+    Ok(())
+}
+
+async fn quick_scan(settings: &Settings, repository: &ScanRepository, scan_type: ScanType) -> Result<()> {
+    let target = prompt_target(&settings.security)?;
+    run_scan(settings, repository, &target, scan_type, None, None).await
+}
+
+/// Menu option 4: target, then a port range or preset, then the technique
+async fn custom_scan(settings: &Settings, repository: &ScanRepository) -> Result<()> {
+    let target = prompt_target(&settings.security)?;
+    let selection = prompt_ports(settings)?;
+    let technique = prompt_technique()?;
+
+    let preset = match &selection {
+        PortSelection::Preset(preset) => Some(preset),
+        PortSelection::Range(_) => None,
+    };
+    run_scan(settings, repository, &target, selection.scan_type()?, preset, Some(technique)).await
+}
+
+fn prompt_target(security: &SecuritySettings) -> Result<String> {
+    let target: String = Input::new()
+        .with_prompt("Target (IP address or hostname)")
+        .validate_with(|input: &String| parse_target(input, security).map(|_| ()))
+        .interact_text()?;
+    parse_target(&target, security)
+}
+
+fn prompt_ports(settings: &Settings) -> Result<PortSelection> {
+    let mut items = vec![CUSTOM_RANGE_ITEM.to_string()];
+    items.extend(settings.preset_names());
+    let picked = Select::new()
+        .with_prompt("Ports")
+        .items(&items)
+        .default(0)
+        .interact()?;
+
+    if picked > 0 {
+        let preset = settings.preset(&items[picked])?;
+        validate_preset_size(&preset, &settings.security)?;
+        return Ok(PortSelection::Preset(preset));
+    }
+
+    let range: String = Input::new()
+        .with_prompt("Port range (e.g. 1-1000 or 443)")
+        .validate_with(|input: &String| parse_port_range(input, &settings.security).map(|_| ()))
+        .interact_text()?;
+    Ok(PortSelection::Range(parse_port_range(&range, &settings.security)?))
+}
+
+fn prompt_technique() -> Result<ScanTechnique> {
+    let techniques = [ScanTechnique::Connect, ScanTechnique::Syn];
+    let labels = ["connect (full handshake, no privileges needed)", "syn (raw sockets, needs root)"];
+    let picked = Select::new()
+        .with_prompt("Scan technique")
+        .items(&labels)
+        .default(0)
+        .interact()?;
+    Ok(techniques[picked])
+}
+
+/// Trimmed `input` if it is a well-formed target that the security settings allow
+pub fn parse_target(input: &str, security: &SecuritySettings) -> Result<String> {
+    let target = input.trim();
+    crate::utils::validate_target(target)?;
+    security.check_target(target)?;
+    Ok(target.to_string())
+}
+
+/// `input` as a port range within `security.max_ports_per_scan`
+pub fn parse_port_range(input: &str, security: &SecuritySettings) -> Result<PortRange> {
+    let range: PortRange = input.trim().parse().map_err(Error::Validation)?;
+    validate_port_range(&range, security)?;
+    Ok(range)
+}
+
+/// Ask before probing ports that can upset printers and industrial devices.
+/// Declining leaves the engine to refuse the scan.
+pub fn confirm_dangerous_ports(ports: &[u16]) -> Result<bool> {
+    let list = ports.iter().map(u16::to_string).collect::<Vec<_>>().join(", ");
+    let confirmed = Confirm::new()
+        .with_prompt(format!(
+            "Port(s) {} can make printers print garbage or industrial controllers fault. Probe them anyway?",
+            list
+        ))
+        .default(false)
+        .interact()?;
+    Ok(confirmed)
+}
+
+async fn run_scan(
+    settings: &Settings,
+    repository: &ScanRepository,
+    target: &str,
+    scan_type: ScanType,
+    preset: Option<&ScanPreset>,
+    technique: Option<ScanTechnique>,
+) -> Result<()> {
+    let mut config = ScanConfig {
+        timeout: Duration::from_millis(settings.scanner.default_timeout_ms),
+        max_concurrent_tasks: settings.scanner.max_threads,
+        connect_max_concurrent: settings.scanner.connect_max_threads,
+        syn_max_concurrent: settings.scanner.syn_max_threads,
+        udp_max_concurrent: settings.scanner.udp_max_threads,
+        rampup_duration: Duration::from_secs(settings.scanner.rampup_secs),
+        rate_limit: settings.scanner.rate_limit,
+        max_pps: settings.scanner.max_pps,
+        enable_service_detection: settings.scanner.enable_service_detection,
+        enable_banner_grabbing: settings.scanner.enable_banner_grabbing,
+        enable_os_detection: settings.scanner.enable_os_detection,
+        enable_traceroute: settings.scanner.enable_traceroute,
+        stealth_mode: settings.scanner.stealth_mode,
+        exclude: settings.scanner.exclude_ports.clone(),
+        probe_allow: settings.scanner.probe_allow_ports.clone(),
+        probe_deny: settings.scanner.probe_deny_ports.clone(),
+        verify_open: settings.scanner.verify_open_ports,
+        dns_servers: settings.scanner.dns_servers.clone(),
+        global_max_concurrent: settings.scanner.global_max_concurrent,
+        banner_buffer_size: settings.scanner.banner_buffer_size,
+        banner_max_length: settings.scanner.banner_max_length,
+        max_response_bytes: settings.scanner.max_response_bytes,
+        enrich_concurrency: settings.scanner.enrich_concurrency,
+        probe_identity: settings.scanner.probe_identity.clone(),
+        http_keep_alive: settings.scanner.http_keep_alive,
+        auto_tune_concurrency: settings.scanner.auto_tune_concurrency,
+        record_all_statuses: settings.scanner.record_all_statuses,
+        host_discovery: settings.scanner.host_discovery,
+        tcp_nodelay: settings.scanner.tcp_nodelay,
+        tcp_linger_reset: settings.scanner.tcp_linger_reset,
+        geoip: settings.geoip.clone(),
+        dangerous_ports: settings.security.dangerous_ports.clone(),
+        allow_public_targets: settings.security.allow_public_targets,
+        scan_window: settings.security.scan_window.clone(),
+        ..ScanConfig::default()
+    };
+    if let Some(preset) = preset {
+        config = preset.apply(config);
+    }
+    if let Some(technique) = technique {
+        config.technique_chain = vec![technique];
+    }
+
+    let mut engine = ScanEngine::new(config)?;
+    let dangerous = engine.dangerous_ports(&scan_type);
+    if !dangerous.is_empty() && confirm_dangerous_ports(&dangerous)? {
+        engine = engine.with_dangerous_ports_allowed();
+    }
+    PortZiLLAUI::print_scan_start(target, &scan_type.to_string());
+    let scan_result = engine.scan(target, scan_type).await?;
+    let scan_id = repository.save_scan(&scan_result).await?;
+
+    PortZiLLAUI::print_scan_complete(scan_result.open_ports.len(), scan_result.duration());
+    let theme = theme();
+    let min_confidence = settings.export.min_service_confidence;
+    for port in &scan_result.open_ports {
+        let service = port.service.as_ref().map_or_else(|| "unknown".to_string(), |s| {
+            if s.is_confirmed(min_confidence) { s.description() } else { s.reported_name(min_confidence) }
+        });
+        println!("  {:>5}/{:<4} {}", port.port, format!("{:?}", port.protocol).to_lowercase(), theme.text(&service));
+    }
+    println!("  {}  {}", theme.label("💾 Saved as:"), theme.text(&scan_id));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_target_trims_and_checks_allowed_list() {
+        let mut security = Settings::default().security;
+        assert_eq!(parse_target(" 10.0.0.5 ", &security).unwrap(), "10.0.0.5");
+        assert!(parse_target("scanme.example.com", &security).is_ok());
+        assert!(matches!(parse_target("not a host!", &security), Err(Error::Validation(_))));
+        assert!(parse_target("", &security).is_err());
+
+        security.allowed_targets = vec!["10.0.0.5".parse().unwrap()];
+        assert!(parse_target("10.0.0.5", &security).is_ok());
+        assert!(matches!(parse_target("10.0.0.6", &security), Err(Error::Security(_))));
+    }
+
+    #[test]
+    fn test_safe_mode_rejects_public_targets_by_default() {
+        let mut security = Settings::default().security;
+        assert!(parse_target("192.168.1.20", &security).is_ok());
+        assert!(parse_target("127.0.0.1", &security).is_ok());
+
+        match parse_target("8.8.8.8", &security) {
+            Err(Error::Security(message)) => assert!(message.contains("allow_public_targets"), "{}", message),
+            other => panic!("public target accepted: {:?}", other),
+        }
+
+        security.allow_public_targets = true;
+        assert_eq!(parse_target("8.8.8.8", &security).unwrap(), "8.8.8.8");
+    }
+
+    #[test]
+    fn test_parse_port_range_matches_cli_rules() {
+        let mut security = Settings::default().security;
+        let range = parse_port_range("20-25", &security).unwrap();
+        assert_eq!((range.start, range.end), (20, 25));
+        assert_eq!(parse_port_range("443", &security).unwrap().port_count(), 1);
+
+        for bad in ["", "http", "25-20", "1-2-3", "0-70000"] {
+            assert!(parse_port_range(bad, &security).is_err(), "{:?} should be rejected", bad);
+        }
+
+        security.max_ports_per_scan = 100;
+        assert!(parse_port_range("1-100", &security).is_ok());
+        assert!(matches!(parse_port_range("1-101", &security), Err(Error::Validation(_))));
+    }
+
+    #[test]
+    fn test_preset_selection_respects_port_budget() {
+        let mut settings = Settings::default();
+        let preset = settings.preset("db-audit").unwrap();
+        assert!(validate_preset_size(&preset, &settings.security).is_ok());
+        assert!(matches!(PortSelection::Preset(preset.clone()).scan_type().unwrap(), ScanType::Targeted(_)));
+
+        settings.security.max_ports_per_scan = 3;
+        assert!(validate_preset_size(&preset, &settings.security).is_err());
+    }
+}
@@ -1,24 +1,29 @@
 pub mod terminal;
 pub mod progress;
 pub mod dashboard;
+pub mod interactive;
+pub mod theme;
 
 pub use terminal::TerminalUI;
 pub use progress::ProgressBar;
 pub use dashboard::Dashboard;
+pub use theme::Theme;
 
 use colored::*;
+use theme::theme;
 
 pub struct PortZiLLAUI;
 
 impl PortZiLLAUI {
     pub fn print_banner() {
+        let theme = theme();
         println!();
-        println!("{}", "╔══════════════════════════════════════════════════════════════════════════════╗".bright_yellow());
-        println!("{}", "║                            PORT-ZILLA ENTERPRISE                                           ║".bright_yellow().bold());
-        println!("{}", "╚══════════════════════════════════════════════════════════════════════════════╝".bright_yellow());
+        println!("{}", theme.accent("╔══════════════════════════════════════════════════════════════════════════════╗"));
+        println!("{}", theme.accent("║                            PORT-ZILLA ENTERPRISE                                           ║").bold());
+        println!("{}", theme.accent("╚══════════════════════════════════════════════════════════════════════════════╝"));
         println!();
         
-        let ascii_art = r#"
+        let ascii_art = theme.accent(r#"
     .########...#######..########..########.########.####.##.......##..........###...
     .##.....##.##.....##.##.....##....##.........##...##..##.......##.........##.##..
     .##.....##.##.....##.##.....##....##........##....##..##.......##........##...##.
@@ -26,22 +31,23 @@ impl PortZiLLAUI {
     .##........##.....##.##...##......##......##......##..##.......##.......#########
     .##........##.....##.##....##.....##.....##.......##..##.......##.......##.....##
     .##.........#######..##.....##....##....########.####.########.########.##.....##
-        "#.bright_yellow();
+        "#);
         
         println!("{}", ascii_art);
         println!();
-        println!("{}", "           Enterprise-Level Port Scanner & Vulnerability Detector".bright_cyan());
-        println!("{}", "                          Version 1.0.0 - 2024".bright_cyan());
-        println!("{}", "                     For Authorized Security Testing Only".bright_red().bold());
+        println!("{}", theme.label("           Enterprise-Level Port Scanner & Vulnerability Detector"));
+        println!("{}", theme.label("                          Version 1.0.0 - 2024"));
+        println!("{}", theme.bad("                     For Authorized Security Testing Only").bold());
         println!();
-        println!("{}", "══════════════════════════════════════════════════════════════════════════════".bright_yellow());
+        println!("{}", theme.accent("══════════════════════════════════════════════════════════════════════════════"));
         println!();
     }
 
     pub fn print_menu() {
-        println!("{}", "╔══════════════════════════════════════════════════════════╗".bright_yellow());
-        println!("{}", "║                      MAIN MENU                                     ║".bright_yellow().bold());
-        println!("{}", "╚══════════════════════════════════════════════════════════╝".bright_yellow());
+        let theme = theme();
+        println!("{}", theme.accent("╔══════════════════════════════════════════════════════════╗"));
+        println!("{}", theme.accent("║                      MAIN MENU                                     ║").bold());
+        println!("{}", theme.accent("╚══════════════════════════════════════════════════════════╝"));
         println!();
         
         let menu_items = vec![
@@ -60,22 +66,23 @@ impl PortZiLLAUI {
 
         for (num, desc, icon) in menu_items {
             println!("  {} {} {}", 
-                num.bright_cyan().bold(), 
-                icon.bright_green(),
-                desc.bright_white()
+                theme.label(num).bold(), 
+                theme.good(icon),
+                theme.text(desc)
             );
         }
         
         println!();
-        println!("{}", "══════════════════════════════════════════════════════════".bright_yellow());
+        println!("{}", theme.accent("══════════════════════════════════════════════════════════"));
         println!();
     }
 
     pub fn print_help() {
+        let theme = theme();
         println!();
-        println!("{}", "╔══════════════════════════════════════════════════════════╗".bright_yellow());
-        println!("{}", "║                      HELP & USAGE                                  ║".bright_yellow().bold());
-        println!("{}", "╚══════════════════════════════════════════════════════════╝".bright_yellow());
+        println!("{}", theme.accent("╔══════════════════════════════════════════════════════════╗"));
+        println!("{}", theme.accent("║                      HELP & USAGE                                  ║").bold());
+        println!("{}", theme.accent("╚══════════════════════════════════════════════════════════╝"));
         println!();
         
         let help_sections = vec![
@@ -88,39 +95,41 @@ impl PortZiLLAUI {
         ];
 
         for (title, content) in help_sections {
-            println!("  {} {}", "►".bright_green(), title.bright_cyan().bold());
-            println!("    {}", content.bright_white());
+            println!("  {} {}", theme.good("►"), theme.label(title).bold());
+            println!("    {}", theme.text(content));
             println!();
         }
         
-        println!("{}", "Need more help? Visit: https://github.com/FJ-cyberzilla/Port-ZiLLA".bright_blue());
-        println!("{}", "Contact: cyberzilla.systems@gmail.com".bright_blue());
+        println!("{}", theme.link("Need more help? Visit: https://github.com/FJ-cyberzilla/Port-ZiLLA"));
+        println!("{}", theme.link("Contact: cyberzilla.systems@gmail.com"));
         println!();
     }
 
     pub fn print_scan_start(target: &str, scan_type: &str) {
+        let theme = theme();
         println!();
-        println!("{}", "╔══════════════════════════════════════════════════════════╗".bright_yellow());
-        println!("{}", "║                      SCAN STARTED                                   ║".bright_yellow().bold());
-        println!("{}", "╚══════════════════════════════════════════════════════════╝".bright_yellow());
+        println!("{}", theme.accent("╔══════════════════════════════════════════════════════════╗"));
+        println!("{}", theme.accent("║                      SCAN STARTED                                   ║").bold());
+        println!("{}", theme.accent("╚══════════════════════════════════════════════════════════╝"));
         println!();
-        println!("  {}  {}", "🎯 Target:".bright_cyan(), target.bright_white().bold());
-        println!("  {}  {}", "📊 Type:".bright_cyan(), scan_type.bright_white());
-        println!("  {}  {}", "⏰ Started:".bright_cyan(), chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string().bright_white());
+        println!("  {}  {}", theme.label("🎯 Target:"), theme.text(target).bold());
+        println!("  {}  {}", theme.label("📊 Type:"), theme.text(scan_type));
+        println!("  {}  {}", theme.label("⏰ Started:"), theme.text(&chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()));
         println!();
-        println!("{}", "Scanning in progress...".bright_yellow().bold());
+        println!("{}", theme.accent("Scanning in progress...").bold());
         println!();
     }
 
     pub fn print_scan_complete(open_ports: usize, duration: std::time::Duration) {
+        let theme = theme();
         println!();
-        println!("{}", "╔══════════════════════════════════════════════════════════╗".bright_green());
-        println!("{}", "║                      SCAN COMPLETE                                 ║".bright_green().bold());
-        println!("{}", "╚══════════════════════════════════════════════════════════╝".bright_green());
+        println!("{}", theme.good("╔══════════════════════════════════════════════════════════╗"));
+        println!("{}", theme.good("║                      SCAN COMPLETE                                 ║").bold());
+        println!("{}", theme.good("╚══════════════════════════════════════════════════════════╝"));
         println!();
-        println!("  {}  {}", "✅ Open Ports Found:".bright_cyan(), open_ports.to_string().bright_green().bold());
-        println!("  {}  {}", "⏱️  Duration:".bright_cyan(), format_duration(duration).bright_white());
-        println!("  {}  {}", "🏁 Completed:".bright_cyan(), chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string().bright_white());
+        println!("  {}  {}", theme.label("✅ Open Ports Found:"), theme.good(&open_ports.to_string()).bold());
+        println!("  {}  {}", theme.label("⏱️  Duration:"), theme.text(&format_duration(duration)));
+        println!("  {}  {}", theme.label("🏁 Completed:"), theme.text(&chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()));
         println!();
     }
 }
@@ -135,3 +144,339 @@ fn format_duration(duration: std::time::Duration) -> String {
         format!("{} ms", duration.as_millis())
     }
 }
+
+/// Print the connect-time distribution from a scan summary
+pub fn display_latency_summary(statistics: &crate::scanner::ScanStatistics) {
+    let Some(latency) = &statistics.latency else {
+        return;
+    };
+    let theme = theme();
+
+    let ms = |d: std::time::Duration| format!("{:.1}ms", d.as_secs_f64() * 1000.0);
+
+    println!(
+        "  {}  min {}  p50 {}  p90 {}  p99 {}  max {}",
+        theme.label("⏱  Response times:"),
+        theme.text(&ms(latency.min)),
+        theme.text(&ms(latency.median)),
+        theme.warn(&ms(latency.p90)),
+        theme.bad(&ms(latency.p99)),
+        theme.text(&ms(latency.max))
+    );
+    println!();
+}
+
+/// List recent scans, one line each; `detailed` adds type, duration and
+/// status, and `open_ports` lists each scan's open ports on the next line
+pub fn display_scan_history(
+    scans: &[crate::storage::models::ScanSummary],
+    detailed: bool,
+    open_ports: Option<&std::collections::HashMap<String, Vec<crate::storage::models::ScanPortRecord>>>,
+) -> crate::error::Result<()> {
+    let theme = theme();
+    println!();
+    println!("  {}", theme.label("📜 Scan History"));
+    println!();
+
+    if scans.is_empty() {
+        println!("  {}", theme.warn("No scans recorded yet"));
+        println!();
+        return Ok(());
+    }
+
+    for scan in scans {
+        print!(
+            "  {}  {}  {} open",
+            theme.text(&scan.created_at.format("%Y-%m-%d %H:%M").to_string()),
+            theme.text(&scan.target).bold(),
+            theme.good(&scan.open_count.to_string()).bold()
+        );
+        if detailed {
+            print!(
+                "  {}  {}  {}  {}",
+                theme.label(&scan.scan_type),
+                format_duration(std::time::Duration::from_millis(scan.duration_ms.max(0) as u64)),
+                scan.status,
+                scan.id.dimmed()
+            );
+        }
+        println!();
+
+        if let Some(ports) = open_ports.and_then(|open_ports| open_ports.get(&scan.id)) {
+            let listed: Vec<String> = ports.iter()
+                .map(|port| match &port.service_name {
+                    Some(service) => format!("{}/{} {}", port.port, port.protocol, service),
+                    None => format!("{}/{}", port.port, port.protocol),
+                })
+                .collect();
+            println!("      {} {}", theme.accent("↳"), theme.text(&listed.join(", ")));
+        }
+    }
+
+    println!();
+    Ok(())
+}
+
+/// New findings (regressions) in red, resolved in green, then the risk score change
+pub fn display_vulnerability_diff(
+    diff: &crate::vulnerability::VulnDiff,
+) -> crate::error::Result<()> {
+    let theme = theme();
+    println!();
+    println!(
+        "  {}  {}  ({} → {})",
+        theme.label("🔁 Assessment diff:"),
+        theme.text(&diff.target).bold(),
+        diff.previous_scan_id.dimmed(),
+        diff.current_scan_id.dimmed()
+    );
+    println!();
+
+    let line = |vulnerability: &crate::vulnerability::Vulnerability| {
+        format!("{} port {} ({:?})", vulnerability.rule_key(), vulnerability.port, vulnerability.level)
+    };
+    for vulnerability in &diff.new {
+        println!("  {} {}", theme.bad("+ NEW").bold(), theme.bad(&line(vulnerability)));
+    }
+    for vulnerability in &diff.resolved {
+        println!("  {} {}", theme.good("- RESOLVED").bold(), theme.good(&line(vulnerability)));
+    }
+    for vulnerability in &diff.persistent {
+        println!("  {} {}", theme.warn("= OPEN"), line(vulnerability));
+    }
+    if diff.new.is_empty() && diff.resolved.is_empty() && diff.persistent.is_empty() {
+        println!("  {}", theme.good("No findings in either assessment"));
+    }
+
+    let change = diff.risk_score_change();
+    let change_text = format!("{:+.2}", change);
+    println!();
+    println!(
+        "  {}  {:.2} → {:.2}  {}",
+        theme.label("📊 Risk score:"),
+        diff.previous_risk_score,
+        diff.current_risk_score,
+        if change > 0.0 { theme.bad(&change_text).bold() } else { theme.good(&change_text).bold() }
+    );
+    if diff.has_regressions() {
+        println!("  {}", theme.bad("⚠️  Regressions since the previous assessment").bold());
+    }
+    println!();
+    Ok(())
+}
+
+/// Targets ranked by how often they were scanned
+pub fn display_top_targets(targets: &[crate::storage::models::TopTarget]) -> crate::error::Result<()> {
+    let theme = theme();
+    println!();
+    println!("  {}", theme.label("🎯 Top Targets").bold());
+    println!();
+
+    if targets.is_empty() {
+        println!("  {}", theme.warn("No scans stored yet"));
+        println!();
+        return Ok(());
+    }
+
+    println!(
+        "  {:<30} {:>6} {:>10}  {}",
+        theme.label("Target"), theme.label("Scans"), theme.label("Max open"), theme.label("Last scanned")
+    );
+    for target in targets {
+        println!(
+            "  {:<30} {:>6} {:>10}  {}",
+            theme.text(&target.target),
+            theme.text(&target.scan_count.to_string()),
+            theme.good(&target.max_open_ports.to_string()).bold(),
+            theme.text(&target.last_scanned.format("%Y-%m-%d %H:%M").to_string())
+        );
+    }
+
+    println!();
+    Ok(())
+}
+
+/// One "port  service" line per port, with the ProtocolUtils name when
+/// the port has one
+pub fn port_list_lines(ports: &[u16]) -> Vec<String> {
+    use crate::network::protocols::ProtocolUtils;
+
+    ports.iter()
+        .map(|&port| match ProtocolUtils::get_port_description(port) {
+            Some(service) => format!("{:>5}  {}", port, service),
+            None => format!("{:>5}", port),
+        })
+        .collect()
+}
+
+/// The ports a scan would probe, for `list-ports`
+pub fn display_port_list(scope: &str, ports: &[u16]) -> crate::error::Result<()> {
+    let theme = theme();
+    println!();
+    println!("  {}  {}", theme.label("🔢 Ports for:"), theme.text(scope).bold());
+    println!();
+
+    for line in port_list_lines(ports) {
+        println!("  {}", theme.text(&line));
+    }
+
+    println!();
+    println!("  {} {}", theme.good(&ports.len().to_string()).bold(), theme.label("ports would be probed"));
+    println!();
+    Ok(())
+}
+
+/// Expected duration of a scan from `estimate`, as a range
+pub fn display_scan_estimate(
+    target: &str,
+    scope: &str,
+    port_count: usize,
+    estimate: &crate::utils::ScanEstimate,
+) -> crate::error::Result<()> {
+    let theme = theme();
+    println!();
+    println!(
+        "  {}  {}  ({}, {} ports)",
+        theme.label("⏱️  Estimate for:"),
+        theme.text(target).bold(),
+        scope,
+        port_count
+    );
+    println!();
+    println!(
+        "  {}  {} to {}",
+        theme.label("Expected duration:"),
+        theme.good(&format_duration(estimate.optimistic)).bold(),
+        theme.warn(&format_duration(estimate.pessimistic)).bold()
+    );
+    println!(
+        "  {}",
+        "The short end assumes every port answers at once, the long end that every probe times out.".dimmed()
+    );
+    println!();
+    Ok(())
+}
+
+/// Port × technique statuses from `compare-techniques`, then each
+/// technique's timing. Without `all`, only ports open somewhere or seen
+/// differently by the techniques are listed.
+pub fn display_technique_matrix(
+    target: &str,
+    matrix: &crate::scanner::TechniqueMatrix,
+    all: bool,
+) -> crate::error::Result<()> {
+    use crate::scanner::PortStatus;
+
+    let theme = theme();
+    println!();
+    println!("  {}  {}", theme.label("🧪 Technique comparison:"), theme.text(target).bold());
+    println!();
+
+    print!("  {:>5}", theme.label("Port"));
+    for technique in &matrix.techniques {
+        print!("  {:<13}", theme.label(&technique.to_string()));
+    }
+    println!();
+
+    let mut listed = 0;
+    for row in matrix.rows.iter().filter(|row| all || row.is_notable()) {
+        print!("  {:>5}", theme.text(&row.port.to_string()));
+        for status in &row.statuses {
+            let cell = format!("{:<13}", match status {
+                Some(PortStatus::Open) => "open",
+                Some(PortStatus::Closed) => "closed",
+                Some(PortStatus::Filtered) => "filtered",
+                Some(PortStatus::OpenFiltered) => "open|filtered",
+                Some(PortStatus::Unknown) => "unknown",
+                None => "-",
+            });
+            match status {
+                Some(PortStatus::Open) => print!("  {}", theme.good(&cell).bold()),
+                Some(PortStatus::Filtered) | Some(PortStatus::OpenFiltered) => print!("  {}", theme.warn(&cell)),
+                _ => print!("  {}", theme.text(&cell)),
+            }
+        }
+        println!();
+        listed += 1;
+    }
+    if listed == 0 {
+        println!("  {}", theme.warn("No open ports and no disagreement between techniques"));
+    }
+
+    println!();
+    for timing in &matrix.timings {
+        println!(
+            "  {}  {} in {}, median response {}",
+            theme.label(&format!("{:<8}", timing.technique)),
+            theme.good(&format!("{} open", timing.open_ports)).bold(),
+            format_duration(timing.duration),
+            timing.median_response.map_or("-".to_string(), |median| format!("{:.1}ms", median.as_secs_f64() * 1000.0))
+        );
+    }
+    println!();
+    Ok(())
+}
+
+/// Render open-port counts per scan as a simple horizontal bar chart
+pub fn display_target_timeline(
+    target: &str,
+    timeline: &[crate::storage::models::TargetTimelinePoint],
+) -> crate::error::Result<()> {
+    let theme = theme();
+    println!();
+    println!("  {}  {}", theme.label("📈 Timeline:"), theme.text(target).bold());
+    println!();
+
+    if timeline.is_empty() {
+        println!("  {}", theme.warn("No scans found for this target in the selected window"));
+        println!();
+        return Ok(());
+    }
+
+    let max_open = timeline.iter().map(|p| p.open_ports).max().unwrap_or(0).max(1);
+
+    for point in timeline {
+        let bar_len = (point.open_ports as usize * 40) / max_open as usize;
+        println!(
+            "  {}  {} {}",
+            theme.text(&point.timestamp.format("%Y-%m-%d %H:%M").to_string()),
+            theme.accent(&"█".repeat(bar_len)),
+            theme.good(&point.open_ports.to_string()).bold()
+        );
+    }
+
+    println!();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{ScanConfig, ScanEngine, ScanType};
+
+    #[test]
+    fn test_port_list_matches_ports_to_scan_for_each_type() {
+        let engine = ScanEngine::new(ScanConfig {
+            exclude: vec![80, 8080],
+            randomize_order: true,
+            ..ScanConfig::default()
+        })
+        .unwrap();
+
+        for scan_type in [
+            ScanType::Quick,
+            ScanType::Standard,
+            ScanType::Full,
+            ScanType::KnownServices,
+            ScanType::CustomRange(20, 30),
+            ScanType::Targeted(vec![22, 80, 443]),
+        ] {
+            let planned = engine.planned_ports(&scan_type);
+            let lines = port_list_lines(&planned);
+            assert_eq!(lines.len(), engine.get_ports_to_scan(&scan_type).len(), "{:?}", scan_type);
+            assert!(planned.windows(2).all(|pair| pair[0] < pair[1]), "{:?} not sorted", scan_type);
+        }
+
+        assert_eq!(port_list_lines(&[22, 9999]), vec!["   22  SSH", " 9999"]);
+    }
+}
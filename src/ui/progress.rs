@@ -1,5 +1,5 @@
 use std::time::Duration;
-use colored::*;
+use super::theme::theme;
 use indicatif::{ProgressBar, ProgressStyle, MultiProgress};
 
 pub struct AnimatedProgress {
@@ -37,6 +37,12 @@ impl AnimatedProgress {
         self.main_bar.set_message(message.to_string());
     }
 
+    /// Apply an engine progress update; indicatif derives the ETA from the position
+    pub fn update_from_scan(&self, progress: &crate::scanner::ScanProgress) {
+        let completed = (progress.percentage / 100.0 * progress.total_ports as f64).round() as u64;
+        self.update_main(completed, &format!("{} open", progress.open_ports_found));
+    }
+
     pub fn add_port_progress(&mut self, port: u16) {
         if let Some(ref bar) = self.port_bar {
             bar.finish_and_clear();
@@ -75,6 +81,7 @@ impl AnimatedProgress {
     }
 
     pub fn print_animated_banner(&self) {
+        let theme = theme();
         let frames = vec![
             r#"
     ██████╗  ██████╗ ██████╗ ████████╗    ███████╗██╗██╗  ██╗██╗      █████╗ 
@@ -95,13 +102,28 @@ impl AnimatedProgress {
         ];
 
         for frame in frames.iter().cycle().take(10) {
-            println!("{}", frame.bright_yellow());
+            println!("{}", theme.accent(frame));
             std::thread::sleep(Duration::from_millis(200));
             print!("{esc}[2J{esc}[1;1H", esc = 27 as char); // Clear screen
         }
     }
 }
 
+/// Bar for one CVE feed's import, starting at the records an interrupted
+/// run already imported
+pub fn import_bar(feed: &str, processed: u64, total: u64) -> ProgressBar {
+    let bar = ProgressBar::new(total);
+    bar.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} {msg} {wide_bar:.cyan/blue} {pos}/{len} records ({eta})")
+            .unwrap()
+            .progress_chars("█▓▒░ ")
+    );
+    bar.set_message(format!("Importing {}", feed));
+    bar.set_position(processed);
+    bar
+}
+
 impl Drop for AnimatedProgress {
     fn drop(&mut self) {
         self.finish_all();
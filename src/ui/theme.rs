@@ -0,0 +1,152 @@
+//! Terminal colors after `ui.color_scheme`. `init` picks the palette and
+//! decides once, at startup, whether to color at all.
+
+use crate::config::ColorScheme;
+use colored::{Color, ColoredString, Colorize};
+use std::ffi::OsString;
+use std::io::IsTerminal;
+use std::sync::RwLock;
+
+/// Colors by role, so a light terminal can swap them all at once
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    accent: Color,  // Frames, banners, bars
+    label: Color,   // Field names and headings
+    text: Color,    // Values
+    good: Color,
+    warn: Color,
+    bad: Color,
+    link: Color,
+}
+
+const DARK: Theme = Theme {
+    accent: Color::BrightYellow,
+    label: Color::BrightCyan,
+    text: Color::BrightWhite,
+    good: Color::BrightGreen,
+    warn: Color::BrightYellow,
+    bad: Color::BrightRed,
+    link: Color::BrightBlue,
+};
+
+// Bright yellow, cyan and white are unreadable on a white background
+const LIGHT: Theme = Theme {
+    accent: Color::Blue,
+    label: Color::Magenta,
+    text: Color::Black,
+    good: Color::Green,
+    warn: Color::Yellow,
+    bad: Color::Red,
+    link: Color::Blue,
+};
+
+static ACTIVE: RwLock<Theme> = RwLock::new(DARK);
+
+impl Theme {
+    /// `Auto` goes light only when `COLORFGBG` says the background is
+    pub fn for_scheme(scheme: &ColorScheme) -> Self {
+        match scheme {
+            ColorScheme::Dark => DARK,
+            ColorScheme::Light => LIGHT,
+            ColorScheme::Auto if light_background(std::env::var("COLORFGBG").ok().as_deref()) => LIGHT,
+            ColorScheme::Auto => DARK,
+        }
+    }
+
+    pub fn accent(&self, text: &str) -> ColoredString {
+        text.color(self.accent)
+    }
+
+    pub fn label(&self, text: &str) -> ColoredString {
+        text.color(self.label)
+    }
+
+    pub fn text(&self, text: &str) -> ColoredString {
+        text.color(self.text)
+    }
+
+    pub fn good(&self, text: &str) -> ColoredString {
+        text.color(self.good)
+    }
+
+    pub fn warn(&self, text: &str) -> ColoredString {
+        text.color(self.warn)
+    }
+
+    pub fn bad(&self, text: &str) -> ColoredString {
+        text.color(self.bad)
+    }
+
+    pub fn link(&self, text: &str) -> ColoredString {
+        text.color(self.link)
+    }
+}
+
+/// Palette chosen by `init`; dark until then
+pub fn theme() -> Theme {
+    *ACTIVE.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Apply `ui.color_scheme` and turn color off for `--no-color`, `NO_COLOR`
+/// or a stdout that isn't a terminal. Returns whether stdout is colored.
+pub fn init(scheme: &ColorScheme, no_color: bool) -> bool {
+    *ACTIVE.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Theme::for_scheme(scheme);
+
+    let enabled = should_color(no_color, std::io::stdout().is_terminal());
+    colored::control::set_override(enabled);
+    enabled
+}
+
+/// Whether a stream may be colored, given whether it is a terminal
+pub fn should_color(no_color: bool, is_terminal: bool) -> bool {
+    color_enabled(no_color, std::env::var_os("NO_COLOR"), is_terminal)
+}
+
+/// Per https://no-color.org, a `NO_COLOR` that is set and not empty
+/// disables color
+fn color_enabled(no_color: bool, no_color_env: Option<OsString>, is_terminal: bool) -> bool {
+    let env_disabled = no_color_env.is_some_and(|value| !value.is_empty());
+    !no_color && !env_disabled && is_terminal
+}
+
+/// `COLORFGBG` is "fg;bg" (rxvt, Konsole, ...); ANSI 7 and 15 are white
+fn light_background(colorfgbg: Option<&str>) -> bool {
+    colorfgbg
+        .and_then(|value| value.rsplit(';').next())
+        .and_then(|bg| bg.parse::<u8>().ok())
+        .is_some_and(|bg| bg == 7 || bg == 15)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_disabled_without_tty_or_with_no_color() {
+        assert!(color_enabled(false, None, true));
+        assert!(!color_enabled(false, None, false), "piped output must not be colored");
+        assert!(!color_enabled(false, Some("1".into()), true), "NO_COLOR must disable color");
+        assert!(!color_enabled(true, None, true), "--no-color must disable color");
+        // An empty NO_COLOR doesn't count
+        assert!(color_enabled(false, Some("".into()), true));
+
+        colored::control::set_override(color_enabled(false, None, false));
+        let rendered = format!("{} {}", theme().accent("PORT-ZILLA"), Theme::for_scheme(&ColorScheme::Light).bad("22/tcp").bold());
+        colored::control::unset_override();
+        assert_eq!(rendered, "PORT-ZILLA 22/tcp");
+        assert!(!rendered.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_light_scheme_avoids_bright_colors() {
+        assert_eq!(Theme::for_scheme(&ColorScheme::Dark), DARK);
+        assert_eq!(Theme::for_scheme(&ColorScheme::Light), LIGHT);
+        assert_ne!(LIGHT.text, Color::BrightWhite);
+        assert_ne!(LIGHT.accent, Color::BrightYellow);
+
+        assert!(light_background(Some("0;15")));
+        assert!(light_background(Some("0;default;7")));
+        assert!(!light_background(Some("15;0")));
+        assert!(!light_background(None));
+    }
+}
@@ -1,21 +1,69 @@
-use crate::error::Result;
-use tracing_subscriber::{fmt, EnvFilter};
+use crate::config::{LoggingSettings, LogFormat};
+use crate::error::{Error, Result};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer, Registry};
 use tracing::Level;
 
-/// Initialize logging system
-pub fn setup_logging(default_level: Level) -> Result<()> {
+/// Initialize logging system.
+///
+/// Console output goes to stderr (keeping stdout clean for e.g. `--output ndjson`)
+/// in the configured format; `Json` emits one JSON object per event. With
+/// `enable_file_logging`, events are also written to a daily-rotated file in
+/// `log_directory`. Keep the returned guard alive until exit so buffered file
+/// output is flushed. `ansi` colors the console output; files never are.
+pub fn setup_logging(default_level: Level, settings: &LoggingSettings, ansi: bool) -> Result<Option<WorkerGuard>> {
     let filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new(default_level.to_string().to_lowercase()));
     
-    fmt()
-        .with_env_filter(filter)
-        .with_target(true)
-        .with_thread_ids(true)
-        .with_file(true)
-        .with_line_number(true)
-        .init();
+    let mut layers = vec![format_layer(&settings.format, std::io::stderr, ansi)];
     
-    Ok(())
+    let guard = if settings.enable_file_logging {
+        std::fs::create_dir_all(&settings.log_directory)?;
+        let appender = tracing_appender::rolling::daily(&settings.log_directory, "portzilla.log");
+        let (writer, guard) = tracing_appender::non_blocking(appender);
+        layers.push(format_layer(&settings.format, writer, false));
+        Some(guard)
+    } else {
+        None
+    };
+    
+    tracing_subscriber::registry()
+        .with(layers)
+        .with(filter)
+        .try_init()
+        .map_err(|e| Error::Unknown(format!("Failed to initialize logging: {}", e)))?;
+    
+    Ok(guard)
+}
+
+fn format_layer<W>(format: &LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<Registry> + Send + Sync>
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Json => fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_writer(writer)
+            .boxed(),
+        LogFormat::Simple => fmt::layer()
+            .compact()
+            .with_target(false)
+            .with_ansi(ansi)
+            .with_writer(writer)
+            .boxed(),
+        LogFormat::Detailed => fmt::layer()
+            .with_target(true)
+            .with_thread_ids(true)
+            .with_file(true)
+            .with_line_number(true)
+            .with_ansi(ansi)
+            .with_writer(writer)
+            .boxed(),
+    }
 }
 
 /// Validate IP address or hostname
@@ -32,8 +80,47 @@ pub fn validate_target(target: &str) -> Result<()> {
     }
 }
 
-/// Check if string is a valid hostname
+/// RFC 1918, loopback and link-local IPv4; loopback, unique local
+/// (fc00::/7) and link-local IPv6. Everything else is internet-reachable or
+/// reserved, and off limits in safe mode.
+pub fn is_private_address(ip: std::net::IpAddr) -> bool {
+    use std::net::IpAddr;
+
+    match ip {
+        IpAddr::V4(ip) => ip.is_private() || ip.is_loopback() || ip.is_link_local(),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_private_address(IpAddr::V4(mapped)),
+            None => {
+                let first = ip.segments()[0];
+                ip.is_loopback() || (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80
+            }
+        },
+    }
+}
+
+/// `Error::Security` for a public `ip`: the safe-mode check, for callers
+/// that haven't set `security.allow_public_targets`
+pub fn check_private_target(ip: std::net::IpAddr) -> Result<()> {
+    if is_private_address(ip) {
+        return Ok(());
+    }
+    Err(Error::Security(format!(
+        "{} is a public address and safe mode only scans private and loopback ranges; \
+         set security.allow_public_targets = true to scan hosts you are authorized to test",
+        ip
+    )))
+}
+
+/// Check if string is a valid hostname.
+///
+/// Internationalized names are checked on their punycode (A-label) form, so
+/// `müller.de` is validated as `xn--mller-kva.de`.
 pub fn is_valid_hostname(hostname: &str) -> bool {
+    let hostname = match to_ascii_hostname(hostname) {
+        Ok(hostname) => hostname,
+        Err(_) => return false,
+    };
+
     if hostname.len() > 253 {
         return false;
     }
@@ -60,10 +147,119 @@ pub fn is_valid_hostname(hostname: &str) -> bool {
     true
 }
 
-/// Calculate estimated scan time
-pub fn estimate_scan_time(port_count: u16, threads: usize, timeout_ms: u64) -> std::time::Duration {
-    let batches = (port_count as f64 / threads as f64).ceil() as u64;
-    std::time::Duration::from_millis(batches * timeout_ms)
+/// Convert a hostname to its ASCII form, punycode-encoding Unicode labels.
+/// Use this before resolving a user-supplied hostname.
+pub fn to_ascii_hostname(hostname: &str) -> Result<String> {
+    if hostname.is_ascii() {
+        return Ok(hostname.to_string());
+    }
+
+    idna::domain_to_ascii(hostname)
+        .map_err(|e| Error::Validation(format!("Invalid internationalized hostname {}: {:?}", hostname, e)))
+}
+
+/// How long a scan is expected to take, as a range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanEstimate {
+    pub optimistic: std::time::Duration, // Every port answers promptly, few are open
+    pub pessimistic: std::time::Duration, // Every probe times out and is retried, more ports are open
+}
+
+/// Round trip of a probe the port answers (open or refused), on a nearby network
+const ANSWERED_PROBE_TIME: std::time::Duration = std::time::Duration::from_millis(5);
+
+/// Share of probed ports assumed open in the optimistic and the pessimistic case
+const OPEN_SHARE: (f64, f64) = (0.005, 0.05);
+
+/// Service detection and banner grab of an open port that answers promptly;
+/// in the pessimistic case each of them waits out the timeout instead
+const ENRICH_TIME: std::time::Duration = std::time::Duration::from_millis(150);
+
+/// Expected duration of scanning `port_count` ports with `config`, the TCP
+/// sweep using `technique`.
+///
+/// Probes run at the technique's limit (`ScanConfig::concurrency_for`,
+/// after the ramp-up) unless `rate_limit` or `max_pps` allow fewer per
+/// second; a UDP pass adds a sweep at `udp_concurrency`, an SCTP pass one at
+/// `max_concurrent_tasks`. The optimistic end has every port answer at once; the
+/// pessimistic end has every probe time out and be retried `max_retries`
+/// times, the retries the engine makes. Service detection and banner grabs
+/// of the open ports come on top, `enrich_concurrency` at a time.
+pub fn estimate_scan_time(
+    port_count: usize,
+    technique: crate::scanner::ScanTechnique,
+    config: &crate::scanner::ScanConfig,
+) -> ScanEstimate {
+    let mut sweeps = vec![config.concurrency_for(technique)];
+    if config.enable_udp {
+        sweeps.push(config.udp_concurrency());
+    }
+    if config.enable_sctp {
+        sweeps.push(config.max_concurrent_tasks);
+    }
+    let timeout = config.timeout.as_secs_f64();
+    let all_sweeps = |probe_time: f64, attempts: u32| -> f64 {
+        sweeps.iter()
+            .map(|&concurrency| sweep_secs(port_count, probe_time, attempts, concurrency, config))
+            .sum()
+    };
+    let optimistic = all_sweeps(ANSWERED_PROBE_TIME.as_secs_f64(), 1)
+        + enrichment_secs(port_count, OPEN_SHARE.0, ENRICH_TIME.as_secs_f64(), config);
+    let pessimistic = all_sweeps(timeout, config.max_retries.saturating_add(1))
+        + enrichment_secs(port_count, OPEN_SHARE.1, timeout * 2.0, config);
+
+    let duration = |secs: f64| std::time::Duration::try_from_secs_f64(secs).unwrap_or(std::time::Duration::MAX);
+    ScanEstimate {
+        optimistic: duration(optimistic),
+        pessimistic: duration(pessimistic.max(optimistic)),
+    }
+}
+
+/// Seconds for one pass over `port_count` ports, each probed `attempts`
+/// times for `probe_time` seconds: the slower of the pass's `concurrency`
+/// (within `global_max_concurrent`, stretched by the ramp-up) and the rate
+/// caps
+fn sweep_secs(
+    port_count: usize,
+    probe_time: f64,
+    attempts: u32,
+    concurrency: usize,
+    config: &crate::scanner::ScanConfig,
+) -> f64 {
+    if port_count == 0 {
+        return 0.0;
+    }
+    let probes = port_count as f64 * f64::from(attempts);
+
+    let concurrency = concurrency.min(config.global_max_concurrent).max(1) as f64;
+    let full_speed = probes * probe_time / concurrency;
+    // Concurrency grows linearly over the ramp-up, so half its work is lost;
+    // a sweep shorter than that never reaches full speed
+    let rampup = config.rampup_duration.as_secs_f64();
+    let concurrency_bound = if full_speed >= rampup / 2.0 {
+        full_speed + rampup / 2.0
+    } else {
+        (2.0 * rampup * full_speed).sqrt()
+    };
+
+    let packets_per_probe = f64::from(crate::scanner::pacer::CONNECT_PROBE_PACKETS);
+    let rate_bound = [
+        config.rate_limit.map(|limit| probes / f64::from(limit.max(1))),
+        config.max_pps.map(|max_pps| probes * packets_per_probe / f64::from(max_pps.max(1))),
+    ].into_iter().flatten().fold(0.0, f64::max);
+
+    concurrency_bound.max(rate_bound)
+}
+
+/// Seconds of detection and banner grabbing of the `open_share` of
+/// `port_count` ports assumed open, `enrich_concurrency` ports at a time
+fn enrichment_secs(port_count: usize, open_share: f64, per_port: f64, config: &crate::scanner::ScanConfig) -> f64 {
+    let enriched = (config.enable_service_detection || config.enable_banner_grabbing) && !config.discovery_only;
+    if !enriched || port_count == 0 {
+        return 0.0;
+    }
+    let open_ports = (port_count as f64 * open_share).ceil();
+    per_port * (open_ports / config.enrich_concurrency.max(1) as f64).ceil()
 }
 
 /// Generate a unique scan ID
@@ -98,3 +294,166 @@ pub fn format_file_size(bytes: u64) -> String {
     
     format!("{:.2} {}", size, UNITS[digit_groups])
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone)]
+    struct BufferWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for BufferWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_private_address_ranges() {
+        for private in ["10.1.2.3", "172.31.255.1", "192.168.0.10", "127.0.0.1", "169.254.1.1", "::1", "fd00::1", "fe80::1", "::ffff:10.0.0.1"] {
+            assert!(is_private_address(private.parse().unwrap()), "{} should be private", private);
+        }
+        for public in ["8.8.8.8", "172.32.0.1", "100.64.0.1", "0.0.0.0", "2001:4860:4860::8888", "::ffff:8.8.8.8"] {
+            assert!(!is_private_address(public.parse().unwrap()), "{} should be public", public);
+        }
+    }
+
+    #[test]
+    fn test_unicode_hostname_is_punycoded() {
+        assert_eq!(to_ascii_hostname("müller.de").unwrap(), "xn--mller-kva.de");
+        assert!(is_valid_hostname("müller.de"));
+        assert!(validate_target("bücher.example.com").is_ok());
+    }
+
+    #[test]
+    fn test_malformed_unicode_hostname_is_rejected() {
+        assert!(!is_valid_hostname("mül ler.de"));
+        assert!(!is_valid_hostname("müller..de"));
+        // 60 'ü' fit in a Unicode label but not in the 63-byte A-label
+        assert!(!is_valid_hostname(&format!("{}.de", "ü".repeat(60))));
+    }
+
+    #[test]
+    fn test_estimate_grows_with_ports_and_timeout() {
+        use crate::scanner::{ScanConfig, ScanTechnique};
+        use std::time::Duration;
+
+        let config = ScanConfig {
+            timeout: Duration::from_millis(500),
+            max_concurrent_tasks: 100,
+            ..ScanConfig::default()
+        };
+        let estimate = |port_count: usize, config: &ScanConfig| estimate_scan_time(port_count, ScanTechnique::Connect, config);
+
+        let base = estimate(1000, &config);
+        assert!(base.optimistic < base.pessimistic, "{:?}", base);
+        assert_eq!(estimate(0, &config), ScanEstimate { optimistic: Duration::ZERO, pessimistic: Duration::ZERO });
+
+        // More ports, a longer timeout or more retries: never shorter
+        let mut previous = estimate(1, &config);
+        for port_count in [10, 100, 1000, 10_000, 65_535] {
+            let next = estimate(port_count, &config);
+            assert!(next.optimistic >= previous.optimistic && next.pessimistic > previous.pessimistic);
+            previous = next;
+        }
+        let slower = estimate(1000, &ScanConfig { timeout: Duration::from_secs(2), ..config.clone() });
+        assert!(slower.pessimistic > base.pessimistic);
+        assert_eq!(slower.optimistic, base.optimistic); // Answered probes don't wait for the timeout
+        let retried = estimate(1000, &ScanConfig { max_retries: 2, ..config.clone() });
+        assert!(retried.pessimistic > base.pessimistic);
+
+        // More concurrency: never longer
+        let wider = estimate(1000, &ScanConfig { max_concurrent_tasks: 400, ..config.clone() });
+        assert!(wider.optimistic <= base.optimistic && wider.pessimistic < base.pessimistic);
+        let capped = estimate(1000, &ScanConfig { global_max_concurrent: 10, ..config.clone() });
+        assert!(capped.pessimistic > base.pessimistic);
+    }
+
+    #[test]
+    fn test_estimate_honors_rate_caps_rampup_and_enrichment() {
+        use crate::scanner::{ScanConfig, ScanTechnique};
+        use std::time::Duration;
+
+        let config = ScanConfig {
+            timeout: Duration::from_millis(500),
+            max_concurrent_tasks: 100,
+            enable_service_detection: false,
+            enable_banner_grabbing: false,
+            ..ScanConfig::default()
+        };
+        let base = estimate_scan_time(1000, ScanTechnique::Connect, &config);
+        // 1000 filtered ports, 100 at a time, 0.5s each
+        assert_eq!(base.pessimistic, Duration::from_secs(5));
+
+        // 10 probes a second bind long before the concurrency does
+        let paced = estimate_scan_time(1000, ScanTechnique::Connect, &ScanConfig { rate_limit: Some(10), ..config.clone() });
+        assert_eq!(paced.optimistic, Duration::from_secs(100));
+        assert_eq!(paced.pessimistic, Duration::from_secs(100));
+        let max_pps = estimate_scan_time(1000, ScanTechnique::Connect, &ScanConfig { max_pps: Some(40), ..config.clone() });
+        assert!(max_pps.optimistic > base.optimistic && max_pps.pessimistic > base.pessimistic);
+
+        let ramped = estimate_scan_time(1000, ScanTechnique::Connect, &ScanConfig { rampup_duration: Duration::from_secs(4), ..config.clone() });
+        assert_eq!(ramped.pessimistic, Duration::from_secs(7));
+        assert!(ramped.optimistic > base.optimistic);
+
+        let enriched = estimate_scan_time(1000, ScanTechnique::Connect, &ScanConfig { enable_service_detection: true, ..config.clone() });
+        assert!(enriched.optimistic > base.optimistic && enriched.pessimistic > base.pessimistic);
+        let discovery = ScanConfig { enable_service_detection: true, discovery_only: true, ..config.clone() };
+        assert_eq!(estimate_scan_time(1000, ScanTechnique::Connect, &discovery), base);
+
+        let with_udp = estimate_scan_time(1000, ScanTechnique::Connect, &ScanConfig { enable_udp: true, ..config.clone() });
+        assert_eq!(with_udp.pessimistic, Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_estimate_uses_the_per_technique_limits() {
+        use crate::scanner::{ScanConfig, ScanTechnique};
+        use std::time::Duration;
+
+        let config = ScanConfig {
+            timeout: Duration::from_millis(500),
+            max_concurrent_tasks: 100,
+            syn_max_concurrent: Some(500),
+            enable_service_detection: false,
+            enable_banner_grabbing: false,
+            ..ScanConfig::default()
+        };
+        // 1000 filtered ports at 0.5s each: 100 or 500 at a time
+        assert_eq!(estimate_scan_time(1000, ScanTechnique::Connect, &config).pessimistic, Duration::from_secs(5));
+        assert_eq!(estimate_scan_time(1000, ScanTechnique::Syn, &config).pessimistic, Duration::from_secs(1));
+
+        // The UDP pass runs at its own limit, not the TCP one
+        let with_udp = ScanConfig { enable_udp: true, udp_max_concurrent: Some(50), ..config.clone() };
+        assert_eq!(estimate_scan_time(1000, ScanTechnique::Syn, &with_udp).pessimistic, Duration::from_secs(11));
+    }
+
+    #[test]
+    fn test_json_logs_are_parseable_lines() {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let writer = BufferWriter(Arc::clone(&buffer));
+        let subscriber = tracing_subscriber::registry()
+            .with(format_layer(&LogFormat::Json, move || writer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(port = 443, "first event");
+            tracing::warn!("second event");
+        });
+
+        let output = String::from_utf8(buffer.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("log line is not JSON"))
+            .collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["fields"]["message"], "first event");
+        assert_eq!(lines[0]["fields"]["port"], 443);
+        assert_eq!(lines[1]["level"], "WARN");
+    }
+}
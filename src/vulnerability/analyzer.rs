@@ -13,6 +13,13 @@ impl VulnerabilityAnalyzer {
         Ok(Self { detector })
     }
 
+    /// Analyzer whose detector also applies the rule file at
+    /// `custom_rules_path`, if one is configured
+    pub fn from_settings(settings: &crate::config::VulnerabilitySettings) -> Result<Self> {
+        let detector = super::VulnerabilityDetector::from_settings(settings)?;
+        Ok(Self { detector })
+    }
+
     pub async fn analyze_scan(&self, scan_result: &ScanResult) -> Result<VulnerabilityReport> {
         self.detector.analyze_scan(scan_result).await
     }
@@ -220,3 +227,49 @@ impl Default for VulnerabilityTrends {
         Self::new()
     }
   }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::VulnerabilitySettings;
+    use crate::scanner::{PortInfo, PortStatus, Protocol, ScanType};
+
+    #[tokio::test]
+    async fn test_custom_rule_file_findings_reach_the_analyzer() {
+        let dir = tempfile::tempdir().unwrap();
+        let rules_path = dir.path().join("rules.toml");
+        std::fs::write(&rules_path, r#"
+            [[rules]]
+            id = "internal-admin"
+            title = "Internal Admin Console Exposed"
+            description = "The admin console answers on this port"
+            level = "High"
+            mitigation = "Restrict the console to the management network"
+            banner_regex = "AcmeAdmin/"
+            "#).unwrap();
+
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        scan.add_open_port(PortInfo {
+            port: 9443,
+            status: PortStatus::Open,
+            service: None,
+            banner: Some("HTTP/1.1 200 OK | Server: AcmeAdmin/3.1".to_string()),
+            banner_raw: None,
+            response_time: None,
+            protocol: Protocol::Tcp,
+            tls: None,
+        });
+        let has_rule_finding = |report: &VulnerabilityReport| report.vulnerabilities.iter()
+            .any(|v| v.title == "Internal Admin Console Exposed" && v.port == 9443);
+
+        let settings = VulnerabilitySettings {
+            custom_rules_path: Some(rules_path.display().to_string()),
+            ..VulnerabilitySettings::default()
+        };
+        let report = VulnerabilityAnalyzer::from_settings(&settings).unwrap().analyze_scan(&scan).await.unwrap();
+        assert!(has_rule_finding(&report));
+
+        let report = VulnerabilityAnalyzer::new().unwrap().analyze_scan(&scan).await.unwrap();
+        assert!(!has_rule_finding(&report));
+    }
+}
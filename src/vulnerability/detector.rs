@@ -1,26 +1,51 @@
 use super::models::{Vulnerability, VulnerabilityLevel, VulnerabilityReport, ServiceVulnerability};
+use super::rules::RuleSet;
+use crate::config::VulnerabilitySettings;
 use crate::error::{Error, Result};
 use crate::scanner::ScanResult;
 use std::collections::HashMap;
 use std::net::IpAddr;
+use std::path::Path;
 use tracing::{info, debug, warn};
 
+/// Certificates expiring within this many days are reported
+const TLS_EXPIRY_WARNING_DAYS: i64 = 30;
+
 pub struct VulnerabilityDetector {
     database: super::database::VulnerabilityDatabase,
     checks: Vec<Box<dyn VulnerabilityCheck>>,
+    rules: RuleSet,
 }
 
 impl VulnerabilityDetector {
     pub fn new() -> Result<Self> {
         let database = super::database::VulnerabilityDatabase::new()?;
         let checks = Self::initialize_checks();
+        let rules = RuleSet::default_rules()?;
         
         Ok(Self {
             database,
             checks,
+            rules,
         })
     }
 
+    /// Built-in rules extended (or overridden by id) with the user's rule file
+    pub fn from_settings(settings: &VulnerabilitySettings) -> Result<Self> {
+        let mut detector = Self::new()?;
+
+        if let Some(path) = &settings.custom_rules_path {
+            detector.rules.extend(RuleSet::load(Path::new(path))?);
+        }
+
+        Ok(detector)
+    }
+
+    pub fn with_rules(mut self, rules: RuleSet) -> Self {
+        self.rules = rules;
+        self
+    }
+
     pub async fn analyze_scan(&self, scan_result: &ScanResult) -> Result<VulnerabilityReport> {
         info!("Starting vulnerability analysis for scan: {}", scan_result.id);
         
@@ -42,11 +67,19 @@ impl VulnerabilityDetector {
             for vulnerability in service_vulns {
                 report.add_vulnerability(vulnerability);
             }
+
+            // Rule-file checks
+            for vulnerability in self.rules.evaluate(port_info) {
+                report.add_vulnerability(vulnerability);
+            }
         }
 
         // Run general security checks
         self.run_general_checks(&scan_result, &mut report).await?;
 
+        // A CVE match and a rule can flag the same issue; count it once
+        report.deduplicate();
+
         info!(
             "Vulnerability analysis completed: {} vulnerabilities found",
             report.vulnerabilities.len()
@@ -112,32 +145,10 @@ impl VulnerabilityDetector {
         scan_result: &ScanResult,
         report: &mut VulnerabilityReport,
     ) -> Result<()> {
+        // Single-port exposures (Telnet, anonymous FTP, open Redis...) are
+        // covered by the rule set; only cross-port checks live here
         for port_info in &scan_result.open_ports {
             match port_info.port {
-                // FTP anonymous access
-                21 => {
-                    let vuln = Vulnerability::new(
-                        "FTP Anonymous Access Enabled".to_string(),
-                        "FTP server allows anonymous access without authentication".to_string(),
-                        VulnerabilityLevel::Medium,
-                        port_info.port,
-                        "FTP".to_string(),
-                        "Anonymous FTP access detected".to_string(),
-                    );
-                    report.add_vulnerability(vuln);
-                }
-                // Telnet cleartext protocol
-                23 => {
-                    let vuln = Vulnerability::new(
-                        "Telnet Service Exposed".to_string(),
-                        "Telnet transmits credentials in cleartext and is considered insecure".to_string(),
-                        VulnerabilityLevel::High,
-                        port_info.port,
-                        "Telnet".to_string(),
-                        "Telnet service detected".to_string(),
-                    );
-                    report.add_vulnerability(vuln);
-                }
                 // HTTP without HTTPS redirect
                 80 => {
                     if !scan_result.open_ports.iter().any(|p| p.port == 443) {
@@ -152,18 +163,6 @@ impl VulnerabilityDetector {
                         report.add_vulnerability(vuln);
                     }
                 }
-                // Redis without authentication
-                6379 => {
-                    let vuln = Vulnerability::new(
-                        "Redis Without Authentication".to_string(),
-                        "Redis database exposed without authentication".to_string(),
-                        VulnerabilityLevel::High,
-                        port_info.port,
-                        "Redis".to_string(),
-                        "Redis service detected - check authentication".to_string(),
-                    );
-                    report.add_vulnerability(vuln);
-                }
                 _ => {}
             }
         }
@@ -196,11 +195,73 @@ impl VulnerabilityDetector {
 
     async fn check_weak_configurations(
         &self,
+        scan_result: &ScanResult,
         report: &mut VulnerabilityReport,
     ) -> Result<()> {
-        // Check for default credentials (would require actual testing)
-        // Check for outdated protocols
-        // etc.
+        let now = chrono::Utc::now();
+
+        for port_info in &scan_result.open_ports {
+            let Some(tls) = &port_info.tls else {
+                continue;
+            };
+            let service = port_info.service.as_ref().map(|s| s.name.clone()).unwrap_or("tls".to_string());
+            let subject = tls.subject.as_deref().unwrap_or("unknown subject");
+
+            if let Some(not_after) = tls.not_after {
+                let evidence = format!("Subject: {}, expires {}", subject, not_after.format("%Y-%m-%d %H:%M UTC"));
+
+                let vuln = if not_after < now {
+                    Some(Vulnerability::new(
+                        "TLS Certificate Expired".to_string(),
+                        "The server presents a certificate past its expiry date; clients will reject it or users learn to click through warnings".to_string(),
+                        VulnerabilityLevel::High,
+                        port_info.port,
+                        service.clone(),
+                        evidence,
+                    ))
+                } else if not_after - now <= chrono::Duration::days(TLS_EXPIRY_WARNING_DAYS) {
+                    Some(Vulnerability::new(
+                        "TLS Certificate Expiring Soon".to_string(),
+                        format!("The server certificate expires within {} days", TLS_EXPIRY_WARNING_DAYS),
+                        VulnerabilityLevel::Medium,
+                        port_info.port,
+                        service.clone(),
+                        evidence,
+                    ))
+                } else {
+                    None
+                };
+
+                if let Some(mut vuln) = vuln {
+                    vuln.mitigation = "Renew the certificate and automate renewal".to_string();
+                    vuln.certainty = 100;
+                    vuln.tags = vec!["tls".to_string(), "certificate".to_string()];
+                    report.add_vulnerability(vuln);
+                }
+            }
+
+            if !tls.legacy_protocols.is_empty() {
+                let level = if tls.legacy_protocols.iter().any(|p| p == "SSLv3") {
+                    VulnerabilityLevel::High
+                } else {
+                    VulnerabilityLevel::Medium
+                };
+
+                let mut vuln = Vulnerability::new(
+                    "Deprecated TLS Protocol Supported".to_string(),
+                    "The server accepts protocol versions with known weaknesses (POODLE, BEAST)".to_string(),
+                    level,
+                    port_info.port,
+                    service,
+                    format!("Subject: {}, accepts {}", subject, tls.legacy_protocols.join(", ")),
+                );
+                vuln.mitigation = "Disable SSLv3 and TLS 1.0; require TLS 1.2 or later".to_string();
+                vuln.certainty = 95;
+                vuln.tags = vec!["tls".to_string(), "protocol".to_string()];
+                report.add_vulnerability(vuln);
+            }
+        }
+
         Ok(())
     }
 
@@ -230,6 +291,7 @@ impl VulnerabilityDetector {
             impact: "See CVE description".to_string(),
             certainty: 90, // High confidence for CVE matches
             tags: vec!["cve".to_string(), "database".to_string()],
+            suppressed: false,
         }
     }
 
@@ -413,3 +475,86 @@ impl VulnerabilityCheck for RdpVulnerabilityCheck {
         )))
     }
           }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{PortInfo, PortStatus, Protocol, ScanType, TlsInfo};
+    use chrono::{DateTime, Duration, Utc};
+
+    fn tls_info(not_after: DateTime<Utc>, legacy_protocols: &[&str]) -> TlsInfo {
+        TlsInfo {
+            protocol_version: "TLSv1_3".to_string(),
+            subject: Some("CN=portal.example.test".to_string()),
+            issuer: Some("CN=Example CA".to_string()),
+            not_before: Some(not_after - Duration::days(365)),
+            not_after: Some(not_after),
+            legacy_protocols: legacy_protocols.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    async fn weak_configuration_findings(tls: TlsInfo) -> Vec<Vulnerability> {
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        scan.add_open_port(PortInfo {
+            port: 443,
+            status: PortStatus::Open,
+            service: None,
+            banner: None,
+            banner_raw: None,
+            response_time: None,
+            protocol: Protocol::Tcp,
+            tls: Some(tls),
+        });
+
+        let mut report = VulnerabilityReport::new(scan.id.clone(), scan.target.clone(), scan.target_ip);
+        VulnerabilityDetector::new().unwrap().check_weak_configurations(&scan, &mut report).await.unwrap();
+        report.vulnerabilities
+    }
+
+    fn expiry_evidence(not_after: DateTime<Utc>) -> String {
+        format!("Subject: CN=portal.example.test, expires {}", not_after.format("%Y-%m-%d %H:%M UTC"))
+    }
+
+    #[tokio::test]
+    async fn test_expired_certificate() {
+        let not_after = Utc::now() - Duration::days(3);
+        let findings = weak_configuration_findings(tls_info(not_after, &[])).await;
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].title, "TLS Certificate Expired");
+        assert_eq!(findings[0].level, VulnerabilityLevel::High);
+        assert_eq!((findings[0].port, findings[0].service.as_str()), (443, "tls"));
+        assert_eq!(findings[0].evidence, expiry_evidence(not_after));
+    }
+
+    #[tokio::test]
+    async fn test_certificate_expiring_within_warning_window() {
+        let not_after = Utc::now() + Duration::days(TLS_EXPIRY_WARNING_DAYS - 1);
+        let findings = weak_configuration_findings(tls_info(not_after, &[])).await;
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].title, "TLS Certificate Expiring Soon");
+        assert_eq!(findings[0].level, VulnerabilityLevel::Medium);
+        assert_eq!(findings[0].evidence, expiry_evidence(not_after));
+
+        let later = Utc::now() + Duration::days(TLS_EXPIRY_WARNING_DAYS + 30);
+        assert!(weak_configuration_findings(tls_info(later, &[])).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_legacy_protocols() {
+        let not_after = Utc::now() + Duration::days(365);
+
+        let findings = weak_configuration_findings(tls_info(not_after, &["TLSv1.0"])).await;
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].title, "Deprecated TLS Protocol Supported");
+        assert_eq!(findings[0].level, VulnerabilityLevel::Medium);
+        assert_eq!(findings[0].evidence, "Subject: CN=portal.example.test, accepts TLSv1.0");
+
+        // SSLv3 is POODLE: worse than TLS 1.0 alone
+        let findings = weak_configuration_findings(tls_info(not_after, &["SSLv3", "TLSv1.0"])).await;
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].level, VulnerabilityLevel::High);
+        assert_eq!(findings[0].evidence, "Subject: CN=portal.example.test, accepts SSLv3, TLSv1.0");
+    }
+}
@@ -0,0 +1,448 @@
+//! CVE feed downloads for `--update-db`.
+//!
+//! Each feed is fetched into `<cache_dir>/<name>.json.part` and renamed to
+//! `<name>.json` once complete, so the last good copy survives a failed
+//! update. Rate limiting (429) and server errors are retried with exponential
+//! backoff, honoring `Retry-After`; an interrupted transfer resumes from the
+//! partial file with a `Range` request.
+//!
+//! The NVD 2.0 API returns at most 2000 CVEs per request. Pages are requested
+//! with `startIndex`/`resultsPerPage` until `totalResults` is reached, each
+//! into `<name>.<startIndex>.json.part`, pausing between requests as NVD asks
+//! of clients without an API key, and merged into one document. A response
+//! without `totalResults` is taken as the whole feed.
+
+use crate::config::{CveFeedSettings, VulnerabilitySettings};
+use crate::error::{Error, Result};
+use reqwest::header::{RANGE, RETRY_AFTER};
+use reqwest::StatusCode;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, warn};
+
+/// Largest page the NVD 2.0 API serves
+pub const NVD_RESULTS_PER_PAGE: u64 = 2000;
+
+/// NVD's recommended pause between requests without an API key
+pub const NVD_PAGE_DELAY: Duration = Duration::from_secs(6);
+
+#[derive(Debug, Clone)]
+pub enum FeedStatus {
+    Downloaded(PathBuf),
+    Cached(PathBuf, String), // Download failed; reusing the last good copy
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct FeedOutcome {
+    pub name: String,
+    pub status: FeedStatus,
+}
+
+/// Per-feed results of an update run
+#[derive(Debug, Clone, Default)]
+pub struct FeedUpdateReport {
+    pub outcomes: Vec<FeedOutcome>,
+}
+
+impl FeedUpdateReport {
+    pub fn failures(&self) -> impl Iterator<Item = &FeedOutcome> {
+        self.outcomes.iter().filter(|o| matches!(o.status, FeedStatus::Failed(_)))
+    }
+
+    pub fn all_succeeded(&self) -> bool {
+        self.outcomes.iter().all(|o| matches!(o.status, FeedStatus::Downloaded(_)))
+    }
+}
+
+enum FetchError {
+    Retryable { retry_after: Option<Duration>, reason: String },
+    Fatal(Error),
+}
+
+pub struct CveFeedDownloader {
+    client: reqwest::Client,
+    cache_dir: PathBuf,
+    max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    page_size: u64,
+    page_delay: Duration,
+}
+
+impl CveFeedDownloader {
+    pub fn new(cache_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .user_agent(concat!("Port-ZiLLA/", env!("CARGO_PKG_VERSION")))
+                .build()
+                .unwrap_or_default(),
+            cache_dir: cache_dir.into(),
+            max_retries: 5,
+            base_delay: Duration::from_secs(2),
+            max_delay: Duration::from_secs(120),
+            page_size: NVD_RESULTS_PER_PAGE,
+            page_delay: NVD_PAGE_DELAY,
+        }
+    }
+
+    pub fn from_settings(settings: &VulnerabilitySettings) -> Self {
+        Self::new(&settings.feed_cache_dir)
+    }
+
+    /// Retries after the first attempt; the delay doubles from `base_delay`
+    pub fn with_retry_policy(mut self, max_retries: u32, base_delay: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// `resultsPerPage` to request, and the pause before each page after the first
+    pub fn with_paging(mut self, page_size: u64, page_delay: Duration) -> Self {
+        self.page_size = page_size.max(1);
+        self.page_delay = page_delay;
+        self
+    }
+
+    pub fn cached_path(&self, feed: &CveFeedSettings) -> PathBuf {
+        self.cache_dir.join(format!("{}.json", feed.name))
+    }
+
+    fn partial_path(&self, feed: &CveFeedSettings) -> PathBuf {
+        self.cache_dir.join(format!("{}.json.part", feed.name))
+    }
+
+    /// The first page downloads straight to `partial_path`, so an unpaged
+    /// feed is renamed into place as it was
+    fn page_path(&self, feed: &CveFeedSettings, start_index: u64) -> PathBuf {
+        match start_index {
+            0 => self.partial_path(feed),
+            _ => self.cache_dir.join(format!("{}.{}.json.part", feed.name, start_index)),
+        }
+    }
+
+    fn page_url(&self, feed: &CveFeedSettings, start_index: u64) -> Result<String> {
+        let mut url = reqwest::Url::parse(&feed.url)
+            .map_err(|e| Error::VulnerabilityDb(format!("Feed {} has an invalid URL: {}", feed.name, e)))?;
+        let kept: Vec<(String, String)> = url.query_pairs()
+            .filter(|(key, _)| key != "startIndex" && key != "resultsPerPage")
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(kept)
+            .append_pair("startIndex", &start_index.to_string())
+            .append_pair("resultsPerPage", &self.page_size.to_string());
+        Ok(url.into())
+    }
+
+    /// Download every feed. A failure never stops the remaining feeds; a
+    /// failed feed with a cached copy is reported as `Cached`.
+    pub async fn update_all(&self, feeds: &[CveFeedSettings]) -> FeedUpdateReport {
+        let mut report = FeedUpdateReport::default();
+
+        for feed in feeds {
+            let status = match self.download(feed).await {
+                Ok(path) => FeedStatus::Downloaded(path),
+                Err(e) => {
+                    let cached = self.cached_path(feed);
+                    if fs::metadata(&cached).await.is_ok() {
+                        warn!("Feed {} update failed ({}); using cached copy {}", feed.name, e, cached.display());
+                        FeedStatus::Cached(cached, e.to_string())
+                    } else {
+                        FeedStatus::Failed(e.to_string())
+                    }
+                }
+            };
+            report.outcomes.push(FeedOutcome { name: feed.name.clone(), status });
+        }
+
+        report
+    }
+
+    pub async fn download(&self, feed: &CveFeedSettings) -> Result<PathBuf> {
+        fs::create_dir_all(&self.cache_dir).await?;
+        let partial = self.partial_path(feed);
+        let cached = self.cached_path(feed);
+
+        // The first page, with the items of every later page appended
+        let mut document: Option<serde_json::Value> = None;
+        let mut start_index = 0;
+        loop {
+            if start_index > 0 {
+                tokio::time::sleep(self.page_delay).await;
+            }
+            let page_path = self.page_path(feed, start_index);
+            self.fetch_with_retries(feed, &self.page_url(feed, start_index)?, &page_path).await?;
+
+            let mut page: serde_json::Value = serde_json::from_slice(&fs::read(&page_path).await?)
+                .map_err(|e| Error::VulnerabilityDb(format!(
+                    "Feed {} page at {} is not valid JSON: {}", feed.name, start_index, e
+                )))?;
+            let total = page.get("totalResults").and_then(serde_json::Value::as_u64);
+            let items = match page.get_mut("vulnerabilities").and_then(serde_json::Value::as_array_mut) {
+                Some(items) => std::mem::take(items),
+                None => Vec::new(),
+            };
+            start_index += items.len() as u64;
+            let done = items.is_empty() || total.map_or(true, |total| start_index >= total);
+
+            if document.is_none() && done {
+                fs::rename(&partial, &cached).await?;
+                info!("Downloaded CVE feed {} to {}", feed.name, cached.display());
+                return Ok(cached);
+            }
+            match document.as_mut().and_then(|d| d.get_mut("vulnerabilities")).and_then(serde_json::Value::as_array_mut) {
+                Some(merged) => merged.extend(items),
+                None => {
+                    page["vulnerabilities"] = serde_json::Value::Array(items);
+                    document = Some(page);
+                }
+            }
+            fs::remove_file(&page_path).await?;
+
+            if done {
+                break;
+            }
+            debug!("Feed {}: {} of {} CVEs downloaded", feed.name, start_index, total.unwrap_or(start_index));
+        }
+
+        fs::write(&partial, serde_json::to_vec(&document)?).await?;
+        fs::rename(&partial, &cached).await?;
+        info!("Downloaded CVE feed {} ({} CVEs) to {}", feed.name, start_index, cached.display());
+        Ok(cached)
+    }
+
+    async fn fetch_with_retries(&self, feed: &CveFeedSettings, url: &str, partial: &Path) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            match self.fetch(feed, url, partial).await {
+                Ok(()) => return Ok(()),
+                Err(FetchError::Fatal(e)) => return Err(e),
+                Err(FetchError::Retryable { reason, .. }) if attempt >= self.max_retries => {
+                    return Err(Error::VulnerabilityDb(format!(
+                        "Feed {} failed after {} attempts: {}", feed.name, attempt + 1, reason
+                    )));
+                }
+                Err(FetchError::Retryable { retry_after, reason }) => {
+                    let delay = retry_after
+                        .unwrap_or_else(|| self.base_delay * 2u32.saturating_pow(attempt))
+                        .min(self.max_delay);
+                    warn!("Feed {}: {}; retrying in {:?}", feed.name, reason, delay);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    async fn fetch(&self, feed: &CveFeedSettings, url: &str, partial: &Path) -> std::result::Result<(), FetchError> {
+        let resume_from = fs::metadata(partial).await.map(|m| m.len()).unwrap_or(0);
+
+        let mut request = self.client.get(url);
+        if resume_from > 0 {
+            debug!("Resuming feed {} at byte {}", feed.name, resume_from);
+            request = request.header(RANGE, format!("bytes={}-", resume_from));
+        }
+
+        let mut response = request.send().await.map_err(|e| FetchError::Retryable {
+            retry_after: None,
+            reason: e.to_string(),
+        })?;
+
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            return Err(FetchError::Retryable {
+                retry_after: response.headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(parse_retry_after),
+                reason: format!("HTTP {}", status),
+            });
+        }
+        if status == StatusCode::RANGE_NOT_SATISFIABLE {
+            // Stale partial file; start over
+            let _ = fs::remove_file(partial).await;
+            return Err(FetchError::Retryable { retry_after: Some(Duration::ZERO), reason: "stale partial download".to_string() });
+        }
+        if !status.is_success() {
+            return Err(FetchError::Fatal(Error::VulnerabilityDb(format!(
+                "Feed {} returned HTTP {}", feed.name, status
+            ))));
+        }
+
+        // A server ignoring Range answers 200 with the full body
+        let append = status == StatusCode::PARTIAL_CONTENT;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(partial)
+            .await
+            .map_err(|e| FetchError::Fatal(e.into()))?;
+
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => file.write_all(&chunk).await.map_err(|e| FetchError::Fatal(e.into()))?,
+                Ok(None) => break,
+                Err(e) => {
+                    // Keep what we have; the next attempt resumes from it
+                    let _ = file.flush().await;
+                    return Err(FetchError::Retryable { retry_after: None, reason: e.to_string() });
+                }
+            }
+        }
+
+        file.flush().await.map_err(|e| FetchError::Fatal(e.into()))?;
+        Ok(())
+    }
+}
+
+/// `Retry-After` as delta-seconds or an HTTP date
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    if let Ok(seconds) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    let wait = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    /// HTTP server answering the n-th request with `responses[n]`
+    /// (the last response repeats)
+    async fn spawn_mock_server(responses: Vec<String>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/feed.json", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&requests);
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match socket.read(&mut buffer).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buffer[..n]),
+                    }
+                }
+
+                let index = counter.fetch_add(1, Ordering::SeqCst).min(responses.len() - 1);
+                let _ = socket.write_all(responses[index].as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+
+        (url, requests)
+    }
+
+    fn response(status: &str, headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status, headers, body.len(), body
+        )
+    }
+
+    #[tokio::test]
+    async fn test_retries_after_rate_limit() {
+        let body = r#"{"vulnerabilities":[]}"#;
+        let (url, requests) = spawn_mock_server(vec![
+            response("429 Too Many Requests", "Retry-After: 0\r\n", ""),
+            response("200 OK", "", body),
+        ]).await;
+
+        let cache = tempfile::tempdir().unwrap();
+        let downloader = CveFeedDownloader::new(cache.path())
+            .with_retry_policy(3, Duration::from_millis(10));
+        let feed = CveFeedSettings { name: "nvd".to_string(), url };
+
+        let report = downloader.update_all(std::slice::from_ref(&feed)).await;
+
+        assert!(report.all_succeeded());
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        let saved = std::fs::read_to_string(downloader.cached_path(&feed)).unwrap();
+        assert_eq!(saved, body);
+    }
+
+    #[tokio::test]
+    async fn test_failed_update_falls_back_to_cache() {
+        let (url, _) = spawn_mock_server(vec![response("503 Service Unavailable", "", "")]).await;
+
+        let cache = tempfile::tempdir().unwrap();
+        let downloader = CveFeedDownloader::new(cache.path())
+            .with_retry_policy(1, Duration::from_millis(10));
+        let cached = CveFeedSettings { name: "cached".to_string(), url: url.clone() };
+        let missing = CveFeedSettings { name: "missing".to_string(), url };
+        std::fs::write(downloader.cached_path(&cached), "{}").unwrap();
+
+        let report = downloader.update_all(&[cached, missing]).await;
+
+        assert!(matches!(report.outcomes[0].status, FeedStatus::Cached(..)));
+        assert!(matches!(report.outcomes[1].status, FeedStatus::Failed(_)));
+        assert_eq!(report.failures().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_nvd_pages_are_fetched_until_total_results() {
+        let page = |start: u64, id: &str| response("200 OK", "", &serde_json::json!({
+            "resultsPerPage": 1,
+            "startIndex": start,
+            "totalResults": 2,
+            "vulnerabilities": [{ "cve": { "id": id } }],
+        }).to_string());
+        let (url, requests) = spawn_mock_server(vec![
+            page(0, "CVE-2024-0001"),
+            page(1, "CVE-2024-0002"),
+        ]).await;
+
+        let cache = tempfile::tempdir().unwrap();
+        let page_delay = Duration::from_millis(100);
+        let downloader = CveFeedDownloader::new(cache.path())
+            .with_retry_policy(1, Duration::from_millis(10))
+            .with_paging(1, page_delay);
+        let feed = CveFeedSettings { name: "nvd".to_string(), url };
+
+        let started = std::time::Instant::now();
+        let path = downloader.download(&feed).await.unwrap();
+
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(started.elapsed() >= page_delay);
+        let records = crate::vulnerability::import::parse_feed(&std::fs::read(path).unwrap()).unwrap();
+        let ids: Vec<&str> = records.iter().map(|r| r.cve_id.as_str()).collect();
+        assert_eq!(ids, ["CVE-2024-0001", "CVE-2024-0002"]);
+        assert_eq!(std::fs::read_dir(cache.path()).unwrap().count(), 1, "page files left behind");
+    }
+
+    #[test]
+    fn test_page_url_replaces_paging_parameters() {
+        let downloader = CveFeedDownloader::new("unused").with_paging(500, Duration::ZERO);
+        let feed = CveFeedSettings {
+            name: "nvd".to_string(),
+            url: "https://services.nvd.nist.gov/rest/json/cves/2.0?startIndex=7&noRejected".to_string(),
+        };
+
+        assert_eq!(
+            downloader.page_url(&feed, 1000).unwrap(),
+            "https://services.nvd.nist.gov/rest/json/cves/2.0?noRejected=&startIndex=1000&resultsPerPage=500"
+        );
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
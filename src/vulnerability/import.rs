@@ -0,0 +1,339 @@
+//! Loads downloaded CVE feeds (NVD 2.0 JSON) into `cve_records`.
+//!
+//! Records are upserted in batches, each committed together with the feed's
+//! next offset in `cve_import_state`. An interrupted import picks up after
+//! the last committed batch, and a batch replayed after a crash only rewrites
+//! the same rows. The checkpoint is cleared once the feed is fully imported
+//! and ignored when the feed file has changed since it was written.
+
+use crate::error::{Error, Result};
+use serde::Deserialize;
+use sqlx::sqlite::SqlitePool;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+
+/// Records written per transaction, and so per checkpoint
+pub const DEFAULT_IMPORT_BATCH_SIZE: usize = 500;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CveRecord {
+    pub cve_id: String,
+    pub description: String,
+    pub cvss_score: Option<f64>, // Newest CVSS version the feed scores it with
+    pub published: Option<String>,
+}
+
+/// Sent once a feed's import starts and after every committed batch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportProgress {
+    pub feed: String,
+    pub processed: usize, // Records of the feed imported so far, resumed ones included
+    pub total: usize,
+}
+
+/// Outcome of one feed's import
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedImport {
+    pub feed: String,
+    pub total: usize,
+    pub resumed_from: usize, // Records an earlier, interrupted run had already imported
+}
+
+pub struct CveImporter {
+    pool: SqlitePool,
+    batch_size: usize,
+    progress: Option<mpsc::Sender<ImportProgress>>,
+}
+
+impl CveImporter {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self {
+            pool,
+            batch_size: DEFAULT_IMPORT_BATCH_SIZE,
+            progress: None,
+        }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Report progress on `progress`; a full channel holds the import back
+    /// rather than dropping updates
+    pub fn with_progress(mut self, progress: mpsc::Sender<ImportProgress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Import each `(feed name, downloaded file)` in turn, resuming any feed
+    /// whose last import was interrupted
+    pub async fn update_database(&self, feeds: &[(String, PathBuf)]) -> Result<Vec<FeedImport>> {
+        let mut imports = Vec::with_capacity(feeds.len());
+        for (feed, path) in feeds {
+            imports.push(self.import_feed(feed, path).await?);
+        }
+        Ok(imports)
+    }
+
+    pub async fn import_feed(&self, feed: &str, path: &Path) -> Result<FeedImport> {
+        let fingerprint = fingerprint(path).await?;
+        let records = parse_feed(&tokio::fs::read(path).await?)
+            .map_err(|e| Error::VulnerabilityDb(format!("Feed {} ({}): {}", feed, path.display(), e)))?;
+        let total = records.len();
+
+        let resumed_from = self.checkpoint(feed, &fingerprint, total).await?;
+        if resumed_from > 0 {
+            info!("Resuming import of feed {} at record {} of {}", feed, resumed_from, total);
+        }
+        self.report(feed, resumed_from, total).await;
+
+        let mut processed = resumed_from;
+        for batch in records[resumed_from..].chunks(self.batch_size) {
+            let mut transaction = self.pool.begin().await?;
+            for record in batch {
+                sqlx::query(
+                    r#"
+                    INSERT INTO cve_records (cve_id, feed, description, cvss_score, published)
+                    VALUES (?, ?, ?, ?, ?)
+                    ON CONFLICT(cve_id) DO UPDATE SET
+                        feed = excluded.feed,
+                        description = excluded.description,
+                        cvss_score = excluded.cvss_score,
+                        published = excluded.published,
+                        imported_at = CURRENT_TIMESTAMP
+                    "#
+                )
+                .bind(&record.cve_id)
+                .bind(feed)
+                .bind(&record.description)
+                .bind(record.cvss_score)
+                .bind(&record.published)
+                .execute(&mut *transaction)
+                .await?;
+            }
+
+            processed += batch.len();
+            sqlx::query(
+                r#"
+                INSERT INTO cve_import_state (feed, fingerprint, next_offset, total)
+                VALUES (?, ?, ?, ?)
+                ON CONFLICT(feed) DO UPDATE SET
+                    fingerprint = excluded.fingerprint,
+                    next_offset = excluded.next_offset,
+                    total = excluded.total,
+                    updated_at = CURRENT_TIMESTAMP
+                "#
+            )
+            .bind(feed)
+            .bind(&fingerprint)
+            .bind(processed as i64)
+            .bind(total as i64)
+            .execute(&mut *transaction)
+            .await?;
+            transaction.commit().await?;
+
+            self.report(feed, processed, total).await;
+        }
+
+        sqlx::query("DELETE FROM cve_import_state WHERE feed = ?")
+            .bind(feed)
+            .execute(&self.pool)
+            .await?;
+        info!("Imported {} records from CVE feed {}", total, feed);
+
+        Ok(FeedImport { feed: feed.to_string(), total, resumed_from })
+    }
+
+    pub async fn record_count(&self) -> Result<i64> {
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM cve_records")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(count)
+    }
+
+    /// Offset to resume `feed` at: the checkpoint, if it was written for
+    /// this very file
+    async fn checkpoint(&self, feed: &str, fingerprint: &str, total: usize) -> Result<usize> {
+        let saved: Option<(String, i64)> =
+            sqlx::query_as("SELECT fingerprint, next_offset FROM cve_import_state WHERE feed = ?")
+                .bind(feed)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(match saved {
+            Some((saved, offset)) if saved == fingerprint => (offset.max(0) as usize).min(total),
+            Some(_) => {
+                debug!("Feed {} changed since its import was interrupted; starting over", feed);
+                0
+            }
+            None => 0,
+        })
+    }
+
+    async fn report(&self, feed: &str, processed: usize, total: usize) {
+        if let Some(progress) = &self.progress {
+            // Nobody listening just means nobody wants a bar
+            let _ = progress.send(ImportProgress { feed: feed.to_string(), processed, total }).await;
+        }
+    }
+}
+
+/// Size and modification time: the downloader renames every new copy into
+/// place, so a fresh download never matches an old checkpoint
+async fn fingerprint(path: &Path) -> Result<String> {
+    let metadata = tokio::fs::metadata(path).await?;
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH).unwrap_or_default();
+    Ok(format!("{}:{}", metadata.len(), modified.as_nanos()))
+}
+
+pub fn parse_feed(bytes: &[u8]) -> serde_json::Result<Vec<CveRecord>> {
+    let feed: NvdFeed = serde_json::from_slice(bytes)?;
+    Ok(feed.vulnerabilities.into_iter().map(|item| item.cve.into_record()).collect())
+}
+
+#[derive(Deserialize)]
+struct NvdFeed {
+    #[serde(default)]
+    vulnerabilities: Vec<NvdItem>,
+}
+
+#[derive(Deserialize)]
+struct NvdItem {
+    cve: NvdCve,
+}
+
+#[derive(Deserialize)]
+struct NvdCve {
+    id: String,
+    #[serde(default)]
+    published: Option<String>,
+    #[serde(default)]
+    descriptions: Vec<NvdDescription>,
+    #[serde(default)]
+    metrics: NvdMetrics,
+}
+
+#[derive(Deserialize)]
+struct NvdDescription {
+    lang: String,
+    value: String,
+}
+
+#[derive(Deserialize, Default)]
+struct NvdMetrics {
+    #[serde(default, rename = "cvssMetricV31")]
+    v31: Vec<NvdMetric>,
+    #[serde(default, rename = "cvssMetricV30")]
+    v30: Vec<NvdMetric>,
+    #[serde(default, rename = "cvssMetricV2")]
+    v2: Vec<NvdMetric>,
+}
+
+#[derive(Deserialize)]
+struct NvdMetric {
+    #[serde(rename = "cvssData")]
+    cvss_data: NvdCvssData,
+}
+
+#[derive(Deserialize)]
+struct NvdCvssData {
+    #[serde(rename = "baseScore")]
+    base_score: f64,
+}
+
+impl NvdCve {
+    fn into_record(self) -> CveRecord {
+        let cvss_score = [&self.metrics.v31, &self.metrics.v30, &self.metrics.v2]
+            .into_iter()
+            .find_map(|metrics| metrics.first())
+            .map(|metric| metric.cvss_data.base_score);
+        let description = self.descriptions.iter()
+            .find(|description| description.lang == "en")
+            .or_else(|| self.descriptions.first())
+            .map(|description| description.value.clone())
+            .unwrap_or_default();
+
+        CveRecord { cve_id: self.id, description, cvss_score, published: self.published }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vulnerability::database::Database;
+
+    fn write_feed(dir: &Path, count: usize) -> PathBuf {
+        let items: Vec<serde_json::Value> = (1..=count).map(|n| serde_json::json!({
+            "cve": {
+                "id": format!("CVE-2024-{:05}", n),
+                "published": "2024-03-01T12:00:00.000",
+                "descriptions": [{ "lang": "en", "value": format!("Flaw number {}", n) }],
+                "metrics": { "cvssMetricV31": [{ "cvssData": { "baseScore": 7.5 } }] }
+            }
+        })).collect();
+        let path = dir.join("nvd.json");
+        std::fs::write(&path, serde_json::json!({ "vulnerabilities": items }).to_string()).unwrap();
+        path
+    }
+
+    async fn database(dir: &Path, name: &str) -> Database {
+        Database::new(&format!("sqlite://{}?mode=rwc", dir.join(name).display())).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_import_resumes_to_the_same_count() {
+        let dir = tempfile::tempdir().unwrap();
+        let feed = write_feed(dir.path(), 10);
+
+        let uninterrupted = CveImporter::new(database(dir.path(), "full.db").await.get_pool().clone())
+            .with_batch_size(2);
+        uninterrupted.import_feed("nvd", &feed).await.unwrap();
+        assert_eq!(uninterrupted.record_count().await.unwrap(), 10);
+
+        // Stop listening after two batches; with a one-slot channel the
+        // import stalls on a progress update, where it is aborted
+        let pool = database(dir.path(), "resumed.db").await.get_pool().clone();
+        let (progress_tx, mut progress_rx) = mpsc::channel(1);
+        let importer = CveImporter::new(pool.clone()).with_batch_size(2).with_progress(progress_tx);
+        let path = feed.clone();
+        let run = tokio::spawn(async move { importer.import_feed("nvd", &path).await });
+
+        let start = progress_rx.recv().await.unwrap();
+        assert_eq!((start.processed, start.total), (0, 10));
+        progress_rx.recv().await.unwrap();
+        assert_eq!(progress_rx.recv().await.unwrap().processed, 4);
+        run.abort();
+        assert!(run.await.unwrap_err().is_cancelled());
+
+        let resumed = CveImporter::new(pool).with_batch_size(2);
+        let interrupted_count = resumed.record_count().await.unwrap();
+        assert!((4..10).contains(&interrupted_count), "{} records before resuming", interrupted_count);
+
+        let import = resumed.import_feed("nvd", &feed).await.unwrap();
+        assert_eq!(import.resumed_from as i64, interrupted_count);
+        assert_eq!(resumed.record_count().await.unwrap(), 10);
+
+        // Finished imports leave no checkpoint: the next run starts afresh
+        assert_eq!(resumed.import_feed("nvd", &feed).await.unwrap().resumed_from, 0);
+        assert_eq!(resumed.record_count().await.unwrap(), 10);
+    }
+
+    #[test]
+    fn test_parse_feed_prefers_english_and_newest_cvss() {
+        let records = parse_feed(br#"{"vulnerabilities":[{"cve":{
+            "id":"CVE-2023-0001",
+            "descriptions":[{"lang":"es","value":"Fallo"},{"lang":"en","value":"Flaw"}],
+            "metrics":{"cvssMetricV2":[{"cvssData":{"baseScore":5.0}}],"cvssMetricV31":[{"cvssData":{"baseScore":9.8}}]}
+        }}]}"#).unwrap();
+
+        assert_eq!(records, vec![CveRecord {
+            cve_id: "CVE-2023-0001".to_string(),
+            description: "Flaw".to_string(),
+            cvss_score: Some(9.8),
+            published: None,
+        }]);
+    }
+}
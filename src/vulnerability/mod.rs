@@ -2,8 +2,16 @@ pub mod detector;
 pub mod database;
 pub mod models;
 pub mod analyzer;
+pub mod suppression;
+pub mod rules;
+pub mod feed;
+pub mod import;
 
 pub use detector::VulnerabilityDetector;
 pub use database::VulnerabilityDatabase;
-pub use models::{Vulnerability, VulnerabilityLevel, VulnerabilityReport};
+pub use models::{Vulnerability, VulnerabilityLevel, VulnerabilityReport, VulnDiff};
 pub use analyzer::VulnerabilityAnalyzer;
+pub use suppression::{Suppression, SuppressionList};
+pub use rules::RuleSet;
+pub use feed::{CveFeedDownloader, FeedStatus, FeedUpdateReport};
+pub use import::{CveImporter, FeedImport, ImportProgress};
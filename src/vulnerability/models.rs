@@ -14,6 +14,23 @@ pub struct VulnerabilityReport {
     pub summary: VulnerabilitySummary,
     pub risk_assessment: RiskAssessment,
     pub recommendations: Vec<Recommendation>,
+    #[serde(default)]
+    pub include_suppressed: bool, // Count suppressed findings in the summary
+}
+
+/// How the findings for a target changed between two assessments.
+/// Findings are matched on `(rule_key, port)`, so a CVE reported on a new
+/// port counts as new.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VulnDiff {
+    pub target: String,
+    pub previous_scan_id: String,
+    pub current_scan_id: String,
+    pub new: Vec<Vulnerability>, // Regressions: absent before, present now
+    pub resolved: Vec<Vulnerability>, // Present before, absent now (taken from the previous report)
+    pub persistent: Vec<Vulnerability>, // In both; taken from the current report
+    pub previous_risk_score: f32,
+    pub current_risk_score: f32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,10 +54,12 @@ pub struct Vulnerability {
     pub impact: String,
     pub certainty: u8, // 0-100%
     pub tags: Vec<String>,
+    #[serde(default)]
+    pub suppressed: bool, // Risk-acknowledged via a suppression entry
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum VulnerabilityLevel {
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VulnerabilityLevel { // Declared from least to most severe
     Info,
     Low,
     Medium,
@@ -48,6 +67,18 @@ pub enum VulnerabilityLevel {
     Critical,
 }
 
+impl std::fmt::Display for VulnerabilityLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VulnerabilityLevel::Info => write!(f, "Info"),
+            VulnerabilityLevel::Low => write!(f, "Low"),
+            VulnerabilityLevel::Medium => write!(f, "Medium"),
+            VulnerabilityLevel::High => write!(f, "High"),
+            VulnerabilityLevel::Critical => write!(f, "Critical"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ExploitMaturity {
     Unproven,
@@ -192,18 +223,57 @@ impl VulnerabilityReport {
             summary: VulnerabilitySummary::default(),
             risk_assessment: RiskAssessment::default(),
             recommendations: Vec::new(),
+            include_suppressed: false,
         }
     }
 
     pub fn add_vulnerability(&mut self, vulnerability: Vulnerability) {
         self.vulnerabilities.push(vulnerability);
+        self.refresh();
+    }
+
+    /// Recompute summary, risk assessment and recommendations after the
+    /// vulnerability list or suppression flags changed
+    pub fn refresh(&mut self) {
         self.update_summary();
         self.update_risk_assessment();
         self.generate_recommendations();
     }
 
+    /// Merge findings that two detection paths (say a CVE match and a custom
+    /// rule) reported for the same issue, so it counts once: same port and
+    /// service, and the same CVE or, failing that, the same title up to
+    /// case and punctuation. The merged finding takes the most severe
+    /// level, the highest certainty and every reference and tag of the
+    /// duplicates; the summary is recomputed from what is left.
+    pub fn deduplicate(&mut self) {
+        let mut merged: Vec<Vulnerability> = Vec::with_capacity(self.vulnerabilities.len());
+        for vulnerability in self.vulnerabilities.drain(..) {
+            match merged.iter_mut().find(|kept| kept.is_duplicate_of(&vulnerability)) {
+                Some(kept) => kept.absorb(vulnerability),
+                None => merged.push(vulnerability),
+            }
+        }
+
+        self.vulnerabilities = merged;
+        self.refresh();
+    }
+
+    /// Drop findings below `min_level` from the listing. Summary, risk
+    /// assessment and recommendations are not recomputed, so they still
+    /// report the true totals.
+    pub fn retain_min_level(&mut self, min_level: &VulnerabilityLevel) {
+        self.vulnerabilities.retain(|v| v.level >= *min_level);
+    }
+
+    /// Findings that count towards the summary
+    pub fn counted_vulnerabilities(&self) -> impl Iterator<Item = &Vulnerability> {
+        let include_suppressed = self.include_suppressed;
+        self.vulnerabilities.iter().filter(move |v| include_suppressed || !v.suppressed)
+    }
+
     pub fn calculate_risk_score(&self) -> f32 {
-        let weighted_sum: f32 = self.vulnerabilities.iter()
+        let weighted_sum: f32 = self.counted_vulnerabilities()
             .map(|v| {
                 let weight = match v.level {
                     VulnerabilityLevel::Critical => 10.0,
@@ -216,30 +286,65 @@ impl VulnerabilityReport {
             })
             .sum();
 
-        weighted_sum / self.vulnerabilities.len().max(1) as f32
+        weighted_sum / self.counted_vulnerabilities().count().max(1) as f32
+    }
+
+    /// Compare against an earlier assessment of the same target. Only
+    /// findings counted in the summaries are compared, so suppressed
+    /// findings drop out unless `include_suppressed` is set.
+    pub fn diff(&self, previous: &VulnerabilityReport) -> crate::error::Result<VulnDiff> {
+        if self.target_ip != previous.target_ip {
+            return Err(crate::error::Error::Validation(format!(
+                "Cannot compare assessments of different hosts: {} and {}",
+                previous.target_ip, self.target_ip
+            )));
+        }
+
+        let key = |v: &Vulnerability| (v.rule_key().to_string(), v.port);
+        let before: HashMap<_, _> = previous.counted_vulnerabilities().map(|v| (key(v), v)).collect();
+        let now: HashMap<_, _> = self.counted_vulnerabilities().map(|v| (key(v), v)).collect();
+
+        let (persistent, new): (Vec<_>, Vec<_>) = self.counted_vulnerabilities()
+            .cloned()
+            .partition(|v| before.contains_key(&key(v)));
+        let resolved = previous.counted_vulnerabilities()
+            .filter(|v| !now.contains_key(&key(v)))
+            .cloned()
+            .collect();
+
+        Ok(VulnDiff {
+            target: self.target.clone(),
+            previous_scan_id: previous.scan_id.clone(),
+            current_scan_id: self.scan_id.clone(),
+            new,
+            resolved,
+            persistent,
+            previous_risk_score: previous.summary.risk_score,
+            current_risk_score: self.summary.risk_score,
+        })
     }
 
     fn update_summary(&mut self) {
-        let critical_count = self.vulnerabilities.iter()
+        let critical_count = self.counted_vulnerabilities()
             .filter(|v| v.level == VulnerabilityLevel::Critical)
             .count();
-        let high_count = self.vulnerabilities.iter()
+        let high_count = self.counted_vulnerabilities()
             .filter(|v| v.level == VulnerabilityLevel::High)
             .count();
-        let medium_count = self.vulnerabilities.iter()
+        let medium_count = self.counted_vulnerabilities()
             .filter(|v| v.level == VulnerabilityLevel::Medium)
             .count();
-        let low_count = self.vulnerabilities.iter()
+        let low_count = self.counted_vulnerabilities()
             .filter(|v| v.level == VulnerabilityLevel::Low)
             .count();
-        let info_count = self.vulnerabilities.iter()
+        let info_count = self.counted_vulnerabilities()
             .filter(|v| v.level == VulnerabilityLevel::Info)
             .count();
 
-        let total_cvss: f32 = self.vulnerabilities.iter()
+        let total_cvss: f32 = self.counted_vulnerabilities()
             .filter_map(|v| v.cvss_score)
             .sum();
-        let cvss_count = self.vulnerabilities.iter()
+        let cvss_count = self.counted_vulnerabilities()
             .filter(|v| v.cvss_score.is_some())
             .count();
 
@@ -250,7 +355,7 @@ impl VulnerabilityReport {
         };
 
         self.summary = VulnerabilitySummary {
-            total_vulnerabilities: self.vulnerabilities.len(),
+            total_vulnerabilities: self.counted_vulnerabilities().count(),
             critical_count,
             high_count,
             medium_count,
@@ -329,10 +434,10 @@ impl VulnerabilityReport {
         self.recommendations.clear();
 
         // Generate recommendations based on vulnerabilities
-        for vuln in &self.vulnerabilities {
-            let recommendation = self.create_recommendation_for_vulnerability(vuln);
-            self.recommendations.push(recommendation);
-        }
+        let recommendations: Vec<Recommendation> = self.counted_vulnerabilities()
+            .map(|vuln| self.create_recommendation_for_vulnerability(vuln))
+            .collect();
+        self.recommendations.extend(recommendations);
 
         // Sort by priority
         self.recommendations.sort_by(|a, b| {
@@ -391,6 +496,18 @@ impl VulnerabilityReport {
     }
 }
 
+impl VulnDiff {
+    /// Positive when risk went up
+    pub fn risk_score_change(&self) -> f32 {
+        self.current_risk_score - self.previous_risk_score
+    }
+
+    /// New findings, or a higher risk score, since the previous assessment
+    pub fn has_regressions(&self) -> bool {
+        !self.new.is_empty() || self.risk_score_change() > 0.0
+    }
+}
+
 impl VulnerabilitySummary {
     pub fn overall_risk(&self) -> VulnerabilityLevel {
         if self.critical_count > 0 {
@@ -463,6 +580,215 @@ impl Vulnerability {
             impact: "Varies".to_string(),
             certainty: 80, // Default confidence
             tags: Vec::new(),
+            suppressed: false,
+        }
+    }
+
+    /// Identifier used to match suppressions: the CVE ID, or the title for
+    /// findings that come from built-in checks
+    pub fn rule_key(&self) -> &str {
+        self.cve_id.as_deref().unwrap_or(&self.title)
+    }
+
+    /// Same port and service, and the same CVE or (when either has none) the
+    /// same normalized title; see `VulnerabilityReport::deduplicate`
+    pub fn is_duplicate_of(&self, other: &Vulnerability) -> bool {
+        if self.port != other.port || !self.service.eq_ignore_ascii_case(&other.service) {
+            return false;
+        }
+        match (&self.cve_id, &other.cve_id) {
+            (Some(cve), Some(other_cve)) => cve.eq_ignore_ascii_case(other_cve),
+            _ => normalized_title(&self.title) == normalized_title(&other.title),
+        }
+    }
+
+    /// Fold a duplicate into this finding. The more severe (then more
+    /// certain) of the two provides the text; references and tags are
+    /// unioned, and the finding stays counted unless both were suppressed.
+    pub fn absorb(&mut self, mut other: Vulnerability) {
+        if (&other.level, other.certainty) > (&self.level, self.certainty) {
+            std::mem::swap(self, &mut other);
+        }
+
+        self.certainty = self.certainty.max(other.certainty);
+        self.cve_id = self.cve_id.take().or(other.cve_id);
+        if other.cvss_score > self.cvss_score {
+            self.cvss_score = other.cvss_score;
+            self.cvss_vector = other.cvss_vector;
+        }
+        self.exploit_available |= other.exploit_available;
+        self.exploit_maturity = self.exploit_maturity.take().or(other.exploit_maturity);
+        self.discovered_at = self.discovered_at.min(other.discovered_at);
+        self.suppressed &= other.suppressed;
+        for reference in other.references {
+            if !self.references.contains(&reference) {
+                self.references.push(reference);
+            }
+        }
+        for tag in other.tags {
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
         }
     }
           }
+
+/// Lowercase words of `title`, punctuation and extra spacing dropped
+fn normalized_title(title: &str) -> String {
+    title.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(cve: &str, port: u16, level: VulnerabilityLevel) -> Vulnerability {
+        let mut vulnerability = Vulnerability::new(
+            cve.to_string(),
+            "test finding".to_string(),
+            level,
+            port,
+            "HTTP".to_string(),
+            "evidence".to_string(),
+        );
+        vulnerability.cve_id = Some(cve.to_string());
+        vulnerability
+    }
+
+    fn report(findings: Vec<Vulnerability>) -> VulnerabilityReport {
+        let mut report = VulnerabilityReport::new(
+            uuid::Uuid::new_v4().to_string(),
+            "10.0.0.1".to_string(),
+            "10.0.0.1".parse().unwrap(),
+        );
+        for vulnerability in findings {
+            report.add_vulnerability(vulnerability);
+        }
+        report
+    }
+
+    fn cves(findings: &[Vulnerability]) -> Vec<(&str, u16)> {
+        let mut keys: Vec<_> = findings.iter().map(|v| (v.rule_key(), v.port)).collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_diff_new_resolved_and_persistent() {
+        let previous = report(vec![
+            finding("CVE-2021-0001", 80, VulnerabilityLevel::Critical),
+            finding("CVE-2021-0002", 443, VulnerabilityLevel::Medium),
+        ]);
+        let current = report(vec![
+            finding("CVE-2021-0002", 443, VulnerabilityLevel::Medium),
+            finding("CVE-2023-0003", 8080, VulnerabilityLevel::Low),
+        ]);
+
+        let diff = current.diff(&previous).unwrap();
+
+        assert_eq!(cves(&diff.new), vec![("CVE-2023-0003", 8080)]);
+        assert_eq!(cves(&diff.resolved), vec![("CVE-2021-0001", 80)]);
+        assert_eq!(cves(&diff.persistent), vec![("CVE-2021-0002", 443)]);
+        assert!(diff.risk_score_change() < 0.0);
+        assert!(diff.has_regressions());
+    }
+
+    #[test]
+    fn test_diff_matches_on_port_too() {
+        let previous = report(vec![finding("CVE-2021-0001", 80, VulnerabilityLevel::High)]);
+        let current = report(vec![finding("CVE-2021-0001", 8080, VulnerabilityLevel::High)]);
+
+        let diff = current.diff(&previous).unwrap();
+
+        assert_eq!(cves(&diff.new), vec![("CVE-2021-0001", 8080)]);
+        assert_eq!(cves(&diff.resolved), vec![("CVE-2021-0001", 80)]);
+        assert!(diff.persistent.is_empty());
+    }
+
+    #[test]
+    fn test_diff_unchanged() {
+        let findings = vec![finding("CVE-2021-0001", 22, VulnerabilityLevel::High)];
+        let diff = report(findings.clone()).diff(&report(findings)).unwrap();
+
+        assert!(diff.new.is_empty());
+        assert!(diff.resolved.is_empty());
+        assert_eq!(diff.persistent.len(), 1);
+        assert_eq!(diff.risk_score_change(), 0.0);
+        assert!(!diff.has_regressions());
+    }
+
+    #[test]
+    fn test_diff_rejects_different_hosts() {
+        let mut other = report(Vec::new());
+        other.target_ip = "10.0.0.2".parse().unwrap();
+        assert!(report(Vec::new()).diff(&other).is_err());
+    }
+
+    #[test]
+    fn test_overlapping_findings_collapse_into_one() {
+        // The CVE database and a custom rule both flag CVE-2021-41773 on 80
+        let mut database_match = finding("CVE-2021-41773", 80, VulnerabilityLevel::High);
+        database_match.certainty = 90;
+        database_match.references = vec!["https://nvd.nist.gov/vuln/detail/CVE-2021-41773".to_string()];
+        database_match.tags = vec!["apache".to_string()];
+        let mut custom_rule = finding("cve-2021-41773", 80, VulnerabilityLevel::Critical);
+        custom_rule.title = "Apache path traversal".to_string();
+        custom_rule.certainty = 70;
+        custom_rule.references = vec!["https://httpd.apache.org/security/vulnerabilities_24.html".to_string()];
+        custom_rule.tags = vec!["apache".to_string(), "rce".to_string()];
+
+        // Built-in checks without a CVE match on the normalized title
+        let mut telnet = finding("unused", 23, VulnerabilityLevel::Medium);
+        telnet.cve_id = None;
+        telnet.title = "Telnet service detected".to_string();
+        let mut telnet_rule = telnet.clone();
+        telnet_rule.title = "  TELNET service-detected ".to_string();
+
+        let mut assessed = report(vec![
+            database_match,
+            custom_rule,
+            telnet,
+            telnet_rule,
+            finding("CVE-2021-41773", 8080, VulnerabilityLevel::High), // Another port: a separate issue
+        ]);
+        assert_eq!(assessed.summary.total_vulnerabilities, 5);
+
+        assessed.deduplicate();
+
+        assert_eq!(assessed.vulnerabilities.len(), 3);
+        let merged = assessed.vulnerabilities.iter().find(|v| v.port == 80).unwrap();
+        assert_eq!(merged.level, VulnerabilityLevel::Critical);
+        assert_eq!(merged.certainty, 90);
+        assert_eq!(merged.title, "Apache path traversal");
+        assert_eq!(merged.references.len(), 2);
+        assert_eq!(merged.tags, vec!["apache".to_string(), "rce".to_string()]);
+
+        assert_eq!(assessed.summary.total_vulnerabilities, 3);
+        assert_eq!(assessed.summary.critical_count, 1);
+        assert_eq!(assessed.summary.high_count, 1);
+        assert_eq!(assessed.summary.medium_count, 1);
+    }
+
+    #[test]
+    fn test_min_level_filters_listing_but_not_totals() {
+        let mut filtered = report(vec![
+            finding("CVE-2021-0001", 80, VulnerabilityLevel::Critical),
+            finding("CVE-2021-0002", 443, VulnerabilityLevel::High),
+            finding("CVE-2021-0003", 22, VulnerabilityLevel::Medium),
+            finding("CVE-2021-0004", 25, VulnerabilityLevel::Info),
+        ]);
+        let risk_score = filtered.summary.risk_score;
+
+        filtered.retain_min_level(&VulnerabilityLevel::High);
+
+        assert_eq!(cves(&filtered.vulnerabilities), vec![("CVE-2021-0001", 80), ("CVE-2021-0002", 443)]);
+        assert_eq!(filtered.summary.total_vulnerabilities, 4);
+        assert_eq!(filtered.summary.medium_count, 1);
+        assert_eq!(filtered.summary.info_count, 1);
+        assert_eq!(filtered.summary.risk_score, risk_score);
+    }
+}
@@ -0,0 +1,260 @@
+use super::models::{Vulnerability, VulnerabilityLevel};
+use crate::error::{Error, Result};
+use crate::scanner::PortInfo;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::info;
+
+const DEFAULT_RULES: &str = include_str!("../../config/vulnerability_rules.toml");
+
+/// A user-defined check. Every criterion that is set must match the port.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleDefinition {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub level: VulnerabilityLevel,
+    pub mitigation: String,
+    pub port: Option<u16>,
+    pub service: Option<String>,
+    pub protocol: Option<String>,
+    pub banner_regex: Option<String>,
+    /// Matched against the detected service's details, e.g. the HTTP
+    /// probe's "Missing headers: ..." list
+    pub extra_info_regex: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RuleFile {
+    #[serde(default)]
+    rules: Vec<RuleDefinition>,
+}
+
+struct CompiledRule {
+    definition: RuleDefinition,
+    banner_regex: Option<Regex>,
+    extra_info_regex: Option<Regex>,
+}
+
+pub struct RuleSet {
+    rules: Vec<CompiledRule>,
+}
+
+impl RuleSet {
+    pub fn empty() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Rules shipped with Port-ZiLLA (Telnet, anonymous FTP, open Redis/MongoDB,
+    /// missing HTTP security headers)
+    pub fn default_rules() -> Result<Self> {
+        Self::parse(DEFAULT_RULES, "built-in rules")
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let rule_set = Self::parse(&content, &path.display().to_string())?;
+        info!("Loaded {} vulnerability rules from {}", rule_set.len(), path.display());
+        Ok(rule_set)
+    }
+
+    pub fn parse(content: &str, source: &str) -> Result<Self> {
+        let file: RuleFile = toml::from_str(content)
+            .map_err(|e| Error::Validation(format!("Invalid rule file {}: {}", source, e)))?;
+
+        let rules = file.rules
+            .into_iter()
+            .map(|definition| {
+                let compile = |pattern: &Option<String>, field: &str| {
+                    pattern.as_deref()
+                        .map(Regex::new)
+                        .transpose()
+                        .map_err(|e| Error::Validation(format!("Rule {}: invalid {}: {}", definition.id, field, e)))
+                };
+                let banner_regex = compile(&definition.banner_regex, "banner_regex")?;
+                let extra_info_regex = compile(&definition.extra_info_regex, "extra_info_regex")?;
+                Ok(CompiledRule { definition, banner_regex, extra_info_regex })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { rules })
+    }
+
+    /// Add rules from another set; a rule with the same id replaces the existing one
+    pub fn extend(&mut self, other: RuleSet) {
+        for rule in other.rules {
+            self.rules.retain(|r| r.definition.id != rule.definition.id);
+            self.rules.push(rule);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn evaluate(&self, port_info: &PortInfo) -> Vec<Vulnerability> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(port_info))
+            .map(|rule| rule.to_vulnerability(port_info))
+            .collect()
+    }
+}
+
+impl CompiledRule {
+    fn matches(&self, port_info: &PortInfo) -> bool {
+        let definition = &self.definition;
+
+        if let Some(port) = definition.port {
+            if port != port_info.port {
+                return false;
+            }
+        }
+
+        if let Some(service) = &definition.service {
+            let matched = port_info.service.as_ref()
+                .map(|s| s.name.eq_ignore_ascii_case(service))
+                .unwrap_or(false);
+            if !matched {
+                return false;
+            }
+        }
+
+        if let Some(protocol) = &definition.protocol {
+            if !format!("{:?}", port_info.protocol).eq_ignore_ascii_case(protocol) {
+                return false;
+            }
+        }
+
+        if let Some(regex) = &self.banner_regex {
+            let matched = port_info.banner.as_deref()
+                .map(|banner| regex.is_match(banner))
+                .unwrap_or(false);
+            if !matched {
+                return false;
+            }
+        }
+
+        if let Some(regex) = &self.extra_info_regex {
+            let matched = port_info.service.as_ref()
+                .and_then(|s| s.extra_info.as_deref())
+                .map(|extra_info| regex.is_match(extra_info))
+                .unwrap_or(false);
+            if !matched {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn to_vulnerability(&self, port_info: &PortInfo) -> Vulnerability {
+        let definition = &self.definition;
+        let service = port_info.service.as_ref()
+            .map(|s| s.name.clone())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let extra_info = port_info.service.as_ref().and_then(|s| s.extra_info.as_ref());
+        let evidence = match (&port_info.banner, extra_info) {
+            (Some(banner), _) if self.banner_regex.is_some() => format!("Banner: {}", banner),
+            (_, Some(extra_info)) if self.extra_info_regex.is_some() => format!("Service: {}", extra_info),
+            _ => format!("Rule {} matched port {}", definition.id, port_info.port),
+        };
+
+        let mut vulnerability = Vulnerability::new(
+            definition.title.clone(),
+            definition.description.clone(),
+            definition.level.clone(),
+            port_info.port,
+            service,
+            evidence,
+        );
+        vulnerability.mitigation = definition.mitigation.clone();
+        vulnerability.protocol = format!("{:?}", port_info.protocol).to_uppercase();
+        vulnerability.tags = vec!["rule".to_string(), definition.id.clone()];
+        vulnerability
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{PortStatus, Protocol, ServiceInfo};
+
+    fn open_port(port: u16, banner: Option<&str>) -> PortInfo {
+        PortInfo {
+            port,
+            status: PortStatus::Open,
+            service: None,
+            banner: banner.map(|b| b.to_string()),
+            banner_raw: None,
+            response_time: None,
+            protocol: Protocol::Tcp,
+            tls: None,
+        }
+    }
+
+    #[test]
+    fn test_default_rules_flag_telnet() {
+        let rules = RuleSet::default_rules().unwrap();
+        let findings = rules.evaluate(&open_port(23, None));
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].title, "Telnet Service Exposed");
+        assert!(rules.evaluate(&open_port(443, None)).is_empty());
+    }
+
+    #[test]
+    fn test_banner_regex_rule() {
+        let rules = RuleSet::parse(
+            r#"
+            [[rules]]
+            id = "apache-2.2"
+            title = "Outdated Apache"
+            description = "Apache 2.2 is end of life"
+            level = "High"
+            mitigation = "Upgrade Apache"
+            banner_regex = "Server: Apache/2\\.2"
+            "#,
+            "test",
+        ).unwrap();
+
+        assert_eq!(rules.evaluate(&open_port(80, Some("HTTP/1.1 200 OK | Server: Apache/2.2.15"))).len(), 1);
+        assert!(rules.evaluate(&open_port(80, Some("Server: Apache/2.4.57"))).is_empty());
+        assert!(rules.evaluate(&open_port(80, None)).is_empty());
+    }
+
+    #[test]
+    fn test_default_rules_flag_missing_http_headers() {
+        let rules = RuleSet::default_rules().unwrap();
+        let web_port = |name: &str, extra_info: &str| {
+            let mut port_info = open_port(443, None);
+            port_info.service = Some(ServiceInfo {
+                name: name.to_string(),
+                version: None,
+                product: None,
+                extra_info: Some(extra_info.to_string()),
+                confidence: 90,
+                tunnel: None,
+                alpn: None,
+            });
+            port_info
+        };
+
+        let findings = rules.evaluate(&web_port(
+            "https",
+            "TLS: TLSv1.3; HTTP 200; Missing headers: Strict-Transport-Security, Content-Security-Policy",
+        ));
+        let mut titles: Vec<_> = findings.iter().map(|v| v.title.as_str()).collect();
+        titles.sort();
+        assert_eq!(titles, vec!["Missing Content-Security-Policy Header", "Missing HSTS Header"]);
+        assert!(findings[0].evidence.starts_with("Service: TLS: TLSv1.3; HTTP 200"));
+
+        assert!(rules.evaluate(&web_port("https", "TLS: TLSv1.3; HTTP 200; Server: nginx")).is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+use super::models::{Vulnerability, VulnerabilityReport};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A risk-acknowledged finding that should not be re-reported.
+///
+/// `port: None` matches the finding on any port. `rule_id` is compared with the
+/// vulnerability's CVE ID, falling back to its title for non-CVE findings.
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct Suppression {
+    pub id: String,
+    pub target: String,
+    pub port: Option<u16>,
+    pub rule_id: String,
+    pub reason: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Suppression {
+    pub fn new(target: String, port: Option<u16>, rule_id: String, reason: String) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            target,
+            port,
+            rule_id,
+            reason,
+            expires_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn with_expiry(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.map(|expires| expires <= now).unwrap_or(false)
+    }
+
+    pub fn matches(&self, target: &str, vulnerability: &Vulnerability, now: DateTime<Utc>) -> bool {
+        if self.is_expired(now) || self.target != target {
+            return false;
+        }
+
+        if let Some(port) = self.port {
+            if port != vulnerability.port {
+                return false;
+            }
+        }
+
+        self.rule_id == vulnerability.rule_key()
+    }
+}
+
+pub struct SuppressionList {
+    suppressions: Vec<Suppression>,
+}
+
+impl SuppressionList {
+    pub fn new(suppressions: Vec<Suppression>) -> Self {
+        Self { suppressions }
+    }
+
+    /// Flag matching findings as suppressed and recompute the summary.
+    /// Suppressed findings are excluded from the counts unless `include_suppressed`.
+    pub fn apply(&self, report: &mut VulnerabilityReport, include_suppressed: bool) {
+        let now = Utc::now();
+
+        for vulnerability in &mut report.vulnerabilities {
+            vulnerability.suppressed = self.suppressions
+                .iter()
+                .any(|s| s.matches(&report.target, vulnerability, now));
+        }
+
+        report.include_suppressed = include_suppressed;
+        report.refresh();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vulnerability::VulnerabilityLevel;
+
+    fn telnet_finding() -> Vulnerability {
+        Vulnerability::new(
+            "Telnet Service Exposed".to_string(),
+            "Telnet in use".to_string(),
+            VulnerabilityLevel::High,
+            23,
+            "Telnet".to_string(),
+            "Telnet service detected".to_string(),
+        )
+    }
+
+    fn report_with(vulnerability: Vulnerability) -> VulnerabilityReport {
+        let mut report = VulnerabilityReport::new(
+            "scan-1".to_string(),
+            "10.0.0.1".to_string(),
+            "10.0.0.1".parse().unwrap(),
+        );
+        report.add_vulnerability(vulnerability);
+        report
+    }
+
+    #[test]
+    fn test_matching_suppression_excluded_from_summary() {
+        let mut report = report_with(telnet_finding());
+        let list = SuppressionList::new(vec![Suppression::new(
+            "10.0.0.1".to_string(),
+            Some(23),
+            "Telnet Service Exposed".to_string(),
+            "Legacy device, accepted".to_string(),
+        )]);
+
+        list.apply(&mut report, false);
+        assert!(report.vulnerabilities[0].suppressed);
+        assert_eq!(report.summary.high_count, 0);
+
+        list.apply(&mut report, true);
+        assert_eq!(report.summary.high_count, 1);
+    }
+
+    #[test]
+    fn test_expired_suppression_ignored() {
+        let mut report = report_with(telnet_finding());
+        let list = SuppressionList::new(vec![Suppression::new(
+            "10.0.0.1".to_string(),
+            Some(23),
+            "Telnet Service Exposed".to_string(),
+            "Temporary exception".to_string(),
+        ).with_expiry(Utc::now() - chrono::Duration::days(1))]);
+
+        list.apply(&mut report, false);
+        assert!(!report.vulnerabilities[0].suppressed);
+        assert_eq!(report.summary.high_count, 1);
+    }
+
+    #[test]
+    fn test_wildcard_port_suppression() {
+        let mut finding = telnet_finding();
+        finding.port = 2323;
+        let mut report = report_with(finding);
+
+        let wildcard = Suppression::new(
+            "10.0.0.1".to_string(),
+            None,
+            "Telnet Service Exposed".to_string(),
+            "Accepted on every port".to_string(),
+        );
+        let other_target = Suppression {
+            target: "10.0.0.2".to_string(),
+            ..wildcard.clone()
+        };
+
+        SuppressionList::new(vec![other_target]).apply(&mut report, false);
+        assert!(!report.vulnerabilities[0].suppressed);
+
+        SuppressionList::new(vec![wildcard]).apply(&mut report, false);
+        assert!(report.vulnerabilities[0].suppressed);
+    }
+}
@@ -1,16 +1,25 @@
 use crate::error::{Error, Result};
-use crate::scanner::{ScanEngine, ScanResult, ScanType};
-use crate::vulnerability::VulnerabilityDetector;
+use crate::scanner::{ScanConfig, ScanEngine, ScanType};
+use crate::vulnerability::{Suppression, VulnerabilityDetector};
 use crate::storage::ScanRepository;
+use crate::storage::repository::ScanStatusRecorder;
+use crate::storage::models::{PaginatedResults, ScanQuery};
 use crate::export::ExportManager;
-use crate::config::ConfigManager;
+use crate::config::{Settings, SharedSettings};
+use super::auth::{ApiAuthenticator, Permission};
+use super::metrics::ScanMetrics;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tracing::{info, debug, error};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Page size of `handle_get_scans` when no `limit` is given
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+pub const MAX_PAGE_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ScanRequest {
     pub target: String,
     pub scan_type: ScanTypeDto,
@@ -18,7 +27,7 @@ pub struct ScanRequest {
     pub max_threads: Option<usize>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ScanResponse {
     pub scan_id: String,
     pub status: String,
@@ -28,7 +37,7 @@ pub struct ScanResponse {
     pub estimated_duration: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ScanResultResponse {
     pub scan_id: String,
     pub status: String,
@@ -39,7 +48,7 @@ pub struct ScanResultResponse {
     pub results: Vec<PortResultDto>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PortResultDto {
     pub port: u16,
     pub status: String,
@@ -48,7 +57,7 @@ pub struct PortResultDto {
     pub response_time_ms: Option<u64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ServiceDto {
     pub name: String,
     pub version: Option<String>,
@@ -56,21 +65,53 @@ pub struct ServiceDto {
     pub confidence: u8,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ExportRequest {
     pub scan_id: String,
     pub format: String,
     pub output_path: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SuppressionRequest {
+    pub target: String,
+    pub port: Option<u16>,
+    pub rule_id: String,
+    pub reason: String,
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
     pub error: String,
     pub code: String,
     pub message: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl ErrorResponse {
+    /// Single Error -> (HTTP status, body) mapping used by every route
+    pub fn from_error(err: &Error) -> (u16, Self) {
+        let status = err.http_status();
+        let error = reqwest::StatusCode::from_u16(status)
+            .ok()
+            .and_then(|code| code.canonical_reason())
+            .unwrap_or("Internal Server Error");
+
+        (status, Self {
+            error: error.to_string(),
+            code: err.error_code().to_string(),
+            message: err.to_string(),
+        })
+    }
+}
+
+impl From<&Error> for ErrorResponse {
+    fn from(err: &Error) -> Self {
+        Self::from_error(err).1
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum ScanTypeDto {
     Quick,
@@ -80,30 +121,66 @@ pub enum ScanTypeDto {
 }
 
 pub struct ApiServer {
-    scan_engine: Arc<ScanEngine>,
+    scan_engine: std::sync::RwLock<Arc<ScanEngine>>, // Built from the settings; rebuilt by reload_settings
+    status_recorder: Arc<ScanStatusRecorder>, // Observes every scan of the engine
     vulnerability_detector: Arc<VulnerabilityDetector>,
     scan_repository: Arc<ScanRepository>,
     export_manager: Arc<ExportManager>,
-    config: Arc<ConfigManager>,
+    config: SharedSettings, // Hot-reloaded by ConfigWatcher
+    authenticator: Arc<ApiAuthenticator>,
     active_scans: Arc<Mutex<Vec<String>>>, // Track active scan IDs
+    metrics: Arc<ScanMetrics>,
 }
 
 impl ApiServer {
     pub fn new(
-        scan_engine: Arc<ScanEngine>,
         vulnerability_detector: Arc<VulnerabilityDetector>,
         scan_repository: Arc<ScanRepository>,
         export_manager: Arc<ExportManager>,
-        config: Arc<ConfigManager>,
-    ) -> Self {
-        Self {
-            scan_engine,
+        config: SharedSettings,
+        authenticator: Arc<ApiAuthenticator>,
+    ) -> Result<Self> {
+        let status_recorder = Arc::new(ScanStatusRecorder::new(scan_repository.as_ref().clone()));
+        let scan_engine = {
+            let settings = config.read()
+                .map_err(|_| Error::Unknown("Settings lock poisoned".to_string()))?;
+            Self::build_engine(&settings, &status_recorder)?
+        };
+
+        Ok(Self {
+            scan_engine: std::sync::RwLock::new(Arc::new(scan_engine)),
+            status_recorder,
             vulnerability_detector,
             scan_repository,
             export_manager,
             config,
+            authenticator,
             active_scans: Arc::new(Mutex::new(Vec::new())),
-        }
+            metrics: Arc::new(ScanMetrics::new()),
+        })
+    }
+
+    fn build_engine(settings: &Settings, status_recorder: &Arc<ScanStatusRecorder>) -> Result<ScanEngine> {
+        Ok(ScanEngine::new(ScanConfig::from_settings(settings))?
+            .with_observer(status_recorder.clone()))
+    }
+
+    /// Apply reloaded settings to the scan engine: `validate_target` reads
+    /// the shared settings on every request, but the engine keeps the config
+    /// it was built with. Scans already running finish on the old engine.
+    /// Register with `ConfigWatcher::on_reload`.
+    pub fn reload_settings(&self, settings: &Settings) -> Result<()> {
+        let engine = Self::build_engine(settings, &self.status_recorder)?;
+        *self.scan_engine.write()
+            .map_err(|_| Error::Unknown("Scan engine lock poisoned".to_string()))? = Arc::new(engine);
+        info!("Scan engine rebuilt from reloaded settings");
+        Ok(())
+    }
+
+    fn scan_engine(&self) -> Result<Arc<ScanEngine>> {
+        self.scan_engine.read()
+            .map(|engine| Arc::clone(&engine))
+            .map_err(|_| Error::Unknown("Scan engine lock poisoned".to_string()))
     }
 
     pub async fn start_server(&self, bind_addr: SocketAddr) -> Result<()> {
@@ -116,7 +193,8 @@ impl ApiServer {
 
     async fn start_http_server(&self, _bind_addr: SocketAddr) -> Result<()> {
         // Implementation would use Actix Web, Warp, or similar
-        // This is where we'd define routes and start the server
+        // This is where we'd define routes and start the server; the route
+        // table is documented in web::openapi (served at /openapi.json and /docs)
         info!("HTTP server would start here on {}", _bind_addr);
         
         // Placeholder - actual implementation would be framework-specific
@@ -126,6 +204,7 @@ impl ApiServer {
     // API Handler Methods
     pub async fn handle_start_scan(&self, request: ScanRequest, api_key: &str) -> Result<ScanResponse> {
         debug!("API: Starting scan for target: {}", request.target);
+        self.authorize(api_key, Permission::ScanWrite)?;
         
         // Validate target
         self.validate_target(&request.target)?;
@@ -136,26 +215,57 @@ impl ApiServer {
         // Convert DTO to domain type
         let scan_type = self.convert_scan_type(request.scan_type)?;
         
-        // Start scan (async, non-blocking)
-        let scan_engine = Arc::clone(&self.scan_engine);
+        // The engine would refuse these only inside the spawned scan, after
+        // the client was told it started
+        let scan_engine = self.scan_engine()?;
+        scan_engine.check_scan_window(&request.target)?;
+        let dangerous = scan_engine.dangerous_ports(&scan_type);
+        if !dangerous.is_empty() && !scan_engine.dangerous_ports_allowed() {
+            let list = dangerous.iter().map(u16::to_string).collect::<Vec<_>>().join(", ");
+            return Err(Error::Security(format!(
+                "Port(s) {} can disrupt printers and industrial devices; scan a range without them",
+                list
+            )));
+        }
+        
+        // Start scan (async, non-blocking) under an ID the client can poll;
+        // the recorder stores it as running once the target resolved
         let target = request.target.clone();
         let scan_type_clone = scan_type.clone();
+        let status_recorder = Arc::clone(&self.status_recorder);
+        let active_scans = Arc::clone(&self.active_scans);
+        let metrics = Arc::clone(&self.metrics);
+        let scan_id = uuid::Uuid::new_v4().to_string();
+        active_scans.lock().await.push(scan_id.clone());
+        metrics.scan_started();
         
+        let tracking_id = scan_id.clone();
         tokio::spawn(async move {
-            match scan_engine.scan(&target, scan_type_clone).await {
-                Ok(ScanResult { id, .. }) => {
-                    info!("Scan completed successfully: {}", id);
-                    // Save to repository, etc.
-                }
+            match scan_engine.scan_with_id(&tracking_id, &target, scan_type_clone).await {
+                Ok(result) => match status_recorder.save(&result).await {
+                    Ok(_) => {
+                        info!("Scan completed successfully: {}", result.id);
+                        metrics.scan_completed(result.open_ports.len());
+                    }
+                    Err(e) => {
+                        error!("Scan {} finished but could not be saved: {}", result.id, e);
+                        metrics.scan_failed();
+                    }
+                },
                 Err(e) => {
-                    error!("Scan failed: {}", e);
+                    error!("Scan {} failed: {}", tracking_id, e);
+                    if let Err(e) = status_recorder.fail(&tracking_id, &e.to_string()).await {
+                        error!("Could not record scan {} as failed: {}", tracking_id, e);
+                    }
+                    metrics.scan_failed();
                 }
             }
+            active_scans.lock().await.retain(|active| *active != tracking_id);
         });
 
         // Generate response
         Ok(ScanResponse {
-            scan_id: "temp-id".to_string(), // Would be actual scan ID
+            scan_id,
             status: "started".to_string(),
             target: request.target,
             scan_type: format!("{:?}", scan_type),
@@ -164,8 +274,9 @@ impl ApiServer {
         })
     }
 
-    pub async fn handle_get_scan(&self, scan_id: &str, _api_key: &str) -> Result<ScanResultResponse> {
+    pub async fn handle_get_scan(&self, scan_id: &str, api_key: &str) -> Result<ScanResultResponse> {
         debug!("API: Getting scan results for: {}", scan_id);
+        self.authorize(api_key, Permission::ScanRead)?;
         
         // Get scan from repository
         let scan_record = self.scan_repository.get_scan(scan_id).await?
@@ -194,15 +305,17 @@ impl ApiServer {
             scan_id: scan_record.id,
             status: scan_record.status,
             target: scan_record.target,
-            open_ports: port_results.len(),
+            // --all-statuses scans store closed and filtered ports too
+            open_ports: port_results.iter().filter(|port| port.status == "open").count(),
             total_ports: scan_record.total_ports as u16,
             duration_seconds: scan_record.scan_duration_ms as f64 / 1000.0,
             results: port_results,
         })
     }
 
-    pub async fn handle_export_scan(&self, request: ExportRequest, _api_key: &str) -> Result<String> {
+    pub async fn handle_export_scan(&self, request: ExportRequest, api_key: &str) -> Result<String> {
         debug!("API: Exporting scan: {}", request.scan_id);
+        self.authorize(api_key, Permission::ExportWrite)?;
         
         // Get scan from repository
         let scan_record = self.scan_repository.get_scan(&request.scan_id).await?
@@ -219,36 +332,142 @@ impl ApiServer {
         Ok(output_path.to_string_lossy().to_string())
     }
 
-    pub async fn handle_get_scans(&self, _limit: Option<usize>, _api_key: &str) -> Result<Vec<ScanResponse>> {
+    /// One page of stored scans, newest first (`?limit=&offset=`)
+    pub async fn handle_get_scans(
+        &self,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        api_key: &str,
+    ) -> Result<PaginatedResults<ScanResponse>> {
         debug!("API: Listing scans");
-        
-        let scans = self.scan_repository.get_scan_history(_limit).await?;
-        
-        let responses: Vec<ScanResponse> = scans.into_iter().map(|scan| {
-            ScanResponse {
-                scan_id: scan.id,
-                status: scan.status,
-                target: scan.target,
-                scan_type: scan.scan_type,
-                started_at: scan.start_time.to_rfc3339(),
-                estimated_duration: "Completed".to_string(),
-            }
-        }).collect();
+        self.authorize(api_key, Permission::ScanRead)?;
+
+        let query = ScanQuery {
+            limit: Some(limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE) as i64),
+            offset: Some(offset.unwrap_or(0) as i64),
+            ..ScanQuery::default()
+        };
+        let scans = self.scan_repository.search_scans(query).await?;
+
+        Ok(scans.map(|scan| ScanResponse {
+            scan_id: scan.id,
+            status: scan.status,
+            target: scan.target,
+            scan_type: scan.scan_type,
+            started_at: scan.start_time.to_rfc3339(),
+            estimated_duration: "Completed".to_string(),
+        }))
+    }
+
+    pub async fn handle_get_target_timeline(
+        &self,
+        target: &str,
+        days: Option<i64>,
+        api_key: &str,
+    ) -> Result<Vec<crate::storage::models::TargetTimelinePoint>> {
+        debug!("API: Getting timeline for target: {}", target);
+        self.authorize(api_key, Permission::ScanRead)?;
+
+        let since = chrono::Utc::now() - chrono::Duration::days(days.unwrap_or(30));
+        self.scan_repository.get_target_timeline(target, since).await
+    }
+
+    /// Most-scanned targets with their peak exposure (`?limit=`)
+    pub async fn handle_get_top_targets(
+        &self,
+        limit: Option<usize>,
+        api_key: &str,
+    ) -> Result<Vec<crate::storage::models::TopTarget>> {
+        debug!("API: Getting top targets");
+        self.authorize(api_key, Permission::ScanRead)?;
+
+        self.scan_repository
+            .top_targets(limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE))
+            .await
+    }
+
+    /// New, resolved and persistent findings between two assessed scans
+    pub async fn handle_vulnerability_diff(
+        &self,
+        previous_scan_id: &str,
+        current_scan_id: &str,
+        api_key: &str,
+    ) -> Result<crate::vulnerability::VulnDiff> {
+        debug!("API: Comparing assessments {} -> {}", previous_scan_id, current_scan_id);
+        self.authorize(api_key, Permission::ScanRead)?;
 
-        Ok(responses)
+        self.scan_repository
+            .diff_vulnerability_reports(previous_scan_id, current_scan_id, false)
+            .await
+    }
+
+    pub async fn handle_add_suppression(&self, request: SuppressionRequest, api_key: &str) -> Result<String> {
+        debug!("API: Adding suppression {} for {}", request.rule_id, request.target);
+        self.authorize(api_key, Permission::ScanWrite)?;
+
+        let mut suppression = Suppression::new(request.target, request.port, request.rule_id, request.reason);
+        if let Some(days) = request.expires_in_days {
+            suppression = suppression.with_expiry(chrono::Utc::now() + chrono::Duration::days(days));
+        }
+
+        self.scan_repository.add_suppression(&suppression).await
+    }
+
+    pub async fn handle_list_suppressions(&self, target: Option<&str>, api_key: &str) -> Result<Vec<Suppression>> {
+        debug!("API: Listing suppressions");
+        self.authorize(api_key, Permission::ScanRead)?;
+        self.scan_repository.list_suppressions(target).await
+    }
+
+    pub async fn handle_delete_suppression(&self, suppression_id: &str, api_key: &str) -> Result<()> {
+        debug!("API: Deleting suppression: {}", suppression_id);
+        self.authorize(api_key, Permission::ScanDelete)?;
+
+        if !self.scan_repository.delete_suppression(suppression_id).await? {
+            return Err(Error::Validation("Suppression not found".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Convert a handler result into `(status, JSON body)` for the HTTP layer
+    pub fn into_response<T: Serialize>(result: Result<T>) -> (u16, serde_json::Value) {
+        match result {
+            Ok(body) => match serde_json::to_value(body) {
+                Ok(value) => (200, value),
+                Err(e) => Self::error_body(&Error::Serialization(e)),
+            },
+            Err(e) => Self::error_body(&e),
+        }
+    }
+
+    fn error_body(err: &Error) -> (u16, serde_json::Value) {
+        if err.http_status() >= 500 {
+            error!("API request failed: {}", err);
+        }
+        let (status, response) = ErrorResponse::from_error(err);
+        (status, serde_json::to_value(response).unwrap_or_default())
     }
 
     // Utility methods
+
+    /// Unknown keys fail with `Error::Unauthorized` (401), known keys
+    /// lacking the permission with `Error::Auth` (403)
+    fn authorize(&self, api_key: &str, permission: Permission) -> Result<()> {
+        self.authenticator.authenticate(api_key, &permission)
+    }
+
     fn validate_target(&self, target: &str) -> Result<()> {
         // Basic target validation
         if target.is_empty() {
             return Err(Error::Validation("Target cannot be empty".to_string()));
         }
 
-        // Check if target is in allowed list
-        if !self.config.get_settings().security.is_target_allowed(target) {
-            return Err(Error::Security("Target not in allowed list".to_string()));
-        }
+        // Check if target is in allowed list, and not public in safe mode
+        self.config.read()
+            .map_err(|_| Error::Unknown("Settings lock poisoned".to_string()))?
+            .security
+            .check_target(target)?;
 
         // Validate format (IP or hostname)
         if target.parse::<IpAddr>().is_err() {
@@ -283,27 +502,297 @@ impl ApiServer {
     }
 }
 
+// API Documentation (unauthenticated, like the health check)
+impl ApiServer {
+    /// `GET /openapi.json`
+    pub fn handle_openapi_spec(&self) -> Result<String> {
+        debug!("API: Serving OpenAPI spec");
+        super::openapi::ApiDoc::to_json()
+    }
+
+    /// `GET /docs`
+    pub fn handle_docs(&self) -> String {
+        super::openapi::ApiDoc::swagger_ui_html()
+    }
+}
+
+// API Metrics (unauthenticated, like the health check)
+impl ApiServer {
+    /// `GET /metrics` in Prometheus text format
+    pub async fn handle_metrics(&self) -> Result<String> {
+        debug!("API: Serving metrics");
+        let database = self.scan_repository.health_details().await;
+        let active_scans = self.active_scans.lock().await.len();
+        self.metrics.render(active_scans, &database)
+    }
+}
+
 // API Health Check
 impl ApiServer {
     pub async fn health_check(&self) -> Result<HealthStatus> {
-        let database_healthy = self.scan_repository.health_check().await.unwrap_or(false);
+        let database = self.scan_repository.health_details().await;
+        let database_healthy = database.reachable && database.schema_ok;
         let active_scans = self.active_scans.lock().await.len();
 
         Ok(HealthStatus {
             status: if database_healthy { "healthy" } else { "degraded" }.to_string(),
             version: env!("CARGO_PKG_VERSION").to_string(),
             database_healthy,
+            db_latency_ms: database.db_latency_ms,
+            schema_ok: database.schema_ok,
             active_scans,
             uptime_seconds: 0, // Would track actual uptime
         })
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct HealthStatus {
     pub status: String,
     pub version: String,
     pub database_healthy: bool,
+    pub db_latency_ms: Option<f64>,
+    pub schema_ok: bool,
     pub active_scans: usize,
     pub uptime_seconds: u64,
   }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::ScanResult;
+    use crate::storage::database::Database;
+    use crate::web::auth::ApiKey;
+    use std::collections::HashSet;
+
+    const READ_ONLY_KEY: &str = "portzilla-read-only-test-key";
+    const SCAN_WRITE_KEY: &str = "portzilla-scan-write-test-key";
+
+    async fn test_server() -> ApiServer {
+        let authenticator = ApiAuthenticator::new();
+        authenticator.add_api_key(ApiKey {
+            key: READ_ONLY_KEY.to_string(),
+            name: "Read only".to_string(),
+            permissions: HashSet::from([Permission::ScanRead]),
+            rate_limit: None,
+        }).unwrap();
+        authenticator.add_api_key(ApiKey {
+            key: SCAN_WRITE_KEY.to_string(),
+            name: "Scan write".to_string(),
+            permissions: HashSet::from([Permission::ScanRead, Permission::ScanWrite]),
+            rate_limit: None,
+        }).unwrap();
+
+        let db = Database::new("sqlite::memory:").await.unwrap();
+
+        ApiServer::new(
+            Arc::new(VulnerabilityDetector::new().unwrap()),
+            Arc::new(ScanRepository::new(db)),
+            Arc::new(ExportManager::new()),
+            Arc::new(std::sync::RwLock::new(crate::config::Settings::default())),
+            Arc::new(authenticator),
+        ).unwrap()
+    }
+
+    fn quick_scan_request() -> ScanRequest {
+        ScanRequest {
+            target: "127.0.0.1".to_string(),
+            scan_type: ScanTypeDto::Quick,
+            timeout_ms: None,
+            max_threads: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_only_key_can_list_but_not_start() {
+        let server = test_server().await;
+
+        assert!(server.handle_get_scans(Some(10), None, READ_ONLY_KEY).await.is_ok());
+
+        let result = server.handle_start_scan(quick_scan_request(), READ_ONLY_KEY).await;
+        assert!(matches!(result, Err(Error::Auth(_))));
+    }
+
+    #[tokio::test]
+    async fn test_dangerous_ports_are_refused_before_the_scan_starts() {
+        let server = test_server().await;
+        let request = ScanRequest {
+            scan_type: ScanTypeDto::Custom { start_port: 500, end_port: 510 },
+            ..quick_scan_request()
+        };
+
+        match server.handle_start_scan(request, SCAN_WRITE_KEY).await {
+            Err(Error::Security(message)) => assert!(message.contains("502"), "{}", message),
+            other => panic!("expected a security error, got {:?}", other.map(|r| r.status)),
+        }
+        assert!(server.active_scans.lock().await.is_empty());
+        assert!(!server.handle_metrics().await.unwrap().lines().any(|l| l == "portzilla_scans_started_total 1"));
+    }
+
+    #[tokio::test]
+    async fn test_scans_outside_the_window_are_refused_before_they_start() {
+        let server = test_server().await;
+        let now = chrono::Utc::now();
+        let mut settings = crate::config::Settings::default();
+        settings.security.scan_window = Some(crate::config::ScanWindow {
+            days: vec!["daily".to_string()],
+            start: (now + chrono::Duration::hours(2)).format("%H:%M").to_string(),
+            end: (now + chrono::Duration::hours(3)).format("%H:%M").to_string(),
+            timezone: "UTC".to_string(),
+        });
+        server.reload_settings(&settings).unwrap();
+
+        let result = server.handle_start_scan(quick_scan_request(), SCAN_WRITE_KEY).await;
+        let err = result.err().expect("a scan outside the window started");
+        assert!(matches!(&err, Error::Security(message) if message.contains("the next one opens")), "{}", err);
+        assert_eq!(ErrorResponse::from_error(&err).0, 403);
+        assert!(server.active_scans.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_started_scan_is_stored_under_the_returned_id() {
+        let server = test_server().await;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let request = ScanRequest {
+            scan_type: ScanTypeDto::Custom { start_port: port, end_port: port },
+            ..quick_scan_request()
+        };
+
+        let response = server.handle_start_scan(request, SCAN_WRITE_KEY).await.unwrap();
+        assert!(server.active_scans.lock().await.contains(&response.scan_id));
+
+        let started = std::time::Instant::now();
+        while !server.active_scans.lock().await.is_empty() {
+            assert!(started.elapsed() < std::time::Duration::from_secs(30), "the scan never finished");
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        let scan = server.handle_get_scan(&response.scan_id, READ_ONLY_KEY).await.unwrap();
+        assert_eq!(scan.status, "completed");
+        assert_eq!(scan.results.iter().map(|p| p.port).collect::<Vec<_>>(), vec![port]);
+    }
+
+    #[test]
+    fn test_error_response_names_the_status() {
+        let timeout = Error::Timeout { operation: "scan".to_string(), duration: std::time::Duration::from_secs(5) };
+        let (status, response) = ErrorResponse::from_error(&timeout);
+        assert_eq!((status, response.error.as_str()), (504, "Gateway Timeout"));
+
+        let (status, response) = ErrorResponse::from_error(&Error::RateLimit("slow down".to_string()));
+        assert_eq!((status, response.error.as_str()), (429, "Too Many Requests"));
+    }
+
+    #[tokio::test]
+    async fn test_reloaded_settings_reach_the_scan_engine() {
+        let server = test_server().await;
+        let scan_type = ScanType::CustomRange(500, 510);
+        assert!(!server.scan_engine().unwrap().dangerous_ports(&scan_type).is_empty());
+
+        let mut settings = crate::config::Settings::default();
+        settings.security.dangerous_ports.clear();
+        server.reload_settings(&settings).unwrap();
+
+        assert!(server.scan_engine().unwrap().dangerous_ports(&scan_type).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_key_is_rejected_everywhere() {
+        let server = test_server().await;
+        let key = "not-a-real-key";
+        let unauthorized = |result: Result<()>| matches!(result, Err(Error::Unauthorized(_)));
+
+        assert!(unauthorized(server.handle_start_scan(quick_scan_request(), key).await.map(|_| ())));
+        assert!(unauthorized(server.handle_get_scan("scan-id", key).await.map(|_| ())));
+        assert!(unauthorized(server.handle_get_scans(None, None, key).await.map(|_| ())));
+        assert!(unauthorized(server.handle_export_scan(ExportRequest {
+            scan_id: "scan-id".to_string(),
+            format: "json".to_string(),
+            output_path: None,
+        }, key).await.map(|_| ())));
+        assert!(unauthorized(server.handle_get_target_timeline("127.0.0.1", None, key).await.map(|_| ())));
+        assert!(unauthorized(server.handle_get_top_targets(None, key).await.map(|_| ())));
+        assert!(unauthorized(server.handle_vulnerability_diff("previous", "current", key).await.map(|_| ())));
+        assert!(unauthorized(server.handle_add_suppression(SuppressionRequest {
+            target: "127.0.0.1".to_string(),
+            port: None,
+            rule_id: "telnet-exposed".to_string(),
+            reason: "test".to_string(),
+            expires_in_days: None,
+        }, key).await.map(|_| ())));
+        assert!(unauthorized(server.handle_list_suppressions(None, key).await.map(|_| ())));
+        assert!(unauthorized(server.handle_delete_suppression("id", key).await));
+    }
+
+    #[tokio::test]
+    async fn test_scan_pages_are_disjoint_and_ordered() {
+        let server = test_server().await;
+        for i in 0..25 {
+            let scan = ScanResult::new(format!("10.0.0.{}", i), format!("10.0.0.{}", i).parse().unwrap(), ScanType::Quick);
+            server.scan_repository.save_scan(&scan).await.unwrap();
+        }
+
+        let first = server.handle_get_scans(Some(10), Some(0), READ_ONLY_KEY).await.unwrap();
+        let second = server.handle_get_scans(Some(10), Some(10), READ_ONLY_KEY).await.unwrap();
+        let all = server.handle_get_scans(Some(25), None, READ_ONLY_KEY).await.unwrap();
+
+        assert_eq!((first.total, first.total_pages, first.page, second.page), (25, 3, 0, 1));
+        assert_eq!(first.data.len(), 10);
+        assert_eq!(second.data.len(), 10);
+
+        let ids = |page: &PaginatedResults<ScanResponse>| page.data.iter().map(|s| s.scan_id.clone()).collect::<Vec<_>>();
+        let paged: Vec<String> = ids(&first).into_iter().chain(ids(&second)).collect();
+        assert_eq!(paged.iter().collect::<HashSet<_>>().len(), 20);
+        assert_eq!(paged, ids(&all)[..20]);
+    }
+
+    #[tokio::test]
+    async fn test_open_port_count_ignores_closed_and_filtered_rows() {
+        let server = test_server().await;
+        let port = |port, status| crate::scanner::PortInfo {
+            port,
+            status,
+            service: None,
+            banner: None,
+            banner_raw: None,
+            response_time: None,
+            protocol: crate::scanner::Protocol::Tcp,
+            tls: None,
+        };
+        let mut scan = ScanResult::new("10.0.0.1".to_string(), "10.0.0.1".parse().unwrap(), ScanType::Quick);
+        scan.add_open_port(port(22, crate::scanner::PortStatus::Open));
+        scan.all_ports.push(port(23, crate::scanner::PortStatus::Closed));
+        scan.all_ports.push(port(25, crate::scanner::PortStatus::Filtered));
+        scan.finalize();
+        server.scan_repository.save_scan(&scan).await.unwrap();
+
+        let response = server.handle_get_scan(&scan.id, READ_ONLY_KEY).await.unwrap();
+        assert_eq!(response.results.len(), 3);
+        assert_eq!(response.open_ports, 1);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_reports_scan_lifecycle() {
+        let server = test_server().await;
+        server.metrics.scan_started();
+        server.metrics.scan_completed(3);
+        server.metrics.scan_started();
+        server.metrics.scan_failed();
+        server.active_scans.lock().await.push("running".to_string());
+
+        let body = server.handle_metrics().await.unwrap();
+
+        for line in [
+            "portzilla_scans_started_total 2",
+            "portzilla_scans_completed_total 1",
+            "portzilla_scans_failed_total 1",
+            "portzilla_active_scans 1",
+            "portzilla_open_ports_found_count 1",
+            "portzilla_open_ports_found_sum 3",
+            "portzilla_db_healthy 1",
+        ] {
+            assert!(body.lines().any(|l| l == line), "missing {:?} in:\n{}", line, body);
+        }
+        assert!(body.contains("# TYPE portzilla_open_ports_found histogram"));
+        assert!(body.contains("portzilla_db_latency_ms"));
+    }
+}
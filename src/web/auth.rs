@@ -1,3 +1,4 @@
+use super::jwt::{looks_like_jwt, JwtVerifier};
 use crate::error::{Error, Result};
 use std::collections::HashSet;
 use std::sync::RwLock;
@@ -22,6 +23,7 @@ pub enum Permission {
 
 pub struct ApiAuthenticator {
     api_keys: RwLock<Vec<ApiKey>>,
+    jwt: Option<JwtVerifier>,
 }
 
 impl ApiAuthenticator {
@@ -46,16 +48,34 @@ impl ApiAuthenticator {
 
         Self {
             api_keys: RwLock::new(default_keys),
+            jwt: None,
         }
     }
 
-    pub fn authenticate(&self, api_key: &str, required_permission: &Permission) -> Result<()> {
+    /// Also accept JWT bearer tokens; API keys keep working alongside
+    pub fn with_jwt(mut self, verifier: JwtVerifier) -> Self {
+        self.jwt = Some(verifier);
+        self
+    }
+
+    /// `credential` is an API key or, with JWT enabled, a bearer token
+    /// (with or without the `Bearer ` prefix)
+    pub fn authenticate(&self, credential: &str, required_permission: &Permission) -> Result<()> {
+        let credential = credential.strip_prefix("Bearer ").unwrap_or(credential);
+        if let Some(jwt) = self.jwt.as_ref().filter(|_| looks_like_jwt(credential)) {
+            if !jwt.verify(credential)?.contains(required_permission) {
+                return Err(Error::Auth("Insufficient permissions".to_string()));
+            }
+            return Ok(());
+        }
+
+        let api_key = credential;
         let keys = self.api_keys.read()
             .map_err(|_| Error::Auth("Failed to read API keys".to_string()))?;
 
         let key = keys.iter()
             .find(|k| k.key == api_key)
-            .ok_or_else(|| Error::Auth("Invalid API key".to_string()))?;
+            .ok_or_else(|| Error::Unauthorized("Invalid API key".to_string()))?;
 
         if !key.permissions.contains(required_permission) {
             return Err(Error::Auth("Insufficient permissions".to_string()));
@@ -0,0 +1,94 @@
+//! Prometheus metrics served at `GET /metrics`. Counters live on a registry
+//! owned by the `ApiServer`, so several servers (or tests) never share state.
+
+use crate::error::{Error, Result};
+use crate::storage::models::DatabaseHealth;
+use prometheus::{Gauge, Histogram, HistogramOpts, IntCounter, IntGauge, Registry, TextEncoder};
+
+pub const METRICS_PATH: &str = "/metrics";
+
+/// Bucket bounds of the open-ports-per-scan histogram
+const OPEN_PORT_BUCKETS: [f64; 9] = [0.0, 1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0, 500.0];
+
+pub struct ScanMetrics {
+    registry: Registry,
+    scans_started: IntCounter,
+    scans_completed: IntCounter,
+    scans_failed: IntCounter,
+    active_scans: IntGauge,
+    open_ports_found: Histogram,
+    db_healthy: IntGauge,
+    db_latency_ms: Gauge,
+}
+
+impl ScanMetrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+        let metrics = Self {
+            scans_started: IntCounter::new("portzilla_scans_started_total", "Scans started through the API")
+                .expect("valid metric"),
+            scans_completed: IntCounter::new("portzilla_scans_completed_total", "Scans that finished successfully")
+                .expect("valid metric"),
+            scans_failed: IntCounter::new("portzilla_scans_failed_total", "Scans that ended with an error")
+                .expect("valid metric"),
+            active_scans: IntGauge::new("portzilla_active_scans", "Scans currently running")
+                .expect("valid metric"),
+            open_ports_found: Histogram::with_opts(
+                HistogramOpts::new("portzilla_open_ports_found", "Open ports found per completed scan")
+                    .buckets(OPEN_PORT_BUCKETS.to_vec()),
+            )
+            .expect("valid metric"),
+            db_healthy: IntGauge::new("portzilla_db_healthy", "1 when the database is reachable and its schema complete")
+                .expect("valid metric"),
+            db_latency_ms: Gauge::new("portzilla_db_latency_ms", "Round trip of the last database health probe")
+                .expect("valid metric"),
+            registry,
+        };
+
+        // Names are distinct constants, so registration can't collide
+        for collector in [
+            Box::new(metrics.scans_started.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(metrics.scans_completed.clone()),
+            Box::new(metrics.scans_failed.clone()),
+            Box::new(metrics.active_scans.clone()),
+            Box::new(metrics.open_ports_found.clone()),
+            Box::new(metrics.db_healthy.clone()),
+            Box::new(metrics.db_latency_ms.clone()),
+        ] {
+            metrics.registry.register(collector).expect("unique metric names");
+        }
+
+        metrics
+    }
+
+    pub fn scan_started(&self) {
+        self.scans_started.inc();
+    }
+
+    pub fn scan_completed(&self, open_ports: usize) {
+        self.scans_completed.inc();
+        self.open_ports_found.observe(open_ports as f64);
+    }
+
+    pub fn scan_failed(&self) {
+        self.scans_failed.inc();
+    }
+
+    /// Prometheus text exposition, with the gauges set from the values
+    /// sampled at scrape time
+    pub fn render(&self, active_scans: usize, database: &DatabaseHealth) -> Result<String> {
+        self.active_scans.set(active_scans as i64);
+        self.db_healthy.set((database.reachable && database.schema_ok) as i64);
+        self.db_latency_ms.set(database.db_latency_ms.unwrap_or(0.0));
+
+        TextEncoder::new()
+            .encode_to_string(&self.registry.gather())
+            .map_err(|e| Error::Unknown(format!("Failed to encode metrics: {}", e)))
+    }
+}
+
+impl Default for ScanMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
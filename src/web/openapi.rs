@@ -0,0 +1,272 @@
+//! OpenAPI 3 description of the REST API.
+//!
+//! utoipa can't annotate methods inside an `impl` block, so each route gets a
+//! body-less stub here mirroring the matching `ApiServer::handle_*` method.
+//! Update the stub whenever a handler's route, parameters or DTOs change.
+
+use super::api::{
+    ErrorResponse, ExportRequest, HealthStatus, PortResultDto, ScanRequest, ScanResponse,
+    ScanResultResponse, ScanTypeDto, ServiceDto, SuppressionRequest,
+};
+use crate::storage::models::{TargetTimelinePoint, TopTarget};
+use crate::vulnerability::Suppression;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+/// Header carrying the API key checked by `ApiAuthenticator`
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
+pub const OPENAPI_PATH: &str = "/openapi.json";
+pub const DOCS_PATH: &str = "/docs";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Port-ZiLLA API", description = "Port scanning and vulnerability assessment"),
+    paths(
+        start_scan,
+        list_scans,
+        get_scan,
+        export_scan,
+        target_timeline,
+        top_targets,
+        vulnerability_diff,
+        add_suppression,
+        list_suppressions,
+        delete_suppression,
+        health,
+        metrics,
+    ),
+    components(schemas(
+        ScanRequest,
+        ScanResponse,
+        ScanPage,
+        ScanResultResponse,
+        PortResultDto,
+        ServiceDto,
+        ScanTypeDto,
+        ExportRequest,
+        SuppressionRequest,
+        Suppression,
+        TargetTimelinePoint,
+        TopTarget,
+        HealthStatus,
+        ErrorResponse,
+    )),
+    modifiers(&ApiKeyAuth),
+    security(("api_key" = []), ("bearer" = [])),
+    tags(
+        (name = "scans", description = "Start and inspect scans"),
+        (name = "exports", description = "Export stored scans"),
+        (name = "suppressions", description = "Risk-acknowledged findings"),
+        (name = "system", description = "Service health"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Shape of the `PaginatedResults<ScanResponse>` returned by `handle_get_scans`
+#[derive(ToSchema)]
+#[allow(dead_code)]
+struct ScanPage {
+    data: Vec<ScanResponse>,
+    total: i64,
+    /// Zero-based
+    page: i64,
+    page_size: i64,
+    total_pages: i64,
+}
+
+struct ApiKeyAuth;
+
+impl Modify for ApiKeyAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))),
+        );
+        // Only honored when `security.jwt` is configured
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+    }
+}
+
+impl ApiDoc {
+    pub fn to_json() -> crate::error::Result<String> {
+        Ok(Self::openapi().to_pretty_json()?)
+    }
+
+    /// Swagger UI page that loads the spec from `OPENAPI_PATH`
+    pub fn swagger_ui_html() -> String {
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <title>Port-ZiLLA API</title>
+    <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+    <div id="swagger-ui"></div>
+    <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+    <script>
+        window.ui = SwaggerUIBundle({{ url: "{}", dom_id: "#swagger-ui" }});
+    </script>
+</body>
+</html>"#,
+            OPENAPI_PATH
+        )
+    }
+}
+
+#[utoipa::path(
+    post, path = "/api/v1/scans", tag = "scans",
+    request_body = ScanRequest,
+    responses(
+        (status = 200, description = "Scan started", body = ScanResponse),
+        (status = 400, description = "Invalid target or port range", body = ErrorResponse),
+        (status = 401, description = "Unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing ScanWrite permission or target not allowed", body = ErrorResponse),
+    )
+)]
+#[allow(dead_code)]
+fn start_scan() {}
+
+#[utoipa::path(
+    get, path = "/api/v1/scans", tag = "scans",
+    params(
+        ("limit" = Option<usize>, Query, description = "Page size (default 50, at most 500)"),
+        ("offset" = Option<usize>, Query, description = "Number of scans to skip"),
+    ),
+    responses(
+        (status = 200, description = "One page of scans, newest first", body = ScanPage),
+        (status = 401, description = "Unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing ScanRead permission", body = ErrorResponse),
+    )
+)]
+#[allow(dead_code)]
+fn list_scans() {}
+
+#[utoipa::path(
+    get, path = "/api/v1/scans/{scan_id}", tag = "scans",
+    params(("scan_id" = String, Path, description = "Scan ID")),
+    responses(
+        (status = 200, description = "Scan results", body = ScanResultResponse),
+        (status = 400, description = "Scan not found", body = ErrorResponse),
+        (status = 401, description = "Unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing ScanRead permission", body = ErrorResponse),
+    )
+)]
+#[allow(dead_code)]
+fn get_scan() {}
+
+#[utoipa::path(
+    post, path = "/api/v1/exports", tag = "exports",
+    request_body = ExportRequest,
+    responses(
+        (status = 200, description = "Path of the written export", body = String),
+        (status = 400, description = "Scan not found", body = ErrorResponse),
+        (status = 401, description = "Unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing ExportWrite permission", body = ErrorResponse),
+    )
+)]
+#[allow(dead_code)]
+fn export_scan() {}
+
+#[utoipa::path(
+    get, path = "/api/v1/scans/{previous}/vulnerabilities/diff/{current}", tag = "scans",
+    params(
+        ("previous" = String, Path, description = "Baseline scan ID"),
+        ("current" = String, Path, description = "Scan ID to compare against the baseline"),
+    ),
+    responses(
+        (status = 200, description = "New, resolved and persistent findings with the risk score change", body = Object),
+        (status = 400, description = "Scan not found or scans cover different targets", body = ErrorResponse),
+        (status = 401, description = "Unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing ScanRead permission", body = ErrorResponse),
+    )
+)]
+#[allow(dead_code)]
+fn vulnerability_diff() {}
+
+#[utoipa::path(
+    get, path = "/api/v1/targets/{target}/timeline", tag = "scans",
+    params(
+        ("target" = String, Path, description = "Target IP or hostname"),
+        ("days" = Option<i64>, Query, description = "Look-back window in days (default 30)"),
+    ),
+    responses(
+        (status = 200, description = "Open-port counts over time", body = Vec<TargetTimelinePoint>),
+        (status = 401, description = "Unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing ScanRead permission", body = ErrorResponse),
+    )
+)]
+#[allow(dead_code)]
+fn target_timeline() {}
+
+#[utoipa::path(
+    get, path = "/api/v1/targets/top", tag = "scans",
+    params(
+        ("limit" = Option<usize>, Query, description = "Targets to return (default 50, at most 500)"),
+    ),
+    responses(
+        (status = 200, description = "Most-scanned targets first, then the most exposed", body = Vec<TopTarget>),
+        (status = 401, description = "Unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing ScanRead permission", body = ErrorResponse),
+    )
+)]
+#[allow(dead_code)]
+fn top_targets() {}
+
+#[utoipa::path(
+    post, path = "/api/v1/suppressions", tag = "suppressions",
+    request_body = SuppressionRequest,
+    responses(
+        (status = 200, description = "ID of the new suppression", body = String),
+        (status = 401, description = "Unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing ScanWrite permission", body = ErrorResponse),
+    )
+)]
+#[allow(dead_code)]
+fn add_suppression() {}
+
+#[utoipa::path(
+    get, path = "/api/v1/suppressions", tag = "suppressions",
+    params(("target" = Option<String>, Query, description = "Only suppressions for this target")),
+    responses(
+        (status = 200, description = "Suppressions", body = Vec<Suppression>),
+        (status = 401, description = "Unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing ScanRead permission", body = ErrorResponse),
+    )
+)]
+#[allow(dead_code)]
+fn list_suppressions() {}
+
+#[utoipa::path(
+    delete, path = "/api/v1/suppressions/{id}", tag = "suppressions",
+    params(("id" = String, Path, description = "Suppression ID")),
+    responses(
+        (status = 200, description = "Suppression removed"),
+        (status = 400, description = "Suppression not found", body = ErrorResponse),
+        (status = 401, description = "Unknown API key", body = ErrorResponse),
+        (status = 403, description = "Missing ScanDelete permission", body = ErrorResponse),
+    )
+)]
+#[allow(dead_code)]
+fn delete_suppression() {}
+
+#[utoipa::path(
+    get, path = "/health", tag = "system",
+    security(()),
+    responses((status = 200, description = "Service and database health", body = HealthStatus))
+)]
+#[allow(dead_code)]
+fn health() {}
+
+#[utoipa::path(
+    get, path = "/metrics", tag = "system",
+    security(()),
+    responses((status = 200, description = "Scan counters, active scans, open ports per scan and database health in Prometheus text format", body = String, content_type = "text/plain"))
+)]
+#[allow(dead_code)]
+fn metrics() {}
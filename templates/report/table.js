@@ -0,0 +1,78 @@
+// Sortable columns and a keyword filter for the report tables. Inline and
+// dependency-free so reports keep working offline.
+(function () {
+    var SORTABLE_TABLES = ["tcp-ports", "udp-ports", "vulnerabilities"];
+
+    function cellValue(row, column) {
+        var cell = row.cells[column];
+        if (!cell) {
+            return "";
+        }
+        return cell.getAttribute("data-sort") || cell.textContent.trim();
+    }
+
+    function compare(a, b) {
+        var x = parseFloat(a);
+        var y = parseFloat(b);
+        if (!isNaN(x) && !isNaN(y)) {
+            return x - y;
+        }
+        return a.localeCompare(b, undefined, { numeric: true, sensitivity: "base" });
+    }
+
+    function makeSortable(table) {
+        var headers = table.tHead ? table.tHead.rows[0].cells : [];
+        Array.prototype.forEach.call(headers, function (header, column) {
+            header.classList.add("sortable");
+            header.title = "Sort by " + header.textContent.trim();
+            header.addEventListener("click", function () {
+                var ascending = header.getAttribute("aria-sort") !== "ascending";
+                Array.prototype.forEach.call(headers, function (other) {
+                    other.removeAttribute("aria-sort");
+                });
+                header.setAttribute("aria-sort", ascending ? "ascending" : "descending");
+
+                var body = table.tBodies[0];
+                // Service group headers only hold for the exported order
+                Array.prototype.slice.call(body.querySelectorAll("tr.group-row")).forEach(function (row) {
+                    body.removeChild(row);
+                });
+                var rows = Array.prototype.slice.call(body.rows);
+                rows.sort(function (a, b) {
+                    var order = compare(cellValue(a, column), cellValue(b, column));
+                    return ascending ? order : -order;
+                });
+                rows.forEach(function (row) {
+                    body.appendChild(row);
+                });
+            });
+        });
+    }
+
+    function addFilter(table) {
+        var input = document.createElement("input");
+        input.type = "search";
+        input.className = "table-filter";
+        input.placeholder = "Filter rows...";
+        input.setAttribute("aria-label", "Filter " + table.id);
+        input.addEventListener("input", function () {
+            var needle = input.value.trim().toLowerCase();
+            Array.prototype.forEach.call(table.tBodies[0].rows, function (row) {
+                var match = !needle || (!row.classList.contains("group-row") &&
+                    row.textContent.toLowerCase().indexOf(needle) !== -1);
+                row.style.display = match ? "" : "none";
+            });
+        });
+        table.parentNode.insertBefore(input, table);
+    }
+
+    document.addEventListener("DOMContentLoaded", function () {
+        SORTABLE_TABLES.forEach(function (id) {
+            var table = document.getElementById(id);
+            if (table && table.tBodies.length) {
+                addFilter(table);
+                makeSortable(table);
+            }
+        });
+    });
+})();
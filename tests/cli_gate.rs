@@ -0,0 +1,70 @@
+//! `scan --fail-on-open` and `vulnerability --fail-on-severity` exit codes
+//! as a CI pipeline sees them: 0 clean, 2 policy violation, 1 when the scan
+//! could not complete
+
+mod common;
+
+use common::Workspace;
+use std::net::TcpListener;
+use std::process::Output;
+
+fn gate_scan(workspace: &Workspace, target: &str, port_range: &str, fail_on_open: &str, extra: &[&str]) -> Output {
+    workspace.portzilla()
+        .args(["scan", target, "--port-range", port_range, "--fail-on-open", fail_on_open])
+        .args(["--no-reverse-dns", "--i-understand-the-risk", "--timeout", "300"])
+        .args(extra)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_gate_passes_when_no_listed_port_is_open() {
+    let workspace = Workspace::new();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port().to_string();
+
+    let output = gate_scan(&workspace, "127.0.0.1", &port, "1", &["--skip-host-discovery"]);
+    assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_gate_fails_with_2_listing_the_open_port() {
+    let workspace = Workspace::new();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port().to_string();
+
+    let output = gate_scan(&workspace, "127.0.0.1", &port, &port, &["--skip-host-discovery"]);
+    assert_eq!(output.status.code(), Some(2), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), format!("{}/tcp\n", port));
+}
+
+#[test]
+fn test_gate_errors_with_1_when_the_target_is_unreachable() {
+    let workspace = Workspace::new();
+
+    // .invalid never resolves (RFC 6761), whatever network the tests run on
+    let output = gate_scan(&workspace, "unreachable.invalid", "22", "22", &[]);
+    assert_eq!(output.status.code(), Some(1), "{}", String::from_utf8_lossy(&output.stdout));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_severity_gate_fails_with_2_at_or_above_the_threshold() {
+    let workspace = Workspace::new();
+    // Matched by the built-in telnet-exposed rule (High)
+    let scan_id = workspace.import_scan(23, "Ubuntu 22.04 LTS\r\nlogin: ");
+    let severity_gate = |severity: &str| workspace.portzilla()
+        .args(["vulnerability", "--scan-id", &scan_id, "--fail-on-severity", severity])
+        .output()
+        .unwrap();
+
+    let output = severity_gate("high");
+    assert_eq!(output.status.code(), Some(2), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.lines().any(|line| line == "23/tcp High Telnet Service Exposed"), "{}", stdout);
+
+    let output = severity_gate("critical");
+    assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stdout.is_empty());
+}
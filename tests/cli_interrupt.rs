@@ -0,0 +1,65 @@
+//! Ctrl-C during `scan`: the scan stops, what it found is stored as a
+//! cancelled scan, and the process exits 130
+#![cfg(unix)]
+
+mod common;
+
+use common::Workspace;
+use std::net::TcpListener;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_sigint_saves_a_cancelled_scan_and_exits_130() {
+    let workspace = Workspace::new();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    // Paced so the range takes minutes, starting at the open port
+    let range = format!("{}-{}", port, port.saturating_add(5000));
+    let mut scan = workspace.portzilla()
+        .args(["scan", "127.0.0.1", "--port-range", &range, "--max-pps", "20"])
+        .args(["--skip-host-discovery", "--no-reverse-dns", "--i-understand-the-risk", "--timeout", "300"])
+        // Nothing reads the output while the scan runs
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    // The scan is recorded as running once the engine starts, after the
+    // Ctrl-C handler is installed
+    let started = Instant::now();
+    let running = || -> bool {
+        let Ok(db) = rusqlite::Connection::open(workspace.database()) else {
+            return false;
+        };
+        db.query_row("SELECT COUNT(*) FROM scans WHERE status = 'running'", [], |row| row.get::<_, i64>(0))
+            .is_ok_and(|count| count == 1)
+    };
+    while !running() {
+        assert!(started.elapsed() < Duration::from_secs(30), "the scan never started");
+        assert!(scan.try_wait().unwrap().is_none(), "the scan exited before it was interrupted");
+        sleep(Duration::from_millis(100));
+    }
+    // Long enough for the first port to be probed
+    sleep(Duration::from_secs(2));
+
+    let sent = Command::new("kill").args(["-INT", &scan.id().to_string()]).status().unwrap();
+    assert!(sent.success());
+    assert_eq!(scan.wait().unwrap().code(), Some(130));
+
+    let db = rusqlite::Connection::open(workspace.database()).unwrap();
+    let (scan_id, status): (String, String) = db
+        .query_row("SELECT id, status FROM scans", [], |row| Ok((row.get(0)?, row.get(1)?)))
+        .unwrap();
+    assert_eq!(status, "cancelled");
+    let open: Vec<u16> = db
+        .prepare("SELECT port FROM scan_ports WHERE scan_id = ? AND status = 'open'")
+        .unwrap()
+        .query_map([&scan_id], |row| row.get(0))
+        .unwrap()
+        .collect::<rusqlite::Result<_>>()
+        .unwrap();
+    assert_eq!(open, vec![port]);
+}
@@ -0,0 +1,38 @@
+//! `vulnerability` against stored scans, with the rules file from the
+//! config applied
+
+mod common;
+
+use common::Workspace;
+
+#[test]
+fn test_custom_rules_file_produces_findings() {
+    let workspace = Workspace::new();
+    let rules = workspace.path().join("rules.toml");
+    std::fs::write(&rules, r#"
+        [[rules]]
+        id = "internal-admin"
+        title = "Internal Admin Console Exposed"
+        description = "The admin console answers on this port"
+        level = "High"
+        mitigation = "Restrict the console to the management network"
+        banner_regex = "AcmeAdmin/"
+        "#).unwrap();
+    workspace.configure(
+        r#"# custom_rules_path = "config/vulnerability_rules.toml""#,
+        &format!("custom_rules_path = {:?}", rules.display().to_string()),
+    );
+    let scan_id = workspace.import_scan(9443, "HTTP/1.1 200 OK | Server: AcmeAdmin/3.1");
+
+    let output = workspace.portzilla()
+        .args(["vulnerability", "--scan-id", &scan_id, "--fail-on-severity", "high"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.lines().any(|line| line.starts_with("9443/tcp") && line.ends_with("Internal Admin Console Exposed")),
+        "{}", stdout
+    );
+}
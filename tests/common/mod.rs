@@ -0,0 +1,74 @@
+//! Runs the `portzilla` binary in a scratch directory, with the shipped
+//! config pointed at a database of its own
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub struct Workspace {
+    dir: tempfile::TempDir,
+}
+
+impl Workspace {
+    pub fn new() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let shipped = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/config/default.toml")).unwrap();
+        let config = shipped.replace(
+            r#"connection_string = "sqlite:portzilla.db""#,
+            r#"connection_string = "sqlite:portzilla.db?mode=rwc""#,
+        );
+        assert_ne!(config, shipped, "config/default.toml no longer sets the database connection string");
+
+        std::fs::create_dir_all(dir.path().join("config")).unwrap();
+        std::fs::write(dir.path().join("config/default.toml"), config).unwrap();
+        Self { dir }
+    }
+
+    /// `portzilla` run from the workspace, so `config/default.toml`, the
+    /// database and the logs all resolve inside it
+    pub fn portzilla(&self) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_portzilla"));
+        command.current_dir(self.dir.path()).arg("--no-color");
+        command
+    }
+
+    pub fn database(&self) -> PathBuf {
+        self.dir.path().join("portzilla.db")
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Replace `line` of the workspace's config with `replacement`
+    pub fn configure(&self, line: &str, replacement: &str) {
+        let path = self.dir.path().join("config/default.toml");
+        let config = std::fs::read_to_string(&path).unwrap();
+        assert!(config.contains(line), "config/default.toml has no line {:?}", line);
+        std::fs::write(&path, config.replacen(line, replacement, 1)).unwrap();
+    }
+
+    /// Store a scan of 10.0.0.8 with one open port and its banner through
+    /// `portzilla import`; returns the scan ID
+    pub fn import_scan(&self, port: u16, banner: &str) -> String {
+        let scan_id = format!("imported-{}", port);
+        let export = serde_json::json!({
+            "metadata": {
+                "scan_id": scan_id,
+                "target": "10.0.0.8",
+                "target_ip": "10.0.0.8",
+                "scan_type": "Quick",
+                "start_time": "2024-05-01T10:00:00+00:00",
+                "end_time": "2024-05-01T10:00:42+00:00"
+            },
+            "results": {
+                "open_ports": [{ "port": port, "status": "open", "protocol": "tcp", "banner": banner }]
+            }
+        });
+        let file = self.dir.path().join(format!("{}.json", scan_id));
+        std::fs::write(&file, export.to_string()).unwrap();
+
+        let output = self.portzilla().arg("import").arg(&file).output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        scan_id
+    }
+}